@@ -1,7 +1,17 @@
 use std::io::{BufRead, BufReader, Write};
 use std::net::{TcpListener, TcpStream};
+use std::time::Instant;
+
+const DEFAULT_ITERATIONS: usize = 1000000000;
+const DEFAULT_ROUNDS: usize = 5;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(|arg| arg.as_str()) == Some("bench") {
+        run_locally(&args[1..]);
+        return;
+    }
+
     let listener = TcpListener::bind("0.0.0.0:3000").unwrap();
 
     for stream in listener.incoming() {
@@ -25,6 +35,33 @@ fn handle_connection(mut stream: TcpStream) {
     stream.write_all(format!("{}\r\n\r\n{}", response_header, response_body).as_bytes()).unwrap();
 }
 
+/// Runs `calc_pi` directly without the server, for quick local profiling.
+/// Usage: `cargo run --release -- bench [iterations] [rounds]`
+fn run_locally(args: &[String]) {
+    let iterations = parse_arg(args, 0, "iterations", DEFAULT_ITERATIONS);
+    let rounds = parse_arg(args, 1, "rounds", DEFAULT_ROUNDS).max(1);
+    println!("Running {} rounds with {} iterations", rounds, iterations);
+
+    let mut times = Vec::new();
+    for round in 1..=rounds {
+        let start = Instant::now();
+        let result = calc_pi(iterations);
+        let elapsed = start.elapsed().as_millis();
+        println!("Round {}: {} ms ({};{};{})", round, elapsed, result.0, result.1, result.2);
+        times.push(elapsed);
+    }
+
+    times.sort();
+    println!("Median: {} ms, min: {} ms, max: {} ms", times[times.len() / 2], times[0], times[times.len() - 1]);
+}
+
+fn parse_arg(args: &[String], index: usize, name: &str, default: usize) -> usize {
+    match args.get(index) {
+        Some(arg) => arg.parse().unwrap_or_else(|_| panic!("Invalid {}: {}", name, arg)),
+        None => default,
+    }
+}
+
 fn calc_pi(iterations: usize) -> (f64, f64, f64) {
     let mut pi = 0.0;
     let mut denominator = 1.0;
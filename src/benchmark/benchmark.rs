@@ -1,9 +1,23 @@
 use std::time::Duration;
 use std::{thread};
+use std::cmp::Ordering;
 use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use indexmap::IndexMap;
-use crate::utils::docker_runner::run_docker_compose;
-use crate::utils::percentile;
+use serde::{Deserialize, Serialize};
+use regex::Regex;
+use crate::benchmark::deadline;
+use crate::benchmark::error::BenchmarkError;
+use crate::output::{eta, tui};
+use crate::output::stream::{self, Event};
+use crate::utils::docker_runner::{container_pid, exec_in_container, run_docker_compose, BuildOptions};
+use crate::utils::{environment, gc_metrics, image, log, serialization};
+use crate::utils::image::ImageInfo;
+use crate::utils::meta_data_parser::BenchmarkManifest;
+use crate::utils::format::{format_number, SizeFormat};
+use crate::utils::{percentile, reset};
 use crate::utils::version_migrator::VersionMigrator;
 
 const COMPOSE_FILE: &str = r#"
@@ -26,21 +40,346 @@ networks:
     external: true
 "#;
 
+/// A benchmark directory may provide this file to replace `COMPOSE_FILE`, see `custom_compose_file`.
+pub(crate) const CUSTOM_COMPOSE_FILE: &str = "docker-compose.yml";
+
+/// The service (and container name) the runner attaches to.
+pub(crate) const BENCHMARK_SERVICE: &str = "benchmark";
+
+/// The port the benchmark has to publish.
+pub(crate) const BENCHMARK_PORT: &str = "3000";
+
+/// The external network of `COMPOSE_FILE`, created once per Docker host.
+pub(crate) const BENCHMARK_NETWORK: &str = "sharkbench-benchmark-network";
+
+/// Samples per second of `perf record`.
+const PROFILE_FREQUENCY: &str = "99";
+
+/// A measured round is suspect if its time is this many times
+/// above the slowest or below the fastest warmup round.
+const SUSPECT_FACTOR: i64 = 10;
+
+/// The standard deviation of the warmup rounds is at least this share of their mean and at least `MIN_STDDEV_MS`,
+/// so warmup rounds with (almost) the same time do not make every other time suspect.
+const MIN_RELATIVE_STDDEV: f64 = 0.05;
+
+/// The times are whole milliseconds, so a spread below it is rounding.
+const MIN_STDDEV_MS: f64 = 1.0;
+
+/// Warmup rounds needed for their standard deviation, see `BenchmarkOptions::outlier_sigma`.
+const MIN_OUTLIER_WARMUP: usize = 2;
+
+/// Pause after every measured round to let the container cool down, see `BenchmarkOptions::cooldown`.
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(2);
+
+/// How long to wait for the container to pass the health check.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often suspect rounds are re-run before they are recorded anyway.
+const MAX_SUSPECT_RERUNS: usize = 3;
+
+/// Longer bodies are cut off in the message of a contract violation.
+const MAX_RESPONSE_LENGTH: usize = 200;
+
+#[derive(Serialize, Deserialize, Default)]
 pub struct BenchmarkResult {
     pub time_median: i64,
-    pub memory_median: i64,
-    pub memory_p99: i64,
+
+    /// Best-case time of the measured rounds (1st percentile), the hot path with the least interference.
+    /// `None` in results written before it was measured.
+    #[serde(default)]
+    pub time_p1: Option<i64>,
+
+    /// Not measured when running against an external service.
+    pub memory_median: Option<i64>,
+    pub memory_p99: Option<i64>,
+
+    /// Median of the memory the application reported itself (e.g. heap used),
+    /// to be compared with `memory_median` measured by docker.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub self_reported_memory_median: Option<i64>,
+
+    #[serde(serialize_with = "serialization::sorted_map")]
     pub additional_data: IndexMap<String, AdditionalData>,
+
+    /// Time of the first round right after the container became healthy (see `BenchmarkOptions::cold_start`).
+    /// It is not part of the warmup or measured rounds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cold_start_ms: Option<i64>,
+
+    /// Time of the graceful shutdown of the container after the last round (`docker compose stop`),
+    /// e.g. to compare how long runtimes flush and clean up. Only part of the result of the first level of a sweep.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shutdown_ms: Option<i64>,
+
+    /// Path of the `perf` recording of the profiled round (see `BenchmarkOptions::profile_dir`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+
+    /// The runtime version reported by the container (see `BenchmarkOptions::version_command`).
+    /// It may differ from the requested version if base image tags drift.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual_version: Option<String>,
+
+    /// The built image and the digests of its base images, to tell whether two results ran on the same image contents.
+    /// Not known when running against an external service.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageInfo>,
+
+    /// The `_benchmark.yaml` of the benchmark (see `BenchmarkManifest`), `None` if it has none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<BenchmarkManifest>,
+
+    /// Free-form annotations of the caller (e.g. `power=battery`) to organize results.
+    #[serde(default, serialize_with = "serialization::sorted_map")]
+    pub tags: IndexMap<String, String>,
+
+    /// Median time of the measured rounds so far after every measured round (see `BenchmarkOptions::running_median`),
+    /// to tell whether the median converged or more rounds are needed. The last value is `time_median`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub running_median: Vec<i64>,
+
+    /// Times of the warmup rounds of all workloads of this result. Every level of a sweep has its own warmup.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warmup_times: Vec<i64>,
+
+    /// Times of the warmup rounds of every single workload, e.g. `shells: [900, 450]`, see `BenchmarkOptions::workload_warmup_rounds`.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub workload_warmup_times: IndexMap<String, Vec<i64>>,
+
+    /// The effective warmup, rounds and cooldown the result was measured with.
+    /// `None` in results written before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub measurement: Option<Measurement>,
+
+    /// Whether the benchmark ran out of time (see `deadline`) before all rounds of this result were measured.
+    /// The values are of the rounds measured until then, the benchmark is recorded as failed with `benchmark_timeout`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+
+    /// All recorded rounds (including warmup) in the order they ran.
+    /// Only available in memory, not part of the serialized result.
+    #[serde(skip)]
+    pub samples: Vec<RoundSample>,
+}
+
+/// The effective settings of a measurement, after the command line, benchmark and category overrides.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Measurement {
+    pub warmup_rounds: usize,
+    pub rounds: usize,
+    pub cooldown_ms: u64,
+}
+
+/// The raw measurement of a single round.
+#[derive(Serialize, Clone, Debug, PartialEq, Default)]
+pub struct RoundSample {
+    pub warmup: bool,
+
+    /// The single workload warmed up by this round, `None` for a round of all workloads.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workload: Option<String>,
+
+    /// Unix timestamp in milliseconds when the round started.
+    pub started_at_ms: i64,
+    pub time: i64,
+    pub memory_median: Option<i64>,
+    pub memory_p99: Option<i64>,
+    pub self_reported_memory: Option<i64>,
+    #[serde(serialize_with = "serialization::sorted_map")]
+    pub additional_data: IndexMap<String, AdditionalData>,
+
+    /// Errors of the failed attempts that were retried before this round.
+    pub failed_attempts: Vec<String>,
+
+    /// Times of the suspect runs that were discarded and re-run before this round.
+    pub suspect_reruns: Vec<i64>,
+
+    /// Whether this round is suspect but was recorded because it was re-run too often.
+    pub suspect: bool,
+
+    /// Median time of the measured rounds up to and including this one, see `BenchmarkOptions::running_median`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub running_median: Option<i64>,
+}
+
+impl BenchmarkResult {
+    /// Renders the result as aligned `label  value` lines, e.g. `Time median   1500 ms`.
+    /// Time percentiles are calculated from the measured rounds.
+    pub fn format_summary(&self) -> String {
+        let times: Vec<i64> = self.samples.iter().filter(|sample| !sample.warmup).map(|sample| sample.time).collect();
+
+        let mut rows: Vec<(String, String)> = vec![("Time median".to_string(), format!("{} ms", format_number(self.time_median)))];
+        if let Some(time_p1) = self.time_p1 {
+            rows.push(("Time p1".to_string(), format!("{} ms", format_number(time_p1))));
+        }
+        if !times.is_empty() {
+            rows.push(("Time p99".to_string(), format!("{} ms", format_number(percentile::p99(&times)))));
+        }
+        if let Some(cold_start_ms) = self.cold_start_ms {
+            rows.push(("Cold start".to_string(), format!("{} ms", format_number(cold_start_ms))));
+        }
+        if let Some(shutdown_ms) = self.shutdown_ms {
+            rows.push(("Shutdown".to_string(), format!("{} ms", format_number(shutdown_ms))));
+        }
+        if !self.warmup_times.is_empty() {
+            let times: Vec<String> = self.warmup_times.iter().map(|time| format_number(*time)).collect();
+            rows.push(("Warmup".to_string(), format!("{} ms", times.join(" "))));
+        }
+        for (workload, warmup_times) in &self.workload_warmup_times {
+            let times: Vec<String> = warmup_times.iter().map(|time| format_number(*time)).collect();
+            rows.push((format!("Warmup {}", workload), format!("{} ms", times.join(" "))));
+        }
+        rows.push(("Memory median".to_string(), self.memory_median.bytes_to_string()));
+        rows.push(("Memory p99".to_string(), self.memory_p99.bytes_to_string()));
+        if self.self_reported_memory_median.is_some() {
+            rows.push(("Self-reported memory".to_string(), self.self_reported_memory_median.bytes_to_string()));
+        }
+        for (key, value) in &self.additional_data {
+            rows.push((key.clone(), value.to_string()));
+        }
+        if !self.running_median.is_empty() {
+            let series: Vec<String> = self.running_median.iter().map(|median| format_number(*median)).collect();
+            rows.push(("Running median".to_string(), format!("{} ms", series.join(" "))));
+        }
+
+        let label_width = rows.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+        let value_width = rows.iter().map(|(_, value)| value.chars().count()).max().unwrap_or(0);
+        rows.iter()
+            .map(|(label, value)| format!("{:<label_width$}  {:>value_width$}\n", label, value))
+            .collect()
+    }
 }
 
+/// How a benchmark is measured.
+pub struct BenchmarkOptions {
+    /// Warmup rounds (per level of a sweep).
+    pub warmup_rounds: usize,
+
+    /// Warmup rounds of single workloads of a round (e.g. the endpoints of the web benchmark) instead of `warmup_rounds`,
+    /// e.g. for workloads that exercise other code paths. Every workload warms up on its own before the measured rounds,
+    /// which run all workloads. Empty to warm up all workloads together.
+    pub workload_warmup_rounds: IndexMap<String, usize>,
+
+    /// Measured rounds (per level of a sweep).
+    pub rounds: usize,
+
+    /// Pause after every measured round and after the cold start round, outside the measured time.
+    pub cooldown: Duration,
+
+    /// Command executed in the container after it became healthy to determine the actual runtime version,
+    /// e.g. `node --version`.
+    pub version_command: Option<String>,
+
+    /// Command executed once in the container after it became healthy and before the first round (outside the measured time),
+    /// e.g. to seed a database. A failing command fails the benchmark.
+    pub setup_command: Option<String>,
+
+    /// Whether to measure the first round after boot as `cold_start_ms`, before the warmup begins.
+    pub cold_start: bool,
+
+    /// If set, an extra round after the measured rounds is recorded with `perf` into this directory.
+    pub profile_dir: Option<PathBuf>,
+
+    /// Overrides `Aggregation::default_for` of additional data keys.
+    pub aggregation: IndexMap<String, Aggregation>,
+
+    /// POSTed after every round (outside the measured time) so stateful benchmarks (e.g. a cache that fills up)
+    /// start every round in the same state.
+    pub reset_url: Option<String>,
+
+    /// Checked against the response of the health check and of the first round of every level.
+    /// A violation fails the benchmark immediately instead of measuring an error page.
+    pub validate_response: Option<ResponseValidator>,
+
+    /// Whether to record the median time after every measured round (`BenchmarkResult::running_median`).
+    pub running_median: bool,
+
+    /// Only run the warmup rounds (e.g. to prime the build cache in CI). The results have no measured rounds.
+    pub warmup_only: bool,
+
+    /// Keep the built images after the run instead of removing them, so a following run starts from the cache.
+    pub keep_images: bool,
+
+    /// How the image of the benchmark is built, e.g. without the cache.
+    pub build: BuildOptions,
+
+    /// Memory budget in bytes: the benchmark fails if the `memory_p99` of any level exceeds it.
+    pub max_memory: Option<i64>,
+
+    /// Rejects measured rounds by the spread of the warmup rounds instead of `SUSPECT_FACTOR`, see `is_suspect`.
+    /// Needs at least `MIN_OUTLIER_WARMUP` warmup rounds of all workloads, it is ignored with a warning otherwise.
+    pub outlier_sigma: Option<f64>,
+}
+
+/// Additional data key of the size of the response body in bytes as received, measured by the runner for every round with a response.
+pub const RESPONSE_SIZE_KEY: &str = "response_bytes";
+
+/// A single response of the benchmark.
+pub struct Response {
+    /// `None` for transports without status (e.g. TCP).
+    pub status: Option<u16>,
+    pub body: String,
+
+    /// Bytes of the body as received, before it was decoded (see `compression`).
+    pub size: usize,
+
+    /// The phases the benchmark reported in its `Server-Timing` header, see `server_timing`.
+    pub phases: IndexMap<String, Duration>,
+}
+
+/// Checks the status and the shape of the body, returns what is wrong otherwise.
+pub type ResponseValidator = fn(&Response) -> Result<(), String>;
+
 pub struct IterationResult {
+    /// The measured part of the round, e.g. the request without the queries of the metrics before and after it.
+    pub time: Duration,
+
     pub additional_data: IndexMap<String, AdditionalData>,
     pub debugging_data: IndexMap<String, AdditionalData>,
+
+    /// Memory in bytes the application reported after the round, if supported.
+    pub self_reported_memory: Option<i64>,
+
+    /// A response of the round, see `BenchmarkOptions::validate_response`.
+    pub response: Option<Response>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
 pub enum AdditionalData {
     Int(i32),
+
+    /// A duration in µs, e.g. a phase of the request reported by the benchmark (see `server_timing`).
+    Duration(i64),
+}
+
+impl AdditionalData {
+    pub fn as_i64(&self) -> i64 {
+        match self {
+            AdditionalData::Int(value) => *value as i64,
+            AdditionalData::Duration(value) => *value,
+        }
+    }
+
+    /// A total order of the values, so their median does not depend on the order of the rounds:
+    /// by value, and equal values of different kinds by the order of the kinds.
+    /// A floating point kind has to compare with `f64::total_cmp` and place NaN last.
+    pub fn total_cmp(&self, other: &AdditionalData) -> Ordering {
+        let kind = |data: &AdditionalData| match data {
+            AdditionalData::Int(_) => 0,
+            AdditionalData::Duration(_) => 1,
+        };
+        self.as_i64().cmp(&other.as_i64()).then_with(|| kind(self).cmp(&kind(other)))
+    }
+
+    /// The value in the kind of `kind`, clamped to its range.
+    fn with_kind_of(&self, kind: &AdditionalData) -> AdditionalData {
+        match kind {
+            AdditionalData::Int(_) => AdditionalData::Int(self.as_i64().clamp(i32::MIN as i64, i32::MAX as i64) as i32),
+            AdditionalData::Duration(_) => AdditionalData::Duration(self.as_i64()),
+        }
+    }
 }
 
 impl Debug for AdditionalData {
@@ -58,163 +397,1212 @@ impl Display for AdditionalData {
 fn format_additional_data(data: &AdditionalData, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     write!(f, "{}", match data {
         AdditionalData::Int(value) => value.to_string(),
+        AdditionalData::Duration(value) => value.to_string(),
         // AdditionalData::Float(value) => value.to_string(),
     })
 }
 
-pub fn run_benchmark<F>(
+/// How the values of an additional data key are combined over the measured rounds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregation {
+    /// Robust against outliers, the default for measurements like `rps_median`.
+    Median,
+
+    /// Average per round (rounded), e.g. for counters that are reset every round.
+    Mean,
+
+    /// Total over all rounds.
+    Sum,
+
+    Max,
+}
+
+impl Aggregation {
+    /// The aggregation of keys without an explicit one: `mean` for GC pauses (per round counters), `median` otherwise.
+    pub fn default_for(key: &str) -> Aggregation {
+        match key {
+            gc_metrics::PAUSE_COUNT_KEY | gc_metrics::PAUSE_TIME_KEY => Aggregation::Mean,
+            _ => Aggregation::Median,
+        }
+    }
+
+    fn aggregate(&self, values: &[i32]) -> i32 {
+        let values: Vec<i64> = values.iter().map(|value| *value as i64).collect();
+        self.aggregate_i64(&values).clamp(i32::MIN as i64, i32::MAX as i64) as i32
+    }
+
+    fn aggregate_i64(&self, values: &[i64]) -> i64 {
+        match self {
+            Aggregation::Median => {
+                let mut sorted = values.to_vec();
+                sorted.sort();
+                sorted[sorted.len() / 2]
+            }
+            Aggregation::Mean => (values.iter().map(|value| *value as f64).sum::<f64>() / values.len() as f64).round() as i64,
+            Aggregation::Sum => values.iter().fold(0i64, |sum, value| sum.saturating_add(*value)),
+            Aggregation::Max => *values.iter().max().unwrap(),
+        }
+    }
+}
+
+/// Adds the size of the response of the round as `RESPONSE_SIZE_KEY`, web benchmarks only keep the first response of a round.
+/// Rounds without a response are left as they are.
+fn insert_response_size(result: &mut IterationResult) {
+    if let Some(response) = &result.response {
+        let size = response.size.min(i32::MAX as usize) as i32;
+        result.additional_data.insert(RESPONSE_SIZE_KEY.to_string(), AdditionalData::Int(size));
+    }
+}
+
+/// Combines the additional data of the measured rounds per key, see `Aggregation`.
+/// `aggregation` overrides `Aggregation::default_for` of single keys. Keys keep the order they first appeared in.
+fn aggregate_additional_data(
+    rounds: &[IndexMap<String, AdditionalData>],
+    aggregation: &IndexMap<String, Aggregation>,
+) -> IndexMap<String, AdditionalData> {
+    let mut values: IndexMap<&str, Vec<&AdditionalData>> = IndexMap::new();
+    for data in rounds {
+        for (key, value) in data {
+            values.entry(key).or_default().push(value);
+        }
+    }
+
+    // The kind of the first round is kept
+    values.into_iter()
+        .map(|(key, values)| {
+            let aggregation = aggregation.get(key).copied().unwrap_or(Aggregation::default_for(key));
+            let value = match values[0] {
+                first if aggregation == Aggregation::Median => {
+                    let mut sorted = values.clone();
+                    sorted.sort_by(|a, b| a.total_cmp(b));
+                    sorted[sorted.len() / 2].with_kind_of(first)
+                }
+                AdditionalData::Int(_) => {
+                    let values: Vec<i32> = values.iter().map(|value| value.as_i64().clamp(i32::MIN as i64, i32::MAX as i64) as i32).collect();
+                    AdditionalData::Int(aggregation.aggregate(&values))
+                }
+                AdditionalData::Duration(_) => {
+                    let values: Vec<i64> = values.iter().map(|value| value.as_i64()).collect();
+                    AdditionalData::Duration(aggregation.aggregate_i64(&values))
+                }
+            };
+            (key.to_string(), value)
+        })
+        .collect()
+}
+
+/// Runs the benchmark in `dir` at every level (e.g. concurrency or iterations) within the same container.
+///
+/// `health_check` is called repeatedly after the container started until it succeeds.
+/// Its response has to pass `BenchmarkOptions::validate_response`. Afterward, the version command is executed and `on_iteration` is called for every warmup and measured round.
+///
+/// Without `stats_reader`, the benchmark runs against an already running external service:
+/// No container is started, no versions are migrated and no memory is measured.
+/// Memory is also not reported if docker stats is not available.
+///
+/// `on_iteration` is called with the current level and the workload to warm up (`None` for all workloads, see `BenchmarkOptions::workload_warmup_rounds`).
+/// Every level gets its own warmup and measured rounds.
+/// The first error that ends the benchmark is returned, after the container was stopped and the versions were restored.
+/// A level that ran out of time (see `BenchmarkResult::timed_out`) is the last one measured.
+/// `None` if the user skipped the benchmark or quit the run through `--tui` (see `tui::take_request`).
+pub fn run_sweep<K, H, F>(
     dir: &str,
-    stats_reader: &mut crate::utils::docker_stats::DockerStatsReader,
+    mut stats_reader: Option<&mut crate::utils::docker_stats::DockerStatsReader>,
     mut version_migrations: Vec<&mut VersionMigrator>,
-    warmup_rounds: usize,
-    rounds: usize,
+    options: &BenchmarkOptions,
+    health_check: H,
+    levels: &[K],
     on_iteration: F,
-) -> BenchmarkResult
-    where F: Fn() -> Result<IterationResult, Box<dyn std::error::Error>>
+) -> Result<Option<IndexMap<K, BenchmarkResult>>, BenchmarkError>
+    where
+        K: Hash + Eq + Clone + Debug,
+        H: Fn() -> Result<Response, BenchmarkError>,
+        F: Fn(&K, Option<&str>) -> Result<IterationResult, BenchmarkError>,
 {
+    let external = stats_reader.is_none();
+    if external {
+        version_migrations.clear();
+    }
+
+    // Checked first, so a failing migration does not leave the files migrated
+    version_migrations.iter()
+        .try_for_each(|version_migrator| version_migrator.check(|path| std::fs::read_to_string(path)))
+        .map_err(BenchmarkError::InvalidBenchmark)?;
     for version_migrator in &mut version_migrations {
         version_migrator.migrate();
     }
 
+    let mut results: IndexMap<K, BenchmarkResult> = IndexMap::new();
+    // Whether all levels were measured
+    let measure = || -> Result<bool, BenchmarkError> {
+        let response = wait_until_healthy(health_check)?;
+        if let Some(validate_response) = options.validate_response {
+            check_response(validate_response, &response, "health check")?;
+        }
+
+        let actual_version = match (&options.version_command, external) {
+            (Some(command), false) => query_actual_version(dir, command),
+            _ => None,
+        };
+        let image = match external {
+            true => None,
+            false => Some(image::inspect(dir, BENCHMARK_SERVICE)),
+        };
+        match (&options.setup_command, external) {
+            (Some(_), true) => logln!(" -> [Setup]: Not available for external services"),
+            (Some(command), false) => run_setup(dir, command)?,
+            (None, _) => {}
+        }
+
+        // The container only boots once, so only the first level has a cold start
+        let mut cold_start_ms = match (options.cold_start, levels.first()) {
+            (true, Some(level)) => measure_cold_start(options.cooldown, || on_iteration(level, None)),
+            _ => None,
+        };
+
+        let warmup_rounds = match options.workload_warmup_rounds.is_empty() {
+            true => options.warmup_rounds,
+            false => options.workload_warmup_rounds.values().sum(),
+        };
+        // Checked against the warmup of all workloads, see `is_suspect`
+        let outlier_warmup = match options.workload_warmup_rounds.is_empty() {
+            true => options.warmup_rounds,
+            false => 0,
+        };
+        if options.outlier_sigma.is_some() && outlier_warmup < MIN_OUTLIER_WARMUP && !options.warmup_only {
+            warnln!(
+                " -> Ignoring outlier_sigma, it needs at least {} warmup rounds of all workloads (got {})",
+                MIN_OUTLIER_WARMUP,
+                outlier_warmup,
+            );
+        }
+        for level in levels {
+            if levels.len() > 1 {
+                logln!(" -> Level {:?}", level);
+            }
+            let Some(mut result) = measure_rounds(dir, &mut stats_reader, options, |workload| on_iteration(level, workload))? else {
+                return Ok(false);
+            };
+            let timed_out = result.timed_out;
+            result.actual_version = actual_version.clone();
+            result.image = image.clone();
+            result.cold_start_ms = cold_start_ms.take();
+            result.measurement = Some(Measurement {
+                warmup_rounds,
+                rounds: options.rounds,
+                cooldown_ms: options.cooldown.as_millis() as u64,
+            });
+            results.insert(level.clone(), result);
+            // The following levels would fail at their first round
+            if timed_out {
+                return Ok(true);
+            }
+        }
+
+        // Profiling adds overhead, so the profiled round is not part of the measured rounds
+        if let (Some(profile_dir), Some((level, result)), false) = (&options.profile_dir, results.first_mut(), options.warmup_only) {
+            match external {
+                true => logln!(" -> [Profile]: Not available for external services"),
+                false => result.profile = profile_round(dir, profile_dir, || on_iteration(level, None)),
+            }
+        }
+        Ok(true)
+    };
+
+    let outcome = match external {
+        true => measure().map(|completed| (completed, None)),
+        false => compose_file(dir).map_err(BenchmarkError::InvalidBenchmark).and_then(|compose_file| {
+            if compose_file.is_none() {
+                logln!(" -> Using {} of the benchmark", CUSTOM_COMPOSE_FILE);
+            }
+            run_docker_compose(
+                dir,
+                Duration::from_secs(5),
+                compose_file,
+                &options.build,
+                !options.keep_images,
+                measure,
+            )
+        }),
+    };
+
+    for version_migrator in &version_migrations {
+        version_migrator.restore();
+    }
+
+    let (completed, shutdown) = outcome?;
+    if !completed {
+        logln!(" -> Stopped on request");
+        return Ok(None);
+    }
+    // Like the cold start, the container only shuts down once
+    if let (Some(shutdown), Some((_, result))) = (shutdown, results.first_mut()) {
+        logln!(" -> Shutdown: t = {} ms", format_number(shutdown.as_millis() as i64));
+        result.shutdown_ms = Some(shutdown.as_millis() as i64);
+    }
+    check_memory_budget(&results, options.max_memory)?;
+    Ok(Some(results))
+}
+
+/// Checks the `memory_p99` of every level against the budget. Levels without measured memory pass.
+fn check_memory_budget<K>(results: &IndexMap<K, BenchmarkResult>, max_memory: Option<i64>) -> Result<(), BenchmarkError> {
+    let Some(max_memory) = max_memory else {
+        return Ok(());
+    };
+    match results.values().filter_map(|result| result.memory_p99).max() {
+        Some(memory_p99) if memory_p99 > max_memory => Err(BenchmarkError::MemoryBudgetExceeded { memory_p99, max_memory }),
+        _ => Ok(()),
+    }
+}
+
+/// Times the first round after boot. Its result is discarded.
+/// Returns `None` if the round failed, as a retry would not be cold anymore.
+fn measure_cold_start<F>(cooldown: Duration, on_iteration: F) -> Option<i64>
+    where
+        F: Fn() -> Result<IterationResult, BenchmarkError>,
+{
+    logln!(" -> [Cold start]: Running...");
+    match on_iteration() {
+        Ok(result) => {
+            let elapsed = result.time.as_millis() as i64;
+            logln!(" -> [Cold start]: t = {} ms", format_number(elapsed));
+
+            // Same cooldown as between the rounds
+            thread::sleep(cooldown);
+            Some(elapsed)
+        }
+        Err(e) => {
+            warnln!(" -> [Cold start]: Error: {}. Not recording a cold start.", e);
+            None
+        }
+    }
+}
+
+/// The compose file the runner writes into `dir`, `None` if the benchmark provides its own (see `has_custom_compose_file`).
+pub(crate) fn compose_file(dir: &str) -> Result<Option<&'static str>, String> {
+    match has_custom_compose_file(dir)? {
+        true => Ok(None),
+        false => Ok(Some(COMPOSE_FILE)),
+    }
+}
+
+/// Whether the benchmark provides its own `docker-compose.yml` (e.g. for GPU reservations or dependent services).
+/// It is used verbatim instead of `COMPOSE_FILE`.
+///
+/// Fails if it violates the contract the runner relies on, see `check_compose_contract`.
+fn has_custom_compose_file(dir: &str) -> Result<bool, String> {
+    let path = std::path::Path::new(dir).join(CUSTOM_COMPOSE_FILE);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => return Ok(false),
+    };
+
+    // Left over by an aborted run, overwritten and removed as usual
+    if content == COMPOSE_FILE {
+        return Ok(false);
+    }
+
+    check_compose_contract(&content).map_err(|e| format!("{} does not fulfill the contract of the runner: {}", path.display(), e))?;
+    Ok(true)
+}
+
+/// A custom compose file must define the `benchmark` service with the container name `benchmark`
+/// (used by `docker stats` and `docker compose exec`) that publishes port 3000 on the host.
+pub(crate) fn check_compose_contract(content: &str) -> Result<(), String> {
+    let compose: serde_yaml::Value = serde_yaml::from_str(content).map_err(|e| format!("invalid YAML: {}", e))?;
+    let service = compose.get("services")
+        .and_then(|services| services.get(BENCHMARK_SERVICE))
+        .ok_or(format!("missing service `{}`", BENCHMARK_SERVICE))?;
+
+    if service.get("container_name").and_then(|name| name.as_str()) != Some(BENCHMARK_SERVICE) {
+        return Err(format!("service `{}` must have `container_name: {}`", BENCHMARK_SERVICE, BENCHMARK_SERVICE));
+    }
+
+    let expected_port = format!("{}:{}", BENCHMARK_PORT, BENCHMARK_PORT);
+    let publishes_port = service.get("ports")
+        .and_then(|ports| ports.as_sequence())
+        .is_some_and(|ports| ports.iter().any(|port| port.as_str() == Some(expected_port.as_str())));
+    if !publishes_port {
+        return Err(format!("service `{}` must publish port \"{}\"", BENCHMARK_SERVICE, expected_port));
+    }
+    Ok(())
+}
+
+/// Runs the warmup and measured rounds against the running service and aggregates them.
+/// `on_iteration` is called with the workload to warm up, `None` for all workloads (see `BenchmarkOptions::workload_warmup_rounds`).
+/// A round that fails with a transient error is retried (see `BenchmarkError::is_transient`), any other error ends the benchmark.
+/// If the benchmark runs out of time (see `deadline`), the rounds measured so far are returned as `BenchmarkResult::timed_out`.
+/// `None` if the user asked to stop before a round, see `tui::is_requested`.
+fn measure_rounds<F>(
+    dir: &str,
+    stats_reader: &mut Option<&mut crate::utils::docker_stats::DockerStatsReader>,
+    options: &BenchmarkOptions,
+    on_iteration: F,
+) -> Result<Option<BenchmarkResult>, BenchmarkError>
+    where
+        F: Fn(Option<&str>) -> Result<IterationResult, BenchmarkError>,
+{
+    let rounds = match options.warmup_only {
+        true => 0,
+        false => options.rounds,
+    };
+    // The workload of every warmup round, one workload after another
+    let warmups: Vec<Option<&str>> = match options.workload_warmup_rounds.is_empty() {
+        true => vec![None; options.warmup_rounds],
+        false => options.workload_warmup_rounds.iter()
+            .flat_map(|(workload, warmup_rounds)| std::iter::repeat_n(Some(workload.as_str()), *warmup_rounds))
+            .collect(),
+    };
+    let warmup_rounds = warmups.len();
     let mut execution_times: Vec<i64> = Vec::new();
+    let mut warmup_times: Vec<i64> = Vec::new();
+    let mut workload_warmup_times: IndexMap<String, Vec<i64>> = IndexMap::new();
     let mut memory_median: Vec<i64> = Vec::new();
     let mut memory_p99: Vec<i64> = Vec::new();
+    let mut self_reported_memory: Vec<i64> = Vec::new();
     let mut additional_data: Vec<IndexMap<String, AdditionalData>> = Vec::new();
+    let mut samples: Vec<RoundSample> = Vec::new();
+    let mut failed_attempts: Vec<String> = Vec::new();
+    let mut suspect_reruns: Vec<i64> = Vec::new();
 
-    run_docker_compose(
-        dir,
-        Duration::from_secs(5),
-        Some(COMPOSE_FILE),
-        || {
-            println!(" -> Running benchmark");
-            let mut fail_count = 0;
-            let mut warmup_counter = 0;
-            while execution_times.len() < rounds {
-                if warmup_counter < warmup_rounds {
-                    println!(" -> [Warmup]: Running...");
-                } else {
-                    println!(" -> [Run #{}]: Running...", execution_times.len() + 1);
-                }
+    logln!(" -> Running benchmark");
+    eta::rounds_planned(warmup_rounds + rounds);
+    let mut fail_count = 0;
+    let mut warmup_counter = 0;
+    let mut timed_out = None;
+    while warmup_counter < warmup_rounds || execution_times.len() < rounds {
+        if tui::is_requested() {
+            return Ok(None);
+        }
+        if let Err(e) = deadline::check() {
+            timed_out = Some(e);
+            break;
+        }
+        let workload = warmups.get(warmup_counter).copied().flatten();
+        if warmup_counter < warmup_rounds {
+            logln!(" -> [Warmup{}]: Running...", format_workload(workload));
+        } else {
+            logln!(" -> [Run #{}]: Running...", execution_times.len() + 1);
+        }
+
+        let started_at_ms = environment::now_unix_ms();
+        if let Some(stats_reader) = stats_reader.as_deref_mut() {
+            stats_reader.start();
+        }
 
-                let start = std::time::Instant::now();
-                stats_reader.start();
-
-                let result = match on_iteration() {
-                    Ok(result) => result,
-                    Err(e) => {
-                        println!(" -> Error: {}", e);
-                        fail_count += 1;
-                        if fail_count > 10 {
-                            panic!("Too many errors");
-                        }
-                        thread::sleep(Duration::from_secs(1));
-                        println!("Retrying...");
-                        continue;
+        let mut result = match on_iteration(workload) {
+            Ok(result) => result,
+            // E.g. the container was killed as the benchmark ran out of time, the check above ends it
+            Err(e) if deadline::exceeded().is_some() => {
+                errorln!(" -> Error: {}", e);
+                continue;
+            }
+            Err(e) if !e.is_transient() => {
+                errorln!(" -> Error: {} (not retried)", e);
+                return Err(e);
+            }
+            Err(e) => {
+                errorln!(" -> Error: {}", e);
+                // A retry cannot reach a container that stopped
+                if stats_reader.is_some() {
+                    if let Err(stopped) = container_pid(BENCHMARK_SERVICE) {
+                        return Err(BenchmarkError::ContainerCrashed(format!("{} (last error: {})", stopped, e)));
                     }
-                };
-
-                stats_reader.stop();
-
-                let elapsed = start.elapsed().as_millis() as i64;
-                let memory_usage = stats_reader.get_memory_usage();
-
-                if warmup_counter < warmup_rounds {
-                    warmup_counter += 1;
-                    println!(
-                        " -> [Warmup]: t = {} ms, RAM = {}, {:?}, {:?}",
-                        elapsed,
-                        memory_usage.median.bytes_to_string(),
-                        result.additional_data,
-                        result.debugging_data,
-                    );
-                    continue;
                 }
+                failed_attempts.push(e.to_string());
+                fail_count += 1;
+                if fail_count > 10 {
+                    return Err(BenchmarkError::TooManyFailures { attempts: fail_count, last_error: e.to_string() });
+                }
+                thread::sleep(Duration::from_secs(1));
+                logln!("Retrying...");
+                continue;
+            }
+        };
+
+        let elapsed = result.time.as_millis() as i64;
+        insert_response_size(&mut result);
+        if let (Some(validate_response), Some(response), true) = (options.validate_response, &result.response, samples.is_empty()) {
+            check_response(validate_response, response, "first round")?;
+        }
+        let memory_usage = stats_reader.as_deref_mut().and_then(|stats_reader| {
+            stats_reader.stop();
+            stats_reader.get_memory_usage()
+        });
+        let round_memory_median = memory_usage.as_ref().map(|usage| usage.median);
+        let round_memory_p99 = memory_usage.as_ref().map(|usage| usage.p99);
 
-                println!(
-                    " -> [Run #{}]: t = {} ms, RAM = {}, {:?}, {:?}",
+        if warmup_counter < warmup_rounds {
+            warmup_counter += 1;
+            match workload {
+                Some(workload) => workload_warmup_times.entry(workload.to_string()).or_default().push(elapsed),
+                None => warmup_times.push(elapsed),
+            }
+            samples.push(RoundSample {
+                warmup: true,
+                workload: workload.map(str::to_string),
+                started_at_ms,
+                time: elapsed,
+                memory_median: round_memory_median,
+                memory_p99: round_memory_p99,
+                self_reported_memory: result.self_reported_memory,
+                additional_data: result.additional_data.clone(),
+                failed_attempts: std::mem::take(&mut failed_attempts),
+                suspect_reruns: Vec::new(),
+                suspect: false,
+                running_median: None,
+            });
+            emit_iteration_completed(dir, &samples);
+            logln!(
+                " -> [Warmup{}]: t = {} ms, RAM = {}{}, {:?}, {:?}",
+                format_workload(workload),
+                format_number(elapsed),
+                round_memory_median.bytes_to_string(),
+                format_self_reported_memory(result.self_reported_memory),
+                result.additional_data,
+                result.debugging_data,
+            );
+            reset_state(options)?;
+            continue;
+        }
+
+        // Only the warmup of all workloads tells how long a round of all of them takes
+        let suspect = is_suspect(elapsed, &warmup_times, options.outlier_sigma);
+        if suspect {
+            // Every round has its own re-runs, they are taken with the sample of the round
+            if suspect_reruns.len() < MAX_SUSPECT_RERUNS {
+                suspect_reruns.push(elapsed);
+                warnln!(
+                    " -> [Run #{}]: t = {} ms is outside of the plausible range established by warmup [{}] (e.g. system suspend). Re-running...",
                     execution_times.len() + 1,
-                    elapsed,
-                    memory_usage.median.bytes_to_string(),
-                    result.additional_data,
-                    result.debugging_data,
+                    format_number(elapsed),
+                    format_times(&warmup_times),
                 );
-                execution_times.push(elapsed);
-                memory_median.push(memory_usage.median);
-                memory_p99.push(memory_usage.p99);
-                additional_data.push(result.additional_data);
-
-                // Wait for 2 seconds to let the container cool down
-                thread::sleep(Duration::from_secs(2));
+                // The re-run starts like any other round
+                reset_state(options)?;
+                thread::sleep(options.cooldown);
+                continue;
             }
-        },
-    );
+            warnln!(" -> [Run #{}]: t = {} ms is suspect but was re-run too often. Recording anyway.", execution_times.len() + 1, format_number(elapsed));
+        }
 
-    for version_migrator in &version_migrations {
-        version_migrator.restore();
+        execution_times.push(elapsed);
+        let running_median = options.running_median.then(|| median(&execution_times));
+        logln!(
+            " -> [Run #{}]: t = {} ms, RAM = {}{}, {:?}, {:?}{}",
+            execution_times.len(),
+            format_number(elapsed),
+            round_memory_median.bytes_to_string(),
+            format_self_reported_memory(result.self_reported_memory),
+            result.additional_data,
+            result.debugging_data,
+            running_median.map(|median| format!(", median so far = {} ms", format_number(median))).unwrap_or_default(),
+        );
+        samples.push(RoundSample {
+            warmup: false,
+            workload: None,
+            started_at_ms,
+            time: elapsed,
+            memory_median: round_memory_median,
+            memory_p99: round_memory_p99,
+            self_reported_memory: result.self_reported_memory,
+            additional_data: result.additional_data.clone(),
+            failed_attempts: std::mem::take(&mut failed_attempts),
+            suspect_reruns: std::mem::take(&mut suspect_reruns),
+            suspect,
+            running_median,
+        });
+        emit_iteration_completed(dir, &samples);
+        if let Some(memory_usage) = memory_usage {
+            memory_median.push(memory_usage.median);
+            memory_p99.push(memory_usage.p99);
+        }
+        if let Some(memory) = result.self_reported_memory {
+            self_reported_memory.push(memory);
+        }
+        additional_data.push(result.additional_data);
+
+        reset_state(options)?;
+        // Let the container cool down
+        thread::sleep(options.cooldown);
     }
 
+    if let Some(e) = timed_out.clone() {
+        errorln!(" -> Error: {}", e);
+        if execution_times.is_empty() {
+            return Err(e);
+        }
+        warnln!(" -> Keeping the {} measured round(s) as a partial result", execution_times.len());
+    }
+    if execution_times.is_empty() {
+        logln!(" -> Warmup only, no measured rounds (warmup: [{}] ms)", format_times(&warmup_times));
+        return Ok(Some(BenchmarkResult { warmup_times, workload_warmup_times, samples, ..Default::default() }));
+    }
+
+    let running_median: Vec<i64> = samples.iter().filter_map(|sample| sample.running_median).collect();
+
     // Calculate medians
+    let time_median = median(&execution_times);
     execution_times.sort();
-    let time_median = execution_times[execution_times.len() / 2];
-    let additional_data_median = {
-        // find total unique keys
-        let mut keys: Vec<String> = Vec::new();
-        for data in &additional_data {
-            for key in data.keys() {
-                if !keys.contains(key) {
-                    keys.push(key.clone());
-                }
-            }
+    let additional_data_aggregated = aggregate_additional_data(&additional_data, &options.aggregation);
+
+    let result = BenchmarkResult {
+        time_median,
+        time_p1: Some(percentile::p1(&execution_times)),
+        memory_median: if memory_median.is_empty() { None } else { Some(percentile::p50(&memory_median)) },
+        memory_p99: if memory_p99.is_empty() { None } else { Some(percentile::p99(&memory_p99)) },
+        self_reported_memory_median: if self_reported_memory.is_empty() { None } else { Some(percentile::p50(&self_reported_memory)) },
+        additional_data: additional_data_aggregated,
+        cold_start_ms: None,
+        shutdown_ms: None,
+        profile: None,
+        actual_version: None,
+        image: None,
+        manifest: None,
+        tags: IndexMap::new(),
+        running_median,
+        warmup_times,
+        workload_warmup_times,
+        measurement: None,
+        timed_out: timed_out.is_some(),
+        samples,
+    };
+
+    logln!(" -> Summary:");
+    for line in result.format_summary().lines() {
+        logln!("    {}", line);
+    }
+    Ok(Some(result))
+}
+
+/// Example: ` shells`, empty for all workloads
+fn format_workload(workload: Option<&str>) -> String {
+    workload.map(|workload| format!(" {}", workload)).unwrap_or_default()
+}
+
+/// Example: `9,000, 2,100`
+fn format_times(times: &[i64]) -> String {
+    times.iter().map(|time| format_number(*time)).collect::<Vec<String>>().join(", ")
+}
+
+/// The median as used for `time_median`: the upper one of an even number of values.
+fn median(values: &[i64]) -> i64 {
+    let mut sorted = values.to_vec();
+    sorted.sort();
+    sorted[sorted.len() / 2]
+}
+
+/// Splits `benchmark/<category>/<language>/<benchmark>` into the category and `<language>/<benchmark>`.
+pub fn split_benchmark_dir(full_dir: &str) -> (String, String) {
+    let mut parts = full_dir.trim_start_matches("benchmark/").splitn(2, '/');
+    let category = parts.next().unwrap_or_default().to_string();
+    let benchmark = parts.next().unwrap_or_default().to_string();
+    (category, benchmark)
+}
+
+/// Resolves the directory of a single benchmark, relative to `working_dir` or absolute, to `benchmark/<category>/<language>/<benchmark>`.
+/// The benchmarks run from the root of the repository, so the directory has to be inside its `benchmark` directory.
+pub fn resolve_benchmark_dir(path: &Path, working_dir: &Path) -> Result<String, String> {
+    let absolute = working_dir.join(path).canonicalize().map_err(|e| format!("{} does not exist ({})", path.display(), e))?;
+    let root = working_dir.canonicalize().map_err(|e| format!("{} does not exist ({})", working_dir.display(), e))?;
+    let relative = absolute.strip_prefix(&root)
+        .map_err(|_| format!("{} is not inside {}, run sharkbench from the root of the repository", path.display(), root.display()))?;
+
+    let parts: Vec<&str> = relative.iter().filter_map(|part| part.to_str()).collect();
+    match parts.as_slice() {
+        ["benchmark", category @ ("computation" | "web"), language, benchmark] if *benchmark != crate::utils::copy_files::COMMON_DIR => {
+            Ok(format!("benchmark/{}/{}/{}", category, language, benchmark))
         }
+        _ => Err(format!(
+            "{} is not a benchmark directory, expected benchmark/<computation|web>/<language>/<benchmark>",
+            relative.display(),
+        )),
+    }
+}
+
+/// Emits the last sample for `--stream-json` and reports the progress.
+fn emit_iteration_completed(dir: &str, samples: &[RoundSample]) {
+    eta::round_completed();
+    let (category, benchmark) = split_benchmark_dir(dir);
+    if let Some(sample) = samples.last() {
+        stream::emit(&Event::IterationCompleted { category: &category, benchmark: &benchmark, round: samples.len(), sample });
+    }
+}
+
+/// Runs one round while `perf record` is attached to the container process.
+/// Returns the path of the recording, or `None` (with a warning) if profiling failed.
+fn profile_round<F>(dir: &str, profile_dir: &Path, on_iteration: F) -> Option<String>
+    where
+        F: Fn() -> Result<IterationResult, BenchmarkError>,
+{
+    let pid = match container_pid(BENCHMARK_SERVICE) {
+        Ok(pid) => pid,
+        Err(e) => {
+            warnln!(" -> [Profile]: Warning: {}", e);
+            return None;
+        }
+    };
+
+    let (category, benchmark) = split_benchmark_dir(dir);
+    let path = profile_dir.join(category).join(format!("{}_{}.perf.data", benchmark.replace('/', "_"), environment::now_unix_ms()));
+    if let Err(e) = std::fs::create_dir_all(path.parent().unwrap()) {
+        warnln!(" -> [Profile]: Warning: Could not create {}: {}", profile_dir.display(), e);
+        return None;
+    }
+
+    logln!(" -> [Profile]: Running with perf attached to pid {}...", pid);
+    let mut perf = match Command::new("perf")
+        .args(["record", "-F", PROFILE_FREQUENCY, "-g", "-p", &pid.to_string(), "-o"])
+        .arg(&path)
+        .stdout(log::child_stdout())
+        .stderr(log::child_stderr())
+        .spawn() {
+        Ok(perf) => perf,
+        Err(e) => {
+            warnln!(" -> [Profile]: Warning: Could not start perf: {}", e);
+            return None;
+        }
+    };
+    // perf needs a moment to attach
+    thread::sleep(Duration::from_millis(500));
+
+    let outcome = on_iteration();
+
+    // perf only writes a complete recording when interrupted
+    let interrupted = Command::new("kill").args(["-INT", &perf.id().to_string()]).status().is_ok_and(|status| status.success());
+    if !interrupted {
+        let _ = perf.kill();
+    }
+    let finished = perf.wait().is_ok_and(|status| status.success() || interrupted);
+
+    match (outcome, finished && path.exists()) {
+        (Ok(result), true) => {
+            logln!(" -> [Profile]: t = {} ms (not measured), written {}", format_number(result.time.as_millis() as i64), path.display());
+            Some(path.display().to_string())
+        }
+        (Err(e), _) => {
+            warnln!(" -> [Profile]: Error: {}. Discarding the recording.", e);
+            None
+        }
+        (Ok(_), false) => {
+            warnln!(" -> [Profile]: Warning: perf did not write a recording (see its output above)");
+            None
+        }
+    }
+}
 
-        // for each key, find the median value
-        let mut map: IndexMap<String, AdditionalData> = IndexMap::new();
+/// Executes the version command in the container and returns the version within its output.
+fn query_actual_version(dir: &str, command: &str) -> Option<String> {
+    match exec_in_container(dir, command) {
+        Ok(output) => {
+            let version = parse_version_output(&output);
+            logln!(" -> Actual version: {:?} (`{}`)", version, command);
+            version
+        }
+        Err(e) => {
+            warnln!(" -> Warning: Could not determine the actual version: {}", e);
+            None
+        }
+    }
+}
+
+/// Executes the setup command in the container, fails the benchmark with its output if it fails.
+fn run_setup(dir: &str, command: &str) -> Result<(), BenchmarkError> {
+    let start = std::time::Instant::now();
+    exec_in_container(dir, command).map_err(BenchmarkError::SetupFailed)?;
+    logln!(" -> [Setup]: t = {} ms (`{}`)", format_number(start.elapsed().as_millis() as i64), command);
+    Ok(())
+}
+
+/// Returns the first version-like token of the output, e.g. `v20.11.0` -> `20.11.0`.
+/// Falls back to the trimmed first line.
+fn parse_version_output(output: &str) -> Option<String> {
+    let version_regex = Regex::new(r"\d+(\.\d+)+").unwrap();
+    match version_regex.find(output) {
+        Some(version) => Some(version.as_str().to_string()),
+        None => output.lines().next().map(|line| line.trim().to_string()).filter(|line| !line.is_empty()),
+    }
+}
+
+/// Warns loudly if the actual version of the result does not match the requested version.
+/// The actual version may be more specific, e.g. `1.74` matches `1.74.1`.
+pub fn check_actual_version(requested: &str, result: &BenchmarkResult) {
+    if let Some(actual) = &result.actual_version {
+        if !version_matches(requested, actual) {
+            logln!();
+            warnln!(" -> !!! WARNING: Requested version {} but the container runs {} !!!", requested, actual);
+            logln!();
+        }
+    }
+}
+
+fn version_matches(requested: &str, actual: &str) -> bool {
+    let requested = requested.trim_start_matches('v');
+    let actual = actual.trim_start_matches('v');
+    actual == requested || actual.starts_with(&format!("{}.", requested))
+}
 
-        for key in keys {
-            let mut values: Vec<AdditionalData> = Vec::new();
-            for data in &additional_data {
-                if let Some(value) = data.get(&key) {
-                    values.push(value.clone());
+fn format_self_reported_memory(memory: Option<i64>) -> String {
+    match memory {
+        Some(memory) => format!(" (self-reported: {})", memory.bytes_to_string()),
+        None => String::new(),
+    }
+}
+
+/// Returns the first successful response of the health check.
+fn wait_until_healthy<H>(health_check: H) -> Result<Response, BenchmarkError>
+    where H: Fn() -> Result<Response, BenchmarkError>
+{
+    let start = std::time::Instant::now();
+    loop {
+        match health_check() {
+            Ok(response) => return Ok(response),
+            Err(e) => {
+                if start.elapsed() > HEALTH_CHECK_TIMEOUT {
+                    return Err(BenchmarkError::HealthCheckTimeout { timeout: HEALTH_CHECK_TIMEOUT, last_error: e.to_string() });
                 }
+                logln!(" -> Waiting for health check: {}", e);
+                thread::sleep(Duration::from_millis(500));
             }
-            values.sort_by(|a, b| {
-                match (a, b) {
-                    (AdditionalData::Int(a), AdditionalData::Int(b)) => a.cmp(b),
-                    // (AdditionalData::Float(a), AdditionalData::Float(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
-                    // _ => panic!("Invalid type"),
-                }
-            });
-            map.insert(key, values[values.len() / 2].clone());
         }
+    }
+}
+
+/// Resets the state of the service if the benchmark has a reset endpoint.
+/// Fails the benchmark if it fails, as the following rounds would not be comparable.
+fn reset_state(options: &BenchmarkOptions) -> Result<(), BenchmarkError> {
+    if let Some(url) = &options.reset_url {
+        let start = std::time::Instant::now();
+        if let Err(e) = reset::post(url) {
+            warnln!(" -> [Reset]: Failed to reset the state of the benchmark");
+            return Err(e);
+        }
+        logln!(" -> [Reset]: t = {} ms", format_number(start.elapsed().as_millis() as i64));
+    }
+    Ok(())
+}
 
-        map
-    };
+/// Fails the benchmark if the response violates the contract of the benchmark.
+/// A retry would not help, as the service is up but answers with something else (e.g. an error page).
+fn check_response(validate_response: ResponseValidator, response: &Response, stage: &str) -> Result<(), BenchmarkError> {
+    contract_violation(validate_response, response)
+        .map_err(|e| BenchmarkError::InvalidResponse(format!("response of the {} does not match the contract of the benchmark: {}", stage, e)))
+}
 
-    memory_median.sort();
-    memory_p99.sort();
-    return BenchmarkResult {
-        time_median,
-        memory_median: percentile::p50(&memory_median),
-        memory_p99: percentile::p99(&memory_p99),
-        additional_data: additional_data_median,
-    };
+/// Adds the status and the (cut off) body to the reason of a violation.
+fn contract_violation(validate_response: ResponseValidator, response: &Response) -> Result<(), String> {
+    validate_response(response).map_err(|reason| {
+        let mut body: String = response.body.chars().take(MAX_RESPONSE_LENGTH).collect();
+        if response.body.chars().count() > MAX_RESPONSE_LENGTH {
+            body.push_str("...");
+        }
+        match response.status {
+            Some(status) => format!("{} (status {}, body: {:?})", reason, status, body),
+            None => format!("{} (body: {:?})", reason, body),
+        }
+    })
 }
 
-trait SizeFormat {
-    fn bytes_to_string(&self) -> String;
+/// Returns true if the elapsed time is implausible compared to the warmup rounds.
+/// Without warmup rounds, every time is plausible.
+///
+/// With `outlier_sigma` and at least `MIN_OUTLIER_WARMUP` warmup rounds, times further than `outlier_sigma` standard deviations
+/// (at least `MIN_RELATIVE_STDDEV` of the mean and `MIN_STDDEV_MS`) from the mean of the warmup rounds are implausible,
+/// so the range adapts to the variance of the benchmark. Otherwise, the range is fixed by `SUSPECT_FACTOR`,
+/// with a warmup round of 0 ms counted as 1 ms.
+fn is_suspect(elapsed: i64, warmup_times: &[i64], outlier_sigma: Option<f64>) -> bool {
+    if let Some(sigma) = outlier_sigma.filter(|_| warmup_times.len() >= MIN_OUTLIER_WARMUP) {
+        let mean = warmup_times.iter().sum::<i64>() as f64 / warmup_times.len() as f64;
+        let variance = warmup_times.iter().map(|time| (*time as f64 - mean).powi(2)).sum::<f64>() / (warmup_times.len() - 1) as f64;
+        let stddev = variance.sqrt().max(mean * MIN_RELATIVE_STDDEV).max(MIN_STDDEV_MS);
+        return (elapsed as f64 - mean).abs() > sigma * stddev;
+    }
+    let (min, max) = match (warmup_times.iter().min(), warmup_times.iter().max()) {
+        (Some(min), Some(max)) => (*min, (*max).max(1)),
+        _ => return false,
+    };
+    elapsed > max * SUSPECT_FACTOR || elapsed * SUSPECT_FACTOR < min
 }
 
-impl SizeFormat for i64 {
-    fn bytes_to_string(&self) -> String {
-        let kb = *self as f64 / 1024.0;
-        if kb < 1024.0 {
-            return format!("{:.2} KB", kb);
+#[cfg(test)]
+mod tests {
+    use rand::seq::SliceRandom;
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{self, AtomicUsize};
+    use std::sync::{Arc, Mutex};
+    use crate::utils::test_dir::TestDir;
+
+    fn expect_ok(response: &Response) -> Result<(), String> {
+        match response.body == "ok" {
+            true => Ok(()),
+            false => Err("expected `ok`".to_string()),
         }
-        let mb = kb / 1024.0;
-        if mb < 1024.0 {
-            return format!("{:.2} MB", mb);
+    }
+
+    fn options(warmup_rounds: usize, rounds: usize) -> BenchmarkOptions {
+        BenchmarkOptions {
+            warmup_rounds,
+            workload_warmup_rounds: IndexMap::new(),
+            rounds,
+            cooldown: Duration::ZERO,
+            version_command: None,
+            setup_command: None,
+            cold_start: false,
+            profile_dir: None,
+            aggregation: IndexMap::new(),
+            reset_url: None,
+            validate_response: None,
+            running_median: false,
+            warmup_only: false,
+            keep_images: false,
+            build: BuildOptions::default(),
+            max_memory: None,
+            outlier_sigma: None,
         }
-        let gb = mb / 1024.0;
-        format!("{:.2} GB", gb)
+    }
+
+    fn round(time_ms: u64) -> IterationResult {
+        IterationResult {
+            time: Duration::from_millis(time_ms),
+            additional_data: IndexMap::new(),
+            debugging_data: IndexMap::new(),
+            self_reported_memory: None,
+            response: None,
+        }
+    }
+
+    /// Answers every request with `200 OK` and counts them.
+    fn serve_resets() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/reset", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                    line.clear();
+                }
+                counter.fetch_add(1, atomic::Ordering::SeqCst);
+                let mut writer = stream;
+                writer.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    #[test]
+    fn should_fail_on_first_wrong_response() {
+        let attempts = AtomicUsize::new(0);
+        let result = measure_rounds("", &mut None, &options(1, 1), |_| {
+            attempts.fetch_add(1, atomic::Ordering::SeqCst);
+            Err(BenchmarkError::InvalidResponse("42 (expected: 43)".to_string()))
+        });
+
+        assert_eq!(result.err(), Some(BenchmarkError::InvalidResponse("42 (expected: 43)".to_string())));
+        assert_eq!(attempts.load(atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn should_reset_before_rerunning_suspect_rounds() {
+        let (url, resets) = serve_resets();
+        let options = BenchmarkOptions { reset_url: Some(url), ..options(2, 1) };
+        // The third round is suspect (more than `SUSPECT_FACTOR` times the warmup) and re-run
+        let times = Mutex::new(vec![100, 100, 5000, 100].into_iter());
+
+        let result = measure_rounds("", &mut None, &options, |_| Ok(round(times.lock().unwrap().next().unwrap()))).unwrap().unwrap();
+        assert_eq!(result.samples.last().unwrap().suspect_reruns, vec![5000]);
+        // After both warmup rounds, the suspect round and the measured round
+        assert_eq!(resets.load(atomic::Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn should_describe_contract_violations() {
+        let response = |status, body: &str| Response { status, body: body.to_string(), size: body.len(), phases: IndexMap::new() };
+        assert_eq!(contract_violation(expect_ok, &response(Some(200), "ok")), Ok(()));
+        assert_eq!(
+            contract_violation(expect_ok, &response(Some(200), "<h1>Error</h1>")),
+            Err("expected `ok` (status 200, body: \"<h1>Error</h1>\")".to_string()),
+        );
+        assert_eq!(
+            contract_violation(expect_ok, &response(None, &"x".repeat(250))),
+            Err(format!("expected `ok` (body: \"{}...\")", "x".repeat(200))),
+        );
+    }
+
+    #[test]
+    fn should_insert_response_size() {
+        let round = |response: Option<&str>| IterationResult {
+            time: Duration::from_millis(100),
+            additional_data: IndexMap::from([("rps_median".to_string(), AdditionalData::Int(100))]),
+            debugging_data: IndexMap::new(),
+            self_reported_memory: None,
+            response: response.map(|body| Response { status: Some(200), body: body.to_string(), size: 31, phases: IndexMap::new() }),
+        };
+
+        // The compressed size as received, not the decoded body
+        let mut result = round(Some("{\"ok\":\"ü\"}"));
+        insert_response_size(&mut result);
+        assert_eq!(result.additional_data[RESPONSE_SIZE_KEY], AdditionalData::Int(31));
+        assert_eq!(Aggregation::default_for(RESPONSE_SIZE_KEY), Aggregation::Median);
+
+        let mut result = round(None);
+        insert_response_size(&mut result);
+        assert!(!result.additional_data.contains_key(RESPONSE_SIZE_KEY));
+    }
+
+    #[test]
+    fn should_format_aligned_summary() {
+        let sample = |warmup, time| RoundSample { warmup, time, ..Default::default() };
+        let mut additional_data = IndexMap::new();
+        additional_data.insert("rps_median".to_string(), AdditionalData::Int(12345));
+        let result = BenchmarkResult {
+            time_median: 1500,
+            time_p1: Some(1500),
+            memory_median: Some(1024 * 1024),
+            memory_p99: None,
+            additional_data,
+            cold_start_ms: Some(250),
+            shutdown_ms: Some(1200),
+            samples: vec![sample(true, 9000), sample(false, 1600), sample(false, 1500), sample(false, 1400)],
+            ..Default::default()
+        };
+
+        assert_eq!(result.format_summary(), "\
+Time median    1,500 ms
+Time p1        1,500 ms
+Time p99       1,500 ms
+Cold start       250 ms
+Shutdown       1,200 ms
+Memory median   1.00 MB
+Memory p99            -
+rps_median        12345
+");
+    }
+
+    #[test]
+    fn should_calculate_running_median() {
+        let medians: Vec<i64> = (1..=4).map(|n| median(&[1600, 1500, 1400, 1450][..n])).collect();
+        assert_eq!(medians, vec![1600, 1600, 1500, 1500]);
+
+        let result = BenchmarkResult { time_median: 1500, running_median: medians, ..Default::default() };
+        assert!(result.format_summary().ends_with("Running median  1,600 1,600 1,500 1,500 ms\n"));
+    }
+
+    #[test]
+    fn should_resolve_benchmark_dir() {
+        let root = TestDir::new("resolve");
+        std::fs::create_dir_all(root.join("benchmark/computation/rust/rust-1.74")).unwrap();
+        std::fs::create_dir_all(root.join("benchmark/computation/rust/_common")).unwrap();
+
+        let expected = Ok("benchmark/computation/rust/rust-1.74".to_string());
+        assert_eq!(resolve_benchmark_dir(Path::new("./benchmark/computation/rust/rust-1.74/"), &root), expected);
+        assert_eq!(resolve_benchmark_dir(&root.join("benchmark/computation/rust/../rust/rust-1.74"), &root), expected);
+
+        let error = resolve_benchmark_dir(Path::new("benchmark/computation/rust/rust-1.75"), &root).unwrap_err();
+        assert!(error.starts_with("benchmark/computation/rust/rust-1.75 does not exist"), "{}", error);
+        assert_eq!(
+            resolve_benchmark_dir(Path::new("benchmark/computation/rust"), &root),
+            Err("benchmark/computation/rust is not a benchmark directory, expected benchmark/<computation|web>/<language>/<benchmark>".to_string()),
+        );
+        assert!(resolve_benchmark_dir(Path::new("benchmark/computation/rust/_common"), &root).is_err());
+        assert!(resolve_benchmark_dir(Path::new(".."), &root).unwrap_err().contains("is not inside"));
+    }
+
+    #[test]
+    fn should_check_memory_budget() {
+        let results: IndexMap<usize, BenchmarkResult> = [
+            (1, BenchmarkResult { memory_p99: Some(200 * 1024 * 1024), ..Default::default() }),
+            (64, BenchmarkResult { memory_p99: Some(300 * 1024 * 1024), ..Default::default() }),
+            (128, BenchmarkResult::default()),
+        ].into_iter().collect();
+
+        assert_eq!(check_memory_budget(&results, None), Ok(()));
+        assert_eq!(check_memory_budget(&results, Some(300 * 1024 * 1024)), Ok(()));
+        assert_eq!(
+            check_memory_budget(&results, Some(256 * 1024 * 1024)),
+            Err(BenchmarkError::MemoryBudgetExceeded { memory_p99: 300 * 1024 * 1024, max_memory: 256 * 1024 * 1024 }),
+        );
+    }
+
+    #[test]
+    fn should_report_warmup_times() {
+        let result = BenchmarkResult { time_median: 1500, warmup_times: vec![9000, 2100], ..Default::default() };
+        assert!(result.format_summary().contains("Warmup         9,000 2,100 ms\n"));
+
+        let workload_warmup_times = IndexMap::from([("shells".to_string(), vec![900, 450])]);
+        let result = BenchmarkResult { time_median: 1500, workload_warmup_times, ..Default::default() };
+        assert!(result.format_summary().contains("Warmup shells  900 450 ms\n"));
+    }
+
+    #[test]
+    fn should_accept_built_in_compose_file() {
+        assert_eq!(check_compose_contract(COMPOSE_FILE), Ok(()));
+    }
+
+    #[test]
+    fn should_check_contract_of_custom_compose_file() {
+        let with_dependency = "services:\n  benchmark:\n    build: .\n    container_name: benchmark\n    ports:\n      - \"3000:3000\"\n    depends_on: [db]\n  db:\n    image: postgres\n";
+        assert_eq!(check_compose_contract(with_dependency), Ok(()));
+
+        assert_eq!(
+            check_compose_contract("services:\n  app:\n    build: .\n"),
+            Err("missing service `benchmark`".to_string()),
+        );
+        assert_eq!(
+            check_compose_contract("services:\n  benchmark:\n    build: .\n    ports: [\"3000:3000\"]\n"),
+            Err("service `benchmark` must have `container_name: benchmark`".to_string()),
+        );
+        assert_eq!(
+            check_compose_contract("services:\n  benchmark:\n    container_name: benchmark\n    ports: [\"8080:3000\"]\n"),
+            Err("service `benchmark` must publish port \"3000:3000\"".to_string()),
+        );
+    }
+
+    #[test]
+    fn should_parse_version_output() {
+        assert_eq!(parse_version_output("v20.11.0\n"), Some("20.11.0".to_string()));
+        assert_eq!(parse_version_output("openjdk 17.0.2 2022-01-18\nOpenJDK Runtime"), Some("17.0.2".to_string()));
+        assert_eq!(parse_version_output("nightly\n"), Some("nightly".to_string()));
+        assert_eq!(parse_version_output(""), None);
+    }
+
+    #[test]
+    fn should_match_more_specific_versions() {
+        assert!(version_matches("1.74", "1.74.1"));
+        assert!(version_matches("20", "v20.11.0"));
+        assert!(version_matches("3.12", "3.12"));
+        assert!(!version_matches("1.74", "1.75.0"));
+        assert!(!version_matches("1.7", "1.74.0"));
+    }
+
+    mod is_suspect {
+        use super::*;
+
+        #[test]
+        fn should_accept_without_warmup() {
+            assert!(!is_suspect(1, &[], None));
+            assert!(!is_suspect(1000000, &[], None));
+            assert!(!is_suspect(1000000, &[], Some(3.0)));
+        }
+
+        #[test]
+        fn should_accept_within_range() {
+            assert!(!is_suspect(100, &[100, 120], None));
+            assert!(!is_suspect(11, &[100, 120], None));
+            assert!(!is_suspect(1200, &[100, 120], None));
+        }
+
+        #[test]
+        fn should_flag_outside_of_range() {
+            assert!(is_suspect(9, &[100, 120], None));
+            assert!(is_suspect(1201, &[100, 120], None));
+        }
+
+        #[test]
+        fn should_count_warmup_of_0_ms_as_1_ms() {
+            assert!(!is_suspect(10, &[0], None));
+            assert!(is_suspect(11, &[0], None));
+        }
+
+        #[test]
+        fn should_flag_outside_of_sigma_range_of_warmup() {
+            // Mean 100, standard deviation 10
+            let warmup_times = [90, 100, 110];
+            assert!(!is_suspect(125, &warmup_times, Some(3.0)));
+            assert!(!is_suspect(71, &warmup_times, Some(3.0)));
+            assert!(is_suspect(131, &warmup_times, Some(3.0)));
+            assert!(is_suspect(69, &warmup_times, Some(3.0)));
+            assert!(is_suspect(125, &warmup_times, Some(2.0)));
+
+            // At least 5% of the mean
+            assert!(!is_suspect(114, &[100, 100], Some(3.0)));
+            assert!(is_suspect(116, &[100, 100], Some(3.0)));
+
+            // At least 1 ms
+            assert!(!is_suspect(3, &[0, 0], Some(3.0)));
+            assert!(is_suspect(4, &[0, 0], Some(3.0)));
+
+            // A single warmup round has no spread
+            assert!(!is_suspect(500, &[100], Some(3.0)));
+            assert!(is_suspect(1001, &[100], Some(3.0)));
+        }
+    }
+
+    #[test]
+    fn should_aggregate_per_key() {
+        let round = |rps: i32, pauses: i32, pause_time: i32| IndexMap::from([
+            ("rps_median".to_string(), AdditionalData::Int(rps)),
+            (gc_metrics::PAUSE_COUNT_KEY.to_string(), AdditionalData::Int(pauses)),
+            (gc_metrics::PAUSE_TIME_KEY.to_string(), AdditionalData::Int(pause_time)),
+        ]);
+        // The third round hit a full GC
+        let rounds = vec![round(1000, 4, 10), round(900, 5, 12), round(1100, 12, 95)];
+
+        // Timings stay median, GC pauses are averaged per round
+        assert_eq!(aggregate_additional_data(&rounds, &IndexMap::new()), IndexMap::from([
+            ("rps_median".to_string(), AdditionalData::Int(1000)),
+            ("gc_pause_count".to_string(), AdditionalData::Int(7)),
+            ("gc_pause_time_ms".to_string(), AdditionalData::Int(39)),
+        ]));
+
+        // Totals over all rounds
+        let sum = IndexMap::from([
+            ("gc_pause_count".to_string(), Aggregation::Sum),
+            ("gc_pause_time_ms".to_string(), Aggregation::Sum),
+        ]);
+        assert_eq!(aggregate_additional_data(&rounds, &sum), IndexMap::from([
+            ("rps_median".to_string(), AdditionalData::Int(1000)),
+            ("gc_pause_count".to_string(), AdditionalData::Int(21)),
+            ("gc_pause_time_ms".to_string(), AdditionalData::Int(117)),
+        ]));
+    }
+
+    #[test]
+    fn should_keep_duration_kind() {
+        let round = |setup: i64| IndexMap::from([("phase_setup_us".to_string(), AdditionalData::Duration(setup))]);
+        let rounds = vec![round(1500), round(3_000_000_000_000), round(1400)];
+        assert_eq!(aggregate_additional_data(&rounds, &IndexMap::new())["phase_setup_us"], AdditionalData::Duration(1500));
+
+        let max = IndexMap::from([("phase_setup_us".to_string(), Aggregation::Max)]);
+        assert_eq!(aggregate_additional_data(&rounds, &max)["phase_setup_us"], AdditionalData::Duration(3_000_000_000_000));
+        assert_eq!(serde_json::to_string(&AdditionalData::Duration(1500)).unwrap(), r#"{"type":"duration","value":1500}"#);
+    }
+
+    #[test]
+    fn should_not_depend_on_order_of_rounds() {
+        let values = [
+            AdditionalData::Int(7),
+            AdditionalData::Duration(3),
+            AdditionalData::Int(3),
+            AdditionalData::Duration(i64::MAX),
+            AdditionalData::Int(-2),
+        ];
+        let mut rounds: Vec<IndexMap<String, AdditionalData>> = values.iter()
+            .map(|value| IndexMap::from([("key".to_string(), value.clone())]))
+            .collect();
+        let median = |rounds: &[IndexMap<String, AdditionalData>]| aggregate_additional_data(rounds, &IndexMap::new())["key"].clone();
+
+        // The first round stays first, it determines the kind
+        assert_eq!(median(&rounds), AdditionalData::Int(3));
+        for _ in 0..20 {
+            rounds[1..].shuffle(&mut rand::thread_rng());
+            assert_eq!(median(&rounds), AdditionalData::Int(3));
+        }
+        assert_eq!(AdditionalData::Int(3).total_cmp(&AdditionalData::Duration(3)), Ordering::Less);
+    }
+
+    #[test]
+    fn should_aggregate_values() {
+        assert_eq!(Aggregation::Median.aggregate(&[3, 1, 2]), 2);
+        assert_eq!(Aggregation::Mean.aggregate(&[1, 2]), 2);
+        assert_eq!(Aggregation::Sum.aggregate(&[i32::MAX, 1]), i32::MAX);
+        assert_eq!(Aggregation::Max.aggregate(&[3, 1, 2]), 3);
+        assert_eq!(serde_yaml::from_str::<Aggregation>("sum").unwrap(), Aggregation::Sum);
     }
 }
@@ -0,0 +1,134 @@
+use std::fmt::{Display, Formatter};
+
+/// Fewer points cannot tell the growth models apart.
+const MIN_POINTS: usize = 3;
+
+/// A model of how the time grows with the number of iterations `n`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Complexity {
+    Constant,
+    Logarithmic,
+    Linear,
+    Linearithmic,
+    Quadratic,
+}
+
+impl Complexity {
+    const ALL: [Complexity; 5] = [
+        Complexity::Constant,
+        Complexity::Logarithmic,
+        Complexity::Linear,
+        Complexity::Linearithmic,
+        Complexity::Quadratic,
+    ];
+
+    /// The growth of the time at `n`, up to a constant factor.
+    fn model(&self, n: f64) -> f64 {
+        match self {
+            Complexity::Constant => 1.0,
+            Complexity::Logarithmic => n.ln(),
+            Complexity::Linear => n,
+            Complexity::Linearithmic => n * n.ln(),
+            Complexity::Quadratic => n * n,
+        }
+    }
+}
+
+impl Display for Complexity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Complexity::Constant => "O(1)",
+            Complexity::Logarithmic => "O(log n)",
+            Complexity::Linear => "O(n)",
+            Complexity::Linearithmic => "O(n log n)",
+            Complexity::Quadratic => "O(n²)",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// The growth model that fits the measured points of an iteration sweep best.
+#[derive(Debug, PartialEq)]
+pub struct ComplexityFit {
+    pub complexity: Complexity,
+
+    /// The slope of the time over `n` on a log-log scale, e.g. about `1` for linear growth.
+    pub exponent: f64,
+
+    /// Root mean square of the relative deviation of the points from the fitted model, e.g. `0.05` for 5 %.
+    pub error: f64,
+}
+
+impl ComplexityFit {
+    /// Example: `O(n) (time ~ n^1.02, error 3.1 %)`
+    pub fn summary(&self) -> String {
+        format!("{} (time ~ n^{:.2}, error {:.1} %)", self.complexity, self.exponent, self.error * 100.0)
+    }
+}
+
+/// Fits the (iterations, median time) points of a sweep to every model with the least relative squared error
+/// (so slow points do not dominate) and returns the best one.
+/// Points without measurable time (0 ms) are ignored, `None` if fewer than `MIN_POINTS` distinct iteration counts remain.
+pub fn fit(points: &[(u64, i64)]) -> Option<ComplexityFit> {
+    let points: Vec<(f64, f64)> = points.iter()
+        .filter(|(n, time)| *n > 1 && *time > 0)
+        .map(|(n, time)| (*n as f64, *time as f64))
+        .collect();
+    let mut distinct: Vec<f64> = points.iter().map(|(n, _)| *n).collect();
+    distinct.sort_by(f64::total_cmp);
+    distinct.dedup();
+    if distinct.len() < MIN_POINTS {
+        return None;
+    }
+
+    let (complexity, error) = Complexity::ALL.iter()
+        .map(|complexity| (*complexity, relative_error(&points, |n| complexity.model(n))))
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))?;
+    Some(ComplexityFit { complexity, exponent: log_log_slope(&points), error })
+}
+
+/// The RMS relative error of `time = c * model(n)` with the optimal `c`.
+fn relative_error<M: Fn(f64) -> f64>(points: &[(f64, f64)], model: M) -> f64 {
+    // Minimizes Σ ((t - c·f) / t)², so c = Σ (f / t) / Σ (f / t)²
+    let ratios: Vec<f64> = points.iter().map(|(n, time)| model(*n) / time).collect();
+    let factor = ratios.iter().sum::<f64>() / ratios.iter().map(|ratio| ratio * ratio).sum::<f64>();
+    let squared: f64 = ratios.iter().map(|ratio| (1.0 - factor * ratio).powi(2)).sum();
+    (squared / points.len() as f64).sqrt()
+}
+
+/// Least squares slope of `ln(time)` over `ln(n)`.
+fn log_log_slope(points: &[(f64, f64)]) -> f64 {
+    let logs: Vec<(f64, f64)> = points.iter().map(|(n, time)| (n.ln(), time.ln())).collect();
+    let count = logs.len() as f64;
+    let mean_x = logs.iter().map(|(x, _)| x).sum::<f64>() / count;
+    let mean_y = logs.iter().map(|(_, y)| y).sum::<f64>() / count;
+    let covariance: f64 = logs.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = logs.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    covariance / variance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(time: fn(f64) -> f64) -> Vec<(u64, i64)> {
+        [10_000, 100_000, 1_000_000, 10_000_000].iter().map(|n| (*n, time(*n as f64) as i64)).collect()
+    }
+
+    #[test]
+    fn should_fit_growth_models() {
+        let linear = fit(&points(|n| n / 1000.0 + 3.0)).unwrap();
+        assert_eq!(linear.complexity, Complexity::Linear);
+        assert!((linear.exponent - 1.0).abs() < 0.05, "{}", linear.exponent);
+        assert_eq!(fit(&points(|n| n * n.ln() / 1000.0)).unwrap().complexity, Complexity::Linearithmic);
+        assert_eq!(fit(&points(|n| n * n / 1e9 + 1.0)).unwrap().complexity, Complexity::Quadratic);
+        assert_eq!(fit(&points(|_| 250.0)).unwrap().complexity, Complexity::Constant);
+        assert_eq!(linear.summary(), "O(n) (time ~ n^0.96, error 10.2 %)");
+    }
+
+    #[test]
+    fn should_need_three_measurable_points() {
+        assert_eq!(fit(&[(1_000, 10), (10_000, 100)]), None);
+        assert_eq!(fit(&[(1_000, 0), (10_000, 100), (100_000, 1_000), (100_000, 1_010)]), None);
+    }
+}
@@ -1,33 +1,106 @@
 use std::time::Duration;
 use indexmap::IndexMap;
-use crate::benchmark::benchmark::{IterationResult, run_benchmark};
-use crate::utils::copy_files;
+use crate::benchmark::benchmark::{BenchmarkOptions, DEFAULT_COOLDOWN, IterationResult, Response, check_actual_version, run_sweep};
+use crate::benchmark::complexity;
+use crate::benchmark::deadline;
+use crate::benchmark::error::BenchmarkError;
+use crate::benchmark::run_options::RunOptions;
+use crate::output::json::{ResultEntry, ResultWriter, SWEEP_KEY};
+use crate::output::tui::{self, Request};
+use crate::utils::{allocation_metrics, copy_files, gc_metrics, input_hash, self_reported_memory, server_timing};
 use crate::utils::docker_stats::DockerStatsReader;
-use crate::utils::meta_data_parser::BenchmarkMetaData;
+use crate::utils::meta_data_parser::{BenchmarkMetaData, CategoryMetaData, BenchmarkManifest};
+use crate::utils::transport::{self, Client};
 use crate::utils::result_writer::write_result_to_file;
 use crate::utils::version_migrator::VersionMigrator;
 
-const QUERY: [(&str, &str); 1] = [("iterations", "1000000000")];
-const EXPECTED_RESPONSE: &str = "3.1415926525880504;785398157.7092886;0.7853981633136793";
-const DEFAULT_RUNS: usize = 5;
+const CATEGORY_DIR: &str = "benchmark/computation";
+const DEFAULT_ITERATIONS: u64 = 1000000000;
+const DEFAULT_EXPECTED_RESPONSE: &str = "3.1415926525880504;785398157.7092886;0.7853981633136793";
 
-pub fn benchmark_computation(dir: &str, stats_reader: &mut DockerStatsReader) {
-    println!(" -> Benchmarking {}", dir);
+/// Benchmarks the computation benchmark in `dir`.
+/// With `RunOptions::external_url`, the already running service at this URL is measured instead of the container.
+/// Returns the request of the user that stopped the benchmark before its last version (see `--tui`), `None` if it ran to the end.
+pub fn benchmark_computation(
+    dir: &str,
+    stats_reader: &mut DockerStatsReader,
+    result_writer: &mut ResultWriter,
+    run_options: &RunOptions,
+) -> Result<Option<Request>, BenchmarkError> {
+    logln!(" -> Benchmarking {}", dir);
+    let external_url = run_options.external_url.as_deref();
+    let default_url = run_options.default_base_url();
+    let base_url = external_url.unwrap_or(&default_url);
 
-    let meta_data: BenchmarkMetaData = BenchmarkMetaData::read_from_directory(dir).expect("Failed to read meta data");
+    let category: CategoryMetaData = CategoryMetaData::read_from_directory(CATEGORY_DIR)
+        .map_err(|e| BenchmarkError::InvalidBenchmark(format!("Failed to read category meta data: {}", e)))?;
+    category.print_info();
+
+    let meta_data: BenchmarkMetaData = BenchmarkMetaData::read_from_directory(dir)
+        .map_err(|e| BenchmarkError::InvalidBenchmark(format!("Failed to read meta data: {}", e)))?;
     meta_data.print_info();
+    let manifest = BenchmarkManifest::read_for_benchmark(dir)
+        .map_err(|e| BenchmarkError::InvalidBenchmark(format!("Failed to read the manifest: {}", e)))?;
+    if let Some(max_duration) = meta_data.max_duration {
+        deadline::restrict(max_duration);
+    }
+
+    // The command line wins over the meta data
+    let warmup = run_options.warmup.unwrap_or(category.resolve_warmup(meta_data.warmup, meta_data.extended_warmup));
+    let runs = run_options.rounds.unwrap_or(category.resolve_rounds(meta_data.runs));
+    let cooldown = run_options.cooldown.unwrap_or(category.resolve_cooldown(meta_data.cooldown_seconds, DEFAULT_COOLDOWN));
+    stats_reader.set_stabilization_prefix(category.resolve_memory_stabilization(meta_data.memory_stabilization));
+    let iterations = category.resolve_iterations(meta_data.iterations, DEFAULT_ITERATIONS);
+    logln!(" -> Using warmup = {}, runs = {}, cooldown = {:?}, iterations = {}", warmup, runs, cooldown, iterations);
 
-    let runs = match meta_data.runs {
-        Some(runs) => {
-            println!(" -> Running {} times instead of default = {}", runs, DEFAULT_RUNS);
-            runs
-        },
-        None => DEFAULT_RUNS,
+    let iterations_sweep = category.resolve_iterations_sweep(&meta_data.iterations_sweep);
+    if let Some(levels) = &iterations_sweep {
+        logln!(" -> Sweeping iterations {:?} instead of {}", levels, iterations);
+    }
+    let levels = iterations_sweep.clone().unwrap_or(vec![iterations]);
+    let expected_responses: IndexMap<u64, String> = levels.iter().map(|iterations| (*iterations, expected_response(*iterations))).collect();
+    let options = BenchmarkOptions {
+        warmup_rounds: warmup,
+        workload_warmup_rounds: IndexMap::new(),
+        rounds: runs,
+        cooldown,
+        version_command: meta_data.version_command.clone(),
+        setup_command: meta_data.setup_command.clone(),
+        cold_start: meta_data.cold_start,
+        profile_dir: run_options.profile_dir.clone(),
+        aggregation: meta_data.aggregation.clone(),
+        reset_url: meta_data.reset_path.as_ref().map(|path| format!("{}{}", base_url.trim_end_matches('/'), path)),
+        validate_response: Some(validate_response),
+        running_median: run_options.running_median,
+        warmup_only: run_options.warmup_only,
+        keep_images: run_options.keep_images,
+        build: run_options.build_options(dir),
+        max_memory: meta_data.max_memory,
+        outlier_sigma: meta_data.outlier_sigma,
     };
 
+    // Shared by all versions, a connection closed by the restarted container is re-established
+    let client = Client::new(meta_data.transport);
+    // The metrics are queried around every round, a client per query would open a connection every time
+    let metrics_client = reqwest::blocking::Client::new();
+    let path = dir.replace("benchmark/computation/", "");
     for language_version in &meta_data.language_version {
+        if !run_options.filter.matches_version(dir, language_version) {
+            logln!(" -> Skipping version {} (not selected by --lang-version)", language_version);
+            continue;
+        }
         if let Some(copy_files) = &meta_data.copy {
-            copy_files::copy_files(dir, &copy_files);
+            copy_files::copy_files(dir, copy_files).map_err(BenchmarkError::InvalidBenchmark)?;
+        }
+
+        // Hashed before the versions are migrated, so the hash only depends on the checkout
+        let input_hash = input_hash::hash_inputs(dir, CATEGORY_DIR, language_version)
+            .map_err(|e| BenchmarkError::InvalidBenchmark(format!("Failed to hash the benchmark inputs: {}", e)))?;
+        if !run_options.warmup_only && result_writer.reuse("computation", &path, &input_hash).map_err(|e| BenchmarkError::WriteFailed(e.to_string()))? {
+            if let Some(copy_files) = &meta_data.copy {
+                copy_files::delete_copied_files(dir, copy_files);
+            }
+            continue;
         }
 
         let mut version_migrations: Vec<VersionMigrator> = match meta_data.language_version.len() {
@@ -39,62 +112,192 @@ pub fn benchmark_computation(dir: &str, stats_reader: &mut DockerStatsReader) {
                 language_version.clone(),
             )],
         };
-        let result = run_benchmark(
+        let results = run_sweep(
             dir,
-            stats_reader,
-            version_migrations.iter_mut().collect(),
-            match meta_data.extended_warmup {
-                true => 3,
-                false => 1,
+            match external_url {
+                Some(_) => None,
+                None => Some(&mut *stats_reader),
             },
-            runs,
-            || {
-                let client = reqwest::blocking::Client::new();
-                let response = match client.get("http://localhost:3000")
-                    .query(&QUERY)
-                    .timeout(Duration::from_secs(600))
-                    .send() {
-                    Ok(response) => Ok(response),
-                    Err(e) => Err(e.to_string()),
-                }?;
-                let body = response.text()?;
-                if !body.contains(EXPECTED_RESPONSE) {
-                    return Err(Box::from(format!("Invalid response: {} (expected: {})", body, EXPECTED_RESPONSE)));
+            version_migrations.iter_mut().collect(),
+            &options,
+            || client.send(base_url, 1, Duration::from_secs(5)),
+            &levels,
+            |iterations, _| {
+                let gc_before = match meta_data.gc_metrics {
+                    true => Some(gc_metrics::query(&metrics_client, base_url)?),
+                    false => None,
+                };
+                let allocations_before = match meta_data.allocation_metrics {
+                    true => Some(allocation_metrics::query(&metrics_client, base_url)?),
+                    false => None,
+                };
+                let expected_response = &expected_responses[iterations];
+                // Only the request is measured, the metrics are queried around it
+                let (response, time) = client.send_timed(base_url, *iterations, Duration::from_secs(600))?;
+                transport::check_status(&response, base_url)?;
+                if !response.body.contains(expected_response) {
+                    return Err(BenchmarkError::InvalidResponse(format!("{} (expected: {})", response.body, expected_response)));
+                }
+
+                let mut additional_data = IndexMap::new();
+                server_timing::insert_into(&response.phases, &mut additional_data);
+                if let Some(gc_before) = gc_before {
+                    gc_metrics::query(&metrics_client, base_url)?.since(&gc_before).insert_into(&mut additional_data);
+                }
+                if let Some(allocations_before) = allocations_before {
+                    allocation_metrics::query(&metrics_client, base_url)?.since(&allocations_before).insert_into(1, &mut additional_data);
                 }
 
                 Ok(IterationResult {
-                    additional_data: IndexMap::new(),
+                    time,
+                    additional_data,
                     debugging_data: IndexMap::new(),
+                    self_reported_memory: match meta_data.self_reported_memory {
+                        true => Some(self_reported_memory::query(&metrics_client, base_url)?),
+                        false => None,
+                    },
+                    response: Some(response),
                 })
             },
         );
 
         if let Some(copy_files) = &meta_data.copy {
-            copy_files::delete_copied_files(dir, &copy_files);
+            copy_files::delete_copied_files(dir, copy_files);
+        }
+        let Some(results) = results? else {
+            return Ok(tui::take_request());
+        };
+        if run_options.warmup_only {
+            logln!(" -> Warmup only, no result is recorded");
+            continue;
+        }
+
+        // Only a sweep of enough points tells the growth models apart
+        let fit = iterations_sweep.as_ref()
+            .and_then(|_| complexity::fit(&results.iter().map(|(iterations, result)| (*iterations, result.time_median)).collect::<Vec<_>>()));
+        if let Some(fit) = &fit {
+            logln!(" -> Complexity: {}", fit.summary());
         }
+        let complexity = fit.as_ref().map(|fit| fit.complexity.to_string());
+        let complexity_exponent = fit.as_ref().map(|fit| format!("{:.2}", fit.exponent));
+        let timed_out = results.values().any(|result| result.timed_out);
+
+        for (iterations, mut result) in results {
+            check_actual_version(language_version, &result);
 
-        write_result_to_file(
-            "result/computation_result.csv",
-            &Vec::from([
+            let iterations_string = iterations.to_string();
+            let mut descriptors = Vec::from([
                 ("language", meta_data.language.as_str()),
                 ("mode", meta_data.mode.as_str()),
                 ("version", language_version.as_str()),
-                ("path", dir.replace("benchmark/computation/", "").as_str()),
-            ]),
-            &Vec::from([
-                ("time_median", result.time_median.to_string().as_str()),
-                ("memory_median", result.memory_median.to_string().as_str()),
-            ]),
-            take_lower_time_median,
-        ).expect("Failed to write result to file");
+                ("path", path.as_str()),
+            ]);
+            if iterations_sweep.is_some() {
+                descriptors.push(("iterations", iterations_string.as_str()));
+                descriptors.push((SWEEP_KEY, "iterations"));
+            }
+            if let (Some(complexity), Some(complexity_exponent)) = (&complexity, &complexity_exponent) {
+                descriptors.push(("complexity", complexity.as_str()));
+                descriptors.push(("complexity_exponent", complexity_exponent.as_str()));
+            }
+
+            if external_url.is_none() && iterations_sweep.is_none() && !result.timed_out {
+                // Results of external services, sweeps and partial results are not comparable to the website results
+                write_result_to_file(
+                    "result/computation_result.csv",
+                    &descriptors,
+                    &Vec::from([
+                        ("time_median", result.time_median.to_string().as_str()),
+                        ("memory_median", result.memory_median.unwrap_or_default().to_string().as_str()),
+                    ]),
+                    take_lower_time_median,
+                ).map_err(|e| BenchmarkError::WriteFailed(e.to_string()))?;
+            }
+
+            result.manifest = manifest.clone();
+            let mut entry = ResultEntry::new(
+                "computation",
+                &path,
+                &meta_data.language,
+                language_version,
+                &descriptors,
+                result,
+            );
+            entry.input_hash = Some(input_hash.clone());
+            result_writer.write(entry).map_err(|e| BenchmarkError::WriteFailed(e.to_string()))?;
+        }
+        // The partial result is recorded, the benchmark still fails
+        if timed_out {
+            deadline::check()?;
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the expected response for the given number of iterations.
+/// Non-default iteration counts are computed with the reference algorithm.
+fn expected_response(iterations: u64) -> String {
+    if iterations == DEFAULT_ITERATIONS {
+        return DEFAULT_EXPECTED_RESPONSE.to_string();
+    }
+
+    let mut pi: f64 = 0.0;
+    let mut denominator: f64 = 1.0;
+    let mut total_sum: f64 = 0.0;
+    let mut alternating_sum: f64 = 0.0;
+    for x in 0..iterations {
+        if x % 2 == 0 {
+            pi += 1.0 / denominator;
+        } else {
+            pi -= 1.0 / denominator;
+        }
+        denominator += 2.0;
+
+        total_sum += pi;
+        match x % 3 {
+            0 => alternating_sum += pi,
+            1 => alternating_sum -= pi,
+            _ => alternating_sum /= 2.0,
+        }
     }
+    format!("{};{};{}", pi * 4.0, total_sum, alternating_sum)
+}
+
+/// A successful status (if any) and three numbers separated by `;`, independent of the number of iterations.
+fn validate_response(response: &Response) -> Result<(), String> {
+    if let Some(status) = response.status.filter(|status| !(200..300).contains(status)) {
+        return Err(format!("unexpected status {}", status));
+    }
+    let values: Vec<&str> = response.body.trim().split(';').collect();
+    if values.len() != 3 || values.iter().any(|value| value.trim().parse::<f64>().is_err()) {
+        return Err("expected three numbers separated by `;`".to_string());
+    }
+    Ok(())
 }
 
 fn take_lower_time_median<'a>(old_values: &'a [&'a str], new_values: &'a [&'a str]) -> &'a [&'a str] {
     if old_values[0].parse::<i32>().unwrap() < new_values[0].parse::<i32>().unwrap() {
-        println!(" -> Keeping old values (time_median: {} < {})", old_values[0], new_values[0]);
+        logln!(" -> Keeping old values (time_median: {} < {})", old_values[0], new_values[0]);
         old_values
     } else {
         new_values
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: Option<u16>, body: &str) -> Response {
+        Response { status, body: body.to_string(), size: body.len(), phases: IndexMap::new() }
+    }
+
+    #[test]
+    fn should_validate_response_shape() {
+        assert_eq!(validate_response(&response(Some(200), DEFAULT_EXPECTED_RESPONSE)), Ok(()));
+        assert_eq!(validate_response(&response(None, "4;4;4\n")), Ok(()));
+        assert_eq!(validate_response(&response(Some(500), DEFAULT_EXPECTED_RESPONSE)), Err("unexpected status 500".to_string()));
+        assert!(validate_response(&response(Some(200), "<html>Error</html>")).is_err());
+        assert!(validate_response(&response(Some(200), "1;2")).is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::benchmark::error::BenchmarkError;
+use crate::utils::format::format_duration;
+
+/// The `--benchmark-timeout` of every benchmark of the run, see `set_timeout`. A benchmark can shorten its own, see `restrict`.
+static TIMEOUT: Mutex<Option<Duration>> = Mutex::new(None);
+
+/// The benchmark that is running, see `start`.
+static CURRENT: Mutex<Option<Deadline>> = Mutex::new(None);
+
+/// Tells the watchdog of a finished benchmark apart from the one of the running benchmark.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone)]
+struct Deadline {
+    generation: u64,
+    started: Instant,
+
+    /// `None` is unlimited.
+    timeout: Option<Duration>,
+
+    /// Whether the watchdog fired, so the benchmark fails with `BenchmarkTimeout` whatever error the kill caused.
+    expired: bool,
+
+    /// What the benchmark is waiting for, see `guard`.
+    target: Option<Target>,
+}
+
+/// What the watchdog kills once the benchmark ran out of time, so the benchmark does not wait for it any longer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    /// A process of the runner by its PID, e.g. `docker compose build`.
+    Process(u32),
+
+    /// The container of the benchmark by its ID, so a container of the same name started later is not affected.
+    Container(String),
+}
+
+/// Limits the wall-clock time of every following benchmark, from its build to its last round. `None` is unlimited.
+pub fn set_timeout(timeout: Option<Duration>) {
+    *TIMEOUT.lock().unwrap() = timeout;
+}
+
+/// Starts the clock of a benchmark. Once it ran out, a watchdog kills what the benchmark waits for (see `guard`),
+/// so e.g. a round that is blocked waiting for a response fails and the benchmark ends at the next check.
+pub fn start() {
+    let timeout = *TIMEOUT.lock().unwrap();
+    let generation = GENERATION.fetch_add(1, Ordering::Relaxed) + 1;
+    *CURRENT.lock().unwrap() = Some(Deadline { generation, started: Instant::now(), timeout, expired: false, target: None });
+    if let Some(timeout) = timeout {
+        watch(generation, timeout, timeout);
+    }
+}
+
+/// Limits the running benchmark to `max_duration` from its start (the `max_duration` of its `benchmark.yaml`),
+/// if that is shorter than its timeout. Both end the benchmark in the same way.
+pub fn restrict(max_duration: Duration) {
+    let (generation, remaining) = {
+        let mut current = CURRENT.lock().unwrap();
+        let Some(deadline) = current.as_mut().filter(|deadline| deadline.timeout.is_none_or(|timeout| max_duration < timeout)) else {
+            return;
+        };
+        deadline.timeout = Some(max_duration);
+        (deadline.generation, max_duration.saturating_sub(deadline.started.elapsed()))
+    };
+    watch(generation, max_duration, remaining);
+}
+
+/// Sets what the running benchmark waits for, `None` once it does not anymore (e.g. before its teardown).
+/// Killed right away if the benchmark already ran out of time.
+pub fn guard(target: Option<Target>) {
+    let mut current = CURRENT.lock().unwrap();
+    let Some(deadline) = current.as_mut() else {
+        return;
+    };
+    deadline.target = target;
+    if let (true, Some(target)) = (deadline.expired, &deadline.target) {
+        kill(target);
+    }
+}
+
+/// Expires the benchmark of `generation` after `remaining` and kills its target, unless it finished or expired before.
+fn watch(generation: u64, timeout: Duration, remaining: Duration) {
+    thread::spawn(move || {
+        thread::sleep(remaining);
+        // Killed while locked, so the benchmark cannot finish and the next one start in the meantime
+        let mut current = CURRENT.lock().unwrap();
+        let Some(deadline) = current.as_mut().filter(|deadline| deadline.generation == generation && !deadline.expired) else {
+            return;
+        };
+        deadline.expired = true;
+        errorln!(" -> Benchmark timeout of {} exceeded, stopping the benchmark", format_duration(timeout));
+        if let Some(target) = &deadline.target {
+            kill(target);
+        }
+    });
+}
+
+/// Fails if the target already exited, the next check ends the benchmark then.
+fn kill(target: &Target) {
+    let mut command = match target {
+        Target::Process(pid) => {
+            let mut command = Command::new("kill");
+            command.arg(pid.to_string());
+            command
+        }
+        Target::Container(id) => {
+            let mut command = Command::new("docker");
+            command.args(["kill", id]);
+            command
+        }
+    };
+    let _ = command.stdout(Stdio::null()).stderr(Stdio::null()).status();
+}
+
+/// Stops the clock of the benchmark, its watchdog does nothing anymore.
+pub fn finish() {
+    *CURRENT.lock().unwrap() = None;
+}
+
+/// Checked before every round of a benchmark, the first one starts after the build and the start of the container.
+pub fn check() -> Result<(), BenchmarkError> {
+    match CURRENT.lock().unwrap().as_ref() {
+        Some(deadline) => check_deadline(deadline),
+        None => Ok(()),
+    }
+}
+
+/// The error of the benchmark if it ran out of time, e.g. to replace the error the killed container caused.
+pub fn exceeded() -> Option<BenchmarkError> {
+    check().err()
+}
+
+fn check_deadline(deadline: &Deadline) -> Result<(), BenchmarkError> {
+    let Some(timeout) = deadline.timeout else {
+        return Ok(());
+    };
+    let elapsed = deadline.started.elapsed();
+    match deadline.expired || elapsed > timeout {
+        true => Err(BenchmarkError::BenchmarkTimeout { elapsed, timeout }),
+        false => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_check_deadline() {
+        let deadline = Deadline { generation: 1, started: Instant::now(), timeout: Some(Duration::from_secs(3600)), expired: false, target: None };
+        assert_eq!(check_deadline(&deadline), Ok(()));
+
+        let error = check_deadline(&Deadline { expired: true, ..deadline.clone() }).unwrap_err();
+        assert!(matches!(error, BenchmarkError::BenchmarkTimeout { timeout, .. } if timeout == Duration::from_secs(3600)));
+        assert_eq!(error.kind(), "benchmark_timeout");
+
+        let started = Instant::now() - Duration::from_secs(120);
+        assert!(check_deadline(&Deadline { started, timeout: Some(Duration::from_secs(60)), ..deadline.clone() }).is_err());
+        assert_eq!(check_deadline(&Deadline { started, timeout: None, ..deadline }), Ok(()));
+    }
+}
@@ -0,0 +1,211 @@
+use std::io::ErrorKind;
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::Command;
+use crate::benchmark::benchmark::{BENCHMARK_NETWORK, BENCHMARK_PORT, BENCHMARK_SERVICE};
+use crate::benchmark::dry_run::{self, Check};
+use crate::utils::format::SizeFormat;
+
+/// Less free space in the data directory of Docker fails the check, the images of a full run need a few GiB.
+const MIN_FREE_DISK: i64 = 5 * 1024 * 1024 * 1024;
+
+/// What `doctor` and `--preflight` check besides Docker itself.
+pub struct DoctorOptions<'a> {
+    pub output_dir: &'a str,
+
+    /// Whether the benchmark port has to be free on this machine, `false` if the containers run elsewhere
+    /// (e.g. with `--service-host`) or the benchmarks run against `--external-url`.
+    pub local_port: bool,
+}
+
+/// What `docker info` reports about the daemon, see `DaemonInfo::parse`.
+#[derive(Debug, Default, PartialEq)]
+struct DaemonInfo {
+    server_version: String,
+
+    /// `1` or `2`.
+    cgroup_version: Option<String>,
+
+    /// Whether the daemon can limit the CPU of a container, as the benchmarks are limited to one CPU.
+    cpu_quota: bool,
+
+    /// e.g. `/var/lib/docker`, on the host of the daemon.
+    root_dir: Option<String>,
+}
+
+impl DaemonInfo {
+    /// Parses the output of `docker info --format '{{json .}}'`.
+    fn parse(json: &str) -> Option<DaemonInfo> {
+        let info: serde_json::Value = serde_json::from_str(json.trim()).ok()?;
+        let text = |key: &str| info[key].as_str().map(str::to_string).filter(|value| !value.is_empty());
+        Some(DaemonInfo {
+            server_version: text("ServerVersion")?,
+            cgroup_version: text("CgroupVersion"),
+            cpu_quota: info["CPUCfsQuota"].as_bool().unwrap_or(false),
+            root_dir: text("DockerRootDir"),
+        })
+    }
+}
+
+/// Checks everything a run needs from this machine: the docker CLI, the daemon, the compose plugin,
+/// the benchmark network, cgroups with CPU quotas, free disk space, the output directory and the benchmark port.
+/// Every problem tells how to fix it. The checks that need the daemon are left out if it is not reachable.
+pub fn check(options: &DoctorOptions) -> Vec<Check> {
+    let mut checks = vec![check_cli()];
+    let daemon = docker(&["info", "--format", "{{json .}}"]).and_then(|json| DaemonInfo::parse(&json).ok_or_else(|| "unexpected output of `docker info`".to_string()));
+    match &daemon {
+        Ok(info) => checks.push(Check { name: format!("Docker daemon {}", info.server_version), problems: vec![] }),
+        Err(e) => checks.push(Check {
+            name: "Docker daemon".to_string(),
+            problems: vec![format!("not reachable ({}), start it (e.g. `sudo systemctl start docker`) or select another with --docker-context", e)],
+        }),
+    }
+    checks.push(check_compose());
+    if let Ok(info) = &daemon {
+        checks.push(check_network(docker(&["network", "inspect", BENCHMARK_NETWORK]).is_ok()));
+        checks.push(check_cgroup(info));
+        // The data directory of a remote daemon (or of the VM of Docker Desktop) is not on this machine
+        if let Some(root_dir) = info.root_dir.as_deref().filter(|dir| Path::new(dir).is_dir()) {
+            checks.push(check_free_disk(root_dir, free_disk(root_dir)));
+        }
+    }
+    checks.push(dry_run::check_output_dir(options.output_dir));
+    if options.local_port {
+        checks.push(check_port(BENCHMARK_PORT.parse().expect("Invalid benchmark port")));
+    }
+    checks
+}
+
+fn check_cli() -> Check {
+    match docker(&["version", "--format", "{{.Client.Version}}"]) {
+        Ok(version) => Check { name: format!("Docker CLI {}", version), problems: vec![] },
+        // Also fails if only the daemon is not reachable, with the version of the client on stdout
+        Err(e) if e.starts_with("could not execute") => Check {
+            name: "Docker CLI".to_string(),
+            problems: vec![format!("{}, install Docker (see https://docs.docker.com/get-docker)", e)],
+        },
+        Err(_) => Check { name: "Docker CLI".to_string(), problems: vec![] },
+    }
+}
+
+fn check_compose() -> Check {
+    match docker(&["compose", "version", "--short"]) {
+        Ok(version) => Check { name: format!("Docker Compose {}", version), problems: vec![] },
+        Err(e) => Check {
+            name: "Docker Compose".to_string(),
+            problems: vec![format!("{}, install the compose plugin (see https://docs.docker.com/compose/install)", e)],
+        },
+    }
+}
+
+fn check_network(exists: bool) -> Check {
+    let problems = match exists {
+        true => vec![],
+        false => vec![format!("does not exist, create it with `docker network create {}`", BENCHMARK_NETWORK)],
+    };
+    Check { name: format!("Network {}", BENCHMARK_NETWORK), problems }
+}
+
+fn check_cgroup(info: &DaemonInfo) -> Check {
+    let name = match &info.cgroup_version {
+        Some(version) => format!("cgroup v{}", version),
+        None => "cgroup".to_string(),
+    };
+    let problems = match info.cpu_quota {
+        true => vec![],
+        false => vec!["the daemon cannot limit the CPU of containers, enable the cpu controller of the cgroups".to_string()],
+    };
+    Check { name, problems }
+}
+
+fn check_free_disk(dir: &str, free: Result<i64, String>) -> Check {
+    let problems = match free {
+        Ok(free) if free >= MIN_FREE_DISK => vec![],
+        Ok(free) => vec![format!(
+            "only {} free, at least {} are needed, remove unused images with `docker system prune`",
+            free.bytes_to_string(), MIN_FREE_DISK.bytes_to_string(),
+        )],
+        Err(e) => vec![format!("could not determine the free space: {}", e)],
+    };
+    Check { name: format!("Free disk {}", dir), problems }
+}
+
+/// The port is free if it can be bound, the container of the benchmark publishes it on all interfaces.
+fn check_port(port: u16) -> Check {
+    let problems = match TcpListener::bind(("0.0.0.0", port)) {
+        Ok(_) => vec![],
+        Err(e) if e.kind() == ErrorKind::AddrInUse => vec![format!(
+            "in use, stop the process using it (see `lsof -i :{}`) or a leftover container with `docker rm -f {}`",
+            port, BENCHMARK_SERVICE,
+        )],
+        Err(e) => vec![format!("could not be bound: {}", e)],
+    };
+    Check { name: format!("Port {}", port), problems }
+}
+
+/// The free space of the file system of `dir` in bytes, as reported by `df`.
+fn free_disk(dir: &str) -> Result<i64, String> {
+    let output = Command::new("df").args(["-Pk", dir]).output().map_err(|e| format!("could not execute df: {}", e))?;
+    parse_df(&String::from_utf8_lossy(&output.stdout)).ok_or_else(|| format!("unexpected output of `df` ({})", output.status))
+}
+
+/// The available kilobytes (the fourth column of the POSIX format of `df -Pk`) in bytes.
+fn parse_df(output: &str) -> Option<i64> {
+    let available: i64 = output.lines().nth(1)?.split_whitespace().nth(3)?.parse().ok()?;
+    Some(available * 1024)
+}
+
+/// The trimmed stdout of the docker command, or the command without its options and the last line of its stderr if it fails.
+fn docker(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("docker").args(args).output().map_err(|e| format!("could not execute docker: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let last_line = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or_default();
+        let command: Vec<&str> = args.iter().copied().take_while(|arg| !arg.starts_with("--")).collect();
+        return Err(format!("`docker {}` failed: {}", command.join(" "), last_line.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_docker_info() {
+        let info = DaemonInfo::parse(r#"{"ServerVersion":"24.0.7","CgroupVersion":"2","CPUCfsQuota":true,"DockerRootDir":"/var/lib/docker"}"#).unwrap();
+        assert_eq!(info, DaemonInfo {
+            server_version: "24.0.7".to_string(),
+            cgroup_version: Some("2".to_string()),
+            cpu_quota: true,
+            root_dir: Some("/var/lib/docker".to_string()),
+        });
+        assert_eq!(check_cgroup(&info), Check { name: "cgroup v2".to_string(), problems: vec![] });
+        assert!(!check_cgroup(&DaemonInfo { cpu_quota: false, ..info }).is_ok());
+
+        assert_eq!(DaemonInfo::parse(r#"{"ServerErrors":["Cannot connect to the Docker daemon"]}"#), None);
+        assert_eq!(DaemonInfo::parse("not json"), None);
+    }
+
+    #[test]
+    fn should_check_free_disk() {
+        let df = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n/dev/sda1        102400000  40000000  62400000      40% /\n";
+        assert_eq!(parse_df(df), Some(62_400_000 * 1024));
+        assert_eq!(parse_df("df: /missing: No such file or directory\n"), None);
+
+        assert!(check_free_disk("/var/lib/docker", Ok(MIN_FREE_DISK)).is_ok());
+        let check = check_free_disk("/var/lib/docker", Ok(1024 * 1024 * 1024));
+        assert_eq!(check.problems, vec!["only 1.00 GB free, at least 5.00 GB are needed, remove unused images with `docker system prune`"]);
+    }
+
+    #[test]
+    fn should_check_port() {
+        let listener = TcpListener::bind(("0.0.0.0", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(check_port(port).problems[0].starts_with("in use"));
+        drop(listener);
+        assert!(check_port(port).is_ok());
+
+        assert!(check_network(false).problems[0].contains("docker network create sharkbench-benchmark-network"));
+    }
+}
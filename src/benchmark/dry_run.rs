@@ -0,0 +1,221 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use indexmap::IndexMap;
+use crate::benchmark::benchmark::split_benchmark_dir;
+use crate::benchmark::filter::{BenchmarkFilter, META_DATA_FILE};
+use crate::benchmark::list::{self, DOCKERFILE};
+use crate::benchmark::validate;
+use crate::utils::copy_files::COMMON_DIR;
+use crate::utils::meta_data_parser::{BenchmarkMetaData, CopyValue, BenchmarkManifest, WebBenchmarkMetaData};
+use crate::utils::table;
+use crate::utils::version_migrator::VersionMigrator;
+
+/// Written and removed again to check that the output directory is writable.
+const PROBE_FILE: &str = ".sharkbench-dry-run";
+
+const HEADER: [&str; 2] = ["Check", "Status"];
+
+/// The outcome of one check of `--dry-run`: the environment or a benchmark.
+#[derive(Debug, PartialEq)]
+pub struct Check {
+    pub name: String,
+
+    /// What would fail, empty if the check passed.
+    pub problems: Vec<String>,
+}
+
+impl Check {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Checks that Docker is reachable and that the output directory is writable.
+pub fn check_environment(output_dir: &str) -> Vec<Check> {
+    vec![check_docker(), check_output_dir(output_dir)]
+}
+
+/// Checks that the Docker daemon is reachable.
+pub fn check_docker() -> Check {
+    let problems = match Command::new("docker").arg("info").stdout(Stdio::null()).stderr(Stdio::null()).status() {
+        Ok(status) if status.success() => vec![],
+        Ok(status) => vec![format!("`docker info` failed ({})", status)],
+        Err(e) => vec![format!("could not execute docker: {}", e)],
+    };
+    Check { name: "Docker".to_string(), problems }
+}
+
+/// Checks that the output directory is writable, creating it if needed.
+pub fn check_output_dir(output_dir: &str) -> Check {
+    let probe = Path::new(output_dir).join(PROBE_FILE);
+    let problems = match fs::create_dir_all(output_dir).and_then(|_| fs::write(&probe, "")).and_then(|_| fs::remove_file(&probe)) {
+        Ok(()) => vec![],
+        Err(e) => vec![format!("{} is not writable: {}", output_dir, e)],
+    };
+    Check { name: format!("Output directory {}", output_dir), problems }
+}
+
+/// Checks everything of the benchmark in `dir` that can be checked without building it:
+/// the files needed to run it (see `list`), the base images and the exposed port of its `Dockerfile`, its meta data
+/// and that the version migrations of the selected versions find the initial versions.
+pub fn check_benchmark(dir: &str, filter: &BenchmarkFilter) -> Check {
+    let (category, benchmark) = split_benchmark_dir(dir);
+    let name = format!("{}/{}", category, benchmark);
+    if !Path::new(dir).is_dir() {
+        return Check { name, problems: vec![format!("{} does not exist", dir)] };
+    }
+
+    let mut problems: Vec<String> = list::find_missing(dir).iter().map(|missing| format!("missing {}", missing)).collect();
+    if let Ok(dockerfile) = fs::read_to_string(Path::new(dir).join(DOCKERFILE)) {
+        problems.extend(check_dockerfile(&dockerfile).err());
+        problems.extend(validate::check_exposed_ports(&dockerfile).err());
+    }
+    problems.extend(BenchmarkManifest::read_for_benchmark(dir).err());
+    if !Path::new(dir).join(META_DATA_FILE).exists() {
+        return Check { name, problems };
+    }
+
+    // (copied files, [(requested versions, version regex)]), the files contain the first version
+    let migrations = match category.as_str() {
+        "web" => WebBenchmarkMetaData::read_from_directory(dir).map(|meta_data| (meta_data.copy, vec![
+            (meta_data.language_version, meta_data.language_version_regex),
+            (meta_data.framework_version, meta_data.framework_version_regex),
+        ])),
+        _ => BenchmarkMetaData::read_from_directory(dir).map(|meta_data| (meta_data.copy, vec![
+            (meta_data.language_version, meta_data.language_version_regex),
+        ])),
+    };
+    let (copy, migrations) = match migrations {
+        Ok(migrations) => migrations,
+        Err(e) => {
+            problems.push(format!("invalid benchmark.yaml: {}", e));
+            return Check { name, problems };
+        }
+    };
+
+    let copied = copied_sources(dir, copy.as_deref().unwrap_or_default());
+    let read = |path: &str| match (Path::new(path).exists(), copied.get(path)) {
+        (false, Some(source)) => fs::read_to_string(source),
+        _ => fs::read_to_string(path),
+    };
+    for (index, (versions, regex)) in migrations.into_iter().enumerate() {
+        // Only the language versions are selected by `--lang-version`, and single versions are not migrated
+        let selected = versions.iter().skip(1).filter(|version| index > 0 || filter.matches_version(dir, version));
+        for version in selected {
+            let migrator = VersionMigrator::new(dir, regex.clone(), versions[0].clone(), version.clone());
+            if let Err(e) = migrator.check(read) {
+                problems.push(format!("migration to {}: {}", version, e));
+            }
+        }
+    }
+    Check { name, problems }
+}
+
+/// The base images of the `FROM` instructions, an error if there are none.
+fn check_dockerfile(content: &str) -> Result<Vec<String>, String> {
+    let images: Vec<String> = content.lines()
+        .map(|line| line.split_whitespace())
+        .filter(|words| words.clone().next().is_some_and(|word| word.eq_ignore_ascii_case("FROM")))
+        .filter_map(|words| words.skip(1).find(|word| !word.starts_with("--")))
+        .map(str::to_string)
+        .collect();
+    match (content.trim().is_empty(), images.is_empty()) {
+        (true, _) => Err("Dockerfile is empty".to_string()),
+        (false, true) => Err("Dockerfile has no FROM instruction".to_string()),
+        (false, false) => Ok(images),
+    }
+}
+
+/// The `_common` source of every copied destination path, as they only exist in the benchmark directory during its run.
+fn copied_sources(dir: &str, copy: &[CopyValue]) -> IndexMap<String, String> {
+    copy.iter()
+        .filter_map(|file| match file {
+            CopyValue::Primitive(src) => Some((src.clone(), src.clone())),
+            CopyValue::Map(map) => map.get_index(0).map(|(src, dst)| (src.clone(), dst.clone())),
+        })
+        .map(|(src, dst)| (format!("{}/{}", dir, dst), format!("{}/../{}/{}", dir, COMMON_DIR, src)))
+        .collect()
+}
+
+/// Renders an aligned table with `OK` or `FAIL` and the problems of every check.
+pub fn to_table(checks: &[Check]) -> String {
+    let rows: Vec<Vec<String>> = checks.iter().map(|check| vec![
+        check.name.clone(),
+        match check.is_ok() {
+            true => "OK".to_string(),
+            false => format!("FAIL: {}", check.problems.join("; ")),
+        },
+    ]).collect();
+    table::render(&HEADER.map(str::to_string), &rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    /// The root of the benchmarks and the directory of the benchmark in it.
+    fn benchmark_dir(dockerfile: &str) -> (TestDir, String) {
+        let root = TestDir::new("dry-run");
+        let dir = root.join("computation");
+        fs::create_dir_all(dir.join("rust").join(COMMON_DIR)).unwrap();
+        fs::create_dir_all(dir.join("rust").join("rust-1.0")).unwrap();
+        fs::write(dir.join("rust/_common/rust-toolchain"), "channel = \"1.0\"").unwrap();
+        fs::write(dir.join("rust/rust-1.0/Dockerfile"), dockerfile).unwrap();
+        fs::write(dir.join("rust/rust-1.0/benchmark.yaml"), "\
+language: Rust
+mode: Default
+version: ['1.0', '2.0']
+version_regex:
+  Dockerfile: DEFAULT_DOCKER_REGEX
+  rust-toolchain: 'channel = \"([\\d.]+)\"'
+copy:
+  - rust-toolchain
+").unwrap();
+        let dir = dir.join("rust/rust-1.0").to_str().unwrap().to_string();
+        (root, dir)
+    }
+
+    #[test]
+    fn should_check_migrations_of_copied_files() {
+        let (_root, dir) = benchmark_dir("FROM rust:1.0\nCOPY . .\n");
+        assert_eq!(check_benchmark(&dir, &BenchmarkFilter::default()).problems, Vec::<String>::new());
+
+        let (root, dir) = benchmark_dir("FROM rust:3.0\n");
+        let check = check_benchmark(&dir, &BenchmarkFilter::default());
+        assert_eq!(check.problems.len(), 1);
+        assert!(check.problems[0].starts_with("migration to 2.0: Expected 1.0 in "), "{:?}", check.problems);
+
+        // Not selected, so not migrated
+        let filter = BenchmarkFilter { versions: vec!["1.0".to_string()], ..Default::default() };
+        assert!(check_benchmark(&dir, &filter).is_ok());
+
+        fs::write(Path::new(&dir).join(DOCKERFILE), "FROM rust:1.0\nEXPOSE 8080\n").unwrap();
+        assert_eq!(check_benchmark(&dir, &filter).problems, vec!["Dockerfile exposes 8080 instead of 3000"]);
+
+        drop(root);
+        assert!(!check_benchmark(&dir, &filter).is_ok());
+    }
+
+    #[test]
+    fn should_find_base_images() {
+        assert_eq!(check_dockerfile("FROM --platform=linux/amd64 rust:1.74 AS build\nfrom alpine\n"), Ok(vec!["rust:1.74".to_string(), "alpine".to_string()]));
+        assert_eq!(check_dockerfile("  \n"), Err("Dockerfile is empty".to_string()));
+        assert_eq!(check_dockerfile("RUN echo\n"), Err("Dockerfile has no FROM instruction".to_string()));
+    }
+
+    #[test]
+    fn should_render_table() {
+        let checks = [
+            Check { name: "Docker".to_string(), problems: vec![] },
+            Check { name: "computation/rust/rust-1.40".to_string(), problems: vec!["missing Dockerfile".to_string(), "a".to_string()] },
+        ];
+        assert_eq!(to_table(&checks), "\
+Check                       Status
+--------------------------  ---------------------------
+Docker                      OK
+computation/rust/rust-1.40  FAIL: missing Dockerfile; a
+");
+    }
+}
@@ -0,0 +1,209 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::panic;
+use std::time::Duration;
+use crate::utils::format::{self, SizeFormat};
+use crate::utils::log;
+
+/// Why a round, a request or a whole benchmark failed, so callers can match on the cause instead of parsing messages.
+///
+/// Rounds return it from `on_iteration` and the health check. Failures that end the benchmark
+/// (e.g. `TooManyFailures`) are returned up to the suite, which records them (see `run_recording_failure`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum BenchmarkError {
+    /// The benchmark directory is invalid, e.g. its meta data, manifest or custom compose file.
+    InvalidBenchmark(String),
+
+    /// Building the image or starting the containers failed.
+    BuildFailed(String),
+
+    /// The setup command failed, see `BenchmarkOptions::setup_command`.
+    SetupFailed(String),
+
+    /// The container stopped.
+    ContainerCrashed(String),
+
+    /// The container did not pass the health check within `timeout`, `last_error` is the error of the last check.
+    HealthCheckTimeout { timeout: Duration, last_error: String },
+
+    /// The containers or images of the benchmark could not be removed (`docker compose down`), so the next benchmark may not start.
+    TeardownFailed(String),
+
+    /// The benchmark did not answer within the timeout.
+    IterationTimeout(String),
+
+    /// The connection could not be established or broke (e.g. connection refused).
+    Connection(String),
+
+    /// The benchmark answered with an unexpected status.
+    HttpStatus { status: u16, url: String },
+
+    /// The response could not be read (e.g. invalid JSON, number or encoding).
+    ParseError(String),
+
+    /// The response was read but has the wrong content (e.g. a wrong result or an error page).
+    InvalidResponse(String),
+
+    /// The result could not be recorded (e.g. the CSV, JSON or history file), or a reused result could not be copied.
+    WriteFailed(String),
+
+    /// The `memory_p99` of the benchmark exceeded its `max_memory` budget (both in bytes), see `BenchmarkOptions::max_memory`.
+    MemoryBudgetExceeded { memory_p99: i64, max_memory: i64 },
+
+    /// The whole benchmark (build, startup, warmup and rounds of all versions) ran longer than `--benchmark-timeout`
+    /// or its `max_duration`, see `deadline::restrict`.
+    BenchmarkTimeout { elapsed: Duration, timeout: Duration },
+
+    /// Too many rounds failed, `last_error` is the error of the last attempt.
+    TooManyFailures { attempts: usize, last_error: String },
+}
+
+impl BenchmarkError {
+    /// The variant in snake case, e.g. `http_status`, as recorded in the failures of the results.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BenchmarkError::InvalidBenchmark(_) => "invalid_benchmark",
+            BenchmarkError::BuildFailed(_) => "build_failed",
+            BenchmarkError::SetupFailed(_) => "setup_failed",
+            BenchmarkError::ContainerCrashed(_) => "container_crashed",
+            BenchmarkError::HealthCheckTimeout { .. } => "health_check_timeout",
+            BenchmarkError::TeardownFailed(_) => "teardown_failed",
+            BenchmarkError::IterationTimeout(_) => "iteration_timeout",
+            BenchmarkError::Connection(_) => "connection",
+            BenchmarkError::HttpStatus { .. } => "http_status",
+            BenchmarkError::ParseError(_) => "parse_error",
+            BenchmarkError::InvalidResponse(_) => "invalid_response",
+            BenchmarkError::WriteFailed(_) => "write_failed",
+            BenchmarkError::MemoryBudgetExceeded { .. } => "memory_budget_exceeded",
+            BenchmarkError::BenchmarkTimeout { .. } => "benchmark_timeout",
+            BenchmarkError::TooManyFailures { .. } => "too_many_failures",
+        }
+    }
+
+    /// Whether a round that failed with the error is retried, see `measure_rounds`.
+    ///
+    /// - Transient: timeouts and connection errors (e.g. connection refused or reset while the container is overloaded)
+    ///   and the status `408 Request Timeout`, `429 Too Many Requests` and `5xx`.
+    /// - Fatal: any other status (e.g. `400 Bad Request` or `404 Not Found`) and responses that could not be read or were wrong,
+    ///   as the same request fails again, and the errors that end a benchmark anyway (invalid benchmark, build, setup, crash, health check, teardown, memory or time budget,
+    ///   timeout or too many failures) and results that could not be written.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            BenchmarkError::IterationTimeout(_) | BenchmarkError::Connection(_) => true,
+            BenchmarkError::HttpStatus { status, .. } => matches!(status, 408 | 429 | 500..=599),
+            BenchmarkError::InvalidBenchmark(_)
+            | BenchmarkError::BuildFailed(_)
+            | BenchmarkError::SetupFailed(_)
+            | BenchmarkError::ContainerCrashed(_)
+            | BenchmarkError::HealthCheckTimeout { .. }
+            | BenchmarkError::TeardownFailed(_)
+            | BenchmarkError::ParseError(_)
+            | BenchmarkError::InvalidResponse(_)
+            | BenchmarkError::WriteFailed(_)
+            | BenchmarkError::MemoryBudgetExceeded { .. }
+            | BenchmarkError::BenchmarkTimeout { .. }
+            | BenchmarkError::TooManyFailures { .. } => false,
+        }
+    }
+}
+
+impl Display for BenchmarkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BenchmarkError::InvalidBenchmark(message) => write!(f, "Invalid benchmark: {}", message),
+            BenchmarkError::BuildFailed(message) => write!(f, "Build failed: {}", message),
+            BenchmarkError::SetupFailed(message) => write!(f, "Setup failed: {}", message),
+            BenchmarkError::ContainerCrashed(message) => write!(f, "Container crashed: {}", message),
+            BenchmarkError::HealthCheckTimeout { timeout, last_error } => {
+                write!(f, "Not healthy within {}, last: {}", format::format_duration(*timeout), last_error)
+            }
+            BenchmarkError::TeardownFailed(message) => write!(f, "Teardown failed: {}", message),
+            BenchmarkError::IterationTimeout(message) => write!(f, "Timeout: {}", message),
+            BenchmarkError::Connection(message) => write!(f, "Connection failed: {}", message),
+            BenchmarkError::HttpStatus { status, url } => write!(f, "Unexpected status {} of {}", status, url),
+            BenchmarkError::ParseError(message) => write!(f, "Invalid response: {}", message),
+            BenchmarkError::InvalidResponse(message) => write!(f, "Unexpected response: {}", message),
+            BenchmarkError::WriteFailed(message) => write!(f, "Failed to write the result: {}", message),
+            BenchmarkError::MemoryBudgetExceeded { memory_p99, max_memory } => {
+                write!(f, "Memory budget exceeded: p99 {} > max {}", memory_p99.bytes_to_string(), max_memory.bytes_to_string())
+            }
+            BenchmarkError::BenchmarkTimeout { elapsed, timeout } => {
+                write!(f, "Benchmark timeout exceeded: ran {} > max {}", format::format_duration(*elapsed), format::format_duration(*timeout))
+            }
+            BenchmarkError::TooManyFailures { attempts, last_error } => write!(f, "Too many errors ({} failed attempts), last: {}", attempts, last_error),
+        }
+    }
+}
+
+impl std::error::Error for BenchmarkError {}
+
+impl From<reqwest::Error> for BenchmarkError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            BenchmarkError::IterationTimeout(e.to_string())
+        } else if let (Some(status), Some(url)) = (e.status(), e.url()) {
+            BenchmarkError::HttpStatus { status: status.as_u16(), url: url.to_string() }
+        } else if e.is_decode() {
+            BenchmarkError::ParseError(e.to_string())
+        } else {
+            BenchmarkError::Connection(e.to_string())
+        }
+    }
+}
+
+impl From<io::Error> for BenchmarkError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => BenchmarkError::IterationTimeout(e.to_string()),
+            io::ErrorKind::InvalidData => BenchmarkError::ParseError(e.to_string()),
+            _ => BenchmarkError::Connection(e.to_string()),
+        }
+    }
+}
+
+/// While the output is captured (see `log::capture`), panics are part of the output.
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| match log::is_captured() {
+        // Printed to stderr, they would break the screen of `--tui`
+        true => logln!("{}", info),
+        false => default_hook(info),
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_classify_io_errors() {
+        assert_eq!(BenchmarkError::from(io::Error::from(io::ErrorKind::TimedOut)).kind(), "iteration_timeout");
+        assert_eq!(BenchmarkError::from(io::Error::from(io::ErrorKind::ConnectionRefused)).kind(), "connection");
+        assert_eq!(BenchmarkError::from(io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8")).kind(), "parse_error");
+    }
+
+    #[test]
+    fn should_classify_transient_errors() {
+        let status = |status: u16| BenchmarkError::HttpStatus { status, url: "http://localhost:3000".to_string() };
+        assert!(BenchmarkError::from(io::Error::from(io::ErrorKind::ConnectionRefused)).is_transient());
+        assert!(BenchmarkError::from(io::Error::from(io::ErrorKind::TimedOut)).is_transient());
+        assert!(status(503).is_transient());
+        assert!(status(429).is_transient());
+        assert!(!status(400).is_transient());
+        assert!(!status(404).is_transient());
+        assert!(!BenchmarkError::InvalidResponse("42 (expected: 43)".to_string()).is_transient());
+        assert!(!BenchmarkError::from(io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8")).is_transient());
+        assert!(!BenchmarkError::ContainerCrashed("exited with 137".to_string()).is_transient());
+    }
+
+    #[test]
+    fn should_describe_errors() {
+        let error = BenchmarkError::TooManyFailures { attempts: 11, last_error: "Timeout: operation timed out".to_string() };
+        assert_eq!(error.to_string(), "Too many errors (11 failed attempts), last: Timeout: operation timed out");
+        assert_eq!(BenchmarkError::InvalidBenchmark("missing benchmark.yaml".to_string()).kind(), "invalid_benchmark");
+
+        let error = BenchmarkError::HealthCheckTimeout { timeout: Duration::from_secs(60), last_error: "Connection failed: refused".to_string() };
+        assert_eq!(error.kind(), "health_check_timeout");
+        assert!(!error.is_transient());
+    }
+}
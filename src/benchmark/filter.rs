@@ -0,0 +1,312 @@
+use std::fs;
+use std::path::Path;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use crate::utils::copy_files::COMMON_DIR;
+use crate::utils::glob;
+use crate::utils::meta_data_parser::BenchmarkManifest;
+
+pub(crate) const META_DATA_FILE: &str = "benchmark.yaml";
+
+/// Selects benchmarks by language, version and benchmark directory (see `glob::matches`) and by the `tags` of their meta data.
+/// Every kind of filter matches if any of its patterns matches, and all kinds have to match.
+/// The skip filters (`skip_languages`, `skip_benchmarks` and `exclude_tags`) are applied after them, see `skips`.
+#[derive(Clone, Debug, Default)]
+pub struct BenchmarkFilter {
+    /// Patterns of the language directory, e.g. `rust`. Case-insensitive.
+    pub languages: Vec<String>,
+
+    /// Patterns of the requested version, e.g. `1.7*`, or of `<language>-<version>`, e.g. `rust-1.*`.
+    pub versions: Vec<String>,
+
+    /// Patterns of the benchmark directory, e.g. `axum-*`, or of `<language>/<benchmark>` if the pattern contains a `/`.
+    pub benchmarks: Vec<String>,
+
+    /// Tags of the meta data, e.g. `fast`. A benchmark is selected if it has any of them.
+    pub tags: Vec<String>,
+
+    /// Tags of the meta data, e.g. `needs-db`. A benchmark with any of them is not selected, whatever else matches.
+    pub exclude_tags: Vec<String>,
+
+    /// Patterns of the language directory like `languages`, a benchmark of a matching language is not selected.
+    pub skip_languages: Vec<String>,
+
+    /// Patterns of the benchmark directory like `benchmarks`, a matching benchmark is not selected.
+    pub skip_benchmarks: Vec<String>,
+}
+
+impl BenchmarkFilter {
+    pub fn is_empty(&self) -> bool {
+        self.languages.is_empty() && self.versions.is_empty() && self.benchmarks.is_empty() && self.tags.is_empty() && self.exclude_tags.is_empty()
+            && self.skip_languages.is_empty() && self.skip_benchmarks.is_empty()
+    }
+
+    /// Whether the benchmark in `dir` (`<category dir>/<language>/<benchmark>`) is excluded by the skip filters,
+    /// whether or not the other filters select it.
+    pub fn skips(&self, dir: &str) -> bool {
+        let path = Path::new(dir);
+        let name = |path: Option<&Path>| path.and_then(Path::file_name).map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let (language, benchmark) = (name(path.parent()), name(Some(path)));
+        any_language_matches(&self.skip_languages, &language)
+            || any_benchmark_matches(&self.skip_benchmarks, &language, &benchmark)
+            || any_tag_matches(&self.exclude_tags, &read_tags(dir))
+    }
+
+    /// The directories of the benchmarks of `category_dir` that the other filters select but the skip filters exclude.
+    pub fn excluded(&self, category_dir: &str) -> Vec<String> {
+        let include = BenchmarkFilter { exclude_tags: Vec::new(), skip_languages: Vec::new(), skip_benchmarks: Vec::new(), ..self.clone() };
+        include.select(category_dir).into_iter().filter(|dir| self.skips(dir)).collect()
+    }
+
+    /// The directories of the benchmarks of `category_dir` (e.g. `benchmark/web`) that match, with at least one matching version.
+    pub fn select(&self, category_dir: &str) -> Vec<String> {
+        let mut selected = Vec::new();
+        for language in sorted_dirs(category_dir) {
+            if !self.matches_language(&language) {
+                continue;
+            }
+            for benchmark in sorted_dirs(&format!("{}/{}", category_dir, language)) {
+                let dir = format!("{}/{}/{}", category_dir, language, benchmark);
+                if benchmark == COMMON_DIR || !self.matches_benchmark(&language, &benchmark) || !self.matches_tags(&read_tags(&dir)) {
+                    continue;
+                }
+                if read_versions(&dir).iter().any(|version| self.matches_version(&dir, version)) {
+                    selected.push(dir);
+                }
+            }
+        }
+        selected
+    }
+
+    /// Whether the requested `version` of the benchmark in `dir` is selected.
+    pub fn matches_version(&self, dir: &str, version: &str) -> bool {
+        // `<category dir>/<language>/<benchmark>`
+        let language = Path::new(dir).parent().and_then(Path::file_name).map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+        let qualified = format!("{}-{}", language, version);
+        self.versions.is_empty() || self.versions.iter().any(|pattern| glob::matches(pattern, version) || glob::matches(pattern, &qualified))
+    }
+
+    /// Whether a benchmark with these `tags` is selected by `tags` and not excluded by `exclude_tags`.
+    pub fn matches_tags(&self, tags: &[String]) -> bool {
+        (self.tags.is_empty() || any_tag_matches(&self.tags, tags)) && !any_tag_matches(&self.exclude_tags, tags)
+    }
+
+    /// The `tags` and `exclude_tags` patterns that match no tag of the benchmarks of the `category_dirs`, most likely a typo.
+    pub fn unknown_tags(&self, category_dirs: &[String]) -> Vec<String> {
+        let known: Vec<String> = category_dirs.iter()
+            .flat_map(|category_dir| sorted_dirs(category_dir).into_iter().map(move |language| format!("{}/{}", category_dir, language)))
+            .flat_map(|language_dir| sorted_dirs(&language_dir).into_iter().map(move |benchmark| format!("{}/{}", language_dir, benchmark)))
+            .flat_map(|dir| read_tags(&dir))
+            .collect();
+        self.tags.iter().chain(&self.exclude_tags).filter(|pattern| !known.iter().any(|tag| glob::matches(pattern, tag))).cloned().collect()
+    }
+
+    pub(crate) fn matches_language(&self, language: &str) -> bool {
+        (self.languages.is_empty() || any_language_matches(&self.languages, language)) && !any_language_matches(&self.skip_languages, language)
+    }
+
+    pub(crate) fn matches_benchmark(&self, language: &str, benchmark: &str) -> bool {
+        (self.benchmarks.is_empty() || any_benchmark_matches(&self.benchmarks, language, benchmark))
+            && !any_benchmark_matches(&self.skip_benchmarks, language, benchmark)
+    }
+}
+
+fn any_language_matches(patterns: &[String], language: &str) -> bool {
+    let language = language.to_lowercase();
+    patterns.iter().any(|pattern| glob::matches(&pattern.to_lowercase(), &language))
+}
+
+fn any_tag_matches(patterns: &[String], tags: &[String]) -> bool {
+    patterns.iter().any(|pattern| tags.iter().any(|tag| glob::matches(pattern, tag)))
+}
+
+fn any_benchmark_matches(patterns: &[String], language: &str, benchmark: &str) -> bool {
+    let qualified = format!("{}/{}", language, benchmark);
+    patterns.iter().any(|pattern| match pattern.contains('/') {
+        true => glob::matches(pattern, &qualified),
+        false => glob::matches(pattern, benchmark),
+    })
+}
+
+/// The names of the directories in `dir`, sorted so the selection is stable.
+pub(crate) fn sorted_dirs(dir: &str) -> Vec<String> {
+    let mut dirs: Vec<String> = fs::read_dir(dir)
+        .unwrap_or_else(|_| panic!("Could not read directory {}", dir))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// The benchmark directories of the `language_dirs` (e.g. `benchmark/web/rust`) in the order they run:
+/// sorted, or shuffled by `rng` for `--shuffle`. All versions of a benchmark run within its directory, so they stay adjacent.
+pub fn execution_order(language_dirs: &[String], rng: Option<&mut StdRng>) -> Vec<String> {
+    let mut dirs: Vec<String> = language_dirs.iter()
+        .flat_map(|language_dir| sorted_dirs(language_dir).into_iter()
+            .filter(|benchmark| benchmark != COMMON_DIR)
+            .map(move |benchmark| format!("{}/{}", language_dir, benchmark)))
+        .collect();
+    if let Some(rng) = rng {
+        dirs.shuffle(rng);
+    }
+    dirs
+}
+
+/// Moves the benchmark directories matching the patterns of `--order` to the front, in the order of the patterns.
+/// A pattern matches `<category>/<language>/<benchmark>` or `<language>/<benchmark>` (see `glob::matches`), e.g. `web/*` or `rust/*`.
+/// A directory matching several patterns is placed by the first one, the others keep their order after all of them.
+pub fn apply_order(mut dirs: Vec<String>, order: &[String]) -> Vec<String> {
+    let rank = |dir: &String| {
+        let qualified = dir.trim_start_matches("benchmark/");
+        let benchmark = qualified.split_once('/').map_or(qualified, |(_, benchmark)| benchmark);
+        order.iter().position(|pattern| glob::matches(pattern, qualified) || glob::matches(pattern, benchmark)).unwrap_or(order.len())
+    };
+    // Stable, so the directories of a pattern stay sorted
+    dirs.sort_by_key(rank);
+    dirs
+}
+
+/// The requested versions of the benchmark, without parsing the rest of the meta data.
+pub(crate) fn read_versions(dir: &str) -> Vec<String> {
+    let content = fs::read_to_string(Path::new(dir).join(META_DATA_FILE)).unwrap_or_default();
+    let meta_data: serde_yaml::Value = serde_yaml::from_str(&content).unwrap_or_default();
+    meta_data.get("version")
+        .and_then(|versions| versions.as_sequence())
+        .map(|versions| versions.iter().filter_map(|version| version.as_str()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// The `tags` of the benchmark and of its manifest (see `BenchmarkManifest`), e.g. `[fast, io-heavy]`, empty if it has none.
+pub(crate) fn read_tags(dir: &str) -> Vec<String> {
+    let content = fs::read_to_string(Path::new(dir).join(META_DATA_FILE)).unwrap_or_default();
+    let meta_data: serde_yaml::Value = serde_yaml::from_str(&content).unwrap_or_default();
+    let mut tags: Vec<String> = meta_data.get("tags")
+        .and_then(|tags| tags.as_sequence())
+        .map(|tags| tags.iter().filter_map(|tag| tag.as_str()).map(str::to_string).collect())
+        .unwrap_or_default();
+    // An invalid manifest stops the run when the benchmarks are selected
+    if let Ok(Some(manifest)) = BenchmarkManifest::read_for_benchmark(dir) {
+        for tag in manifest.tags {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    fn filter(languages: &[&str], versions: &[&str], benchmarks: &[&str]) -> BenchmarkFilter {
+        let strings = |patterns: &[&str]| patterns.iter().map(|pattern| pattern.to_string()).collect();
+        BenchmarkFilter { languages: strings(languages), versions: strings(versions), benchmarks: strings(benchmarks), ..Default::default() }
+    }
+
+    /// The root of the benchmarks and the `web` category dir in it.
+    fn category_dir(name: &str) -> (TestDir, String) {
+        let root = TestDir::new(&format!("filter-{}", name));
+        let dir = root.join("web");
+        let benchmarks = [
+            ("rust/axum-0.7-rust-1.74", "['1.74']", "[fast]"),
+            ("rust/actix-4-rust-1.74", "['1.74', '1.80']", "[fast, needs-db]"),
+            ("go/gin-1.9-go-1.21", "['1.21']", "[]"),
+        ];
+        for (benchmark, versions, tags) in benchmarks {
+            fs::create_dir_all(dir.join(benchmark)).unwrap();
+            fs::write(dir.join(benchmark).join(META_DATA_FILE), format!("language: X\nversion: {}\ntags: {}\n", versions, tags)).unwrap();
+        }
+        fs::create_dir_all(dir.join("rust").join(COMMON_DIR)).unwrap();
+        let dir = dir.to_str().unwrap().to_string();
+        (root, dir)
+    }
+
+    #[test]
+    fn should_intersect_filters() {
+        let (_root, dir) = category_dir("intersect");
+        let names = |filter: BenchmarkFilter| -> Vec<String> {
+            filter.select(&dir).iter().map(|selected| selected.trim_start_matches(&format!("{}/", dir)).to_string()).collect()
+        };
+
+        assert_eq!(names(filter(&[], &[], &[])).len(), 3);
+        assert_eq!(names(filter(&["Rust"], &[], &[])), vec!["rust/actix-4-rust-1.74", "rust/axum-0.7-rust-1.74"]);
+        assert_eq!(names(filter(&["rust"], &["1.8*"], &[])), vec!["rust/actix-4-rust-1.74"]);
+        assert_eq!(names(filter(&[], &["rust-1.*", "go-1.21"], &["axum-*", "go/*"])), vec!["go/gin-1.9-go-1.21", "rust/axum-0.7-rust-1.74"]);
+        assert!(names(filter(&["go"], &["1.74"], &[])).is_empty());
+
+        let tags = |tags: &[&str], exclude_tags: &[&str]| BenchmarkFilter {
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            exclude_tags: exclude_tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        };
+        assert_eq!(names(tags(&["fast"], &[])), vec!["rust/actix-4-rust-1.74", "rust/axum-0.7-rust-1.74"]);
+        assert_eq!(names(tags(&["fast"], &["needs-db"])), vec!["rust/axum-0.7-rust-1.74"]);
+        assert_eq!(names(tags(&[], &["needs-db"])), vec!["go/gin-1.9-go-1.21", "rust/axum-0.7-rust-1.74"]);
+        assert_eq!(names(tags(&[], &["needs-*"])), vec!["go/gin-1.9-go-1.21", "rust/axum-0.7-rust-1.74"]);
+        assert_eq!(tags(&["fast", "fsat"], &["needs-*", "slow"]).unknown_tags(std::slice::from_ref(&dir)), vec!["fsat", "slow"]);
+    }
+
+    #[test]
+    fn should_skip_after_include_filters() {
+        let (_root, dir) = category_dir("skip");
+        let names = |dirs: Vec<String>| -> Vec<String> { dirs.iter().map(|selected| selected.trim_start_matches(&format!("{}/", dir)).to_string()).collect() };
+        let strings = |patterns: &[&str]| patterns.iter().map(|pattern| pattern.to_string()).collect();
+
+        let skip = BenchmarkFilter { languages: strings(&["rust"]), skip_benchmarks: strings(&["actix-*"]), ..Default::default() };
+        assert_eq!(names(skip.select(&dir)), vec!["rust/axum-0.7-rust-1.74"]);
+        assert_eq!(names(skip.excluded(&dir)), vec!["rust/actix-4-rust-1.74"]);
+
+        let skip = BenchmarkFilter { skip_languages: strings(&["GO"]), exclude_tags: strings(&["needs-db"]), ..Default::default() };
+        assert_eq!(names(skip.select(&dir)), vec!["rust/axum-0.7-rust-1.74"]);
+        assert_eq!(names(skip.excluded(&dir)), vec!["go/gin-1.9-go-1.21", "rust/actix-4-rust-1.74"]);
+        assert!(skip.skips(&format!("{}/go/gin-1.9-go-1.21", dir)));
+        assert!(!skip.skips(&format!("{}/rust/axum-0.7-rust-1.74", dir)));
+        assert!(!skip.is_empty());
+    }
+
+    #[test]
+    fn should_shuffle_reproducibly() {
+        let (_root, dir) = category_dir("shuffle");
+        let language_dirs = vec![format!("{}/go", dir), format!("{}/rust", dir)];
+        let names = |dirs: Vec<String>| -> Vec<String> { dirs.iter().map(|selected| selected.trim_start_matches(&format!("{}/", dir)).to_string()).collect() };
+
+        assert_eq!(names(execution_order(&language_dirs, None)), vec!["go/gin-1.9-go-1.21", "rust/actix-4-rust-1.74", "rust/axum-0.7-rust-1.74"]);
+        let shuffled = execution_order(&language_dirs, Some(&mut StdRng::seed_from_u64(42)));
+        assert_eq!(shuffled, execution_order(&language_dirs, Some(&mut StdRng::seed_from_u64(42))));
+        let mut sorted = shuffled.clone();
+        sorted.sort();
+        assert_eq!(sorted, execution_order(&language_dirs, None));
+    }
+
+    #[test]
+    fn should_apply_order() {
+        let dirs: Vec<String> = ["computation/go/go-1.21", "computation/rust/rust-1.74", "web/go/gin-1.9", "web/rust/axum-0.7"].iter()
+            .map(|dir| format!("benchmark/{}", dir))
+            .collect();
+        let names = |dirs: Vec<String>| -> Vec<String> { dirs.iter().map(|dir| dir.trim_start_matches("benchmark/").to_string()).collect() };
+
+        assert_eq!(apply_order(dirs.clone(), &[]), dirs);
+        assert_eq!(
+            names(apply_order(dirs.clone(), &["web/*".to_string(), "rust/*".to_string()])),
+            vec!["web/go/gin-1.9", "web/rust/axum-0.7", "computation/rust/rust-1.74", "computation/go/go-1.21"],
+        );
+        assert_eq!(
+            names(apply_order(dirs, &["go/gin-1.9".to_string(), "unknown/*".to_string()])),
+            vec!["web/go/gin-1.9", "computation/go/go-1.21", "computation/rust/rust-1.74", "web/rust/axum-0.7"],
+        );
+    }
+
+    #[test]
+    fn should_match_versions_with_and_without_language() {
+        let filter = filter(&[], &["rust-1.7*"], &[]);
+        assert!(filter.matches_version("benchmark/computation/rust/rust-1.40", "1.79"));
+        assert!(!filter.matches_version("benchmark/computation/rust/rust-1.40", "1.60"));
+        assert!(!filter.matches_version("benchmark/computation/go/go-1.21", "1.79"));
+        assert!(BenchmarkFilter::default().matches_version("benchmark/computation/go/go-1.21", "1.21"));
+    }
+}
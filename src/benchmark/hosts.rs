@@ -0,0 +1,321 @@
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Duration;
+use crate::benchmark::benchmark::split_benchmark_dir;
+use crate::output::json::{self, LoadedResults};
+use crate::output::paths::OutputPaths;
+use crate::utils::docker_context::DockerContext;
+use crate::utils::machine::MachineInfo;
+
+/// Directory within the run directory with the results and logs of the workers of every host, `hosts/<host>`.
+pub const HOSTS_DIR: &str = "hosts";
+
+/// How often an idle host checks whether a benchmark of a dead host was queued again.
+const WAIT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A Docker host of `--hosts` that runs whole benchmarks: a `DOCKER_HOST` URI or the name of a Docker context.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Host {
+    /// As given, e.g. `ssh://ci@bench-1` or `bench-1`.
+    pub name: String,
+
+    /// The URI for `DOCKER_HOST`, `None` for a Docker context.
+    uri: Option<String>,
+
+    /// Where the benchmark containers publish their port, e.g. `bench-1`.
+    pub address: String,
+}
+
+impl Host {
+    /// Parses a `DOCKER_HOST` URI (containing `://`) or looks up the endpoint of the Docker context of this name in `config_dir`
+    /// (see `docker_context::config_dir`).
+    pub fn parse(spec: &str, config_dir: &Path) -> Result<Host, String> {
+        let spec = spec.trim();
+        if spec.contains("://") {
+            return Ok(Host { name: spec.to_string(), uri: Some(spec.to_string()), address: uri_address(spec)? });
+        }
+        if spec.is_empty() {
+            return Err("empty host".to_string());
+        }
+
+        let context = DockerContext::resolve(spec, config_dir)?;
+        Ok(Host { name: context.name, uri: None, address: uri_address(&context.endpoint)? })
+    }
+
+    /// Points the docker CLI of the command (and of a worker) at this host.
+    fn select(&self, command: &mut Command) {
+        match &self.uri {
+            Some(uri) => command.env("DOCKER_HOST", uri).env_remove("DOCKER_CONTEXT"),
+            // `DOCKER_HOST` would win over the context
+            None => command.env("DOCKER_CONTEXT", &self.name).env_remove("DOCKER_HOST"),
+        };
+    }
+
+    fn docker(&self, args: &[&str]) -> Command {
+        let mut command = Command::new("docker");
+        command.args(args);
+        self.select(&mut command);
+        command
+    }
+
+    pub fn is_reachable(&self) -> bool {
+        self.docker(&["info"]).stdout(Stdio::null()).stderr(Stdio::null()).status().is_ok_and(|status| status.success())
+    }
+
+    /// The machine as reported by the Docker daemon of the host, `None` if it is not reachable.
+    pub fn machine_info(&self, anonymize: bool) -> Option<MachineInfo> {
+        let output = self.docker(&["info", "--format", "{{json .}}"]).stderr(Stdio::null()).output().ok()?;
+        match output.status.success() {
+            true => MachineInfo::from_docker_info(&String::from_utf8_lossy(&output.stdout), anonymize),
+            false => None,
+        }
+    }
+
+    /// The directory of the host within `HOSTS_DIR`, the name with everything but letters, digits, `.`, `-` and `_` replaced.
+    pub fn dir_name(&self) -> String {
+        self.name.chars()
+            .map(|c| match c.is_ascii_alphanumeric() || ".-_".contains(c) {
+                true => c,
+                false => '_',
+            })
+            .collect()
+    }
+}
+
+/// The host of a `DOCKER_HOST` URI, `localhost` for local sockets.
+/// Examples: `ssh://ci@bench-1:2222` -> `bench-1`, `tcp://10.0.0.2:2376` -> `10.0.0.2`, `unix:///var/run/docker.sock` -> `localhost`.
+pub(crate) fn uri_address(uri: &str) -> Result<String, String> {
+    let (scheme, rest) = uri.split_once("://").ok_or_else(|| format!("expected a URI like ssh://user@host, got {:?}", uri))?;
+    if scheme == "unix" || scheme == "npipe" {
+        return Ok("localhost".to_string());
+    }
+
+    let authority = rest.split('/').next().unwrap_or_default();
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let address = match host.find(']') {
+        // IPv6 addresses keep their brackets for the URLs
+        Some(end) if host.starts_with('[') => &host[..=end],
+        _ => host.split(':').next().unwrap_or_default(),
+    };
+    match address.is_empty() {
+        true => Err(format!("no host in {:?}", uri)),
+        false => Ok(address.to_string()),
+    }
+}
+
+/// What a host does next, see `Scheduler::next`.
+#[derive(Debug, PartialEq)]
+pub enum Assignment {
+    /// Run the benchmark in this directory.
+    Run(String),
+
+    /// Nothing is queued, but benchmarks of other hosts are queued again if their host dies.
+    Wait,
+
+    /// Every benchmark ran.
+    Done,
+}
+
+/// Hands out whole benchmarks to the hosts, so all versions of a benchmark are measured on the same machine.
+/// Only one web benchmark runs at a time: its load is generated by this machine, which would otherwise share its CPU
+/// and network between the loads of several hosts.
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    queue: VecDeque<String>,
+
+    /// The benchmark every busy host runs, by the name of the host.
+    running: Vec<(String, String)>,
+}
+
+impl Scheduler {
+    pub fn new(dirs: Vec<String>) -> Scheduler {
+        Scheduler { queue: dirs.into(), running: Vec::new() }
+    }
+
+    /// The next queued benchmark, skipping web benchmarks while another host runs one.
+    pub fn next(&mut self, host: &str) -> Assignment {
+        let web_running = self.running.iter().any(|(_, dir)| is_web(dir));
+        match self.queue.iter().position(|dir| !web_running || !is_web(dir)) {
+            Some(index) => {
+                let dir = self.queue.remove(index).expect("Index of a queued benchmark");
+                self.running.push((host.to_string(), dir.clone()));
+                Assignment::Run(dir)
+            }
+            None if self.queue.is_empty() && self.running.is_empty() => Assignment::Done,
+            None => Assignment::Wait,
+        }
+    }
+
+    /// Ends the benchmark of the host, whether it succeeded or failed.
+    pub fn finish(&mut self, host: &str) {
+        self.running.retain(|(running_host, _)| running_host != host);
+    }
+
+    /// Queues the benchmark of the dead host again as the next one, so another host runs all its versions.
+    pub fn host_died(&mut self, host: &str) -> Option<String> {
+        let index = self.running.iter().position(|(running_host, _)| running_host == host)?;
+        let (_, dir) = self.running.remove(index);
+        self.queue.push_front(dir.clone());
+        Some(dir)
+    }
+
+    /// The benchmarks that did not run because every host died.
+    pub fn remaining(self) -> Vec<String> {
+        self.queue.into()
+    }
+}
+
+fn is_web(dir: &str) -> bool {
+    split_benchmark_dir(dir).0 == "web"
+}
+
+/// What happened on a host, see `run_on_hosts`.
+pub enum HostEvent<'a> {
+    Started { host: &'a Host, dir: String },
+
+    /// The worker ended. `results` are `None` if it did not write any, `error` is set if it did not succeed.
+    Finished { host: &'a Host, dir: String, results: Option<Box<LoadedResults>>, error: Option<String>, log: PathBuf },
+
+    /// The host became unreachable while running the benchmark, which was queued again for the other hosts.
+    Died { host: &'a Host, dir: String },
+}
+
+/// Runs every benchmark directory on one of the hosts: every host runs one benchmark at a time and only one host a web benchmark
+/// (see `Scheduler`), each with a worker process
+/// `sharkbench <worker_args> --service-host <address> run <dir>` that writes into `<run dir>/hosts/<host>` (see `OutputPaths::host`),
+/// with the output of the worker in `<category>/<benchmark>.log` there.
+/// `on_event` is called on the calling thread. Returns the benchmarks that did not run because every host died.
+pub fn run_on_hosts<F>(hosts: &[Host], dirs: Vec<String>, worker_args: &[String], paths: &OutputPaths, mut on_event: F) -> Vec<String>
+    where F: FnMut(HostEvent)
+{
+    let executable = std::env::current_exe().expect("Failed to locate the sharkbench executable");
+    let scheduler = Mutex::new(Scheduler::new(dirs));
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for host in hosts {
+            let sender = sender.clone();
+            let (scheduler, executable) = (&scheduler, &executable);
+            scope.spawn(move || loop {
+                let assignment = scheduler.lock().unwrap().next(&host.name);
+                let dir = match assignment {
+                    Assignment::Run(dir) => dir,
+                    Assignment::Wait => {
+                        thread::sleep(WAIT_INTERVAL);
+                        continue;
+                    }
+                    Assignment::Done => break,
+                };
+
+                let _ = sender.send(HostEvent::Started { host, dir: dir.clone() });
+                let host_paths = paths.host(&host.dir_name());
+                let (category, benchmark) = split_benchmark_dir(&dir);
+                let log = host_paths.file(&format!("{}/{}.log", category, benchmark));
+                let error = run_worker(host, executable, &dir, worker_args, &host_paths, &log).err();
+                if error.is_some() && !host.is_reachable() {
+                    let dir = scheduler.lock().unwrap().host_died(&host.name).unwrap_or(dir);
+                    let _ = sender.send(HostEvent::Died { host, dir });
+                    break;
+                }
+
+                scheduler.lock().unwrap().finish(&host.name);
+                let results = json::load_results(&host_paths.results()).ok().map(Box::new);
+                let _ = sender.send(HostEvent::Finished { host, dir, results, error, log });
+            });
+        }
+
+        drop(sender);
+        for event in receiver {
+            on_event(event);
+        }
+    });
+    scheduler.into_inner().unwrap().remaining()
+}
+
+/// Runs the benchmark with a worker process on the host, returns why it did not succeed.
+fn run_worker(host: &Host, executable: &Path, dir: &str, worker_args: &[String], host_paths: &OutputPaths, log: &Path) -> Result<(), String> {
+    // The results of the previous benchmark of the host must not be taken for the ones of this benchmark
+    let _ = fs::remove_file(host_paths.results());
+    let log_file = log.parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| File::create(log))
+        .map_err(|e| format!("could not create {}: {}", log.display(), e))?;
+    let stderr = log_file.try_clone().map_err(|e| format!("could not create {}: {}", log.display(), e))?;
+
+    let mut command = Command::new(executable);
+    command.args(worker_args)
+        .args(["--service-host", &host.address, "run", dir, "--run-dir"])
+        .arg(host_paths.run_dir())
+        .stdin(Stdio::null())
+        .stdout(log_file)
+        .stderr(stderr);
+    host.select(&mut command);
+    match command.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("worker exited with {}", status)),
+        Err(e) => Err(format!("could not start worker: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    #[test]
+    fn should_parse_docker_host_uris() {
+        assert_eq!(Host::parse("ssh://ci@bench-1:2222", Path::new("")).unwrap().address, "bench-1");
+        assert_eq!(Host::parse(" tcp://10.0.0.2:2376", Path::new("")).unwrap(), Host {
+            name: "tcp://10.0.0.2:2376".to_string(),
+            uri: Some("tcp://10.0.0.2:2376".to_string()),
+            address: "10.0.0.2".to_string(),
+        });
+        assert_eq!(Host::parse("tcp://[fd00::2]:2376", Path::new("")).unwrap().address, "[fd00::2]");
+        assert_eq!(Host::parse("unix:///var/run/docker.sock", Path::new("")).unwrap().address, "localhost");
+        assert!(Host::parse("ssh://", Path::new("")).is_err());
+        assert!(Host::parse("", Path::new("")).is_err());
+        assert_eq!(Host::parse("ssh://ci@bench-1", Path::new("")).unwrap().dir_name(), "ssh___ci_bench-1");
+    }
+
+    #[test]
+    fn should_look_up_docker_contexts() {
+        let config_dir = TestDir::new("hosts");
+        let dir = config_dir.join("contexts/meta/0a1b");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("meta.json"), r#"{"Name":"bench-2","Endpoints":{"docker":{"Host":"ssh://ci@bench-2"}}}"#).unwrap();
+
+        assert_eq!(Host::parse("bench-2", &config_dir), Ok(Host { name: "bench-2".to_string(), uri: None, address: "bench-2".to_string() }));
+        assert!(Host::parse("bench-3", &config_dir).is_err());
+    }
+
+    #[test]
+    fn should_queue_benchmarks_of_dead_hosts_again() {
+        let mut scheduler = Scheduler::new(vec!["benchmark/computation/a".to_string(), "benchmark/computation/b".to_string()]);
+        assert_eq!(scheduler.next("bench-1"), Assignment::Run("benchmark/computation/a".to_string()));
+        assert_eq!(scheduler.next("bench-2"), Assignment::Run("benchmark/computation/b".to_string()));
+        // bench-1 may still die, so bench-2 waits for it
+        scheduler.finish("bench-2");
+        assert_eq!(scheduler.next("bench-2"), Assignment::Wait);
+
+        assert_eq!(scheduler.host_died("bench-1"), Some("benchmark/computation/a".to_string()));
+        assert_eq!(scheduler.next("bench-2"), Assignment::Run("benchmark/computation/a".to_string()));
+        scheduler.finish("bench-2");
+        assert_eq!(scheduler.next("bench-2"), Assignment::Done);
+        assert_eq!(scheduler.remaining(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn should_run_one_web_benchmark_at_a_time() {
+        let dirs = ["benchmark/web/a", "benchmark/web/b", "benchmark/computation/c"].map(str::to_string).to_vec();
+        let mut scheduler = Scheduler::new(dirs);
+        assert_eq!(scheduler.next("bench-1"), Assignment::Run("benchmark/web/a".to_string()));
+        assert_eq!(scheduler.next("bench-2"), Assignment::Run("benchmark/computation/c".to_string()));
+        assert_eq!(scheduler.next("bench-3"), Assignment::Wait);
+
+        scheduler.finish("bench-1");
+        assert_eq!(scheduler.next("bench-3"), Assignment::Run("benchmark/web/b".to_string()));
+    }
+}
@@ -0,0 +1,216 @@
+use std::fs;
+use std::path::Path;
+use serde::Serialize;
+use crate::benchmark::benchmark::CUSTOM_COMPOSE_FILE;
+use crate::benchmark::filter::{self, BenchmarkFilter, META_DATA_FILE};
+use crate::utils::copy_files::COMMON_DIR;
+use crate::utils::meta_data_parser::BenchmarkManifest;
+use crate::utils::table;
+
+pub(crate) const DOCKERFILE: &str = "Dockerfile";
+
+const HEADER: [&str; 6] = ["Category", "Benchmark", "Language", "Versions", "Tags", "Status"];
+
+/// A benchmark found in the benchmark tree, see `list`.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ListedBenchmark {
+    /// E.g. `web`
+    pub category: String,
+
+    /// `<language>/<benchmark>`, as accepted by `--only`
+    pub benchmark: String,
+
+    /// The `language` of the meta data, of the manifest or the language directory if neither has one.
+    pub language: String,
+
+    /// The `website` of the manifest, see `BenchmarkManifest`.
+    pub website: Option<String>,
+    pub versions: Vec<String>,
+
+    /// The `tags` of the meta data and of the manifest, selectable with `--tags`.
+    pub tags: Vec<String>,
+
+    /// What is missing to run the benchmark, e.g. `Dockerfile`. Empty if it can run.
+    pub missing: Vec<String>,
+
+    /// Why the manifest is invalid, e.g. missing `website` in _benchmark.yaml.
+    pub invalid: Option<String>,
+}
+
+/// Walks the benchmarks of the categories in `benchmark_dir` (sorted, `_common` excluded) that match the filter.
+/// Only the files are inspected, nothing is built or started.
+pub fn list(benchmark_dir: &str, categories: &[&str], benchmark_filter: &BenchmarkFilter) -> Vec<ListedBenchmark> {
+    let mut listed = Vec::new();
+    for category in categories {
+        let category_dir = format!("{}/{}", benchmark_dir, category);
+        for language in filter::sorted_dirs(&category_dir) {
+            if !benchmark_filter.matches_language(&language) {
+                continue;
+            }
+            for benchmark in filter::sorted_dirs(&format!("{}/{}", category_dir, language)) {
+                let dir = format!("{}/{}/{}", category_dir, language, benchmark);
+                if benchmark == COMMON_DIR || !benchmark_filter.matches_benchmark(&language, &benchmark) {
+                    continue;
+                }
+                let versions = filter::read_versions(&dir);
+                if !benchmark_filter.versions.is_empty() && !versions.iter().any(|version| benchmark_filter.matches_version(&dir, version)) {
+                    continue;
+                }
+                let tags = filter::read_tags(&dir);
+                if !benchmark_filter.matches_tags(&tags) {
+                    continue;
+                }
+                let (manifest, invalid) = match BenchmarkManifest::read_for_benchmark(&dir) {
+                    Ok(manifest) => (manifest, None),
+                    Err(e) => (None, Some(e)),
+                };
+                listed.push(ListedBenchmark {
+                    category: category.to_string(),
+                    benchmark: format!("{}/{}", language, benchmark),
+                    language: read_language(&dir).or(manifest.as_ref().map(|manifest| manifest.language.clone())).unwrap_or(language.clone()),
+                    website: manifest.map(|manifest| manifest.website),
+                    versions,
+                    tags,
+                    missing: find_missing(&dir),
+                    invalid,
+                });
+            }
+        }
+    }
+    listed
+}
+
+/// Renders an aligned table, one benchmark per line.
+pub fn to_table(listed: &[ListedBenchmark]) -> String {
+    let rows: Vec<Vec<String>> = listed.iter().map(|benchmark| vec![
+        benchmark.category.clone(),
+        benchmark.benchmark.clone(),
+        benchmark.language.clone(),
+        benchmark.versions.join(", "),
+        benchmark.tags.join(", "),
+        match (benchmark.missing.is_empty(), &benchmark.invalid) {
+            (true, None) => "ok".to_string(),
+            (true, Some(invalid)) => invalid.clone(),
+            (false, None) => format!("missing {}", benchmark.missing.join(", ")),
+            (false, Some(invalid)) => format!("missing {}; {}", benchmark.missing.join(", "), invalid),
+        },
+    ]).collect();
+    table::render(&HEADER.map(str::to_string), &rows)
+}
+
+fn read_language(dir: &str) -> Option<String> {
+    let content = fs::read_to_string(Path::new(dir).join(META_DATA_FILE)).ok()?;
+    let meta_data: serde_yaml::Value = serde_yaml::from_str(&content).ok()?;
+    meta_data.get("language")?.as_str().map(str::to_string)
+}
+
+/// The meta data with at least one version, a `Dockerfile` (or own compose file) and the files copied from `_common`.
+pub(crate) fn find_missing(dir: &str) -> Vec<String> {
+    let dir = Path::new(dir);
+    let mut missing = Vec::new();
+    if !dir.join(DOCKERFILE).exists() && !dir.join(CUSTOM_COMPOSE_FILE).exists() {
+        missing.push(DOCKERFILE.to_string());
+    }
+
+    let meta_data: Option<serde_yaml::Value> = fs::read_to_string(dir.join(META_DATA_FILE)).ok()
+        .and_then(|content| serde_yaml::from_str(&content).ok());
+    let Some(meta_data) = meta_data else {
+        missing.push(META_DATA_FILE.to_string());
+        return missing;
+    };
+    if meta_data.get("version").and_then(|versions| versions.as_sequence()).is_none_or(|versions| versions.is_empty()) {
+        missing.push("version".to_string());
+    }
+
+    // Either `file` or `source: destination`
+    let copied = meta_data.get("copy").and_then(|copy| copy.as_sequence()).cloned().unwrap_or_default();
+    for file in copied {
+        let source = match &file {
+            serde_yaml::Value::Mapping(map) => map.keys().next().and_then(|key| key.as_str()).map(str::to_string),
+            value => value.as_str().map(str::to_string),
+        };
+        if let Some(source) = source {
+            if !dir.join("..").join(COMMON_DIR).join(&source).exists() {
+                missing.push(format!("{}/{}", COMMON_DIR, source));
+            }
+        }
+    }
+    missing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    fn benchmark_dir() -> TestDir {
+        let dir = TestDir::new("list");
+        let files: [(&str, &str); 8] = [
+            ("computation/rust/rust-1.40/benchmark.yaml", "language: Rust\nversion: ['1.40', '1.74']\ntags: [fast]\n"),
+            ("computation/rust/rust-1.40/Dockerfile", "FROM rust"),
+            ("computation/rust/rust-1.40/_benchmark.yaml", "language: Rust\nwebsite: https://www.rust-lang.org\ntags: [compiled, fast]\n"),
+            ("computation/dart/jit-2.14/_benchmark.yaml", "language: Dart\nmode: jit\n"),
+            ("computation/dart/jit-2.14/benchmark.yaml", "language: Dart\nversion: ['2.14']\ncopy:\n  - main.dart\n  - pubspec.yaml: pubspec.yaml\n"),
+            ("computation/dart/_common/main.dart", ""),
+            ("web/go/gin-1.9-go-1.21/docker-compose.yml", ""),
+            ("web/go/gin-1.9-go-1.21/src/main.go", ""),
+        ];
+        for (file, content) in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn should_list_benchmarks_with_missing_files() {
+        let root = benchmark_dir();
+        let dir = root.to_str().unwrap();
+        let listed = list(dir, &["computation", "web"], &BenchmarkFilter::default());
+
+        assert_eq!(listed, vec![
+            ListedBenchmark {
+                category: "computation".to_string(),
+                benchmark: "dart/jit-2.14".to_string(),
+                language: "Dart".to_string(),
+                website: None,
+                versions: vec!["2.14".to_string()],
+                tags: vec![],
+                missing: vec!["Dockerfile".to_string(), "_common/pubspec.yaml".to_string()],
+                invalid: Some("missing `website` in _benchmark.yaml".to_string()),
+            },
+            ListedBenchmark {
+                category: "computation".to_string(),
+                benchmark: "rust/rust-1.40".to_string(),
+                language: "Rust".to_string(),
+                website: Some("https://www.rust-lang.org".to_string()),
+                versions: vec!["1.40".to_string(), "1.74".to_string()],
+                tags: vec!["fast".to_string(), "compiled".to_string()],
+                missing: vec![],
+                invalid: None,
+            },
+            ListedBenchmark {
+                category: "web".to_string(),
+                benchmark: "go/gin-1.9-go-1.21".to_string(),
+                language: "go".to_string(),
+                website: None,
+                versions: vec![],
+                tags: vec![],
+                missing: vec!["benchmark.yaml".to_string()],
+                invalid: None,
+            },
+        ]);
+        assert_eq!(to_table(&listed[1..2]), "\
+Category     Benchmark       Language  Versions    Tags            Status
+-----------  --------------  --------  ----------  --------------  ------
+computation  rust/rust-1.40  Rust      1.40, 1.74  fast, compiled  ok
+");
+        assert!(to_table(&listed[0..1]).contains("missing Dockerfile, _common/pubspec.yaml; missing `website` in _benchmark.yaml"));
+
+        let filter = BenchmarkFilter { versions: vec!["1.74".to_string()], ..Default::default() };
+        assert_eq!(list(dir, &["computation", "web"], &filter).len(), 1);
+        let filter = BenchmarkFilter { exclude_tags: vec!["fast".to_string()], ..Default::default() };
+        assert_eq!(list(dir, &["computation", "web"], &filter).len(), 2);
+    }
+}
@@ -1,3 +1,17 @@
+pub mod complexity;
 pub mod computation;
+pub mod deadline;
+pub mod doctor;
+pub mod dry_run;
+pub mod error;
+pub mod filter;
+pub mod hosts;
+pub mod list;
+pub mod prebuild;
+pub mod run_options;
+pub mod scaffold;
+pub mod validate;
+pub mod watch;
 pub mod web;
+#[allow(clippy::module_inception)]
 pub mod benchmark;
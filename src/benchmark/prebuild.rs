@@ -0,0 +1,218 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::Instant;
+use crate::benchmark::benchmark::{compose_file, split_benchmark_dir};
+use crate::benchmark::filter::BenchmarkFilter;
+use crate::utils::{copy_files, input_hash};
+use crate::utils::docker_runner::{build_images, BuildOptions};
+use crate::utils::format::format_duration;
+use crate::utils::meta_data_parser::{BenchmarkMetaData, CopyValue, WebBenchmarkMetaData};
+use crate::utils::version_migrator::VersionMigrator;
+
+/// The most builds of the default of `--jobs`, more rarely help as they compete for the disk and the network.
+const MAX_DEFAULT_JOBS: usize = 4;
+
+/// The default of `--jobs`: one build per core, at most `MAX_DEFAULT_JOBS`.
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map(|cores| cores.get()).unwrap_or(1).min(MAX_DEFAULT_JOBS)
+}
+
+/// Limits how many builds run at the same time.
+struct Semaphore {
+    permits: Mutex<usize>,
+    released: Condvar,
+}
+
+impl Semaphore {
+    fn new(permits: usize) -> Self {
+        Semaphore { permits: Mutex::new(permits), released: Condvar::new() }
+    }
+
+    /// Waits for a permit and runs `f` with it.
+    fn with_permit<T>(&self, f: impl FnOnce() -> T) -> T {
+        let mut permits = self.released.wait_while(self.permits.lock().unwrap(), |permits| *permits == 0).unwrap();
+        *permits -= 1;
+        drop(permits);
+
+        let outcome = panic::catch_unwind(AssertUnwindSafe(f));
+        *self.permits.lock().unwrap() += 1;
+        self.released.notify_one();
+        outcome.unwrap_or_else(|payload| panic::resume_unwind(payload))
+    }
+}
+
+/// The versions a benchmark is built for: the values of the version placeholders, with their migrations.
+pub(crate) struct Version {
+    /// e.g. `1.21 1.9` of a web benchmark with language and framework versions.
+    pub(crate) name: String,
+    pub(crate) migrations: Vec<VersionMigrator>,
+}
+
+/// Builds the images of the benchmarks in `dirs` before the first one is measured, at most `jobs` builds at a time,
+/// so their builds during the run only hit the cache and no build competes with a measurement.
+/// The versions of a benchmark are built one after another, as they are migrated in the same files.
+/// The output of docker is written to `log_file(dir)` per benchmark, the run log only gets one line per build.
+/// Versions whose input hash is in `reused` of their benchmark are not built, the run reuses their results (see `--max-age`).
+/// Returns the benchmarks whose build failed, they fail again with the output of docker when they run.
+pub fn prebuild<L>(
+    dirs: &[String],
+    jobs: usize,
+    build: &BuildOptions,
+    filter: &BenchmarkFilter,
+    reused: &HashMap<String, HashSet<String>>,
+    log_file: L,
+) -> Vec<String>
+    where L: Fn(&str) -> PathBuf + Sync,
+{
+    logln!(" -> Building the images of {} benchmark(s), {} at a time", dirs.len(), jobs);
+    let started = Instant::now();
+    let semaphore = Semaphore::new(jobs);
+    let failed: Vec<String> = thread::scope(|scope| {
+        let builds: Vec<_> = dirs.iter()
+            .map(|dir| {
+                let (semaphore, log_file) = (&semaphore, &log_file);
+                let reused = reused.get(dir.as_str()).cloned().unwrap_or_default();
+                scope.spawn(move || {
+                    // A panic (e.g. of a failing version migration) only fails the build of the benchmark
+                    let built = panic::catch_unwind(AssertUnwindSafe(|| build_benchmark(dir, build, filter, &reused, &log_file(dir), semaphore)));
+                    (dir, built.unwrap_or(false))
+                })
+            })
+            .collect();
+        builds.into_iter().filter_map(|build| build.join().ok()).filter(|(_, built)| !built).map(|(dir, _)| dir.clone()).collect()
+    });
+    logln!(" -> Built {} of {} benchmark(s) in {}", dirs.len() - failed.len(), dirs.len(), format_duration(started.elapsed()));
+    failed
+}
+
+/// Builds the selected versions of the benchmark in `dir` that are not `reused`, `false` if a build failed.
+fn build_benchmark(dir: &str, build: &BuildOptions, filter: &BenchmarkFilter, reused: &HashSet<String>, log_path: &Path, semaphore: &Semaphore) -> bool {
+    let (copy, versions) = match versions(dir, filter) {
+        Ok(versions) => versions,
+        Err(e) => {
+            warnln!(" -> [Build] {}: Could not read the meta data: {}", dir, e);
+            return false;
+        }
+    };
+    let log = log_path.parent().map_or(Ok(()), fs::create_dir_all).and_then(|()| File::create(log_path));
+    let log = match log {
+        Ok(log) => log,
+        Err(e) => {
+            warnln!(" -> [Build] {}: Could not create {}: {}", dir, log_path.display(), e);
+            return false;
+        }
+    };
+
+    let compose_file = match compose_file(dir) {
+        Ok(compose_file) => compose_file,
+        Err(e) => {
+            warnln!(" -> [Build] {}: {}", dir, e);
+            return false;
+        }
+    };
+    let copy = copy.unwrap_or_default();
+    if let Err(e) = copy_files::copy_files(dir, &copy) {
+        warnln!(" -> [Build] {}: {}", dir, e);
+        return false;
+    }
+    let mut built = true;
+    let (category, _) = split_benchmark_dir(dir);
+    let category_dir = format!("benchmark/{}", category);
+    for mut version in versions {
+        // Hashed like by the run, after the files were copied and before the versions are migrated
+        if input_hash::hash_inputs(dir, &category_dir, &version.name).is_ok_and(|hash| reused.contains(&hash)) {
+            logln!(" -> [Build] {} {}: Skipped, the result is reused", dir, version.name);
+            continue;
+        }
+        // Checked first, so a failing migration does not leave the files migrated
+        let outcome = version.migrations.iter().try_for_each(|migration| migration.check(|path| fs::read_to_string(path))).and_then(|()| {
+            for migration in &mut version.migrations {
+                migration.migrate();
+            }
+            let outcome = semaphore.with_permit(|| {
+                let started = Instant::now();
+                build_images(dir, compose_file, build, &log).map(|()| started.elapsed())
+            });
+            for migration in &version.migrations {
+                migration.restore();
+            }
+            outcome
+        });
+
+        match outcome {
+            Ok(elapsed) => logln!(" -> [Build] {} {}: OK ({})", dir, version.name, format_duration(elapsed)),
+            Err(e) => {
+                errorln!(" -> [Build] {} {}: {}, see {}", dir, version.name, e, log_path.display());
+                built = false;
+            }
+        }
+    }
+    copy_files::delete_copied_files(dir, &copy);
+    built
+}
+
+/// The files to copy into `dir` and its versions selected by `filter`, like the run of the benchmark.
+pub(crate) fn versions(dir: &str, filter: &BenchmarkFilter) -> Result<(Option<Vec<CopyValue>>, Vec<Version>), String> {
+    let (category, _) = split_benchmark_dir(dir);
+    // (copied files, [(versions, version regex)]), only the first are selected by `--lang-version`
+    let (copy, placeholders) = match category.as_str() {
+        "web" => WebBenchmarkMetaData::read_from_directory(dir).map(|meta_data| (meta_data.copy, vec![
+            (meta_data.language_version, meta_data.language_version_regex),
+            (meta_data.framework_version, meta_data.framework_version_regex),
+        ])),
+        _ => BenchmarkMetaData::read_from_directory(dir).map(|meta_data| (meta_data.copy, vec![
+            (meta_data.language_version, meta_data.language_version_regex),
+        ])),
+    }.map_err(|e| e.to_string())?;
+
+    // Every combination of the selected values, e.g. each language version with each framework version
+    let mut combinations: Vec<Vec<(usize, &String)>> = vec![Vec::new()];
+    for (index, (values, _)) in placeholders.iter().enumerate() {
+        let selected: Vec<&String> = values.iter().filter(|value| index > 0 || filter.matches_version(dir, value)).collect();
+        combinations = combinations.into_iter()
+            .flat_map(|combination| selected.iter().map(move |value| [combination.clone(), vec![(index, *value)]].concat()))
+            .collect();
+    }
+    let versions = combinations.into_iter()
+        .map(|combination| Version {
+            name: combination.iter().map(|(_, value)| value.as_str()).collect::<Vec<&str>>().join(" "),
+            // Single versions are not migrated
+            migrations: combination.iter()
+                .filter(|(index, _)| placeholders[*index].0.len() > 1)
+                .map(|(index, value)| {
+                    let (values, regex) = &placeholders[*index];
+                    VersionMigrator::new(dir, regex.clone(), values[0].clone(), value.to_string())
+                })
+                .collect(),
+        })
+        .collect();
+    Ok((copy, versions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn should_limit_concurrent_builds() {
+        let semaphore = Semaphore::new(2);
+        let (running, most) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        thread::scope(|scope| {
+            for _ in 0..6 {
+                scope.spawn(|| semaphore.with_permit(|| {
+                    most.fetch_max(running.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(20));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                }));
+            }
+        });
+        assert_eq!(most.load(Ordering::SeqCst), 2);
+        assert!((1..=MAX_DEFAULT_JOBS).contains(&default_jobs()));
+    }
+}
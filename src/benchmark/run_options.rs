@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::benchmark::filter::BenchmarkFilter;
+use crate::utils::docker_runner::BuildOptions;
+
+/// Port the benchmark containers publish on the Docker host.
+const BENCHMARK_PORT: u16 = 3000;
+
+/// Options of the command line that apply to every benchmark of the run.
+#[derive(Default)]
+pub struct RunOptions {
+    /// Print more information (e.g. the output of the load tester).
+    pub verbose: bool,
+
+    /// Measure the already running service at this base URL instead of starting the benchmark container.
+    pub external_url: Option<String>,
+
+    /// Host the benchmark container publishes its port on, `localhost` if not set (e.g. a remote Docker host of `--hosts`).
+    pub service_host: Option<String>,
+
+    /// See `BenchmarkOptions::profile_dir`.
+    pub profile_dir: Option<PathBuf>,
+
+    /// See `BenchmarkOptions::running_median`.
+    pub running_median: bool,
+
+    /// Override the measured rounds of the benchmark and category meta data.
+    pub rounds: Option<usize>,
+
+    /// Override the warmup rounds of the benchmark and category meta data (including `workload_warmup`).
+    pub warmup: Option<usize>,
+
+    /// Overrides `benchmark::DEFAULT_COOLDOWN`.
+    pub cooldown: Option<Duration>,
+
+    /// See `BenchmarkOptions::warmup_only`, no results are written.
+    pub warmup_only: bool,
+
+    /// See `BenchmarkOptions::keep_images`.
+    pub keep_images: bool,
+
+    /// See `BenchmarkOptions::build`, use `build_options` for a benchmark.
+    pub build: BuildOptions,
+
+    /// The benchmarks whose images were built with `build` before the run (see `prebuild::prebuild`).
+    pub prebuilt: HashSet<String>,
+
+    /// Only the versions matching the filter are run.
+    pub filter: BenchmarkFilter,
+}
+
+impl RunOptions {
+    /// Base URL of the benchmark container, see `service_host`.
+    pub fn default_base_url(&self) -> String {
+        format!("http://{}:{}", self.service_host.as_deref().unwrap_or("localhost"), BENCHMARK_PORT)
+    }
+
+    /// How the benchmark in `dir` is built: only from the cache if it was prebuilt, with `build` otherwise.
+    pub fn build_options(&self, dir: &str) -> BuildOptions {
+        match self.prebuilt.contains(dir) {
+            true => BuildOptions::default(),
+            false => self.build.clone(),
+        }
+    }
+}
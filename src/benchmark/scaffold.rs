@@ -0,0 +1,179 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::benchmark::filter::META_DATA_FILE;
+use crate::benchmark::list::DOCKERFILE;
+
+/// The template of the `Dockerfile` of both categories, embedded in the binary like the stubs of the server.
+const DOCKERFILE_TEMPLATE: &str = include_str!("templates/Dockerfile");
+
+const COMPUTATION_SERVER_TEMPLATE: &str = include_str!("templates/computation/server.txt");
+
+const WEB_SERVER_TEMPLATE: &str = include_str!("templates/web/server.txt");
+
+/// The `benchmark.yaml` of a new benchmark, serialized so names like `C#` or `yes` are quoted as needed.
+#[derive(Serialize)]
+struct MetaData<'a> {
+    language: &'a str,
+    mode: &'a str,
+    version: [&'a str; 1],
+    #[serde(flatten)]
+    framework: Option<Framework<'a>>,
+}
+
+#[derive(Serialize)]
+struct Framework<'a> {
+    framework: &'a str,
+    framework_website: &'a str,
+    framework_flavor: &'a str,
+    framework_version: [&'a str; 1],
+}
+
+/// A benchmark to create with `new`, the framework is required for web benchmarks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewBenchmark {
+    pub category: String,
+    pub language: String,
+    pub version: String,
+    pub framework: Option<String>,
+    pub framework_version: Option<String>,
+}
+
+impl NewBenchmark {
+    /// `benchmark/<category>/<language>/<benchmark>` like the existing ones,
+    /// e.g. `benchmark/computation/zig/zig-0.12` or `benchmark/web/go/gin-1-go-1.20`.
+    pub fn dir(&self) -> Result<String, String> {
+        let language = slug(&self.language);
+        let benchmark = match self.category.as_str() {
+            "computation" => format!("{}-{}", language, self.version),
+            "web" => match (&self.framework, &self.framework_version) {
+                (Some(framework), Some(framework_version)) => format!("{}-{}-{}-{}", slug(framework), framework_version, language, self.version),
+                _ => return Err("a web benchmark needs --framework and --framework-version".to_string()),
+            },
+            category => return Err(format!("unknown category {:?}, expected \"computation\" or \"web\"", category)),
+        };
+        Ok(format!("benchmark/{}/{}/{}", self.category, language, benchmark))
+    }
+
+    /// Creates the directory of the benchmark below `root` with the templates of its category and returns it.
+    /// Fails if the directory already exists, so nothing is overwritten.
+    pub fn create(&self, root: &Path) -> Result<PathBuf, String> {
+        if self.language.trim().is_empty() || self.version.trim().is_empty() {
+            return Err("the language and the version must not be empty".to_string());
+        }
+        let dir = root.join(self.dir()?);
+        if dir.exists() {
+            return Err(format!("{} already exists", dir.display()));
+        }
+
+        let server_template = match self.category.as_str() {
+            "web" => WEB_SERVER_TEMPLATE,
+            _ => COMPUTATION_SERVER_TEMPLATE,
+        };
+        let (source_file, comment) = source_file(&self.language);
+        let server: String = self.render(server_template).lines()
+            .map(|line| format!("{}{}\n", comment, if line.is_empty() { String::new() } else { format!(" {}", line) }))
+            .collect();
+        let meta_data = serde_yaml::to_string(&self.meta_data()).map_err(|e| format!("could not serialize {}: {}", META_DATA_FILE, e))?;
+        fs::create_dir_all(&dir).map_err(|e| format!("could not create {}: {}", dir.display(), e))?;
+        for (file, content) in [(DOCKERFILE, self.render(DOCKERFILE_TEMPLATE)), (META_DATA_FILE, meta_data), (source_file, server)] {
+            fs::write(dir.join(file), content).map_err(|e| format!("could not write {}: {}", dir.join(file).display(), e))?;
+        }
+        Ok(dir)
+    }
+
+    fn meta_data(&self) -> MetaData<'_> {
+        let framework = match self.category.as_str() {
+            "web" => Some(Framework {
+                framework: self.framework.as_deref().unwrap_or_default(),
+                framework_website: "TODO",
+                framework_flavor: "Default",
+                framework_version: [self.framework_version.as_deref().unwrap_or_default()],
+            }),
+            _ => None,
+        };
+        MetaData { language: &self.language, mode: "Default", version: [&self.version], framework }
+    }
+
+    fn render(&self, template: &str) -> String {
+        template
+            .replace("{{language}}", &self.language)
+            .replace("{{image}}", &slug(&self.language))
+            .replace("{{version}}", &self.version)
+            .replace("{{framework}}", self.framework.as_deref().unwrap_or_default())
+            .replace("{{framework_version}}", self.framework_version.as_deref().unwrap_or_default())
+    }
+}
+
+/// Lowercase with `-` instead of whitespace, e.g. `C#` stays `c#`, `Spring Boot` becomes `spring-boot`.
+fn slug(name: &str) -> String {
+    name.trim().to_lowercase().split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+/// The name of the stub of the server and the line comment of the language, `main.txt` for unknown languages.
+fn source_file(language: &str) -> (&'static str, &'static str) {
+    match slug(language).as_str() {
+        "c" => ("main.c", "//"),
+        "c#" | "csharp" => ("Program.cs", "//"),
+        "dart" => ("main.dart", "//"),
+        "go" => ("main.go", "//"),
+        "java" => ("Main.java", "//"),
+        "javascript" | "js" => ("main.js", "//"),
+        "julia" => ("main.jl", "#"),
+        "kotlin" => ("Main.kt", "//"),
+        "php" => ("index.php", "//"),
+        "python" => ("main.py", "#"),
+        "ruby" => ("main.rb", "#"),
+        "rust" => ("main.rs", "//"),
+        "swift" => ("main.swift", "//"),
+        "zig" => ("main.zig", "//"),
+        _ => ("main.txt", "#"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmark::validate;
+    use crate::utils::meta_data_parser::WebBenchmarkMetaData;
+    use crate::utils::test_dir::TestDir;
+
+    fn new_benchmark(category: &str, framework: Option<&str>) -> NewBenchmark {
+        NewBenchmark {
+            category: category.to_string(),
+            language: "Zig".to_string(),
+            version: "0.12".to_string(),
+            framework: framework.map(str::to_string),
+            framework_version: framework.map(|_| "2".to_string()),
+        }
+    }
+
+    #[test]
+    fn should_name_dir_like_existing_benchmarks() {
+        assert_eq!(new_benchmark("computation", None).dir(), Ok("benchmark/computation/zig/zig-0.12".to_string()));
+        assert_eq!(new_benchmark("web", Some("Http Zig")).dir(), Ok("benchmark/web/zig/http-zig-2-zig-0.12".to_string()));
+        assert!(new_benchmark("web", None).dir().is_err());
+    }
+
+    #[test]
+    fn should_create_valid_benchmark_once() {
+        let root = TestDir::new("scaffold");
+        let dir = new_benchmark("computation", None).create(&root).unwrap();
+
+        let meta_data = fs::read_to_string(dir.join(META_DATA_FILE)).unwrap();
+        assert_eq!(meta_data, "language: Zig\nmode: Default\nversion:\n- '0.12'\n");
+        assert!(fs::read_to_string(dir.join(DOCKERFILE)).unwrap().contains("FROM zig:0.12\n"));
+        assert!(fs::read_to_string(dir.join("main.zig")).unwrap().starts_with("// TODO: Implement the computation benchmark in Zig 0.12.\n//\n"));
+        assert!(validate::validate(dir.to_str().unwrap(), false).is_ok());
+
+        assert_eq!(new_benchmark("computation", None).create(&root), Err(format!("{} already exists", dir.display())));
+    }
+
+    #[test]
+    fn should_quote_names_in_meta_data() {
+        let new_benchmark = NewBenchmark { language: "C#".to_string(), ..new_benchmark("web", Some("yes")) };
+        let meta_data: WebBenchmarkMetaData = serde_yaml::from_str(&serde_yaml::to_string(&new_benchmark.meta_data()).unwrap()).unwrap();
+        assert_eq!((meta_data.language.as_str(), meta_data.framework.as_str()), ("C#", "yes"));
+        assert_eq!((meta_data.language_version, meta_data.framework_version), (vec!["0.12".to_string()], vec!["2".to_string()]));
+    }
+}
@@ -0,0 +1,57 @@
+//! A global allocator counting the allocations of the process for `allocation_metrics: true` in the `benchmark.yaml`.
+//! Copy it into the `src` of a Rust benchmark and install it in its `main.rs`:
+//!
+//! ```ignore
+//! mod counting_allocator;
+//!
+//! #[global_allocator]
+//! static ALLOCATOR: counting_allocator::CountingAllocator = counting_allocator::CountingAllocator;
+//! ```
+//!
+//! Then answer `GET /_sharkbench/allocations` with `counting_allocator::to_json()`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Counts every allocation and passes it on to the system allocator.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        System.alloc_zeroed(layout)
+    }
+
+    /// A reallocation counts as an allocation of the new size, like a copy into a new buffer.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+fn count(size: usize) {
+    ALLOCATED_BYTES.fetch_add(size as u64, Ordering::Relaxed);
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// The allocations since the start of the process, e.g. `{"bytes": 4096, "count": 12}`.
+/// Read before the response is built, so the allocations of the response are counted by the next query.
+pub fn to_json() -> String {
+    format!(
+        "{{\"bytes\": {}, \"count\": {}}}",
+        ALLOCATED_BYTES.load(Ordering::Relaxed),
+        ALLOCATIONS.load(Ordering::Relaxed),
+    )
+}
@@ -0,0 +1,156 @@
+use std::fs::{self, File};
+use std::path::Path;
+use crate::benchmark::benchmark::{check_compose_contract, compose_file, resolve_benchmark_dir, BENCHMARK_PORT, CUSTOM_COMPOSE_FILE};
+use crate::benchmark::dry_run::{self, Check};
+use crate::benchmark::filter::{self, BenchmarkFilter, META_DATA_FILE};
+use crate::benchmark::prebuild;
+use crate::utils::copy_files::{self, COMMON_DIR};
+use crate::utils::docker_runner::{self, BuildOptions};
+use crate::utils::meta_data_parser::BenchmarkManifest;
+
+/// Resolves `path` (relative to `working_dir` or absolute) to the benchmark directories it contains:
+/// `benchmark` for all of them, a category, a language or a single benchmark directory.
+pub fn resolve_dirs(path: &Path, working_dir: &Path) -> Result<Vec<String>, String> {
+    let absolute = working_dir.join(path).canonicalize().map_err(|e| format!("{} does not exist ({})", path.display(), e))?;
+    let root = working_dir.canonicalize().map_err(|e| format!("{} does not exist ({})", working_dir.display(), e))?;
+    let relative = absolute.strip_prefix(&root)
+        .map_err(|_| format!("{} is not inside {}, run sharkbench from the root of the repository", path.display(), root.display()))?;
+
+    // Listed below the root, but relative to it like the benchmark directories of a run
+    let root_prefix = format!("{}/", root.display());
+    let benchmark_dirs = |language_dirs: Vec<String>| -> Vec<String> {
+        let language_dirs: Vec<String> = language_dirs.iter().map(|dir| format!("{}{}", root_prefix, dir)).collect();
+        filter::execution_order(&language_dirs, None).iter().map(|dir| dir.trim_start_matches(&root_prefix).to_string()).collect()
+    };
+    let language_dirs = |category: &str| -> Vec<String> {
+        let category_dir = format!("benchmark/{}", category);
+        filter::sorted_dirs(&format!("{}{}", root_prefix, category_dir)).into_iter().map(|language| format!("{}/{}", category_dir, language)).collect()
+    };
+    let parts: Vec<&str> = relative.iter().filter_map(|part| part.to_str()).collect();
+    match parts.as_slice() {
+        ["benchmark"] => Ok(benchmark_dirs([language_dirs("computation"), language_dirs("web")].concat())),
+        ["benchmark", category @ ("computation" | "web")] => Ok(benchmark_dirs(language_dirs(category))),
+        ["benchmark", category @ ("computation" | "web"), language] if *language != COMMON_DIR => {
+            Ok(benchmark_dirs(vec![format!("benchmark/{}/{}", category, language)]))
+        }
+        ["benchmark", _, _, _] => resolve_benchmark_dir(path, working_dir).map(|dir| vec![dir]),
+        _ => Err(format!(
+            "{} is not a benchmark directory, expected benchmark[/<computation|web>[/<language>[/<benchmark>]]]",
+            relative.display(),
+        )),
+    }
+}
+
+/// Checks the benchmark in `dir` like `--dry-run` (files, base images and exposed port, meta data and version migrations of all versions)
+/// and additionally the contract of the runner: the meta data names its language and a custom compose file is valid.
+/// With `build`, the images of all versions are built like by the run, with the version migrations, and removed again.
+/// A failing build is a problem.
+pub fn validate(dir: &str, build: bool) -> Check {
+    let mut check = dry_run::check_benchmark(dir, &BenchmarkFilter::default());
+    let path = Path::new(dir);
+    if !path.is_dir() {
+        return check;
+    }
+
+    let meta_data: Option<serde_yaml::Value> = fs::read_to_string(path.join(META_DATA_FILE)).ok()
+        .and_then(|content| serde_yaml::from_str(&content).ok());
+    if let Some(meta_data) = &meta_data {
+        if meta_data.get("language").and_then(|language| language.as_str()).is_none_or(|language| language.trim().is_empty()) {
+            check.problems.push(format!("missing `language` in {}", META_DATA_FILE));
+        }
+        if let Ok(Some(manifest)) = BenchmarkManifest::read_for_benchmark(dir) {
+            let text = |key: &str| meta_data.get(key).and_then(|value| value.as_str()).unwrap_or_default().to_string();
+            check.problems.extend(manifest.check_benchmark(&text("language"), &filter::read_versions(dir)));
+        }
+    }
+    if let Ok(compose) = fs::read_to_string(path.join(CUSTOM_COMPOSE_FILE)) {
+        check.problems.extend(check_compose_contract(&compose).err().map(|e| format!("{}: {}", CUSTOM_COMPOSE_FILE, e)));
+    }
+
+    // A build of a benchmark with missing files would only repeat the problems
+    if build && check.is_ok() {
+        check.problems.extend(build_versions(dir).err());
+    }
+    check
+}
+
+/// The ports of the `EXPOSE` instructions have to include the benchmark port, a `Dockerfile` without any is fine.
+pub(crate) fn check_exposed_ports(dockerfile: &str) -> Result<(), String> {
+    let ports: Vec<&str> = dockerfile.lines()
+        .map(|line| line.split_whitespace())
+        .filter(|words| words.clone().next().is_some_and(|word| word.eq_ignore_ascii_case("EXPOSE")))
+        .flat_map(|words| words.skip(1))
+        .map(|port| port.split('/').next().unwrap_or(port))
+        .collect();
+    match ports.is_empty() || ports.contains(&BENCHMARK_PORT) {
+        true => Ok(()),
+        false => Err(format!("Dockerfile exposes {} instead of {}", ports.join(", "), BENCHMARK_PORT)),
+    }
+}
+
+/// Builds the images of every version of the benchmark in `dir` without keeping them, stops at the first failing build.
+fn build_versions(dir: &str) -> Result<(), String> {
+    let compose_file = compose_file(dir)?;
+    let (copy, versions) = prebuild::versions(dir, &BenchmarkFilter::default())?;
+    let copy = copy.unwrap_or_default();
+    copy_files::copy_files(dir, &copy)?;
+    let log_path = std::env::temp_dir().join(format!("sharkbench-validate-{}.log", std::process::id()));
+    let built = File::create(&log_path).map_err(|e| format!("could not create {}: {}", log_path.display(), e))
+        .and_then(|log| versions.into_iter().try_for_each(|mut version| {
+            // Checked first, so a failing migration does not leave the files migrated
+            version.migrations.iter().try_for_each(|migration| migration.check(|path| fs::read_to_string(path)))?;
+            for migration in &mut version.migrations {
+                migration.migrate();
+            }
+            let built = docker_runner::build_images(dir, compose_file, &BuildOptions::default(), &log);
+            for migration in &version.migrations {
+                migration.restore();
+            }
+            if let Err(e) = docker_runner::remove_images(dir, compose_file) {
+                warnln!(" -> Warning: Could not remove the images of {}: {}", dir, e);
+            }
+            built.map_err(|e| {
+                let output = fs::read_to_string(&log_path).unwrap_or_default();
+                let last_line = output.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or_default();
+                format!("{} of version {}: {}", e, version.name, last_line.trim())
+            })
+        }));
+    copy_files::delete_copied_files(dir, &copy);
+    let _ = fs::remove_file(&log_path);
+    built
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    #[test]
+    fn should_check_exposed_ports() {
+        assert_eq!(check_exposed_ports("FROM rust\nEXPOSE 3000\n"), Ok(()));
+        assert_eq!(check_exposed_ports("FROM rust\nexpose 9000 3000/tcp\n"), Ok(()));
+        assert_eq!(check_exposed_ports("FROM rust\n"), Ok(()));
+        assert_eq!(check_exposed_ports("FROM rust\nEXPOSE 8080\n"), Err("Dockerfile exposes 8080 instead of 3000".to_string()));
+    }
+
+    #[test]
+    fn should_validate_contract_of_benchmark() {
+        let root = TestDir::new("validate");
+        let dir = root.join("benchmark/computation/rust/rust-1.0");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Dockerfile"), "FROM rust:1.0\nEXPOSE 8080\n").unwrap();
+        fs::write(dir.join("benchmark.yaml"), "language: ''\nmode: Default\nversion: ['1.0']\n").unwrap();
+
+        let check = validate(dir.to_str().unwrap(), false);
+        assert_eq!(check.problems, vec!["Dockerfile exposes 8080 instead of 3000", "missing `language` in benchmark.yaml"]);
+
+        fs::write(dir.join("benchmark.yaml"), "language: Rust\nmode: Default\nversion: ['1.0']\n").unwrap();
+        fs::write(dir.join("_benchmark.yaml"), "language: Rust\nwebsite: https://www.rust-lang.org\nmode: default\nversions: ['1.40']\n").unwrap();
+        let check = validate(dir.to_str().unwrap(), false);
+        assert_eq!(check.problems, vec!["Dockerfile exposes 8080 instead of 3000", "version 1.0 is not one of the `versions` of _benchmark.yaml"]);
+
+        assert_eq!(resolve_dirs(Path::new("benchmark/computation/rust"), &root), Ok(vec!["benchmark/computation/rust/rust-1.0".to_string()]));
+        assert_eq!(resolve_dirs(&dir, &root), Ok(vec!["benchmark/computation/rust/rust-1.0".to_string()]));
+        assert!(resolve_dirs(Path::new("benchmark/computation/rust/rust-1.0/src"), &root).is_err());
+    }
+}
@@ -0,0 +1,140 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use crate::utils::input_hash::hash_inputs;
+
+/// How often `wait_for_change` checks whether the watch was stopped while no file changes.
+pub const STOP_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A change is only picked up once the files did not change for this long, so saving several files in a row starts one run.
+pub const DEBOUNCE: Duration = Duration::from_secs(1);
+
+/// Set by the first Ctrl+C, see `request_stop`.
+static STOP: AtomicBool = AtomicBool::new(false);
+
+/// The hash of the files of the benchmark in `dir` and of its `category.yaml`, see `input_hash::hash_inputs`.
+/// `None` if a file could not be read, e.g. while an editor replaces it.
+pub fn inputs_hash(dir: &str, category_dir: &str) -> Option<String> {
+    hash_inputs(dir, category_dir, "").ok()
+}
+
+/// Watches the files of the benchmark in `dir` and the `category.yaml` of `category_dir`, see `wait_for_change`.
+pub struct InputWatcher {
+    /// Stops watching when dropped.
+    _watcher: RecommendedWatcher,
+
+    /// Receives something on every event of the watched files, also of the files a run copies into `dir` and removes again.
+    pub events: Receiver<()>,
+}
+
+impl InputWatcher {
+    pub fn new(dir: &str, category_dir: &str) -> notify::Result<InputWatcher> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if event.is_ok() {
+                let _ = sender.send(());
+            }
+        })?;
+        watcher.watch(Path::new(dir), RecursiveMode::Recursive)?;
+        // The `category.yaml` may be created later, so its directory is watched
+        watcher.watch(Path::new(category_dir), RecursiveMode::NonRecursive)?;
+        Ok(InputWatcher { _watcher: watcher, events })
+    }
+}
+
+/// Waits for `events` until `hash` differs from `previous` once no event arrived for `debounce`, returns the new hash.
+/// Events that do not change the hash (e.g. of the files a run copied and removed, or a reverted change) are ignored,
+/// so `previous` has to be taken before the run. Returns `None` once `stopped`.
+pub fn wait_for_change<H, S>(previous: &str, events: &Receiver<()>, debounce: Duration, mut hash: H, stopped: S) -> Option<String>
+    where
+        H: FnMut() -> Option<String>,
+        S: Fn() -> bool,
+{
+    loop {
+        if stopped() {
+            return None;
+        }
+        match events.recv_timeout(STOP_INTERVAL) {
+            Ok(()) => {}
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => return None,
+        }
+        // Settle: every further event within `debounce` belongs to the same change
+        while events.recv_timeout(debounce).is_ok() {}
+        match hash() {
+            Some(current) if current != previous => return Some(current),
+            _ => {}
+        }
+    }
+}
+
+/// Stops the watch after the current run (see `stop_requested`), returns whether it was already requested.
+pub fn request_stop() -> bool {
+    STOP.swap(true, Ordering::Relaxed)
+}
+
+/// Whether Ctrl+C was pressed, the watch does not start another run then.
+pub fn stop_requested() -> bool {
+    STOP.load(Ordering::Relaxed)
+}
+
+/// Calls `handler` on every Ctrl+C instead of terminating the process.
+pub fn on_ctrl_c<F: Fn() + Send + 'static>(handler: F) {
+    thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().expect("Failed to create the signal runtime");
+        runtime.block_on(async {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                handler();
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::fs;
+    use std::time::Instant;
+    use crate::utils::test_dir::TestDir;
+
+    fn hashes(hashes: &[&str]) -> impl FnMut() -> Option<String> {
+        let mut hashes: Vec<String> = hashes.iter().rev().map(|hash| hash.to_string()).collect();
+        move || hashes.pop()
+    }
+
+    #[test]
+    fn should_wait_until_hash_changed() {
+        let (sender, events) = mpsc::channel();
+        // The first events did not change the hash, e.g. of files the run copied and removed again
+        for _ in 0..3 {
+            sender.send(()).unwrap();
+        }
+        let sent = sender.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            sent.send(()).unwrap();
+        });
+        assert_eq!(wait_for_change("a", &events, Duration::from_millis(10), hashes(&["a", "b"]), || false), Some("b".to_string()));
+
+        // Stopped before any change
+        let polls = Cell::new(0);
+        let stopped = || polls.replace(polls.get() + 1) == 2;
+        assert_eq!(wait_for_change("a", &events, Duration::ZERO, hashes(&[]), stopped), None);
+    }
+
+    #[test]
+    fn should_notify_about_changed_files() {
+        let root = TestDir::new("watch");
+        let dir = root.join("rust");
+        fs::create_dir_all(&dir).unwrap();
+        let watcher = InputWatcher::new(dir.to_str().unwrap(), root.to_str().unwrap()).unwrap();
+
+        fs::write(dir.join("main.rs"), "fn main() {}").unwrap();
+        let started = Instant::now();
+        assert!(watcher.events.recv_timeout(Duration::from_secs(5)).is_ok(), "no event after {:?}", started.elapsed());
+    }
+}
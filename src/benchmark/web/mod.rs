@@ -1,58 +1,152 @@
 use std::collections::HashMap;
 use std::fs;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use indexmap::IndexMap;
 use serde::{Deserialize};
-use crate::benchmark::benchmark::{AdditionalData, IterationResult, run_benchmark};
-use crate::utils::copy_files;
+use crate::benchmark::benchmark::{AdditionalData, BenchmarkOptions, DEFAULT_COOLDOWN, IterationResult, Response, check_actual_version, run_sweep};
+use crate::benchmark::deadline;
+use crate::benchmark::error::BenchmarkError;
+use crate::benchmark::run_options::RunOptions;
+use crate::output::json::{ResultEntry, ResultWriter, SWEEP_KEY};
+use crate::output::tui::{self, Request};
+use crate::utils::{allocation_metrics, copy_files, gc_metrics, input_hash, self_reported_memory};
 use crate::utils::docker_stats::DockerStatsReader;
 use crate::utils::http_load_tester::run_http_load_test;
-use crate::utils::meta_data_parser::{WebBenchmarkMetaData};
+use crate::utils::meta_data_parser::{CategoryMetaData, WebBenchmarkMetaData, BenchmarkManifest};
 use crate::utils::result_writer::write_result_to_file;
 use crate::utils::serialization::SerializedValue;
 use crate::utils::version_migrator::VersionMigrator;
 
+const CATEGORY_DIR: &str = "benchmark/web";
+const HEALTH_CHECK_PATH: &str = "/api/v1/periodic-table/element?symbol=H";
 const DEFAULT_CONCURRENCY: usize = 32;
 
+/// A request of the load test with the fields its response has to contain.
+type LoadRequest = (String, HashMap<String, SerializedValue>);
+
+/// Benchmarks the web benchmark in `dir`.
+/// With `RunOptions::external_url`, the already running service at this URL is measured instead of the container.
+/// Returns the request of the user that stopped the benchmark before its last version (see `--tui`), `None` if it ran to the end.
 pub fn benchmark_web(
     dir: &str,
     stats_reader: &mut DockerStatsReader,
-    verbose: bool,
-) {
-    println!(" -> Benchmarking {}", dir);
+    result_writer: &mut ResultWriter,
+    run_options: &RunOptions,
+) -> Result<Option<Request>, BenchmarkError> {
+    logln!(" -> Benchmarking {}", dir);
+    let external_url = run_options.external_url.as_deref();
+    let default_url = run_options.default_base_url();
+    let base_url = external_url.unwrap_or(&default_url).trim_end_matches('/');
+
+    let category: CategoryMetaData = CategoryMetaData::read_from_directory(CATEGORY_DIR)
+        .map_err(|e| BenchmarkError::InvalidBenchmark(format!("Failed to read category meta data: {}", e)))?;
+    category.print_info();
 
-    let meta_data: WebBenchmarkMetaData = WebBenchmarkMetaData::read_from_directory(dir).expect("Failed to read meta data");
+    let meta_data: WebBenchmarkMetaData = WebBenchmarkMetaData::read_from_directory(dir)
+        .map_err(|e| BenchmarkError::InvalidBenchmark(format!("Failed to read meta data: {}", e)))?;
     meta_data.print_info();
+    let manifest = BenchmarkManifest::read_for_benchmark(dir)
+        .map_err(|e| BenchmarkError::InvalidBenchmark(format!("Failed to read the manifest: {}", e)))?;
+    if let Some(max_duration) = meta_data.max_duration {
+        deadline::restrict(max_duration);
+    }
 
     let data: HashMap<String, PeriodicTableElement> = load_data();
-    let requests: Vec<(String, HashMap<String, SerializedValue>)> = [data.iter().map(|(k, v)|{
-        let url = format!("http://localhost:3000/api/v1/periodic-table/element?symbol={}", k);
+    // The requests of every endpoint, see `WebBenchmarkMetaData::workload_warmup`
+    let workloads: IndexMap<&str, Vec<LoadRequest>> = IndexMap::from([("element", data.iter().map(|(k, v)|{
+        let url = format!("{}/api/v1/periodic-table/element?symbol={}", base_url, k);
         let expected_response = HashMap::from([
             ("name".to_string(), SerializedValue::StringValue(v.name.to_string())),
             ("number".to_string(), SerializedValue::IntValue(v.number as i32)),
             ("group".to_string(), SerializedValue::IntValue(v.group as i32)),
         ]);
         (url, expected_response)
-    }).collect::<Vec<(String, HashMap<String, SerializedValue>)>>(), data.iter().map(|(k, v)|{
-        let url = format!("http://localhost:3000/api/v1/periodic-table/shells?symbol={}", k);
+    }).collect::<Vec<LoadRequest>>()), ("shells", data.iter().map(|(k, v)|{
+        let url = format!("{}/api/v1/periodic-table/shells?symbol={}", base_url, k);
         let expected_response = HashMap::from([
             ("shells".to_string(), SerializedValue::IntListValue(v.shells.iter().map(|v| *v as i32).collect::<Vec<i32>>())),
         ]);
         (url, expected_response)
-    }).collect::<Vec<(String, HashMap<String, SerializedValue>)>>()].concat();
+    }).collect::<Vec<LoadRequest>>())]);
+    let requests: Vec<LoadRequest> = workloads.values().flatten().cloned().collect();
 
     let concurrency = match meta_data.concurrency {
         Some(concurrency) => {
-            println!(" -> Using concurrency = {} instead of default = {}", concurrency, DEFAULT_CONCURRENCY);
+            logln!(" -> Using concurrency = {} instead of default = {}", concurrency, DEFAULT_CONCURRENCY);
             concurrency
         },
         None => DEFAULT_CONCURRENCY,
     };
 
+    let concurrency_sweep = category.resolve_concurrency_sweep(&meta_data.concurrency_sweep);
+    if let Some(levels) = &concurrency_sweep {
+        logln!(" -> Sweeping concurrency levels {:?}", levels);
+    }
+    let levels = concurrency_sweep.clone().unwrap_or(vec![concurrency]);
+
+    // The command line wins over the meta data
+    let warmup = run_options.warmup.unwrap_or(category.resolve_warmup(meta_data.warmup, meta_data.extended_warmup));
+    let runs = run_options.rounds.unwrap_or(category.resolve_rounds(meta_data.runs));
+    let cooldown = run_options.cooldown.unwrap_or(category.resolve_cooldown(meta_data.cooldown_seconds, DEFAULT_COOLDOWN));
+    stats_reader.set_stabilization_prefix(category.resolve_memory_stabilization(meta_data.memory_stabilization));
+    logln!(" -> Using warmup = {}, runs = {}, cooldown = {:?}", warmup, runs, cooldown);
+    let workload_warmup = match run_options.warmup {
+        Some(_) => IndexMap::new(),
+        None => meta_data.workload_warmup.clone(),
+    };
+    for workload in workload_warmup.keys().filter(|workload| !workloads.contains_key(workload.as_str())) {
+        logln!(" -> Ignoring workload_warmup of {}, it is not one of the workloads {:?}", workload, workloads.keys().collect::<Vec<_>>());
+    }
+    let workload_warmup_rounds: IndexMap<String, usize> = match workload_warmup.keys().any(|workload| workloads.contains_key(workload.as_str())) {
+        true => workloads.keys().map(|workload| (workload.to_string(), workload_warmup.get(*workload).copied().unwrap_or(warmup))).collect(),
+        false => IndexMap::new(),
+    };
+    if !workload_warmup_rounds.is_empty() {
+        logln!(" -> Warming up every workload on its own: {:?}", workload_warmup_rounds);
+    }
+    let options = BenchmarkOptions {
+        warmup_rounds: warmup,
+        workload_warmup_rounds,
+        rounds: runs,
+        cooldown,
+        version_command: meta_data.version_command.clone(),
+        setup_command: meta_data.setup_command.clone(),
+        // A round is a load test of a fixed duration, so its time says nothing about the first request
+        cold_start: false,
+        profile_dir: run_options.profile_dir.clone(),
+        aggregation: meta_data.aggregation.clone(),
+        reset_url: meta_data.reset_path.as_ref().map(|path| format!("{}{}", base_url, path)),
+        validate_response: Some(validate_response),
+        running_median: run_options.running_median,
+        warmup_only: run_options.warmup_only,
+        keep_images: run_options.keep_images,
+        build: run_options.build_options(dir),
+        max_memory: meta_data.max_memory,
+        outlier_sigma: meta_data.outlier_sigma,
+    };
+
+    // The metrics are queried around every round, a client per query would open a connection every time
+    let metrics_client = reqwest::blocking::Client::new();
+    let path = dir.replace("benchmark/web/", "");
     for language_version in &meta_data.language_version {
+        if !run_options.filter.matches_version(dir, language_version) {
+            logln!(" -> Skipping version {} (not selected by --lang-version)", language_version);
+            continue;
+        }
         for framework_version in &meta_data.framework_version {
             if let Some(copy_files) = &meta_data.copy {
-                copy_files::copy_files(dir, &copy_files);
+                copy_files::copy_files(dir, copy_files).map_err(BenchmarkError::InvalidBenchmark)?;
+            }
+
+            // Hashed before the versions are migrated, so the hash only depends on the checkout
+            let versions = format!("{} {}", language_version, framework_version);
+            let input_hash = input_hash::hash_inputs(dir, CATEGORY_DIR, &versions)
+                .map_err(|e| BenchmarkError::InvalidBenchmark(format!("Failed to hash the benchmark inputs: {}", e)))?;
+            if !run_options.warmup_only && result_writer.reuse("web", &path, &input_hash).map_err(|e| BenchmarkError::WriteFailed(e.to_string()))? {
+                if let Some(copy_files) = &meta_data.copy {
+                    copy_files::delete_copied_files(dir, copy_files);
+                }
+                continue;
             }
 
             let mut version_migrations = Vec::with_capacity(2);
@@ -76,23 +170,35 @@ pub fn benchmark_web(
                 ));
             }
 
-            let result = run_benchmark(
+            let results = run_sweep(
                 dir,
-                stats_reader,
-                version_migrations.iter_mut().collect(),
-                match meta_data.extended_warmup {
-                    true => 3,
-                    false => 1,
+                match external_url {
+                    Some(_) => None,
+                    None => Some(&mut *stats_reader),
                 },
-                5,
-                || {
-                    let result = run_http_load_test(
-                        concurrency,
+                version_migrations.iter_mut().collect(),
+                &options,
+                || health_check(base_url),
+                &levels,
+                |concurrency, workload| {
+                    let gc_before = match meta_data.gc_metrics {
+                        true => Some(gc_metrics::query(&metrics_client, base_url)?),
+                        false => None,
+                    };
+                    let allocations_before = match meta_data.allocation_metrics {
+                        true => Some(allocation_metrics::query(&metrics_client, base_url)?),
+                        false => None,
+                    };
+                    // Only the load test is measured, the metrics are queried around it
+                    let start = Instant::now();
+                    let mut result = run_http_load_test(
+                        *concurrency,
                         Duration::from_secs(15),
-                        &requests,
+                        workload.map_or(&requests, |workload| &workloads[workload]),
                         response_validator,
-                        verbose,
-                    );
+                        run_options.verbose,
+                    )?;
+                    let time = start.elapsed();
 
                     let mut additional_data: IndexMap<String, AdditionalData> = IndexMap::new();
                     additional_data.insert("rps_median".to_string(), AdditionalData::Int(result.rps_median));
@@ -100,49 +206,105 @@ pub fn benchmark_web(
                     additional_data.insert("latency_median".to_string(), AdditionalData::Int(result.latency_median.as_micros() as i32));
                     additional_data.insert("latency_p99".to_string(), AdditionalData::Int(result.latency_p99.as_micros() as i32));
                     additional_data.insert("errors".to_string(), AdditionalData::Int(result.fail_count));
+                    if let Some(gc_before) = gc_before {
+                        gc_metrics::query(&metrics_client, base_url)?.since(&gc_before).insert_into(&mut additional_data);
+                    }
+                    if let Some(allocations_before) = allocations_before {
+                        let requests = result.success_count as i64 + result.fail_count as i64;
+                        allocation_metrics::query(&metrics_client, base_url)?.since(&allocations_before).insert_into(requests, &mut additional_data);
+                    }
 
                     let mut debugging_data: IndexMap<String, AdditionalData> = IndexMap::new();
                     debugging_data.insert("success".to_string(), AdditionalData::Int(result.success_count));
                     debugging_data.insert("time".to_string(), AdditionalData::Int(result.total_time.as_millis() as i32));
 
                     Ok(IterationResult {
+                        time,
                         additional_data,
                         debugging_data,
+                        self_reported_memory: match meta_data.self_reported_memory {
+                            true => Some(self_reported_memory::query(&metrics_client, base_url)?),
+                            false => None,
+                        },
+                        response: result.first_response.take(),
                     })
                 },
             );
 
             if let Some(copy_files) = &meta_data.copy {
-                copy_files::delete_copied_files(dir, &copy_files);
+                copy_files::delete_copied_files(dir, copy_files);
             }
+            let Some(results) = results? else {
+                return Ok(tui::take_request());
+            };
+
+            let framework_stdlib = meta_data.framework_stdlib.to_string();
 
-            write_result_to_file(
-                "result/web_result.csv",
-                &Vec::from([
+            if run_options.warmup_only {
+                logln!(" -> Warmup only, no result is recorded");
+                continue;
+            }
+            let timed_out = results.values().any(|result| result.timed_out);
+            for (concurrency, mut result) in results {
+                check_actual_version(language_version, &result);
+                let concurrency_string = concurrency.to_string();
+                let mut descriptors = Vec::from([
                     ("language", meta_data.language.as_str()),
                     ("mode", meta_data.mode.as_str()),
                     ("version", language_version.as_str()),
                     ("framework", meta_data.framework.as_str()),
-                    ("framework_stdlib", meta_data.framework_stdlib.to_string().as_str()),
+                    ("framework_stdlib", framework_stdlib.as_str()),
                     ("framework_website", meta_data.framework_website.as_str()),
                     ("framework_flavor", meta_data.framework_flavor.as_str()),
                     ("framework_version", framework_version.as_str()),
-                    ("concurrency", concurrency.to_string().as_str()),
-                    ("path", dir.replace("benchmark/web/", "").as_str()),
-                ]),
-                &Vec::from([
-                    ("rps_median", result.additional_data.get("rps_median").unwrap().to_string().as_str()),
-                    ("rps_p99", result.additional_data.get("rps_p99").unwrap().to_string().as_str()),
-                    ("latency_median", result.additional_data.get("latency_median").unwrap().to_string().as_str()),
-                    ("latency_p99", result.additional_data.get("latency_p99").unwrap().to_string().as_str()),
-                    ("memory_median", result.memory_median.to_string().as_str()),
-                    ("memory_p99", result.memory_p99.to_string().as_str()),
-                    ("errors", result.additional_data.get("errors").unwrap().to_string().as_str()),
-                ]),
-                take_bigger_rps,
-            ).expect("Failed to write result to file");
+                    ("concurrency", concurrency_string.as_str()),
+                    ("path", path.as_str()),
+                ]);
+                if concurrency_sweep.is_some() {
+                    descriptors.push((SWEEP_KEY, "concurrency"));
+                }
+
+                if external_url.is_none() && concurrency_sweep.is_none() && !result.timed_out {
+                    let additional_data = |key: &str| {
+                        result.additional_data.get(key).map(|value| value.to_string())
+                            .ok_or_else(|| BenchmarkError::InvalidResponse(format!("the load test did not measure {}", key)))
+                    };
+                    // Results of external services, sweeps and partial results are not comparable to the website results
+                    write_result_to_file(
+                        "result/web_result.csv",
+                        &descriptors,
+                        &Vec::from([
+                            ("rps_median", additional_data("rps_median")?.as_str()),
+                            ("rps_p99", additional_data("rps_p99")?.as_str()),
+                            ("latency_median", additional_data("latency_median")?.as_str()),
+                            ("latency_p99", additional_data("latency_p99")?.as_str()),
+                            ("memory_median", result.memory_median.unwrap_or_default().to_string().as_str()),
+                            ("memory_p99", result.memory_p99.unwrap_or_default().to_string().as_str()),
+                            ("errors", additional_data("errors")?.as_str()),
+                        ]),
+                        take_bigger_rps,
+                    ).map_err(|e| BenchmarkError::WriteFailed(e.to_string()))?;
+                }
+
+                result.manifest = manifest.clone();
+                let mut entry = ResultEntry::new(
+                    "web",
+                    &path,
+                    &meta_data.language,
+                    language_version,
+                    &descriptors,
+                    result,
+                );
+                entry.input_hash = Some(input_hash.clone());
+                result_writer.write(entry).map_err(|e| BenchmarkError::WriteFailed(e.to_string()))?;
+            }
+            // The partial result is recorded, the benchmark still fails
+            if timed_out {
+                deadline::check()?;
+            }
         }
     }
+    Ok(None)
 }
 
 #[derive(Deserialize)]
@@ -167,6 +329,38 @@ fn load_data() -> HashMap<String, PeriodicTableElement> {
     elements
 }
 
+fn health_check(base_url: &str) -> Result<Response, BenchmarkError> {
+    let url = format!("{}{}", base_url, HEALTH_CHECK_PATH);
+    let response = reqwest::blocking::Client::new()
+        .get(&url)
+        .timeout(Duration::from_secs(5))
+        .send()?;
+    if !response.status().is_success() {
+        return Err(BenchmarkError::HttpStatus { status: response.status().as_u16(), url });
+    }
+    let status = response.status().as_u16();
+    let bytes = response.bytes()?;
+    Ok(Response { status: Some(status), body: String::from_utf8_lossy(&bytes).to_string(), size: bytes.len(), phases: IndexMap::new() })
+}
+
+/// Status 200 and a JSON object of either endpoint: `name`, `number` and `group` of an element or its `shells`.
+fn validate_response(response: &Response) -> Result<(), String> {
+    if response.status != Some(200) {
+        return Err(format!("expected status 200, got {:?}", response.status));
+    }
+    let json: serde_json::Value = serde_json::from_str(&response.body).map_err(|e| format!("invalid JSON: {}", e))?;
+    let is_element = json.get("name").is_some_and(|v| v.is_string())
+        && json.get("number").is_some_and(|v| v.is_i64())
+        && json.get("group").is_some_and(|v| v.is_i64());
+    let is_shells = json.get("shells")
+        .and_then(|v| v.as_array())
+        .is_some_and(|shells| shells.iter().all(|v| v.is_i64()));
+    if !is_element && !is_shells {
+        return Err("expected an element (`name`, `number`, `group`) or its `shells`".to_string());
+    }
+    Ok(())
+}
+
 fn response_validator(body: &str, expected_response: &HashMap<String, SerializedValue>) -> bool {
     let json: serde_json::Value = {
         match serde_json::from_str::<serde_json::Value>(body) {
@@ -176,25 +370,25 @@ fn response_validator(body: &str, expected_response: &HashMap<String, Serialized
     };
 
     for (key, expected_value) in expected_response {
-        let actual_value = json.get(key).expect(&format!(r#"Expected "{}": {} but this key does not exist"#, key, expected_value));
+        let actual_value = json.get(key).unwrap_or_else(|| panic!(r#"Expected "{}": {} but this key does not exist"#, key, expected_value));
         match expected_value {
             SerializedValue::StringValue(v) => {
-                if actual_value.as_str().expect(&format!(r#"Expected "{}": {} but got <{:?}>"#, key, expected_value, actual_value)) != v {
+                if actual_value.as_str().unwrap_or_else(|| panic!(r#"Expected "{}": {} but got <{:?}>"#, key, expected_value, actual_value)) != v {
                     return false;
                 }
             }
             SerializedValue::IntValue(v) => {
-                if actual_value.as_i64().expect(&format!(r#"Expected "{}": {} but got <{:?}>"#, key, expected_value, actual_value)) != *v as i64 {
+                if actual_value.as_i64().unwrap_or_else(|| panic!(r#"Expected "{}": {} but got <{:?}>"#, key, expected_value, actual_value)) != *v as i64 {
                     return false;
                 }
             }
             SerializedValue::IntListValue(v) => {
-                let actual_list = actual_value.as_array().expect(&format!(r#"Expected "{}": {} but got <{:?}>"#, key, expected_value, actual_value));
+                let actual_list = actual_value.as_array().unwrap_or_else(|| panic!(r#"Expected "{}": {} but got <{:?}>"#, key, expected_value, actual_value));
                 if actual_list.len() != v.len() {
                     return false;
                 }
                 for (i, actual_value) in actual_list.iter().enumerate() {
-                    if actual_value.as_i64().expect(&format!(r#"Expected "{}": {} but got <{:?}>"#, key, expected_value, actual_value)) != v[i] as i64 {
+                    if actual_value.as_i64().unwrap_or_else(|| panic!(r#"Expected "{}": {} but got <{:?}>"#, key, expected_value, actual_value)) != v[i] as i64 {
                         return false;
                     }
                 }
@@ -207,9 +401,27 @@ fn response_validator(body: &str, expected_response: &HashMap<String, Serialized
 
 fn take_bigger_rps<'a>(old_values: &'a [&'a str], new_values: &'a [&'a str]) -> &'a [&'a str] {
     if old_values[0].parse::<i32>().unwrap() > new_values[0].parse::<i32>().unwrap() {
-        println!(" -> Keeping old values (rps_median: {} > {})", old_values[0], new_values[0]);
+        logln!(" -> Keeping old values (rps_median: {} > {})", old_values[0], new_values[0]);
         old_values
     } else {
         new_values
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, body: &str) -> Response {
+        Response { status: Some(status), body: body.to_string(), size: body.len(), phases: IndexMap::new() }
+    }
+
+    #[test]
+    fn should_validate_response_shape() {
+        assert_eq!(validate_response(&response(200, r#"{"name":"Hydrogen","number":1,"group":1}"#)), Ok(()));
+        assert_eq!(validate_response(&response(200, r#"{"shells":[2,8,1]}"#)), Ok(()));
+        assert_eq!(validate_response(&response(500, "{}")), Err("expected status 200, got Some(500)".to_string()));
+        assert!(validate_response(&response(200, "<html>Error</html>")).unwrap_err().starts_with("invalid JSON"));
+        assert!(validate_response(&response(200, r#"{"name":"Hydrogen","number":"1"}"#)).is_err());
+    }
+}
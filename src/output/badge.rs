@@ -0,0 +1,136 @@
+use crate::output::escape;
+
+pub const DEFAULT_LABEL: &str = "sharkbench";
+
+const HEIGHT: u32 = 20;
+
+/// Space between the text and the edges of its part of the badge.
+const PADDING: u32 = 6;
+
+const GREEN: &str = "#4c1";
+const YELLOW: &str = "#dfb317";
+const RED: &str = "#e05d44";
+const GREY: &str = "#9f9f9f";
+
+/// Renders a shields.io-style badge, e.g. `sharkbench | 42 passed, 1 regressed`.
+///
+/// The message is red if a benchmark failed, yellow if one regressed (only known with a baseline) and green otherwise.
+pub fn to_badge(label: &str, passed: usize, failed: usize, regressed: Option<usize>) -> String {
+    let mut parts = vec![format!("{} passed", passed)];
+    if failed > 0 {
+        parts.push(format!("{} failed", failed));
+    }
+    if let Some(regressed) = regressed.filter(|regressed| *regressed > 0) {
+        parts.push(format!("{} regressed", regressed));
+    }
+    let (message, color) = match (passed, failed, regressed.unwrap_or(0)) {
+        (0, 0, _) => ("no results".to_string(), GREY),
+        (_, 1.., _) => (parts.join(", "), RED),
+        (_, _, 1..) => (parts.join(", "), YELLOW),
+        _ => (parts.join(", "), GREEN),
+    };
+
+    let label_text = text_width(label);
+    let message_text = text_width(&message);
+    let label_width = label_text + 2 * PADDING;
+    let message_width = message_text + 2 * PADDING;
+    let width = label_width + message_width;
+
+    // Text is rendered at 10 times the size and scaled down, like shields.io does, for more precise positioning.
+    // `textLength` squeezes the text into the estimated width, so it never overflows its part.
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" role="img" aria-label="{label}: {message}">
+<title>{label}: {message}</title>
+<linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>
+<clipPath id="r"><rect width="{width}" height="{height}" rx="3" fill="#fff"/></clipPath>
+<g clip-path="url(#r)"><rect width="{label_width}" height="{height}" fill="#555"/><rect x="{label_width}" width="{message_width}" height="{height}" fill="{color}"/><rect width="{width}" height="{height}" fill="url(#s)"/></g>
+<g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="110">
+<text x="{label_x}" y="150" fill="#010101" fill-opacity=".3" transform="scale(.1)" textLength="{label_length}">{label}</text>
+<text x="{label_x}" y="140" transform="scale(.1)" textLength="{label_length}">{label}</text>
+<text x="{message_x}" y="150" fill="#010101" fill-opacity=".3" transform="scale(.1)" textLength="{message_length}">{message}</text>
+<text x="{message_x}" y="140" transform="scale(.1)" textLength="{message_length}">{message}</text>
+</g>
+</svg>
+"##,
+        width = width,
+        height = HEIGHT,
+        label = escape::xml(label),
+        message = escape::xml(&message),
+        label_width = label_width,
+        message_width = message_width,
+        color = color,
+        label_x = label_width * 5,
+        label_length = label_text * 10,
+        message_x = label_width * 10 + message_width * 5,
+        message_length = message_text * 10,
+    )
+}
+
+/// Estimated width in pixels of the text in Verdana at 11px, rounded up.
+/// Unknown characters (e.g. non-ASCII) count as wide ones, so the badge rather gets too wide than too narrow.
+fn text_width(text: &str) -> u32 {
+    let width: f64 = text.chars().map(char_width).sum();
+    width.ceil() as u32
+}
+
+fn char_width(c: char) -> f64 {
+    match c {
+        'i' | 'l' | '\'' | '|' => 3.0,
+        '.' | ',' | ':' | ';' | '!' => 4.0,
+        ' ' | 'f' | 'j' | 't' | '(' | ')' | '[' | ']' | '/' | 'I' => 4.3,
+        'r' | '-' => 4.7,
+        'c' | 's' | 'z' => 5.8,
+        'k' | 'v' | 'x' | 'y' => 6.5,
+        'a' | 'b' | 'd' | 'e' | 'g' | 'h' | 'n' | 'o' | 'p' | 'q' | 'u' | '_' => 6.9,
+        '0'..='9' | '#' | '$' | '+' | '=' | '?' => 7.0,
+        'w' => 9.0,
+        'm' | 'M' | 'O' | 'Q' => 10.7,
+        'W' | '%' | '@' => 12.0,
+        'A'..='Z' => 8.0,
+        _ => 9.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_choose_color_by_outcome() {
+        assert!(to_badge(DEFAULT_LABEL, 42, 0, None).contains(&format!("fill=\"{}\"", GREEN)));
+        assert!(to_badge(DEFAULT_LABEL, 42, 0, Some(0)).contains(&format!("fill=\"{}\"", GREEN)));
+        assert!(to_badge(DEFAULT_LABEL, 42, 0, Some(1)).contains(&format!("fill=\"{}\"", YELLOW)));
+        assert!(to_badge(DEFAULT_LABEL, 42, 1, Some(1)).contains(&format!("fill=\"{}\"", RED)));
+        assert!(to_badge(DEFAULT_LABEL, 0, 0, None).contains(&format!("fill=\"{}\"", GREY)));
+    }
+
+    #[test]
+    fn should_summarize_run() {
+        let badge = to_badge(DEFAULT_LABEL, 42, 2, Some(1));
+        assert!(badge.contains("<title>sharkbench: 42 passed, 2 failed, 1 regressed</title>"));
+        assert!(to_badge(DEFAULT_LABEL, 0, 0, None).contains("<title>sharkbench: no results</title>"));
+    }
+
+    #[test]
+    fn should_fit_long_labels() {
+        let short = to_badge("a", 1, 0, None);
+        let long = to_badge("benchmarks of the nightly run", 1, 0, None);
+        let width = |badge: &str| badge.split('"').nth(3).unwrap().parse::<u32>().unwrap();
+
+        let label_text = text_width("benchmarks of the nightly run");
+        assert_eq!(width(&long) - width(&short), label_text - text_width("a"));
+        assert!(long.contains(&format!("textLength=\"{}\"", label_text * 10)));
+    }
+
+    #[test]
+    fn should_estimate_text_width() {
+        assert_eq!(text_width(""), 0);
+        assert!(text_width("WWW") > text_width("iii") * 3);
+        assert_eq!(text_width("sharkbench"), 65);
+    }
+
+    #[test]
+    fn should_escape_label() {
+        assert!(to_badge("a<b>&c", 1, 0, None).contains(">a&lt;b&gt;&amp;c</text>"));
+    }
+}
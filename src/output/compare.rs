@@ -0,0 +1,398 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use crate::output::json::{self, ResultEntry};
+use crate::output::escape;
+use crate::output::metric::{self, Metric};
+use crate::output::regression::{display_name, same_benchmark};
+use crate::utils::format::{self, SizeFormat, TimeFormat};
+use crate::utils::table;
+
+/// Formats of `compare`.
+#[derive(ValueEnum, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompareFormat {
+    /// Aligned columns for the terminal.
+    #[default]
+    Table,
+    Md,
+    Json,
+}
+
+/// Shown instead of the change of a metric within the noise floor.
+const NO_SIGNIFICANT_DIFFERENCE: &str = "no significant difference";
+
+/// Differences of time and memory up to which a comparison reports no significant difference instead of a change,
+/// e.g. 1-2 ms between near-identical implementations. A difference is noise if it is within any of the floors, `0` disables a floor.
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct NoiseFloor {
+    /// Absolute difference of the median time in milliseconds, also of the median latency of web benchmarks.
+    pub time_millis: i64,
+
+    /// Absolute difference of the median memory in bytes.
+    pub memory_bytes: i64,
+
+    /// Relative change of time or memory in percent.
+    pub percent: f64,
+}
+
+impl NoiseFloor {
+    pub fn is_disabled(&self) -> bool {
+        self.time_millis <= 0 && self.memory_bytes <= 0 && self.percent <= 0.0
+    }
+
+    /// The absolute floor of the metric, the latency is in microseconds and the requests per second only have the relative floor.
+    fn absolute(&self, metric: Metric) -> i64 {
+        match metric {
+            Metric::Time => self.time_millis,
+            Metric::Latency => self.time_millis * 1000,
+            Metric::Memory => self.memory_bytes,
+            Metric::Rps => 0,
+        }
+    }
+
+    /// Whether the difference of a metric is within the absolute floor `absolute` of the metric or the relative floor.
+    fn is_noise(&self, old: i64, new: i64, change_percent: f64, absolute: i64) -> bool {
+        (absolute > 0 && (new - old).abs() <= absolute) || (self.percent > 0.0 && change_percent.abs() <= self.percent)
+    }
+}
+
+/// A metric of an entry in both results files, `None` if it was not measured.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct MetricComparison {
+    pub old: Option<i64>,
+    pub new: Option<i64>,
+
+    /// Relative change in percent, positive means the value grew (e.g. slower, more memory or more requests per second).
+    pub change_percent: Option<f64>,
+
+    /// Whether the difference is within the noise floor, it is neither highlighted nor a regression then.
+    pub noise: bool,
+
+    /// Whether a growing value is an improvement, e.g. the requests per second.
+    #[serde(skip)]
+    pub higher_is_better: bool,
+}
+
+impl MetricComparison {
+    /// `absolute_floor` is the absolute noise floor of the metric, see `NoiseFloor`.
+    fn new(old: Option<i64>, new: Option<i64>, noise_floor: &NoiseFloor, absolute_floor: i64, higher_is_better: bool) -> Self {
+        let change_percent = match (old, new) {
+            (Some(old), Some(new)) if old > 0 => Some((new - old) as f64 / old as f64 * 100.0),
+            _ => None,
+        };
+        let noise = match (old, new, change_percent) {
+            (Some(old), Some(new), Some(change)) => noise_floor.is_noise(old, new, change, absolute_floor),
+            _ => false,
+        };
+        MetricComparison { old, new, change_percent, noise, higher_is_better }
+    }
+
+    fn exceeds(&self, threshold_percent: f64) -> bool {
+        !self.noise && self.change_percent.is_some_and(|change| change.abs() > threshold_percent)
+    }
+
+    fn regresses(&self, threshold_percent: f64) -> bool {
+        let worse = |change: f64| if self.higher_is_better { -change } else { change };
+        !self.noise && self.change_percent.is_some_and(|change| worse(change) > threshold_percent)
+    }
+
+    fn format_change(&self) -> String {
+        match self.noise {
+            true => NO_SIGNIFICANT_DIFFERENCE.to_string(),
+            false => self.change_percent.map(|change| format!("{:+.1}%", change)).unwrap_or("-".to_string()),
+        }
+    }
+}
+
+/// An entry present in both results files.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ComparedEntry {
+    /// `<benchmark> (<language> <version>)`
+    pub benchmark: String,
+
+    /// The median time of a round, `None` for web benchmarks as their rounds load the server for a fixed duration.
+    pub time: Option<MetricComparison>,
+
+    /// The median requests per second (`rps_median`) of web benchmarks.
+    pub rps: Option<MetricComparison>,
+
+    /// The median latency in microseconds (`latency_median`) of web benchmarks.
+    pub latency: Option<MetricComparison>,
+    pub memory: MetricComparison,
+
+    /// Whether any metric changed by more than the threshold and the noise floor, in either direction.
+    pub significant: bool,
+}
+
+/// The titles of the metrics in the order of `ComparedEntry::metrics`.
+const METRICS: [&str; 4] = ["Time", "RPS", "Latency", "Memory"];
+
+/// The metrics of entries without web benchmarks, indices into `METRICS`.
+const DEFAULT_COLUMNS: [usize; 2] = [0, 3];
+
+/// Formats a value of a metric, see `ComparedEntry::metrics`.
+type FormatValue = fn(i64) -> String;
+
+impl ComparedEntry {
+    /// The metrics in the order of `METRICS` with how their values are formatted,
+    /// `None` if the category of the entry does not have the metric.
+    fn metrics(&self) -> [(Option<&MetricComparison>, FormatValue); 4] {
+        [
+            (self.time.as_ref(), |time| time.millis_to_string()),
+            (self.rps.as_ref(), |rps| format!("{} rps", format::format_number(rps))),
+            (self.latency.as_ref(), |latency| format!("{} ms", format::number_format().decimal(latency as f64 / 1000.0, 2))),
+            (Some(&self.memory), |memory| memory.bytes_to_string()),
+        ]
+    }
+
+    fn is_significant(&self, threshold_percent: f64) -> bool {
+        self.metrics().iter().any(|(metric, _)| metric.is_some_and(|metric| metric.exceeds(threshold_percent)))
+    }
+}
+
+/// The entries of two results files, matched by benchmark, language, version and sweep level.
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct Comparison {
+    pub threshold_percent: f64,
+    pub noise_floor: NoiseFloor,
+    pub entries: Vec<ComparedEntry>,
+
+    /// Entries only present in the new file.
+    pub added: Vec<String>,
+
+    /// Entries only present in the old file.
+    pub removed: Vec<String>,
+}
+
+impl Comparison {
+    /// The entries with a metric that got worse by more than `threshold_percent` and the noise floor,
+    /// e.g. a longer time or fewer requests per second.
+    pub fn regressions(&self, threshold_percent: f64) -> Vec<&ComparedEntry> {
+        self.entries.iter()
+            .filter(|entry| entry.metrics().iter().any(|(metric, _)| metric.is_some_and(|metric| metric.regresses(threshold_percent))))
+            .collect()
+    }
+
+    /// The indices of the `METRICS` that any entry has, e.g. only time and memory without web entries.
+    fn columns(&self) -> Vec<usize> {
+        if self.entries.is_empty() {
+            return DEFAULT_COLUMNS.to_vec();
+        }
+        (0..METRICS.len())
+            .filter(|&i| self.entries.iter().any(|entry| entry.metrics()[i].0.is_some()))
+            .collect()
+    }
+
+    /// The old value, the new value and the change of every metric in `columns`, `-` if the entry does not have it.
+    fn metric_cells(entry: &ComparedEntry, columns: &[usize], change: impl Fn(&MetricComparison) -> String) -> Vec<String> {
+        let metrics = entry.metrics();
+        columns.iter().flat_map(|&i| {
+            let (metric, format_value) = metrics[i];
+            let value = |value: Option<i64>| value.map(format_value).unwrap_or("-".to_string());
+            match metric {
+                Some(metric) => [value(metric.old), value(metric.new), change(metric)],
+                None => ["-".to_string(), "-".to_string(), "-".to_string()],
+            }
+        }).collect()
+    }
+
+    fn metric_headers(columns: &[usize]) -> Vec<String> {
+        columns.iter()
+            .flat_map(|&i| [format!("{} old", METRICS[i]), format!("{} new", METRICS[i]), "Change".to_string()])
+            .collect()
+    }
+
+    pub fn render(&self, format: CompareFormat) -> String {
+        match format {
+            CompareFormat::Table => self.to_table(),
+            CompareFormat::Md => self.to_markdown(),
+            CompareFormat::Json => format!("{}\n", serde_json::to_string_pretty(self).expect("Failed to serialize comparison")),
+        }
+    }
+
+    /// One entry per line, changes beyond the threshold are marked with `!`.
+    pub fn to_table(&self) -> String {
+        let columns = self.columns();
+        let header: Vec<String> = ["".to_string(), "Benchmark".to_string()].into_iter().chain(Self::metric_headers(&columns)).collect();
+        let rows: Vec<Vec<String>> = self.entries.iter()
+            .map(|entry| [if entry.significant { "!" } else { "" }.to_string(), entry.benchmark.clone()].into_iter()
+                .chain(Self::metric_cells(entry, &columns, MetricComparison::format_change))
+                .collect())
+            .collect();
+
+        let mut buffer = table::render(&header, &rows);
+        for (title, names) in [("Added", &self.added), ("Removed", &self.removed)] {
+            buffer.push_str(&format!("\n{} ({}):\n", title, names.len()));
+            for name in names {
+                buffer.push_str(&format!(" - {}\n", name));
+            }
+        }
+        buffer
+    }
+
+    /// A table of the entries for a pull request comment, changes beyond the threshold are bold.
+    pub fn to_markdown(&self) -> String {
+        let change = |metric: &MetricComparison| match metric.exceeds(self.threshold_percent) {
+            true => format!("**{}**", metric.format_change()),
+            false => metric.format_change(),
+        };
+
+        let mut buffer = String::from("# Comparison\n\n");
+        buffer.push_str(&format!("Changes of more than {}% are bold.", self.threshold_percent));
+        if !self.noise_floor.is_disabled() {
+            buffer.push_str(&format!(" Differences within the noise floor ({}) are {}.", self.noise_floor_to_string(), NO_SIGNIFICANT_DIFFERENCE));
+        }
+        buffer.push_str("\n\n");
+        let columns = self.columns();
+        buffer.push_str(&format!("| Benchmark | {} |\n", Self::metric_headers(&columns).join(" | ")));
+        buffer.push_str(&format!("|---|{}\n", "---:|".repeat(columns.len() * 3)));
+        for entry in &self.entries {
+            buffer.push_str(&format!("| {} | {} |\n", escape::markdown(&entry.benchmark), Self::metric_cells(entry, &columns, change).join(" | ")));
+        }
+        for (title, names) in [("Added", &self.added), ("Removed", &self.removed)] {
+            if !names.is_empty() {
+                buffer.push_str(&format!("\n## {}\n\n", title));
+                for name in names {
+                    buffer.push_str(&format!("- {}\n", escape::markdown(name)));
+                }
+            }
+        }
+        buffer
+    }
+
+    /// e.g. `2 ms, 4.00 KB or 1%`, the enabled floors.
+    fn noise_floor_to_string(&self) -> String {
+        let floor = &self.noise_floor;
+        let mut floors = Vec::new();
+        if floor.time_millis > 0 {
+            floors.push(floor.time_millis.millis_to_string());
+        }
+        if floor.memory_bytes > 0 {
+            floors.push(floor.memory_bytes.bytes_to_string());
+        }
+        if floor.percent > 0.0 {
+            floors.push(format!("{}%", floor.percent));
+        }
+        floors.join(" or ")
+    }
+}
+
+/// Compares the metrics (see `metric::compared`) of every entry of `new` with the same entry of `old` (see `regression::compare`),
+/// the median time and memory, for web benchmarks the median requests per second and latency instead of the time.
+/// Unlike `--baseline` every entry is listed, not only those beyond `threshold_percent`.
+/// Differences within the `noise_floor` are reported as no significant difference.
+pub fn compare(old: &[ResultEntry], new: &[ResultEntry], threshold_percent: f64, noise_floor: NoiseFloor) -> Comparison {
+    let mut comparison = Comparison { threshold_percent, noise_floor, ..Default::default() };
+    for entry in json::sorted_entries(new) {
+        let Some(previous) = old.iter().find(|previous| same_benchmark(previous, entry)) else {
+            comparison.added.push(display_name(entry));
+            continue;
+        };
+        let compare_metric = |metric: Metric| {
+            MetricComparison::new(metric.value(previous), metric.value(entry), &noise_floor, noise_floor.absolute(metric), metric.higher_is_better())
+        };
+        let metrics = metric::compared(&entry.category);
+        let compare_if_compared = |metric: Metric| metrics.contains(&metric).then(|| compare_metric(metric));
+        let mut compared = ComparedEntry {
+            benchmark: display_name(entry),
+            time: compare_if_compared(Metric::Time),
+            rps: compare_if_compared(Metric::Rps),
+            latency: compare_if_compared(Metric::Latency),
+            memory: compare_metric(Metric::Memory),
+            significant: false,
+        };
+        compared.significant = compared.is_significant(threshold_percent);
+        comparison.entries.push(compared);
+    }
+    for entry in json::sorted_entries(old) {
+        if !new.iter().any(|current| same_benchmark(current, entry)) {
+            comparison.removed.push(display_name(entry));
+        }
+    }
+    comparison
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_entry::TestEntry;
+
+    #[test]
+    fn should_compare_matching_entries() {
+        let old = vec![TestEntry::new("rust/a").time(1000).memory(1024 * 1024).build(), TestEntry::new("rust/b").time(1000).memory(1024 * 1024).build(), TestEntry::new("rust/old").time(1000).memory(1024).build()];
+        let new = vec![TestEntry::new("rust/b").time(950).memory(1024 * 1024).build(), TestEntry::new("rust/a").time(1200).memory(1024 * 1024).build(), TestEntry::new("rust/new").time(1000).memory(1024).build()];
+
+        let comparison = compare(&old, &new, 10.0, NoiseFloor::default());
+        assert_eq!(comparison.entries[0], ComparedEntry {
+            benchmark: "rust/a (Rust 1.74)".to_string(),
+            time: Some(MetricComparison { old: Some(1000), new: Some(1200), change_percent: Some(20.0), noise: false, higher_is_better: false }),
+            rps: None,
+            latency: None,
+            memory: MetricComparison { old: Some(1024 * 1024), new: Some(1024 * 1024), change_percent: Some(0.0), noise: false, higher_is_better: false },
+            significant: true,
+        });
+        assert!(!comparison.entries[1].significant);
+        assert_eq!(comparison.added, vec!["rust/new (Rust 1.74)"]);
+        assert_eq!(comparison.removed, vec!["rust/old (Rust 1.74)"]);
+        assert_eq!(comparison.regressions(10.0).len(), 1);
+        assert_eq!(comparison.regressions(25.0).len(), 0);
+    }
+
+    #[test]
+    fn should_render_formats() {
+        let comparison = compare(&[TestEntry::new("rust/a").time(1000).memory(1024 * 1024).build()], &[TestEntry::new("rust/a").time(1200).memory(1024 * 1024).build()], 10.0, NoiseFloor::default());
+
+        let table = comparison.to_table();
+        assert!(table.starts_with("   Benchmark           Time old  Time new  Change  Memory old  Memory new  Change\n"), "{}", table);
+        assert!(table.contains("\n!  rust/a (Rust 1.74)  1.00 s    1.20 s    +20.0%  1.00 MB     1.00 MB     +0.0%\n"), "{}", table);
+        assert!(comparison.to_markdown().contains("| rust/a (Rust 1.74) | 1.00 s | 1.20 s | **+20.0%** | 1.00 MB | 1.00 MB | +0.0% |\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&comparison.render(CompareFormat::Json)).unwrap();
+        assert_eq!(json["entries"][0]["time"]["change_percent"], 20.0);
+        assert_eq!(json["added"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn should_report_differences_within_noise_floor() {
+        let old = vec![TestEntry::new("rust/a").time(10).memory(1024 * 1024).build(), TestEntry::new("rust/b").time(1000).memory(1024 * 1024).build()];
+        let new = vec![TestEntry::new("rust/a").time(12).memory(1024 * 1024 + 2048).build(), TestEntry::new("rust/b").time(1300).memory(1024 * 1024 + 8192).build()];
+        let noise_floor = NoiseFloor { time_millis: 2, memory_bytes: 4096, percent: 0.0 };
+
+        let comparison = compare(&old, &new, 10.0, noise_floor);
+        // +20% of the time, but only 2 ms
+        let time = |entry: &ComparedEntry| entry.time.as_ref().unwrap().noise;
+        assert!(time(&comparison.entries[0]) && comparison.entries[0].memory.noise);
+        assert!(!comparison.entries[0].significant);
+        assert!(!time(&comparison.entries[1]) && !comparison.entries[1].memory.noise);
+        assert_eq!(comparison.regressions(10.0).len(), 1);
+        assert!(comparison.to_table().contains("rust/a (Rust 1.74)  10 ms     12 ms     no significant difference"), "{}", comparison.to_table());
+        assert!(comparison.to_markdown().contains("Differences within the noise floor (2 ms or 4.00 KB) are no significant difference.\n"));
+
+        let relative = compare(&old, &new, 10.0, NoiseFloor { percent: 50.0, ..Default::default() });
+        assert!(relative.entries.iter().all(|entry| time(entry) && !entry.significant));
+        assert!(compare(&old, &new, 10.0, NoiseFloor::default()).entries.iter().all(|entry| !time(entry) && !entry.memory.noise));
+    }
+
+    #[test]
+    fn should_compare_throughput_and_latency_of_web_benchmarks() {
+        let web = |time_median: i64, rps: i32, latency: i32| {
+            TestEntry::new("rust/axum").category("web").time(time_median).memory(1024 * 1024).rps(rps).latency(latency).build()
+        };
+        // The time is the fixed duration of the load
+        let comparison = compare(&[web(10000, 2000, 500)], &[web(10000, 1000, 550)], 10.0, NoiseFloor::default());
+
+        let entry = &comparison.entries[0];
+        assert_eq!(entry.time, None);
+        assert_eq!(entry.rps.as_ref().unwrap().change_percent, Some(-50.0));
+        assert_eq!(entry.latency.as_ref().unwrap().change_percent, Some(10.0));
+        assert!(entry.significant);
+        assert_eq!(comparison.regressions(10.0).len(), 1);
+        // More requests per second are no regression
+        assert_eq!(compare(&[web(10000, 1000, 500)], &[web(10000, 2000, 500)], 10.0, NoiseFloor::default()).regressions(10.0).len(), 0);
+
+        let table = comparison.to_table();
+        assert!(table.starts_with("   Benchmark              RPS old    RPS new    Change  Latency old  Latency new  Change  Memory old  Memory new  Change\n"), "{}", table);
+        assert!(table.contains("\n!  rust/axum (Rust 1.74)  2,000 rps  1,000 rps  -50.0%  0.50 ms      0.55 ms      +10.0%  1.00 MB     1.00 MB     +0.0%\n"), "{}", table);
+        assert!(comparison.to_markdown().contains("| RPS old | RPS new | Change | Latency old | Latency new | Change | Memory old |"));
+    }
+}
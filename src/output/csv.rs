@@ -0,0 +1,111 @@
+use crate::output::escape;
+use crate::output::json::{self, ResultEntry};
+
+/// Metadata keys that are already part of the identity columns.
+const IDENTITY_KEYS: [&str; 3] = ["language", "version", "path"];
+
+/// Renders the entries as a flat CSV with one row per benchmark and version.
+///
+/// Columns are: identity, metadata (sorted), metrics, additional data (sorted), tags (sorted, prefixed with `tag:`).
+/// The column order only depends on the set of keys, so it is stable across rows and runs.
+/// Rows are sorted like `json::sorted_entries`. Missing values are left empty. Numbers are written raw (e.g. bytes, not "1.00 MB").
+pub fn to_csv(entries: &[ResultEntry]) -> String {
+    let metadata_keys: Vec<&str> = sorted_keys(entries.iter().flat_map(|entry| {
+        entry.metadata.keys().map(|k| k.as_str()).filter(|k| !IDENTITY_KEYS.contains(k))
+    }));
+    let additional_data_keys: Vec<&str> = sorted_keys(entries.iter().flat_map(|entry| {
+        entry.result.additional_data.keys().map(|k| k.as_str())
+    }));
+    let tag_keys: Vec<&str> = sorted_keys(entries.iter().flat_map(|entry| {
+        entry.result.tags.keys().map(|k| k.as_str())
+    }));
+
+    let mut header: Vec<&str> = vec!["category", "benchmark", "language", "version"];
+    header.extend(&metadata_keys);
+    header.extend(["time_median", "time_p1", "memory_median", "memory_p99", "self_reported_memory_median"]);
+    header.extend(&additional_data_keys);
+
+    let mut buffer = String::new();
+    push_row(&mut buffer, header.iter().map(|h| h.to_string())
+        .chain(tag_keys.iter().map(|k| format!("tag:{}", k)))
+        .collect());
+
+    for entry in json::sorted_entries(entries) {
+        let mut row: Vec<String> = vec![
+            entry.category.clone(),
+            entry.benchmark.clone(),
+            entry.language.clone(),
+            entry.version.clone(),
+        ];
+        for key in &metadata_keys {
+            row.push(entry.metadata.get(*key).cloned().unwrap_or_default());
+        }
+        row.push(entry.result.time_median.to_string());
+        row.push(entry.result.time_p1.map(|v| v.to_string()).unwrap_or_default());
+        row.push(entry.result.memory_median.map(|v| v.to_string()).unwrap_or_default());
+        row.push(entry.result.memory_p99.map(|v| v.to_string()).unwrap_or_default());
+        row.push(entry.result.self_reported_memory_median.map(|v| v.to_string()).unwrap_or_default());
+        for key in &additional_data_keys {
+            row.push(entry.result.additional_data.get(*key).map(|v| v.to_string()).unwrap_or_default());
+        }
+        for key in &tag_keys {
+            row.push(entry.result.tags.get(*key).cloned().unwrap_or_default());
+        }
+        push_row(&mut buffer, row);
+    }
+
+    buffer
+}
+
+fn sorted_keys<'a>(keys: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut keys: Vec<&str> = keys.collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+fn push_row(buffer: &mut String, row: Vec<String>) {
+    let escaped: Vec<String> = row.iter().map(|value| escape::csv(value)).collect();
+    buffer.push_str(&escaped.join(","));
+    buffer.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_entry::TestEntry;
+
+    #[test]
+    fn should_use_stable_columns_with_empty_missing_values() {
+        let measured = |benchmark: &str| TestEntry::new(benchmark).category("web").time(10).time_p1(9).memory(2048).memory_p99(1073741824);
+        let csv = to_csv(&[
+            measured("rust/a").metadata("mode", "Default").metadata("language", "Rust").rps(5).data("errors", 0).build(),
+            measured("rust/b").metadata("framework", "Axum").data("latency_p99", 7).build(),
+        ]);
+
+        assert_eq!(csv, "\
+category,benchmark,language,version,framework,mode,time_median,time_p1,memory_median,memory_p99,self_reported_memory_median,errors,latency_p99,rps_median
+web,rust/a,Rust,1.74,,Default,10,9,2048,1073741824,,0,,5
+web,rust/b,Rust,1.74,Axum,,10,9,2048,1073741824,,,7,
+");
+    }
+
+    #[test]
+    fn should_add_tag_columns() {
+        let mut tagged = TestEntry::new("rust/a").category("web").build();
+        tagged.result.tags.insert("power".to_string(), "battery".to_string());
+        tagged.result.tags.insert("kernel".to_string(), "6.1".to_string());
+
+        let csv = to_csv(&[tagged, TestEntry::new("rust/b").category("web").build()]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].ends_with(",self_reported_memory_median,tag:kernel,tag:power"));
+        assert!(lines[1].ends_with(",,6.1,battery"));
+        assert!(lines[2].ends_with(",,,"));
+    }
+
+    #[test]
+    fn should_leave_unmeasured_memory_empty() {
+        let unmeasured = TestEntry::new("rust/a").category("web").time(10).time_p1(9).build();
+        assert_eq!(to_csv(&[unmeasured]).lines().nth(1), Some("web,rust/a,Rust,1.74,10,9,,,"));
+    }
+}
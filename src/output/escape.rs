@@ -0,0 +1,56 @@
+/// Escapes text for XML, HTML and SVG, in elements and quoted attributes alike.
+/// Control characters other than tabs and line breaks are not allowed in XML 1.0 and are removed.
+pub fn xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\t' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes text for a cell of a Markdown table.
+pub fn markdown(value: &str) -> String {
+    value.replace('|', "\\|")
+}
+
+/// Quotes a CSV field if it contains a separator, a quote or a line break (RFC 4180).
+pub fn csv(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_escape_xml() {
+        assert_eq!(xml("a<b>&\"c\"'"), "a&lt;b&gt;&amp;&quot;c&quot;&apos;");
+        assert_eq!(xml("line\nbreak\u{1b}[0m"), "line&#10;break[0m");
+    }
+
+    #[test]
+    fn should_escape_markdown() {
+        assert_eq!(markdown("a|b"), "a\\|b");
+    }
+
+    #[test]
+    fn should_escape_csv() {
+        assert_eq!(csv("plain"), "plain");
+        assert_eq!(csv("a,b"), "\"a,b\"");
+        assert_eq!(csv("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -0,0 +1,185 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use crate::utils::format::format_duration;
+use crate::utils::log;
+
+/// When the progress of the run is printed, see `--progress`.
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProgressMode {
+    /// Only if the output is an interactive terminal, so CI logs stay clean.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ProgressMode {
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            ProgressMode::Auto => log::is_terminal(),
+            ProgressMode::Always => true,
+            ProgressMode::Never => false,
+        }
+    }
+}
+
+static ETA: Mutex<Option<Eta>> = Mutex::new(None);
+
+/// The progress of a run for `--progress`: the benchmarks done so far and the rounds of the current level.
+///
+/// The ETA adds the remaining rounds of the current level (at the average duration of its rounds so far)
+/// and the remaining benchmarks (at the average duration of the finished ones). Until a benchmark finished,
+/// the current one is projected instead. Further versions and levels of the current benchmark are only
+/// accounted for through the average, so the ETA is rough in the first benchmarks.
+#[derive(Debug)]
+pub struct Eta {
+    /// Benchmarks to run, skipped ones are removed.
+    total: usize,
+    finished: usize,
+    benchmark_durations: Vec<Duration>,
+    benchmark_started: Option<Instant>,
+
+    /// Planned rounds of the current level, warmup included.
+    rounds: usize,
+    round_durations: Vec<Duration>,
+    last_round: Option<Instant>,
+}
+
+impl Eta {
+    pub fn new(total: usize) -> Self {
+        Eta { total, finished: 0, benchmark_durations: Vec::new(), benchmark_started: None, rounds: 0, round_durations: Vec::new(), last_round: None }
+    }
+
+    pub fn start_benchmark(&mut self, now: Instant) {
+        self.benchmark_started = Some(now);
+        self.rounds = 0;
+        self.round_durations.clear();
+        self.last_round = None;
+    }
+
+    /// The current benchmark succeeded or failed.
+    pub fn finish_benchmark(&mut self, now: Instant) {
+        if let Some(started) = self.benchmark_started.take() {
+            self.benchmark_durations.push(now - started);
+        }
+        self.finished += 1;
+        self.rounds = 0;
+    }
+
+    /// A benchmark that was counted is not run.
+    pub fn skip_benchmark(&mut self) {
+        self.total = self.total.saturating_sub(1);
+    }
+
+    /// The rounds of the next level of the current benchmark start.
+    pub fn plan_rounds(&mut self, rounds: usize, now: Instant) {
+        self.rounds = rounds;
+        self.round_durations.clear();
+        self.last_round = Some(now);
+    }
+
+    pub fn complete_round(&mut self, now: Instant) {
+        if let Some(last_round) = self.last_round.replace(now) {
+            self.round_durations.push(now - last_round);
+        }
+    }
+
+    /// `None` until a round completed or a benchmark finished.
+    pub fn eta(&self, now: Instant) -> Option<Duration> {
+        let finished = average(&self.benchmark_durations);
+        let remaining_rounds = self.rounds.saturating_sub(self.round_durations.len()) as u32;
+        let current_remaining = match (self.benchmark_started, average(&self.round_durations)) {
+            (None, _) => Duration::ZERO,
+            (Some(_), Some(round)) => round * remaining_rounds,
+            (Some(started), None) => finished?.saturating_sub(now - started),
+        };
+        let benchmark = finished.or_else(|| self.benchmark_started.map(|started| now - started + current_remaining))?;
+        let remaining_benchmarks = self.total.saturating_sub(self.finished + self.benchmark_started.is_some() as usize) as u32;
+        Some(current_remaining + benchmark * remaining_benchmarks)
+    }
+
+    /// Example: `benchmark 3/12, round 4/6, ETA 12m 30s`
+    pub fn line(&self, now: Instant) -> String {
+        let benchmark = (self.finished + self.benchmark_started.is_some() as usize).min(self.total);
+        let round = match self.rounds {
+            0 => String::new(),
+            rounds => format!(", round {}/{}", self.round_durations.len().min(rounds), rounds),
+        };
+        let eta = self.eta(now).map(format_duration).unwrap_or("unknown".to_string());
+        format!("benchmark {}/{}{}, ETA {}", benchmark, self.total, round, eta)
+    }
+}
+
+fn average(durations: &[Duration]) -> Option<Duration> {
+    match durations.len() {
+        0 => None,
+        count => Some(durations.iter().sum::<Duration>() / count as u32),
+    }
+}
+
+/// Prints the progress of the `total` benchmarks from now on.
+pub fn enable(total: usize) {
+    *ETA.lock().unwrap() = Some(Eta::new(total));
+}
+
+/// Updates the progress if it is enabled, printing it if `report`.
+fn update<F: FnOnce(&mut Eta, Instant)>(report: bool, update: F) {
+    let mut progress = ETA.lock().unwrap();
+    if let Some(progress) = progress.as_mut() {
+        let now = Instant::now();
+        update(progress, now);
+        if report {
+            logln!(" -> [Progress]: {}", progress.line(now));
+        }
+    }
+}
+
+pub fn benchmark_started() {
+    update(true, |progress, now| progress.start_benchmark(now));
+}
+
+pub fn benchmark_finished() {
+    update(false, |progress, now| progress.finish_benchmark(now));
+}
+
+pub fn benchmark_skipped() {
+    update(false, |progress, _| progress.skip_benchmark());
+}
+
+pub fn rounds_planned(rounds: usize) {
+    update(false, |progress, now| progress.plan_rounds(rounds, now));
+}
+
+pub fn round_completed() {
+    update(true, |progress, now| progress.complete_round(now));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_estimate_from_rounds_and_benchmarks() {
+        let start = Instant::now();
+        let at = |seconds: u64| start + Duration::from_secs(seconds);
+        let mut progress = Eta::new(3);
+        assert_eq!(progress.eta(at(0)), None);
+
+        // 10 s setup, then rounds of 5 s: 20 s left of the first benchmark, which is projected for the other two
+        progress.start_benchmark(at(0));
+        progress.plan_rounds(6, at(10));
+        progress.complete_round(at(15));
+        progress.complete_round(at(20));
+        assert_eq!(progress.eta(at(20)), Some(Duration::from_secs(20 + 2 * 40)));
+        assert_eq!(progress.line(at(20)), "benchmark 1/3, round 2/6, ETA 1m 40s");
+
+        progress.finish_benchmark(at(40));
+        progress.skip_benchmark();
+        assert_eq!(progress.eta(at(40)), Some(Duration::from_secs(40)));
+        progress.start_benchmark(at(40));
+        assert_eq!(progress.line(at(40)), "benchmark 2/2, ETA 0m 40s");
+    }
+}
@@ -0,0 +1,159 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use crate::output::json::{FailedBenchmark, ResultEntry};
+use crate::output::escape;
+use crate::output::markdown;
+use crate::output::regression::RegressionReport;
+use crate::utils::format::{SizeFormat, TimeFormat};
+
+/// Set by GitHub Actions to the file that is rendered as job summary.
+pub const STEP_SUMMARY_ENV: &str = "GITHUB_STEP_SUMMARY";
+
+/// Appends the summary to the job summary if running in GitHub Actions.
+/// Returns `false` if not running in GitHub Actions.
+pub fn append_step_summary(
+    entries: &[ResultEntry],
+    failures: &[FailedBenchmark],
+    regressions: Option<&RegressionReport>,
+) -> io::Result<bool> {
+    let path = match std::env::var(STEP_SUMMARY_ENV) {
+        Ok(path) if !path.is_empty() => path,
+        _ => return Ok(false),
+    };
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(to_step_summary(entries, failures, regressions).as_bytes())?;
+    Ok(true)
+}
+
+/// Renders the Markdown report with Actions-specific additions:
+/// failures, a regression section (if compared against a baseline) and collapsible details per benchmark.
+pub fn to_step_summary(entries: &[ResultEntry], failures: &[FailedBenchmark], regressions: Option<&RegressionReport>) -> String {
+    let mut buffer = markdown::to_markdown(entries);
+
+    if !failures.is_empty() {
+        buffer.push_str("\n## Failures\n\n");
+        for failure in failures {
+            buffer.push_str(&format!("- :x: **{}**: {}\n", escape::markdown(&failure.benchmark), escape::markdown(&failure.error)));
+        }
+    }
+
+    if let Some(report) = regressions {
+        buffer.push_str("\n## Comparison with baseline\n\n");
+        if report.regressions.is_empty() && report.improvements.is_empty() && report.added.is_empty() && report.removed.is_empty() {
+            buffer.push_str(":white_check_mark: No changes beyond the threshold.\n");
+        }
+        for delta in &report.regressions {
+            buffer.push_str(&format!("- :red_circle: Regression: {}\n", escape::markdown(&delta.to_string())));
+        }
+        for delta in &report.improvements {
+            buffer.push_str(&format!("- :green_circle: Improvement: {}\n", escape::markdown(&delta.to_string())));
+        }
+        for benchmark in &report.added {
+            buffer.push_str(&format!("- :heavy_plus_sign: Added: {}\n", escape::markdown(benchmark)));
+        }
+        for benchmark in &report.removed {
+            buffer.push_str(&format!("- :heavy_minus_sign: Removed: {}\n", escape::markdown(benchmark)));
+        }
+    }
+
+    if !entries.is_empty() {
+        buffer.push_str("\n## Details\n");
+        for entry in entries {
+            buffer.push_str(&details(entry));
+        }
+    }
+
+    buffer
+}
+
+/// A collapsible block with the metadata, additional data and rounds of the entry.
+fn details(entry: &ResultEntry) -> String {
+    let mut buffer = format!(
+        "\n<details>\n<summary>{} ({} {})</summary>\n\n",
+        escape::xml(&entry.benchmark),
+        escape::xml(&entry.language),
+        escape::xml(&entry.version),
+    );
+
+    buffer.push_str("| Key | Value |\n|---|---|\n");
+    for (key, value) in &entry.metadata {
+        buffer.push_str(&format!("| {} | {} |\n", escape::markdown(key), escape::markdown(value)));
+    }
+    for (key, value) in &entry.result.additional_data {
+        buffer.push_str(&format!("| {} | {} |\n", escape::markdown(key), value));
+    }
+
+    if !entry.result.samples.is_empty() {
+        buffer.push_str("\n| Round | Time | Median memory | P99 memory |\n|---|---:|---:|---:|\n");
+        let mut round = 0;
+        for sample in &entry.result.samples {
+            let name = match sample.warmup {
+                true => "Warmup".to_string(),
+                false => {
+                    round += 1;
+                    format!("#{}", round)
+                }
+            };
+            buffer.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                name,
+                sample.time.millis_to_string(),
+                sample.memory_median.bytes_to_string(),
+                sample.memory_p99.bytes_to_string(),
+            ));
+        }
+    }
+
+    buffer.push_str("\n</details>\n");
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmark::benchmark::RoundSample;
+    use crate::output::regression::{ImageChange, MetricDelta};
+    use crate::utils::test_entry::TestEntry;
+
+    #[test]
+    fn should_add_collapsible_details() {
+        let entry = TestEntry::new("rust/a").metadata("mode", "Default").time(1500).memory(1024).memory_p99(2048).samples(vec![
+            RoundSample { warmup: true, time: 1600, memory_median: Some(1024), memory_p99: Some(2048), ..Default::default() },
+            RoundSample { warmup: false, time: 1500, ..Default::default() },
+        ]).build();
+        let summary = to_step_summary(&[entry], &[], None);
+        assert!(summary.starts_with("# Results\n"));
+        assert!(summary.contains("\n<details>\n<summary>rust/a (Rust 1.74)</summary>\n\n| Key | Value |\n|---|---|\n| mode | Default |\n"));
+        assert!(summary.contains("| Warmup | 1.60 s | 1.00 KB | 2.00 KB |\n| #1 | 1.50 s | - | - |\n"));
+        assert!(!summary.contains("Comparison with baseline"));
+    }
+
+    #[test]
+    fn should_mark_regressions() {
+        let report = RegressionReport {
+            regressions: vec![MetricDelta {
+                benchmark: "rust/a (Rust 1.74)".to_string(),
+                metric: "time",
+                baseline: 100,
+                current: 120,
+                change_percent: 20.0,
+                images: ImageChange::Unknown,
+            }],
+            removed: vec!["rust/old (Rust 1.74)".to_string()],
+            ..Default::default()
+        };
+        let failures = vec![FailedBenchmark {
+            category: "computation".to_string(),
+            benchmark: "rust/b".to_string(),
+            version: None,
+            error: "Too many errors".to_string(),
+            kind: None,
+        }];
+
+        let summary = to_step_summary(&[TestEntry::new("rust/a").time(1500).build()], &failures, Some(&report));
+        assert!(summary.contains("- :x: **rust/b**: Too many errors\n"));
+        assert!(summary.contains("- :red_circle: Regression: rust/a (Rust 1.74) time: 100 -> 120 (+20.0%)\n"));
+        assert!(summary.contains("- :heavy_minus_sign: Removed: rust/old (Rust 1.74)\n"));
+    }
+}
@@ -0,0 +1,122 @@
+use indexmap::IndexMap;
+use crate::output::json::ResultEntry;
+use crate::output::regression::display_name;
+use crate::utils::table;
+
+/// How an additional data value of an entry compares to the golden entry of its category (and sweep level).
+#[derive(Debug, PartialEq)]
+pub enum Compatibility {
+    /// The entry is the golden entry.
+    Golden,
+    Match,
+    Mismatch { golden: i64, actual: i64 },
+
+    /// The entry does not report the key.
+    Missing,
+
+    /// The golden entry does not report the key, so nothing can be compared.
+    NoGolden,
+}
+
+/// The compatibility of every entry with the golden entry, per additional data key.
+#[derive(Debug, Default, PartialEq)]
+pub struct GoldenReport {
+    pub keys: Vec<String>,
+
+    /// `<benchmark> (<language> <version>)` of every entry with its compatibility per key (in the order of `keys`).
+    pub rows: Vec<(String, Vec<Compatibility>)>,
+}
+
+impl GoldenReport {
+    /// Whether any entry reports a different value than the golden entry, or none.
+    pub fn has_mismatches(&self) -> bool {
+        self.rows.iter()
+            .flat_map(|(_, cells)| cells)
+            .any(|cell| matches!(cell, Compatibility::Mismatch { .. } | Compatibility::Missing))
+    }
+
+    /// Renders the compatibility matrix, one entry per line and one key per column.
+    pub fn to_table(&self) -> String {
+        let header: Vec<String> = ["Benchmark".to_string()].into_iter().chain(self.keys.iter().cloned()).collect();
+        let rows: Vec<Vec<String>> = self.rows.iter()
+            .map(|(name, cells)| [name.clone()].into_iter().chain(cells.iter().map(format_cell)).collect())
+            .collect();
+        table::render(&header, &rows)
+    }
+}
+
+fn format_cell(cell: &Compatibility) -> String {
+    match cell {
+        Compatibility::Golden => "golden".to_string(),
+        Compatibility::Match => "ok".to_string(),
+        Compatibility::Mismatch { golden, actual } => format!("MISMATCH {} (golden {})", actual, golden),
+        Compatibility::Missing => "MISSING".to_string(),
+        Compatibility::NoGolden => "-".to_string(),
+    }
+}
+
+/// Checks the `keys` of the additional data of every entry against the golden entry of its category and sweep level:
+/// the first entry (by benchmark and version) of `golden_language` (case-insensitive).
+/// Values may differ by at most `tolerance_percent` of the golden value. Groups without golden entry are not reported.
+pub fn compare(entries: &[ResultEntry], golden_language: &str, keys: &[String], tolerance_percent: f64) -> GoldenReport {
+    let mut groups: IndexMap<(String, Option<String>), Vec<&ResultEntry>> = IndexMap::new();
+    for entry in entries {
+        groups.entry((entry.category.clone(), entry.sweep_level())).or_default().push(entry);
+    }
+
+    let mut report = GoldenReport { keys: keys.to_vec(), rows: Vec::new() };
+    for group in groups.values_mut() {
+        group.sort_by(|a, b| a.benchmark.cmp(&b.benchmark).then_with(|| a.version.cmp(&b.version)));
+        let Some(golden) = group.iter().find(|entry| entry.language.eq_ignore_ascii_case(golden_language)).copied() else {
+            continue;
+        };
+
+        for entry in group.iter() {
+            let cells = keys.iter()
+                .map(|key| {
+                    if std::ptr::eq(*entry, golden) {
+                        return Compatibility::Golden;
+                    }
+                    let value = |entry: &ResultEntry| entry.result.additional_data.get(key).map(|value| value.as_i64());
+                    match (value(golden), value(entry)) {
+                        (None, _) => Compatibility::NoGolden,
+                        (Some(_), None) => Compatibility::Missing,
+                        (Some(golden), Some(actual)) if (actual - golden).abs() as f64 <= golden.abs() as f64 * tolerance_percent / 100.0 => Compatibility::Match,
+                        (Some(golden), Some(actual)) => Compatibility::Mismatch { golden, actual },
+                    }
+                })
+                .collect();
+            report.rows.push((display_name(entry), cells));
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_entry::TestEntry;
+
+    #[test]
+    fn should_check_against_golden_entry() {
+        let entries = [
+            TestEntry::new("rust/rust-1.40").version("1.0").data("checksum", 1000).build(),
+            TestEntry::new("go/go-1.21").language("Go").version("1.0").data("checksum", 1009).build(),
+            TestEntry::new("dart/jit-2.14").language("Dart").version("1.0").data("checksum", 1011).build(),
+            TestEntry::new("java/java-21").language("Java").version("1.0").build(),
+        ];
+        let report = compare(&entries, "rust", &["checksum".to_string(), "errors".to_string()], 1.0);
+
+        assert_eq!(report.rows, vec![
+            ("dart/jit-2.14 (Dart 1.0)".to_string(), vec![Compatibility::Mismatch { golden: 1000, actual: 1011 }, Compatibility::NoGolden]),
+            ("go/go-1.21 (Go 1.0)".to_string(), vec![Compatibility::Match, Compatibility::NoGolden]),
+            ("java/java-21 (Java 1.0)".to_string(), vec![Compatibility::Missing, Compatibility::NoGolden]),
+            ("rust/rust-1.40 (Rust 1.0)".to_string(), vec![Compatibility::Golden, Compatibility::Golden]),
+        ]);
+        assert!(report.has_mismatches());
+        assert_eq!(report.to_table().lines().nth(2).unwrap(), "dart/jit-2.14 (Dart 1.0)   MISMATCH 1011 (golden 1000)  -");
+
+        // Without golden entry nothing is compared
+        assert_eq!(compare(&entries, "zig", &["checksum".to_string()], 1.0), GoldenReport { keys: vec!["checksum".to_string()], rows: vec![] });
+    }
+}
@@ -0,0 +1,126 @@
+use serde_json::{json, Value};
+use crate::output::escape;
+use crate::output::json::{self, FailedBenchmark, ResultEntry};
+use crate::output::metric::{self, Metric};
+use crate::utils::run_metadata::RunMetadata;
+
+const SCRIPT: &str = include_str!("report.js");
+const STYLE: &str = include_str!("report.css");
+
+/// Renders a self-contained HTML report (no network access needed).
+/// The data is embedded as inline JSON and rendered by the bundled script:
+/// a sortable table, bar charts per category and a scatter plot of the rounds if samples are available.
+/// Failed benchmarks are shown greyed out together with their error. The run is summarized below the title.
+/// Only the `metric::ranked` metrics of an entry are embedded, e.g. not the load duration of web benchmarks, and the table
+/// is initially sorted by them (`rank`).
+pub fn to_html(entries: &[ResultEntry], failures: &[FailedBenchmark], run: Option<&RunMetadata>) -> String {
+    let mut entries = json::sorted_entries(entries);
+    entries.sort_by(|a, b| a.category.cmp(&b.category).then_with(|| metric::compare_ranked(a, b)));
+    let ranked = |entry: &ResultEntry, metric: Metric| match metric::ranked(&entry.category).contains(&metric) {
+        true => metric.value(entry),
+        false => None,
+    };
+    let mut rows: Vec<Value> = entries.into_iter().enumerate().map(|(rank, entry)| json!({
+        "rank": rank,
+        "category": entry.category,
+        "benchmark": entry.benchmark,
+        "language": entry.language,
+        "version": entry.version,
+        "time_median": ranked(entry, Metric::Time),
+        "rps_median": ranked(entry, Metric::Rps),
+        "latency_median": ranked(entry, Metric::Latency),
+        "memory_median": entry.result.memory_median,
+        "memory_p99": entry.result.memory_p99,
+        "self_reported_memory_median": entry.result.self_reported_memory_median,
+        "relative_time": entry.relative.as_ref().and_then(|score| score.time_factor),
+        "relative_memory": entry.relative.as_ref().and_then(|score| score.memory_factor),
+        "samples": entry.result.samples,
+        "error": Value::Null,
+    })).collect();
+
+    rows.extend(failures.iter().map(|failure| json!({
+        "rank": Value::Null,
+        "category": failure.category,
+        "benchmark": failure.benchmark,
+        "language": Value::Null,
+        "version": failure.version,
+        "time_median": Value::Null,
+        "rps_median": Value::Null,
+        "latency_median": Value::Null,
+        "memory_median": Value::Null,
+        "memory_p99": Value::Null,
+        "self_reported_memory_median": Value::Null,
+        "relative_time": Value::Null,
+        "relative_memory": Value::Null,
+        "samples": [],
+        "error": failure.error,
+    })));
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Sharkbench Results</title>
+<style>
+{}</style>
+</head>
+<body>
+<h1>Sharkbench Results</h1>
+{}<div id="sharkbench-report"></div>
+<script id="sharkbench-data" type="application/json">{}</script>
+<script>
+{}</script>
+</body>
+</html>
+"#,
+        STYLE,
+        run.map(|run| format!("<p class=\"run\">{}</p>\n", escape::xml(&run.summary()))).unwrap_or_default(),
+        escape_script(&Value::Array(rows).to_string()),
+        SCRIPT,
+    )
+}
+
+/// Prevents the embedded JSON from closing the script tag.
+fn escape_script(json: &str) -> String {
+    json.replace("</", "<\\/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_entry::TestEntry;
+
+    #[test]
+    fn should_embed_results_and_failures() {
+        let html = to_html(
+            &[TestEntry::new("rust/axum").category("web").time(15000).rps(12345).build()],
+            &[FailedBenchmark {
+                category: "web".to_string(),
+                benchmark: "java/broken".to_string(),
+                version: None,
+                error: "Too many errors".to_string(),
+                kind: None,
+            }],
+            Some(&RunMetadata {
+                started_at: "2024-01-31T12:34:56Z".to_string(),
+                sharkbench_version: "0.1.0".to_string(),
+                ..Default::default()
+            }),
+        );
+
+        assert!(html.contains(r#""benchmark":"rust/axum""#));
+        assert!(html.contains("<p class=\"run\">Started 2024-01-31T12:34:56Z with sharkbench 0.1.0</p>"));
+        // The load duration does not tell web benchmarks apart
+        assert!(html.contains(r#""time_median":null"#));
+        assert!(html.contains(r#""rps_median":12345"#));
+        assert!(html.contains(r#""error":"Too many errors""#));
+        // no external resources
+        assert!(!html.contains(" src=") && !html.contains("<link"));
+    }
+
+    #[test]
+    fn should_escape_closing_script_tags() {
+        assert_eq!(escape_script(r#"{"error":"</script>"}"#), r#"{"error":"<\/script>"}"#);
+    }
+}
@@ -0,0 +1,1110 @@
+use std::{fs, io};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use crate::benchmark::benchmark::BenchmarkResult;
+use crate::output::{badge, csv, html, junit, markdown, summary, svg, upload, OutputFormat};
+use crate::output::paths::OutputPaths;
+use crate::output::progress::Progress;
+use crate::output::pushgateway::PushGateway;
+use crate::output::regression::RegressionReport;
+use crate::output::relative::{self, RelativeScore};
+use crate::output::score::{self, Scores, ScoringConfig};
+use crate::output::sqlite::HistoryDatabase;
+use crate::output::stream::{self, Event};
+use crate::utils::environment;
+use crate::utils::machine::MachineInfo;
+use crate::utils::run_metadata::RunMetadata;
+use crate::utils::result_diff::diff_additional_data;
+
+/// Version of the serialized results (entry files and `results.json`).
+/// Increase it whenever a change would break existing readers, and add a line to the changelog.
+///
+/// Changelog:
+/// - 1: Initial format (files without `schema_version`).
+/// - 2: Adds `schema_version`. Memory is `null` if not measured. Adds `failures`, `tags`, `machine`, `relative`, `raw_output`,
+///   `self_reported_memory_median`, `actual_version`, `cold_start_ms`, `shutdown_ms`, `time_p1`, `image`, `run`, `input_hash`, `reused_from`,
+///   `running_median`, `warmup_times`, `workload_warmup_times`, `measurement`, `resumed_at`, `tags`, `exclude_tags`, `skip_languages`, `skip_benchmarks`, `excluded`, `seed` and `docker_context` of `run` and `skipped` of `results.json`, which are absent or `null` in version 1 files. `scores` of `results.json` is absent without scoring config.
+///   Additional data can be of type `duration` (in µs) besides `int`.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// The oldest schema version `load_results` can read.
+pub const MIN_SCHEMA_VERSION: u32 = 1;
+
+/// Metadata key naming the swept metadata key (e.g. `concurrency`) of entries measured in a sweep.
+pub const SWEEP_KEY: &str = "sweep";
+
+/// A single benchmark result together with the information that identifies it.
+#[derive(Serialize, Deserialize)]
+pub struct ResultEntry {
+    /// See `SCHEMA_VERSION`.
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
+
+    /// Category of the benchmark, e.g. `web`.
+    pub category: String,
+
+    /// Path of the benchmark relative to the category, e.g. `rust/axum-0.7-rust-1.74`.
+    pub benchmark: String,
+
+    pub language: String,
+
+    /// The language version the benchmark ran with.
+    pub version: String,
+
+    /// Descriptive values of the benchmark (e.g. mode, framework).
+    pub metadata: IndexMap<String, String>,
+
+    pub result: BenchmarkResult,
+
+    /// The machine that produced the result.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub machine: Option<MachineInfo>,
+
+    /// Path of the file with the raw rounds (see `--raw-output`), relative to the output directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_output: Option<String>,
+
+    /// Time and memory relative to the baseline of the run, only part of the combined results.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relative: Option<RelativeScore>,
+
+    /// The run that produced the result, only part of the file of the entry (the combined results have it once).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run: Option<RunMetadata>,
+
+    /// Hash of the benchmark directory and the requested version (see `input_hash::hash_inputs`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_hash: Option<String>,
+
+    /// Start of the run that measured the result, if it was reused from there because the inputs did not change.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reused_from: Option<String>,
+}
+
+/// A benchmark that did not produce a result.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FailedBenchmark {
+    pub category: String,
+    pub benchmark: String,
+
+    /// The language version that failed, if known.
+    pub version: Option<String>,
+
+    pub error: String,
+
+    /// `BenchmarkError::kind` of the error, `None` for other panics and in results written before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+}
+
+/// A benchmark that was not run (e.g. because a result already exists).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SkippedBenchmark {
+    pub category: String,
+    pub benchmark: String,
+}
+
+#[derive(Serialize)]
+struct CombinedResults<'a> {
+    schema_version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    machine: Option<&'a MachineInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run: Option<&'a RunMetadata>,
+    results: Vec<&'a ResultEntry>,
+    failures: Vec<&'a FailedBenchmark>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped: Vec<&'a SkippedBenchmark>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scores: Option<&'a Scores>,
+}
+
+/// Serializes the combined results file (`results.json`).
+///
+/// The output only depends on the results, not on the order they were measured or collected in:
+/// - results are sorted by category, benchmark, language, version and sweep level, failures by category, benchmark and version,
+///   skipped benchmarks by category and benchmark
+/// - metadata keeps the order of the descriptor, additional data and tags are sorted by key
+/// - relative factors are rounded to `serialization::SIGNIFICANT_DIGITS` significant digits
+/// - timestamps are integer milliseconds since the Unix epoch
+pub fn to_combined_json(
+    machine: Option<&MachineInfo>,
+    run: Option<&RunMetadata>,
+    entries: &[ResultEntry],
+    failures: &[FailedBenchmark],
+    skipped: &[SkippedBenchmark],
+    scores: Option<&Scores>,
+) -> serde_json::Result<String> {
+    let mut failures: Vec<&FailedBenchmark> = failures.iter().collect();
+    failures.sort_by(|a, b| (&a.category, &a.benchmark, &a.version).cmp(&(&b.category, &b.benchmark, &b.version)));
+    let mut skipped: Vec<&SkippedBenchmark> = skipped.iter().collect();
+    skipped.sort_by(|a, b| (&a.category, &a.benchmark).cmp(&(&b.category, &b.benchmark)));
+
+    serde_json::to_string_pretty(&CombinedResults {
+        schema_version: SCHEMA_VERSION,
+        machine,
+        run,
+        results: sorted_entries(entries),
+        failures,
+        skipped,
+        scores,
+    })
+}
+
+/// The entries sorted by category, benchmark, language, version and sweep level.
+pub fn sorted_entries(entries: &[ResultEntry]) -> Vec<&ResultEntry> {
+    let mut sorted: Vec<&ResultEntry> = entries.iter().collect();
+    sorted.sort_by_cached_key(|entry| (
+        entry.category.clone(),
+        entry.benchmark.clone(),
+        entry.language.clone(),
+        entry.version.clone(),
+        entry.sweep_level(),
+    ));
+    sorted
+}
+
+/// A combined results file (`results.json`) read by `load_results`, upgraded to the current schema.
+#[derive(Deserialize, Default)]
+pub struct LoadedResults {
+    /// The schema version of the file.
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
+
+    pub results: Vec<ResultEntry>,
+
+    #[serde(default)]
+    pub failures: Vec<FailedBenchmark>,
+
+    #[serde(default)]
+    pub skipped: Vec<SkippedBenchmark>,
+
+    #[serde(default)]
+    pub run: Option<RunMetadata>,
+}
+
+/// Writes benchmark results into the run directory of `paths`.
+///
+/// For JSON, each entry is written into its own file (`<category>/<benchmark>_<versions>.json`).
+/// The combined files of the whole run (`results.json`, `results.csv`, `report.md`, `report.html`) are rewritten after every entry,
+/// so a crash mid-suite does not lose earlier results.
+pub struct ResultWriter {
+    paths: OutputPaths,
+    formats: Vec<OutputFormat>,
+    entries: Vec<ResultEntry>,
+    failures: Vec<FailedBenchmark>,
+    skipped: Vec<SkippedBenchmark>,
+
+    /// Added to the tags of every entry.
+    tags: IndexMap<String, String>,
+
+    /// Added to every entry and the combined results file.
+    machine: Option<MachineInfo>,
+
+    /// Added to the file of every entry, the combined results file and the reports.
+    run: Option<RunMetadata>,
+
+    /// Additionally records every entry in the results history.
+    history: Option<HistoryDatabase>,
+
+    /// Additionally pushes the metrics of every entry to a Prometheus Pushgateway.
+    push_gateway: Option<PushGateway>,
+
+    /// Language of the baseline of the relative scores, the fastest entry if `None`.
+    relative_to: Option<String>,
+
+    /// Additionally writes the rounds of every entry as newline-delimited JSON.
+    raw_output: bool,
+
+    /// Paths of the files of the entries, see `ResultEntry::file_stem`.
+    file_naming: Option<FileNaming>,
+
+    /// Additionally renders the rounds of every entry as SVG chart.
+    charts: bool,
+
+    /// Entries of a previous run that are recent enough to be reused, see `reuse`.
+    reusable: Vec<ResultEntry>,
+
+    /// Additionally scores every language per group in the combined results file and the Markdown report.
+    scoring: Option<ScoringConfig>,
+
+    /// Keeps failures in memory only, so a `--warmup-only` run does not replace the combined results of the last run.
+    warmup_only: bool,
+
+    /// Rewritten whenever a benchmark starts or ends, see `PROGRESS_FILE`.
+    progress: Progress,
+}
+
+impl ResultEntry {
+    pub fn new(
+        category: &str,
+        benchmark: &str,
+        language: &str,
+        version: &str,
+        metadata: &[(&str, &str)],
+        result: BenchmarkResult,
+    ) -> ResultEntry {
+        ResultEntry {
+            schema_version: SCHEMA_VERSION,
+            category: category.to_string(),
+            benchmark: benchmark.to_string(),
+            language: language.to_string(),
+            version: version.to_string(),
+            metadata: metadata.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            result,
+            machine: None,
+            raw_output: None,
+            relative: None,
+            run: None,
+            input_hash: None,
+            reused_from: None,
+        }
+    }
+
+    /// The level of a sweep this entry was measured at, e.g. `concurrency-8`.
+    /// The `sweep` metadata names the metadata key that was swept.
+    pub fn sweep_level(&self) -> Option<String> {
+        let key = self.metadata.get(SWEEP_KEY)?;
+        Some(format!("{}-{}", key, self.metadata.get(key).map(|v| v.as_str()).unwrap_or_default()))
+    }
+
+    /// The path of the files of this entry relative to the output directory, without extension.
+    /// By default unique per benchmark, version and sweep level, e.g. `web/rust_axum-0.7-rust-1.74_1.74_0.7`.
+    fn file_stem(&self, naming: Option<&FileNaming>) -> String {
+        match naming {
+            Some(naming) => naming.render(self),
+            None => format!("{}/{}", self.category, self.default_file_name()),
+        }
+    }
+
+    fn default_file_name(&self) -> String {
+        let mut name = format!("{}_{}", self.benchmark.replace('/', "_"), self.version);
+        if let Some(framework_version) = self.metadata.get("framework_version") {
+            name.push('_');
+            name.push_str(framework_version);
+        }
+        if let Some(level) = self.sweep_level() {
+            name.push('_');
+            name.push_str(&level);
+        }
+        name
+    }
+}
+
+/// A template for the paths of the files of an entry, relative to the output directory and without extension.
+/// Example: `{category}/{language}-{version}` -> `computation/rust-1.74.json`
+#[derive(Serialize, Clone, Debug)]
+#[serde(transparent)]
+pub struct FileNaming {
+    template: String,
+}
+
+impl FileNaming {
+    /// `{benchmark}` keeps its slashes (one directory per language), `{language}` is lowercase.
+    /// `{framework_version}` and `{sweep_level}` are empty if not applicable.
+    pub const PLACEHOLDERS: [&'static str; 6] = ["category", "benchmark", "language", "version", "framework_version", "sweep_level"];
+
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    pub fn parse(template: &str) -> Result<FileNaming, String> {
+        let placeholder_regex = regex::Regex::new(r"\{([^{}]*)\}").unwrap();
+        for captures in placeholder_regex.captures_iter(template) {
+            if !Self::PLACEHOLDERS.contains(&&captures[1]) {
+                return Err(format!("unknown placeholder {{{}}}, expected one of {:?}", &captures[1], Self::PLACEHOLDERS));
+            }
+        }
+        if template.is_empty() || template.starts_with('/') || template.split('/').any(|part| part.is_empty() || part == "..") {
+            return Err(format!("{:?} must be a relative path without empty or `..` components", template));
+        }
+        Ok(FileNaming { template: template.to_string() })
+    }
+
+    fn render(&self, entry: &ResultEntry) -> String {
+        let values = [
+            entry.category.clone(),
+            entry.benchmark.clone(),
+            entry.language.to_lowercase(),
+            entry.version.clone(),
+            entry.metadata.get("framework_version").cloned().unwrap_or_default(),
+            entry.sweep_level().unwrap_or_default(),
+        ];
+        let mut path = self.template.clone();
+        for (placeholder, value) in Self::PLACEHOLDERS.iter().zip(values) {
+            path = path.replace(&format!("{{{}}}", placeholder), &value);
+        }
+        path
+    }
+}
+
+impl ResultWriter {
+    pub fn new(paths: OutputPaths, formats: &[OutputFormat]) -> ResultWriter {
+        ResultWriter {
+            paths,
+            formats: formats.to_vec(),
+            entries: Vec::new(),
+            failures: Vec::new(),
+            skipped: Vec::new(),
+            tags: IndexMap::new(),
+            machine: None,
+            run: None,
+            history: None,
+            push_gateway: None,
+            relative_to: None,
+            raw_output: false,
+            file_naming: None,
+            charts: false,
+            reusable: Vec::new(),
+            scoring: None,
+            warmup_only: false,
+            progress: Progress::default(),
+        }
+    }
+
+    pub fn set_tags(&mut self, tags: IndexMap<String, String>) {
+        self.tags = tags;
+    }
+
+    pub fn set_machine(&mut self, machine: MachineInfo) {
+        self.machine = Some(machine);
+    }
+
+    pub fn set_run(&mut self, run: RunMetadata) {
+        self.progress.run = Some(run.clone());
+        self.run = Some(run);
+    }
+
+    /// Continues an interrupted run (see `--resume`): the benchmarks it completed are skipped
+    /// and their entries of `previous` are part of the combined results again.
+    /// Failed benchmarks and the benchmark that was in progress run again.
+    pub fn resume(&mut self, progress: Progress, previous: Option<LoadedResults>) {
+        let previous = previous.map(|previous| previous.results).unwrap_or_default();
+        self.progress.completed = progress.completed.into_iter()
+            .filter(|completed| previous.iter().any(|entry| &format!("{}/{}", entry.category, entry.benchmark) == completed))
+            .collect();
+        if let Some(in_progress) = progress.in_progress {
+            logln!(" -> {} was interrupted and runs again", in_progress);
+        }
+        logln!(" -> {} benchmark(s) completed before, they are skipped", self.progress.completed.len());
+
+        self.entries.extend(previous.into_iter().filter(|entry| self.progress.is_completed(&entry.category, &entry.benchmark)));
+        relative::assign(&mut self.entries, self.relative_to.as_deref());
+    }
+
+    /// Merges the results of a previous run into this run of its failed and skipped benchmarks (see `--retry-failed`).
+    /// Entries of benchmarks that are not `retried` are kept and marked with `reused_from`, the time they were measured at,
+    /// failures of benchmarks that are not retried (e.g. removed since) are kept as they were,
+    /// unless the benchmark is `excluded` by the skip filters, then it is only recorded in `run.excluded`.
+    pub fn retry(&mut self, previous: LoadedResults, retried: &[String], excluded: &[String]) {
+        let is_retried = |category: &str, benchmark: &str| retried.iter().chain(excluded).any(|dir| dir == &format!("benchmark/{}/{}", category, benchmark));
+        let run_started_at = previous.run.map(|run| run.started_at);
+        self.entries.extend(previous.results.into_iter()
+            .filter(|entry| !is_retried(&entry.category, &entry.benchmark))
+            .map(|mut entry| {
+                entry.reused_from = entry.reused_from.clone().or(run_started_at.clone());
+                entry
+            }));
+        self.failures.extend(previous.failures.into_iter().filter(|failure| !is_retried(&failure.category, &failure.benchmark)));
+        relative::assign(&mut self.entries, self.relative_to.as_deref());
+    }
+
+    /// Whether the benchmark completed before the run was resumed or earlier in this run.
+    pub fn is_completed(&self, category: &str, benchmark: &str) -> bool {
+        self.progress.is_completed(category, benchmark)
+    }
+
+    /// Records the benchmark as running in the progress file.
+    pub fn start_benchmark(&mut self, category: &str, benchmark: &str) -> io::Result<()> {
+        self.progress.start(category, benchmark);
+        self.write_progress()
+    }
+
+    /// Records the end of the running benchmark in the progress file, only a benchmark that `succeeded` is skipped on resume.
+    pub fn end_benchmark(&mut self, succeeded: bool) -> io::Result<()> {
+        self.progress.end(succeeded);
+        self.write_progress()
+    }
+
+    fn write_progress(&self) -> io::Result<()> {
+        if self.warmup_only {
+            return Ok(());
+        }
+        write_file(&self.paths.progress(), &serde_json::to_string_pretty(&self.progress)?)
+    }
+
+    /// Records the benchmarks selected by the filters in the run metadata.
+    pub fn set_selected(&mut self, selected: Vec<String>) {
+        if let Some(run) = &mut self.run {
+            run.selected = selected;
+        }
+    }
+
+    /// Records the benchmarks excluded by the skip filters in the run metadata.
+    pub fn set_excluded(&mut self, excluded: Vec<String>) {
+        if let Some(run) = &mut self.run {
+            run.excluded = excluded;
+        }
+    }
+
+    /// Records the end of the run and rewrites the combined results files and reports with it.
+    pub fn finish_run(&mut self, duration: Duration) -> io::Result<()> {
+        if let Some(run) = &mut self.run {
+            run.finish(duration);
+            self.progress.run = Some(run.clone());
+        }
+        self.write_progress()?;
+        self.write_combined()
+    }
+
+    /// Offers the entries of a previous run for `reuse` that were measured at most `max_age` ago.
+    pub fn set_reusable(&mut self, previous: LoadedResults, max_age: Duration) {
+        let now = environment::now_unix_ms() as u64 / 1000;
+        let run_started_at = previous.run.map(|run| run.started_at);
+        self.reusable = previous.results.into_iter()
+            // A partial result is measured again
+            .filter(|entry| entry.input_hash.is_some() && !entry.result.timed_out)
+            .filter_map(|mut entry| {
+                // A reused entry keeps the time it was measured at
+                let measured_at = entry.reused_from.clone().or(run_started_at.clone())?;
+                let age = now.saturating_sub(environment::parse_utc(&measured_at)?);
+                if age > max_age.as_secs() {
+                    return None;
+                }
+                entry.reused_from = Some(measured_at);
+                entry.relative = None;
+                Some(entry)
+            })
+            .collect();
+        logln!(" -> {} result(s) of the previous run can be reused", self.reusable.len());
+    }
+
+    /// The input hashes of the versions of a benchmark that `reuse` finds a result of the previous run for, e.g. to not build them.
+    pub fn reusable_hashes(&self, category: &str, benchmark: &str) -> HashSet<String> {
+        self.reusable.iter()
+            .filter(|entry| entry.category == category && entry.benchmark == benchmark)
+            .filter_map(|entry| entry.input_hash.clone())
+            .collect()
+    }
+
+    /// Writes the entries of the previous run with the same inputs (e.g. every level of a sweep) instead of measuring them again.
+    /// Returns whether there were any.
+    pub fn reuse(&mut self, category: &str, benchmark: &str, input_hash: &str) -> io::Result<bool> {
+        let (reused, remaining): (Vec<ResultEntry>, Vec<ResultEntry>) = std::mem::take(&mut self.reusable).into_iter()
+            .partition(|entry| entry.category == category && entry.benchmark == benchmark && entry.input_hash.as_deref() == Some(input_hash));
+        self.reusable = remaining;
+
+        let found = !reused.is_empty();
+        for entry in reused {
+            logln!(
+                " -> Inputs of {}/{} ({}) did not change, reusing the result from {}",
+                category,
+                benchmark,
+                entry.version,
+                entry.reused_from.as_deref().unwrap_or_default(),
+            );
+            self.write(entry)?;
+        }
+        Ok(found)
+    }
+
+    pub fn set_history(&mut self, history: HistoryDatabase) {
+        self.history = Some(history);
+    }
+
+    pub fn set_push_gateway(&mut self, push_gateway: PushGateway) {
+        self.push_gateway = Some(push_gateway);
+    }
+
+    pub fn set_relative_to(&mut self, language: Option<String>) {
+        self.relative_to = language;
+    }
+
+    pub fn set_raw_output(&mut self, raw_output: bool) {
+        self.raw_output = raw_output;
+    }
+
+    pub fn set_file_naming(&mut self, file_naming: FileNaming) {
+        self.file_naming = Some(file_naming);
+    }
+
+    pub fn set_charts(&mut self, charts: bool) {
+        self.charts = charts;
+    }
+
+    pub fn set_scoring(&mut self, scoring: ScoringConfig) {
+        self.scoring = Some(scoring);
+    }
+
+    pub fn set_warmup_only(&mut self, warmup_only: bool) {
+        self.warmup_only = warmup_only;
+    }
+
+    /// Writes the entry into its own file and updates the combined results files.
+    pub fn write(&mut self, entry: ResultEntry) -> io::Result<()> {
+        self.write_from(entry, None)
+    }
+
+    /// Writes the entry of a benchmark that ran on a host of `--hosts`, see `write`.
+    pub fn write_from_host(&mut self, entry: ResultEntry, host: &str) -> io::Result<()> {
+        self.write_from(entry, Some(host))
+    }
+
+    fn write_from(&mut self, mut entry: ResultEntry, host: Option<&str>) -> io::Result<()> {
+        for (key, value) in &self.tags {
+            entry.result.tags.entry(key.clone()).or_insert(value.clone());
+        }
+        if entry.machine.is_none() {
+            entry.machine = self.machine.clone();
+        }
+
+        let file_stem = entry.file_stem(self.file_naming.as_ref());
+        if self.entries.iter().any(|other| other.file_stem(self.file_naming.as_ref()) == file_stem) {
+            warnln!(" -> Warning: {} was already written in this run and is overwritten. Add placeholders to --file-naming to make it unique.", file_stem);
+        }
+
+        if self.raw_output {
+            let raw_path = format!("{}.raw.ndjson", file_stem);
+            write_file(&self.paths.file(&raw_path), &to_ndjson(&entry.result.samples)?)?;
+            logln!(" -> Written {}", self.paths.file(&raw_path).display());
+            entry.raw_output = Some(raw_path);
+        }
+
+        if self.charts {
+            if let Some(chart) = svg::to_svg_chart(&entry) {
+                let chart_path = self.paths.chart(&file_stem);
+                write_file(&chart_path, &chart)?;
+                logln!(" -> Written {}", chart_path.display());
+            }
+        }
+
+        if self.formats.contains(&OutputFormat::Json) {
+            let entry_path = self.paths.file(&format!("{}.json", file_stem));
+            print_changes_since_last_run(&entry_path, &entry);
+            entry.run = self.run.clone();
+            write_file(&entry_path, &serde_json::to_string_pretty(&entry)?)?;
+            entry.run = None;
+            logln!(" -> Written {}", entry_path.display());
+        }
+
+        if let Some(history) = &self.history {
+            history.write(&entry, host)?;
+        }
+
+        if let Some(push_gateway) = &self.push_gateway {
+            push_gateway.push(&entry);
+        }
+
+        stream::emit(&Event::BenchmarkCompleted {
+            category: &entry.category,
+            benchmark: &entry.benchmark,
+            language: &entry.language,
+            version: &entry.version,
+            metadata: &entry.metadata,
+            result: &entry.result,
+        });
+        self.entries.push(entry);
+        // The baseline may have changed with the new entry
+        relative::assign(&mut self.entries, self.relative_to.as_deref());
+        self.write_combined()
+    }
+
+    /// Records a benchmark that failed and updates the combined results files.
+    pub fn write_failure(&mut self, failure: FailedBenchmark) -> io::Result<()> {
+        stream::emit(&Event::BenchmarkFailed {
+            category: &failure.category,
+            benchmark: &failure.benchmark,
+            version: failure.version.as_deref(),
+            error: &failure.error,
+        });
+        self.failures.push(failure);
+        self.write_combined()
+    }
+
+    /// Records a benchmark that was not run, only shown in the summary of the run.
+    pub fn record_skipped(&mut self, skipped: SkippedBenchmark) {
+        stream::emit(&Event::BenchmarkSkipped { category: &skipped.category, benchmark: &skipped.benchmark });
+        self.skipped.push(skipped);
+    }
+
+    fn write_combined(&self) -> io::Result<()> {
+        if self.warmup_only {
+            return Ok(());
+        }
+
+        if self.formats.contains(&OutputFormat::Json) {
+            write_file(&self.paths.results(), &self.combined_json()?)?;
+        }
+
+        if self.formats.contains(&OutputFormat::Csv) {
+            write_file(&self.paths.results_csv(), &csv::to_csv(&self.entries))?;
+        }
+
+        write_file(&self.paths.markdown_report(), &self.markdown_report())?;
+        write_file(&self.paths.html_report(), &html::to_html(&self.entries, &self.failures, self.run.as_ref()))
+    }
+
+    pub fn entries(&self) -> &[ResultEntry] {
+        &self.entries
+    }
+
+    pub fn failures(&self) -> &[FailedBenchmark] {
+        &self.failures
+    }
+
+    pub fn skipped(&self) -> &[SkippedBenchmark] {
+        &self.skipped
+    }
+
+    pub fn html_report_path(&self) -> PathBuf {
+        self.paths.html_report()
+    }
+
+    /// The combined results (`results.json`) of the run so far.
+    pub fn combined_json(&self) -> serde_json::Result<String> {
+        let scores = self.scores();
+        to_combined_json(self.machine.as_ref(), self.run.as_ref(), &self.entries, &self.failures, &self.skipped, scores.as_ref())
+    }
+
+    /// Records the run id assigned by the collection API next to the results.
+    pub fn write_upload_record(&self, record: &upload::UploadRecord) -> io::Result<PathBuf> {
+        let path = self.paths.upload_record();
+        write_file(&path, &serde_json::to_string_pretty(record)?)?;
+        Ok(path)
+    }
+
+    /// Writes the badge summarizing the run. Regressions count benchmarks, not metrics.
+    pub fn write_badge(&self, label: &str, regressions: Option<&RegressionReport>) -> io::Result<PathBuf> {
+        let regressed = regressions.map(|report| {
+            let mut benchmarks: Vec<&str> = report.regressions.iter().map(|delta| delta.benchmark.as_str()).collect();
+            benchmarks.sort();
+            benchmarks.dedup();
+            benchmarks.len()
+        });
+        let path = self.paths.badge();
+        write_file(&path, &badge::to_badge(label, self.entries.len(), self.failures.len(), regressed))?;
+        Ok(path)
+    }
+
+    pub fn junit_report(&self, regressions: Option<&RegressionReport>) -> String {
+        junit::to_junit(&self.entries, &self.failures, &self.skipped, regressions)
+    }
+
+    pub fn summary_table(&self) -> String {
+        summary::to_summary_table(&self.entries, &self.failures, &self.skipped)
+    }
+
+    /// The composite scores of the entries so far, `None` without scoring config.
+    pub fn scores(&self) -> Option<Scores> {
+        self.scoring.as_ref().map(|config| score::compute(config, &self.entries))
+    }
+
+    pub fn markdown_report(&self) -> String {
+        let mut report = markdown::to_markdown(&self.entries);
+        if let Some(scores) = self.scores() {
+            report.push_str(&score::to_markdown(&scores));
+        }
+        if let Some(run) = &self.run {
+            report.push_str(&format!("\n{}\n", run.summary()));
+        }
+        report
+    }
+}
+
+/// One JSON object per line and round, so the file can be processed line by line.
+fn to_ndjson<T: Serialize>(records: &[T]) -> serde_json::Result<String> {
+    let mut buffer = String::new();
+    for record in records {
+        buffer.push_str(&serde_json::to_string(record)?);
+        buffer.push('\n');
+    }
+    Ok(buffer)
+}
+
+/// Reads a combined results file (`results.json`) of the current or an older schema version.
+/// Fields that did not exist in older versions are `None` (or empty).
+pub fn load_results(path: &Path) -> io::Result<LoadedResults> {
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    check_schema_version(path, &json)?;
+
+    let mut results: LoadedResults = serde_json::from_value(json)?;
+    for entry in &mut results.results {
+        entry.schema_version = SCHEMA_VERSION;
+    }
+    Ok(results)
+}
+
+/// Reads the file of a single entry, see `load_results`.
+pub fn load_entry(path: &Path) -> io::Result<ResultEntry> {
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    check_schema_version(path, &json)?;
+
+    let mut entry: ResultEntry = serde_json::from_value(json)?;
+    entry.schema_version = SCHEMA_VERSION;
+    Ok(entry)
+}
+
+/// Rejects files written by a newer version of sharkbench, as their fields may have changed meaning.
+fn check_schema_version(path: &Path, json: &serde_json::Value) -> io::Result<()> {
+    let version = match json.get("schema_version") {
+        None => legacy_schema_version() as u64,
+        Some(version) => version.as_u64().ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: schema_version must be a number, got {}", path.display(), version),
+        ))?,
+    };
+
+    if version > SCHEMA_VERSION as u64 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!(
+            "{} has schema version {}, but only versions up to {} are supported. Please update sharkbench.",
+            path.display(), version, SCHEMA_VERSION,
+        )));
+    }
+    if version < MIN_SCHEMA_VERSION as u64 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!(
+            "{} has schema version {}, but only versions from {} on are supported.",
+            path.display(), version, MIN_SCHEMA_VERSION,
+        )));
+    }
+    Ok(())
+}
+
+/// Files without `schema_version` were written before it was introduced.
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+/// Prints the `additional_data` changes compared to the result of the previous run (if any).
+/// A changed derived value usually indicates a behavioral change that timings alone would not reveal.
+fn print_changes_since_last_run(entry_path: &Path, entry: &ResultEntry) {
+    let previous = match load_entry(entry_path) {
+        Ok(previous) => previous,
+        Err(_) => return,
+    };
+
+    let changes = diff_additional_data(&previous.result, &entry.result);
+    if changes.is_empty() {
+        return;
+    }
+
+    logln!(" -> Additional data changed since last run:");
+    for change in changes {
+        logln!("    {}", change);
+    }
+}
+
+/// Writes the contents to a temporary file first and renames it afterward,
+/// so the target file is never left half-written.
+fn write_file(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(parent_dir) = path.parent() {
+        fs::create_dir_all(parent_dir)?;
+    }
+
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    fs::write(&temp_path, contents.as_bytes())?;
+    fs::rename(&temp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmark::benchmark::{AdditionalData, RoundSample};
+    use crate::utils::test_dir::TestDir;
+    use crate::utils::test_entry::TestEntry;
+
+    #[test]
+    fn should_name_file_by_benchmark_and_versions() {
+        assert_eq!(TestEntry::new("rust/axum-0.7-rust-1.74").category("web").metadata("framework_version", "0.7").build().file_stem(None), "web/rust_axum-0.7-rust-1.74_1.74_0.7");
+    }
+
+    #[test]
+    fn should_name_file_by_template() {
+        let entry = TestEntry::new("rust/axum-0.7-rust-1.74").category("web").metadata("framework_version", "0.7").build();
+        let naming = |template: &str| FileNaming::parse(template).unwrap();
+        assert_eq!(entry.file_stem(Some(&naming("{category}/{language}-{version}"))), "web/rust-1.74");
+        assert_eq!(entry.file_stem(Some(&naming("{category}/{benchmark}{sweep_level}"))), "web/rust/axum-0.7-rust-1.74");
+        assert_eq!(entry.file_stem(Some(&naming("{language}/{framework_version}"))), "rust/0.7");
+    }
+
+    #[test]
+    fn should_reject_invalid_templates() {
+        assert!(FileNaming::parse("{category}/{name}").unwrap_err().contains("unknown placeholder {name}"));
+        assert!(FileNaming::parse("/tmp/{version}").is_err());
+        assert!(FileNaming::parse("../{version}").is_err());
+        assert!(FileNaming::parse("{category}//{version}").is_err());
+    }
+
+    #[test]
+    fn should_name_file_by_sweep_level() {
+        let mut entry = TestEntry::new("rust/axum-0.7-rust-1.74").category("web").metadata("framework_version", "0.7").build();
+        entry.metadata.insert("concurrency".to_string(), "8".to_string());
+        entry.metadata.insert(SWEEP_KEY.to_string(), "concurrency".to_string());
+        assert_eq!(entry.sweep_level(), Some("concurrency-8".to_string()));
+        assert_eq!(entry.file_stem(None), "web/rust_axum-0.7-rust-1.74_1.74_0.7_concurrency-8");
+    }
+
+    #[test]
+    fn should_write_raw_rounds_as_ndjson() {
+        let output_dir = TestDir::new("raw");
+        let mut writer = ResultWriter::new(OutputPaths::at(output_dir.path()), &[OutputFormat::Json]);
+        writer.set_raw_output(true);
+        let mut entry = TestEntry::new("rust/axum-0.7-rust-1.74").category("web").metadata("framework_version", "0.7").build();
+        entry.result.samples = vec![
+            RoundSample { warmup: true, time: 12, ..Default::default() },
+            RoundSample { time: 10, failed_attempts: vec!["Connection refused".to_string()], suspect_reruns: vec![900], ..Default::default() },
+        ];
+        writer.write(entry).unwrap();
+
+        let raw_path = "web/rust_axum-0.7-rust-1.74_1.74_0.7.raw.ndjson";
+        let lines: Vec<serde_json::Value> = fs::read_to_string(output_dir.join(raw_path)).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["warmup"], true);
+        assert_eq!(lines[1]["time"], 10);
+        assert_eq!(lines[1]["failed_attempts"][0], "Connection refused");
+        assert_eq!(lines[1]["suspect_reruns"][0], 900);
+
+        let combined: serde_json::Value = serde_json::from_str(&fs::read_to_string(OutputPaths::at(output_dir.path()).results()).unwrap()).unwrap();
+        assert_eq!(combined["results"][0]["raw_output"], raw_path);
+        assert!(combined["results"][0]["result"].get("samples").is_none());
+    }
+
+    #[test]
+    fn should_serialize_additional_data_with_type() {
+        let json = serde_json::to_value(TestEntry::new("rust/axum").category("web").metadata("mode", "Default").memory_p99(4096).rps(1234).build()).unwrap();
+        assert_eq!(json["result"]["additional_data"]["rps_median"], serde_json::json!({
+            "type": "int",
+            "value": 1234,
+        }));
+        assert_eq!(json["result"]["memory_p99"], 4096);
+        assert_eq!(json["metadata"]["mode"], "Default");
+        assert_eq!(json["result"]["tags"], serde_json::json!({}));
+    }
+
+    /// A `results.json` with the `contents` in a directory removed with the returned guard.
+    fn temp_file(name: &str, contents: &str) -> (TestDir, PathBuf) {
+        let dir = TestDir::new(name);
+        let path = dir.join("results.json");
+        fs::write(&path, contents).unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn should_load_previous_schema_version() {
+        let (_dir, path) = temp_file("schema-v1", include_str!("fixtures/results_v1.json"));
+        let loaded = load_results(&path).unwrap();
+
+        assert_eq!(loaded.schema_version, 1);
+        assert!(loaded.failures.is_empty());
+        let entry = &loaded.results[0];
+        assert_eq!(entry.schema_version, SCHEMA_VERSION);
+        assert_eq!(entry.benchmark, "rust/axum-0.7-rust-1.74");
+        assert_eq!(entry.result.time_median, 10003);
+        assert_eq!(entry.result.memory_p99, Some(9437184));
+        assert_eq!(entry.result.additional_data["rps_median"], AdditionalData::Int(98231));
+        assert_eq!(entry.result.self_reported_memory_median, None);
+        assert_eq!(entry.result.cold_start_ms, None);
+        assert!(entry.result.tags.is_empty());
+    }
+
+    #[test]
+    fn should_round_trip_current_schema_version() {
+        let entries = vec![TestEntry::new("rust/axum").category("web").time(10).memory_p99(4096).rps(1234).build()];
+        let json = to_combined_json(None, None, &entries, &[], &[], None).unwrap();
+        let (_dir, path) = temp_file("schema-current", &json);
+        let loaded = load_results(&path).unwrap();
+
+        assert_eq!(loaded.schema_version, SCHEMA_VERSION);
+        assert_eq!(serde_json::to_value(&loaded.results).unwrap(), serde_json::to_value(&entries).unwrap());
+    }
+
+    #[test]
+    fn should_reject_future_schema_version() {
+        let (_dir, path) = temp_file("schema-future", &format!("{{\"schema_version\": {}, \"results\": []}}", SCHEMA_VERSION + 1));
+        let error = load_results(&path).err().unwrap();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("Please update sharkbench"), "{}", error);
+    }
+
+    #[test]
+    fn should_write_entry_and_combined_file() {
+        let output_dir = TestDir::new("json");
+        let mut writer = ResultWriter::new(OutputPaths::at(output_dir.path()), &[OutputFormat::Json, OutputFormat::Csv]);
+        writer.set_tags(IndexMap::from([("power".to_string(), "battery".to_string())]));
+        writer.set_machine(MachineInfo { cpu_model: Some("Test CPU".to_string()), ..Default::default() });
+        writer.write(TestEntry::new("rust/axum-0.7-rust-1.74").category("web").metadata("framework_version", "0.7").rps(1234).build()).unwrap();
+        writer.write(TestEntry::new("rust/actix-4-rust-1.74").category("web").metadata("framework_version", "4").rps(1234).build()).unwrap();
+
+        assert!(output_dir.join("web/rust_axum-0.7-rust-1.74_1.74_0.7.json").exists());
+        let combined: serde_json::Value = serde_json::from_str(&fs::read_to_string(OutputPaths::at(output_dir.path()).results()).unwrap()).unwrap();
+        assert_eq!(combined["schema_version"], SCHEMA_VERSION);
+        assert_eq!(combined["results"].as_array().unwrap().len(), 2);
+        assert_eq!(combined["results"][0]["result"]["tags"]["power"], "battery");
+        assert_eq!(combined["machine"]["cpu_model"], "Test CPU");
+        assert_eq!(combined["machine"]["hostname"], serde_json::Value::Null);
+        assert_eq!(combined["results"][0]["machine"]["cpu_model"], "Test CPU");
+        assert_eq!(combined["results"][0]["relative"]["time_factor"], 1.0);
+        assert_eq!(fs::read_to_string(OutputPaths::at(output_dir.path()).results_csv()).unwrap().lines().count(), 3);
+        assert!(OutputPaths::at(output_dir.path()).markdown_report().exists());
+        assert!(OutputPaths::at(output_dir.path()).html_report().exists());
+    }
+
+    #[test]
+    fn should_record_run_once_per_file() {
+        let output_dir = TestDir::new("run");
+        let mut writer = ResultWriter::new(OutputPaths::at(output_dir.path()), &[OutputFormat::Json]);
+        writer.set_run(RunMetadata { started_at: "2024-01-31T12:34:56Z".to_string(), ..Default::default() });
+        writer.write(TestEntry::new("rust/axum-0.7-rust-1.74").category("web").metadata("framework_version", "0.7").build()).unwrap();
+        writer.finish_run(Duration::from_secs(90)).unwrap();
+
+        let read = |path: &str| serde_json::from_str::<serde_json::Value>(&fs::read_to_string(output_dir.join(path)).unwrap()).unwrap();
+        let entry_file = read("web/rust_axum-0.7-rust-1.74_1.74_0.7.json");
+        assert_eq!(entry_file["run"]["started_at"], "2024-01-31T12:34:56Z");
+        assert_eq!(entry_file["run"]["finished_at"], serde_json::Value::Null);
+
+        let combined = read("results.json");
+        assert_eq!(combined["run"]["duration_seconds"], 90);
+        assert!(combined["results"][0].get("run").is_none());
+        assert!(fs::read_to_string(OutputPaths::at(output_dir.path()).markdown_report()).unwrap().contains("after 1m 30s"));
+    }
+
+    #[test]
+    fn should_reuse_recent_results_with_same_inputs() {
+        let output_dir = TestDir::new("reuse");
+        let hours_ago = |hours: u64| environment::format_utc(environment::now_unix_ms() as u64 / 1000 - hours * 3600);
+        let with_hash = |benchmark: &str, reused_from: Option<String>| {
+            let mut entry = TestEntry::new(benchmark).category("web").build();
+            entry.input_hash = Some("0123456789abcdef".to_string());
+            entry.reused_from = reused_from;
+            entry
+        };
+        let previous = LoadedResults {
+            schema_version: SCHEMA_VERSION,
+            results: vec![with_hash("rust/fresh", None), with_hash("rust/stale", Some(hours_ago(48))), TestEntry::new("rust/unhashed").category("web").build()],
+            failures: Vec::new(),
+            skipped: Vec::new(),
+            run: Some(RunMetadata { started_at: hours_ago(1), ..Default::default() }),
+        };
+
+        let mut writer = ResultWriter::new(OutputPaths::at(output_dir.path()), &[OutputFormat::Json]);
+        writer.set_reusable(previous, Duration::from_secs(24 * 3600));
+
+        assert!(!writer.reuse("web", "rust/fresh", "fedcba9876543210").unwrap());
+        assert!(writer.reuse("web", "rust/fresh", "0123456789abcdef").unwrap());
+        assert!(!writer.reuse("web", "rust/stale", "0123456789abcdef").unwrap());
+        assert!(!writer.reuse("web", "rust/unhashed", "0123456789abcdef").unwrap());
+
+        assert_eq!(writer.entries().len(), 1);
+        assert_eq!(writer.entries()[0].reused_from, Some(hours_ago(1)));
+    }
+
+    #[test]
+    fn should_resume_completed_benchmarks() {
+        let output_dir = TestDir::new("resume");
+        let previous = LoadedResults {
+            schema_version: SCHEMA_VERSION,
+            results: vec![TestEntry::new("rust/axum-0.7-rust-1.74").category("web").metadata("framework_version", "0.7").build(), TestEntry::new("rust/actix-4-rust-1.74").category("web").build()],
+            failures: Vec::new(),
+            skipped: Vec::new(),
+            run: None,
+        };
+        let progress = Progress {
+            run: None,
+            // A completed benchmark without entries runs again
+            completed: vec!["web/rust/axum-0.7-rust-1.74".to_string(), "web/rust/rocket-0.5-rust-1.74".to_string()],
+            in_progress: Some("web/rust/actix-4-rust-1.74".to_string()),
+        };
+
+        let mut writer = ResultWriter::new(OutputPaths::at(output_dir.path()), &[OutputFormat::Json]);
+        writer.resume(progress, Some(previous));
+        assert_eq!(writer.entries().len(), 1);
+        assert!(writer.is_completed("web", "rust/axum-0.7-rust-1.74"));
+        assert!(!writer.is_completed("web", "rust/rocket-0.5-rust-1.74"));
+        assert!(!writer.is_completed("web", "rust/actix-4-rust-1.74"));
+
+        writer.start_benchmark("web", "rust/actix-4-rust-1.74").unwrap();
+        let progress = crate::output::progress::load(&OutputPaths::at(output_dir.path())).unwrap();
+        assert_eq!(progress.in_progress.as_deref(), Some("web/rust/actix-4-rust-1.74"));
+        writer.end_benchmark(true).unwrap();
+        assert!(writer.is_completed("web", "rust/actix-4-rust-1.74"));
+    }
+
+    #[test]
+    fn should_merge_results_of_retried_run() {
+        let output_dir = TestDir::new("retry-merge");
+        let failed = |benchmark: &str| FailedBenchmark {
+            category: "web".to_string(),
+            benchmark: benchmark.to_string(),
+            version: None,
+            error: "Build failed".to_string(),
+            kind: None,
+        };
+        let previous = LoadedResults {
+            schema_version: SCHEMA_VERSION,
+            results: vec![TestEntry::new("rust/axum-0.7-rust-1.74").category("web").metadata("framework_version", "0.7").build(), TestEntry::new("rust/actix-4-rust-1.74").category("web").build()],
+            failures: vec![failed("rust/actix-4-rust-1.74"), failed("rust/removed"), failed("rust/skipped")],
+            skipped: Vec::new(),
+            run: Some(RunMetadata { started_at: "2024-01-31T12:34:56Z".to_string(), ..Default::default() }),
+        };
+
+        let mut writer = ResultWriter::new(OutputPaths::at(output_dir.path()), &[OutputFormat::Json]);
+        writer.retry(previous, &["benchmark/web/rust/actix-4-rust-1.74".to_string()], &["benchmark/web/rust/skipped".to_string()]);
+
+        assert_eq!(writer.entries().len(), 1);
+        assert_eq!(writer.entries()[0].benchmark, "rust/axum-0.7-rust-1.74");
+        assert_eq!(writer.entries()[0].reused_from.as_deref(), Some("2024-01-31T12:34:56Z"));
+        assert_eq!(writer.failures(), &[failed("rust/removed")]);
+    }
+
+    #[test]
+    fn should_serialize_combined_results_deterministically() {
+        let results = |reversed: bool| {
+            let mut pairs = [
+                ("rps_median", AdditionalData::Int(1234)),
+                ("latency_p99", AdditionalData::Int(25)),
+            ];
+            let mut tags = [("power", "battery"), ("host", "ci")];
+            let mut entries = vec![
+                TestEntry::new("rust/b").category("web").build(),
+                TestEntry::new("rust/a").category("web").version("1.75").build(),
+                TestEntry::new("rust/a").category("web").build(),
+            ];
+            let mut failures = vec![
+                FailedBenchmark { category: "web".to_string(), benchmark: "rust/d".to_string(), version: None, error: "d".to_string(), kind: None },
+                FailedBenchmark { category: "web".to_string(), benchmark: "rust/c".to_string(), version: None, error: "c".to_string(), kind: None },
+            ];
+            if reversed {
+                pairs.reverse();
+                tags.reverse();
+                entries.reverse();
+                failures.reverse();
+            }
+            for entry in &mut entries {
+                entry.result.additional_data = pairs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect();
+                entry.result.tags = tags.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+                entry.relative = Some(RelativeScore { baseline: None, time_factor: Some(2.0 / 3.0), memory_factor: None });
+            }
+            to_combined_json(None, None, &entries, &failures, &[], None).unwrap()
+        };
+
+        let json = results(false);
+        assert_eq!(json, results(true));
+
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let order: Vec<(&str, &str)> = parsed["results"].as_array().unwrap().iter()
+            .map(|entry| (entry["benchmark"].as_str().unwrap(), entry["version"].as_str().unwrap()))
+            .collect();
+        assert_eq!(order, vec![("rust/a", "1.74"), ("rust/a", "1.75"), ("rust/b", "1.74")]);
+        assert_eq!(parsed["failures"][0]["benchmark"], "rust/c");
+        assert!(json.find("\"latency_p99\"").unwrap() < json.find("\"rps_median\"").unwrap());
+        assert!(json.find("\"host\"").unwrap() < json.find("\"power\"").unwrap());
+        assert_eq!(parsed["results"][0]["relative"]["time_factor"], 0.6667);
+    }
+}
@@ -0,0 +1,176 @@
+use indexmap::IndexMap;
+use crate::output::escape;
+use crate::output::json::{FailedBenchmark, ResultEntry, SkippedBenchmark};
+use crate::output::regression::{self, RegressionReport};
+
+/// A test case of the report: an entry, a failed or a skipped benchmark.
+struct TestCase {
+    name: String,
+    classname: String,
+    time_ms: i64,
+    outcome: Outcome,
+}
+
+enum Outcome {
+    Passed,
+    /// `type` attribute and message of the failure.
+    Failed(String, String),
+    Skipped,
+}
+
+/// Renders the results as JUnit XML: a test suite per category with a test case per benchmark and version.
+///
+/// Failed benchmarks and regressions against the baseline (if given) are failures of their test case,
+/// skipped benchmarks are skipped test cases.
+/// The time of a test case is the wall time of its rounds, including the cooldowns.
+pub fn to_junit(
+    entries: &[ResultEntry],
+    failures: &[FailedBenchmark],
+    skipped: &[SkippedBenchmark],
+    regressions: Option<&RegressionReport>,
+) -> String {
+    let mut suites: IndexMap<&str, Vec<TestCase>> = IndexMap::new();
+    for entry in entries {
+        let name = regression::display_name(entry);
+        let regressed: Vec<String> = regressions.iter()
+            .flat_map(|report| report.regressions.iter())
+            .filter(|delta| delta.benchmark == name)
+            .map(|delta| delta.to_string())
+            .collect();
+        suites.entry(&entry.category).or_default().push(TestCase {
+            classname: format!("{}.{}", entry.category, entry.benchmark),
+            time_ms: wall_time_ms(entry),
+            outcome: match regressed.is_empty() {
+                true => Outcome::Passed,
+                false => Outcome::Failed("regression".to_string(), regressed.join("\n")),
+            },
+            name,
+        });
+    }
+    for failure in failures {
+        suites.entry(&failure.category).or_default().push(TestCase {
+            name: match &failure.version {
+                Some(version) => format!("{} ({})", failure.benchmark, version),
+                None => failure.benchmark.clone(),
+            },
+            classname: format!("{}.{}", failure.category, failure.benchmark),
+            time_ms: 0,
+            // The kind of typed errors, e.g. `http_status`
+            outcome: Outcome::Failed(failure.kind.clone().unwrap_or("error".to_string()), failure.error.clone()),
+        });
+    }
+    for skipped in skipped {
+        suites.entry(&skipped.category).or_default().push(TestCase {
+            name: skipped.benchmark.clone(),
+            classname: format!("{}.{}", skipped.category, skipped.benchmark),
+            time_ms: 0,
+            outcome: Outcome::Skipped,
+        });
+    }
+
+    let all: Vec<&TestCase> = suites.values().flatten().collect();
+    let mut buffer = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    buffer.push_str(&format!("<testsuites name=\"sharkbench\" {}>\n", counts(&all)));
+    for (category, test_cases) in &suites {
+        buffer.push_str(&format!(
+            "  <testsuite name=\"{}\" {}>\n",
+            escape::xml(category),
+            counts(&test_cases.iter().collect::<Vec<&TestCase>>()),
+        ));
+        for test_case in test_cases {
+            let attributes = format!(
+                "name=\"{}\" classname=\"{}\" time=\"{}\"",
+                escape::xml(&test_case.name),
+                escape::xml(&test_case.classname),
+                seconds(test_case.time_ms),
+            );
+            match &test_case.outcome {
+                Outcome::Passed => buffer.push_str(&format!("    <testcase {}/>\n", attributes)),
+                Outcome::Failed(kind, message) => buffer.push_str(&format!(
+                    "    <testcase {}>\n      <failure type=\"{}\" message=\"{}\">{}</failure>\n    </testcase>\n",
+                    attributes,
+                    kind,
+                    escape::xml(message.lines().next().unwrap_or_default()),
+                    escape::xml(message),
+                )),
+                Outcome::Skipped => buffer.push_str(&format!("    <testcase {}>\n      <skipped/>\n    </testcase>\n", attributes)),
+            }
+        }
+        buffer.push_str("  </testsuite>\n");
+    }
+    buffer.push_str("</testsuites>\n");
+    buffer
+}
+
+/// The `tests`, `failures`, `errors`, `skipped` and `time` attributes of a suite.
+fn counts(test_cases: &[&TestCase]) -> String {
+    let failures = test_cases.iter().filter(|test_case| matches!(test_case.outcome, Outcome::Failed(..))).count();
+    let skipped = test_cases.iter().filter(|test_case| matches!(test_case.outcome, Outcome::Skipped)).count();
+    let time_ms: i64 = test_cases.iter().map(|test_case| test_case.time_ms).sum();
+    format!("tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\" time=\"{}\"", test_cases.len(), failures, skipped, seconds(time_ms))
+}
+
+/// Example: `5100` -> `5.100`
+fn seconds(millis: i64) -> String {
+    format!("{}.{:03}", millis / 1000, millis % 1000)
+}
+
+/// From the start of the first round to the end of the last one.
+fn wall_time_ms(entry: &ResultEntry) -> i64 {
+    let samples = &entry.result.samples;
+    match (samples.first(), samples.last()) {
+        (Some(first), Some(last)) => (last.started_at_ms + last.time - first.started_at_ms).max(0),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmark::benchmark::RoundSample;
+    use crate::output::regression::{ImageChange, MetricDelta};
+    use crate::utils::test_entry::TestEntry;
+
+    const EXPECTED: &str = include_str!("fixtures/junit.xml");
+
+    #[test]
+    fn should_match_fixture() {
+        let report = RegressionReport {
+            regressions: vec![MetricDelta {
+                benchmark: "rust/b (Rust 1.74)".to_string(),
+                metric: "time",
+                baseline: 100,
+                current: 120,
+                change_percent: 20.0,
+                images: ImageChange::Unknown,
+            }],
+            ..Default::default()
+        };
+        let samples = vec![
+            RoundSample { warmup: true, started_at_ms: 1_000_000, time: 1600, ..Default::default() },
+            RoundSample { started_at_ms: 1_003_600, time: 1500, ..Default::default() },
+        ];
+        let xml = to_junit(
+            &[TestEntry::new("rust/a").time(1500).samples(samples.clone()).build(), TestEntry::new("rust/b").time(1500).samples(samples).build()],
+            &[FailedBenchmark {
+                category: "web".to_string(),
+                benchmark: "rust/axum".to_string(),
+                version: Some("1.74".to_string()),
+                error: "Invalid response: <html> & \"more\"\nsecond line".to_string(),
+                kind: Some("parse_error".to_string()),
+            }],
+            &[SkippedBenchmark { category: "web".to_string(), benchmark: "rust/actix".to_string() }],
+            Some(&report),
+        );
+
+        assert_eq!(xml, EXPECTED);
+    }
+
+    #[test]
+    fn should_render_empty_run() {
+        assert_eq!(
+            to_junit(&[], &[], &[], None),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"sharkbench\" tests=\"0\" failures=\"0\" errors=\"0\" skipped=\"0\" time=\"0.000\">\n</testsuites>\n",
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use indexmap::IndexMap;
+use crate::output::escape;
+use crate::output::json::{self, ResultEntry};
+use crate::output::metric;
+use crate::output::relative::format_factor;
+use crate::utils::format::SizeFormat;
+
+/// Renders the entries as Markdown tables, one per category (sorted by name), sorted by their `metric::ranked` metrics
+/// (the median time, for web benchmarks the median requests per second and latency).
+/// The last columns show the relative scores of each entry (see `relative::assign`).
+pub fn to_markdown(entries: &[ResultEntry]) -> String {
+    let mut categories: IndexMap<&str, Vec<&ResultEntry>> = IndexMap::new();
+    for entry in json::sorted_entries(entries) {
+        categories.entry(entry.category.as_str()).or_default().push(entry);
+    }
+
+    let mut buffer = String::from("# Results\n");
+    for (category, mut entries) in categories {
+        entries.sort_by(|a, b| {
+            metric::compare_ranked(a, b)
+                .then_with(|| a.benchmark.cmp(&b.benchmark))
+                .then_with(|| a.version.cmp(&b.version))
+        });
+
+        buffer.push_str(&format!("\n## {}\n\n", capitalize(category)));
+        if entries.iter().any(|entry| entry.relative.as_ref().is_some_and(|score| score.baseline.is_none())) {
+            buffer.push_str("The baseline language has no result, relative values are not available.\n\n");
+        }
+        let metrics = metric::ranked(category);
+        let titles: Vec<&str> = metrics.iter().map(|metric| metric.title()).collect();
+        buffer.push_str(&format!("| Benchmark | Language | Version | {} | P99 memory | Relative time | Relative memory |\n", titles.join(" | ")));
+        buffer.push_str(&format!("|---|---|---|{}---:|---:|---:|\n", "---:|".repeat(metrics.len())));
+        for entry in entries {
+            let score = entry.relative.as_ref();
+            let values: Vec<String> = metrics.iter().map(|metric| metric.format(metric.value(entry))).collect();
+            buffer.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} | {} |\n",
+                escape::markdown(&entry.benchmark),
+                escape::markdown(&entry.language),
+                escape::markdown(&entry.version),
+                values.join(" | "),
+                entry.result.memory_p99.bytes_to_string(),
+                format_factor(score.and_then(|score| score.time_factor)),
+                format_factor(score.and_then(|score| score.memory_factor)),
+            ));
+        }
+    }
+
+    buffer
+}
+
+fn capitalize(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::relative;
+    use crate::utils::test_entry::TestEntry;
+
+    #[test]
+    fn should_group_by_category_and_rank_entries() {
+        let measured = |benchmark: &str, time_median: i64| TestEntry::new(benchmark).time(time_median).memory(1024).memory_p99(2 * 1024 * 1024);
+        let mut entries = vec![
+            measured("rust/slow", 3000).build(),
+            measured("rust/actix", 15000).category("web").rps(10000).latency(1500).build(),
+            measured("rust/axum", 15000).category("web").rps(20000).latency(800).build(),
+            measured("rust/fast", 1500).build(),
+        ];
+        relative::assign(&mut entries, None);
+        let markdown = to_markdown(&entries);
+
+        assert_eq!(markdown, "\
+# Results
+
+## Computation
+
+| Benchmark | Language | Version | Median time | P99 memory | Relative time | Relative memory |
+|---|---|---|---:|---:|---:|---:|
+| rust/fast | Rust | 1.74 | 1.50 s | 2.00 MB | 1.00x | 1.00x |
+| rust/slow | Rust | 1.74 | 3.00 s | 2.00 MB | 2.00x | 1.00x |
+
+## Web
+
+| Benchmark | Language | Version | Median rps | Median latency | P99 memory | Relative time | Relative memory |
+|---|---|---|---:|---:|---:|---:|---:|
+| rust/axum | Rust | 1.74 | 20,000 rps | 0.80 ms | 2.00 MB | 1.00x | 1.00x |
+| rust/actix | Rust | 1.74 | 10,000 rps | 1.50 ms | 2.00 MB | 2.00x | 1.00x |
+");
+    }
+
+    #[test]
+    fn should_note_missing_baseline() {
+        let mut entries = vec![TestEntry::new("rust/a").time(1500).memory(1024).memory_p99(2 * 1024 * 1024).build()];
+        relative::assign(&mut entries, Some("Go"));
+        let markdown = to_markdown(&entries);
+
+        assert!(markdown.contains("The baseline language has no result, relative values are not available.\n"));
+        assert!(markdown.contains("| rust/a | Rust | 1.74 | 1.50 s | 2.00 MB | - | - |\n"));
+    }
+}
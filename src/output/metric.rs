@@ -0,0 +1,129 @@
+use std::cmp::Ordering;
+use crate::benchmark::benchmark::AdditionalData;
+use crate::output::json::ResultEntry;
+use crate::utils::format::{self, SizeFormat, TimeFormat};
+
+/// A metric that tells two results of a benchmark apart, see `compared`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    /// Median time of a round in milliseconds.
+    Time,
+
+    /// Median requests per second (`rps_median`) of a web benchmark.
+    Rps,
+
+    /// Median latency (`latency_median`) of a web benchmark in microseconds.
+    Latency,
+
+    /// Median memory in bytes.
+    Memory,
+}
+
+impl Metric {
+    /// E.g. `time`, as reported in a `MetricDelta`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::Time => "time",
+            Metric::Rps => "rps",
+            Metric::Latency => "latency",
+            Metric::Memory => "memory",
+        }
+    }
+
+    /// `None` if the entry did not measure the metric.
+    pub fn value(self, entry: &ResultEntry) -> Option<i64> {
+        match self {
+            Metric::Time => Some(entry.result.time_median),
+            Metric::Rps => entry.result.additional_data.get("rps_median").map(AdditionalData::as_i64),
+            Metric::Latency => entry.result.additional_data.get("latency_median").map(AdditionalData::as_i64),
+            Metric::Memory => entry.result.memory_median,
+        }
+    }
+
+    /// Whether a growing value is an improvement, e.g. the requests per second.
+    pub fn higher_is_better(self) -> bool {
+        self == Metric::Rps
+    }
+
+    /// Header of the column in the reports, e.g. `Median time`.
+    pub fn title(self) -> &'static str {
+        match self {
+            Metric::Time => "Median time",
+            Metric::Rps => "Median rps",
+            Metric::Latency => "Median latency",
+            Metric::Memory => "Median memory",
+        }
+    }
+
+    /// E.g. `1.50 s`, `12,345 rps`, `1.23 ms` or `2.00 MB`, `-` if not measured.
+    pub fn format(self, value: Option<i64>) -> String {
+        let Some(value) = value else {
+            return "-".to_string();
+        };
+        match self {
+            Metric::Time => value.millis_to_string(),
+            Metric::Rps => format!("{} rps", format::format_number(value)),
+            Metric::Latency => format!("{} ms", format::number_format().decimal(value as f64 / 1000.0, 2)),
+            Metric::Memory => value.bytes_to_string(),
+        }
+    }
+
+    /// Orders the better entry first, entries without the metric last.
+    pub fn compare(self, a: &ResultEntry, b: &ResultEntry) -> Ordering {
+        match (self.value(a), self.value(b)) {
+            (Some(a), Some(b)) if self.higher_is_better() => b.cmp(&a),
+            (Some(a), Some(b)) => a.cmp(&b),
+            (a, b) => a.is_none().cmp(&b.is_none()),
+        }
+    }
+}
+
+/// The metrics results of the `category` are compared by: the time and memory,
+/// for web benchmarks the requests per second and latency instead of the time, as their rounds load the server for a fixed duration.
+pub fn compared(category: &str) -> &'static [Metric] {
+    match category {
+        "web" => &[Metric::Rps, Metric::Latency, Metric::Memory],
+        _ => &[Metric::Time, Metric::Memory],
+    }
+}
+
+/// The metrics results of the `category` are ranked by in the reports, most important first.
+/// The memory is not one of them, the reports show it on its own.
+pub fn ranked(category: &str) -> &'static [Metric] {
+    match category {
+        "web" => &[Metric::Rps, Metric::Latency],
+        _ => &[Metric::Time],
+    }
+}
+
+/// Orders the entries of a category by the `ranked` metrics, the best first.
+pub fn compare_ranked(a: &ResultEntry, b: &ResultEntry) -> Ordering {
+    ranked(&a.category).iter().fold(Ordering::Equal, |ordering, metric| ordering.then_with(|| metric.compare(a, b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_entry::TestEntry;
+
+    #[test]
+    fn should_compare_web_benchmarks_by_throughput_and_latency() {
+        let entry = TestEntry::new("rust/axum").category("web").time(15000).rps(1234).build();
+
+        assert_eq!(compared(&entry.category), &[Metric::Rps, Metric::Latency, Metric::Memory]);
+        assert_eq!(compared("computation"), &[Metric::Time, Metric::Memory]);
+        assert_eq!((Metric::Rps.value(&entry), Metric::Latency.value(&entry)), (Some(1234), None));
+    }
+
+    #[test]
+    fn should_rank_higher_throughput_first() {
+        let web = || TestEntry::new("rust/axum").category("web").time(15000);
+        let computation = |time_median| TestEntry::new("rust").time(time_median).build();
+
+        assert_eq!(compare_ranked(&web().rps(2000).build(), &web().rps(1000).build()), Ordering::Less);
+        assert_eq!(compare_ranked(&web().build(), &web().rps(1000).build()), Ordering::Greater);
+        assert_eq!(compare_ranked(&computation(1500), &computation(3000)), Ordering::Less);
+        assert_eq!(Metric::Latency.format(Some(1234)), "1.23 ms");
+        assert_eq!(Metric::Rps.format(None), "-");
+    }
+}
@@ -0,0 +1,44 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+pub mod badge;
+pub mod compare;
+pub mod csv;
+pub mod escape;
+pub mod eta;
+pub mod github;
+pub mod golden;
+pub mod html;
+pub mod json;
+pub mod junit;
+pub mod markdown;
+pub mod metric;
+pub mod paths;
+pub mod progress;
+pub mod pushgateway;
+pub mod regression;
+pub mod relative;
+pub mod retry;
+pub mod score;
+pub mod sqlite;
+pub mod stream;
+pub mod summary;
+pub mod svg;
+pub mod tui;
+pub mod upload;
+pub mod webhook;
+
+/// Formats the results can be written in.
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Json,
+    Csv,
+}
+
+/// Formats of the report that can be printed at the end of a run.
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Md,
+}
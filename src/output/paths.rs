@@ -0,0 +1,178 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::benchmark::hosts::HOSTS_DIR;
+use crate::output::progress::PROGRESS_FILE;
+use crate::output::upload::UPLOAD_FILE;
+
+/// The default of `--output-dir`.
+pub const DEFAULT_OUTPUT_DIR: &str = "sharkbench-out";
+
+/// Points to the last successful run in `--output-dir`, see `OutputPaths::mark_latest`.
+pub const LATEST: &str = "latest";
+
+const COMBINED_FILE: &str = "results.json";
+const COMBINED_CSV_FILE: &str = "results.csv";
+const MARKDOWN_REPORT_FILE: &str = "report.md";
+const HTML_REPORT_FILE: &str = "report.html";
+const BADGE_FILE: &str = "badge.svg";
+const PROFILE_DIR: &str = "profiles";
+const BUILD_DIR: &str = "builds";
+const LOG_FILE: &str = "run.log";
+
+/// The name of the SVG chart in the directory of each benchmark.
+const CHART_FILE: &str = "chart.svg";
+
+/// Where the artifacts of a run are written: every run has its own directory, by default a new one below `--output-dir`
+/// named after the start of the run (e.g. `sharkbench-out/2024-01-31T12-34-56Z`).
+/// Every file of the run is located here, so the layout of a run directory is the same everywhere.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputPaths {
+    run_dir: PathBuf,
+}
+
+impl OutputPaths {
+    /// The paths of the run in `run_dir`, e.g. of a resumed run. Nothing is created.
+    pub fn at(run_dir: impl Into<PathBuf>) -> Self {
+        OutputPaths { run_dir: run_dir.into() }
+    }
+
+    /// Creates a new run directory below `output_dir` named after `started_at` (UTC, e.g. `2024-01-31T12:34:56Z`),
+    /// with a counter if a run started in the same second.
+    pub fn new_run(output_dir: &Path, started_at: &str) -> io::Result<Self> {
+        let name = started_at.replace(':', "-");
+        fs::create_dir_all(output_dir)?;
+        for counter in 1.. {
+            let run_dir = match counter {
+                1 => output_dir.join(&name),
+                _ => output_dir.join(format!("{}-{}", name, counter)),
+            };
+            match fs::create_dir(&run_dir) {
+                Ok(()) => return Ok(OutputPaths::at(run_dir)),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        unreachable!("The counter is unbounded")
+    }
+
+    /// Creates the run directory if it does not exist yet.
+    pub fn create(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.run_dir)
+    }
+
+    pub fn run_dir(&self) -> &Path {
+        &self.run_dir
+    }
+
+    /// A file of the run, e.g. the result of a benchmark named by `--file-naming`.
+    pub fn file(&self, relative: &str) -> PathBuf {
+        self.run_dir.join(relative)
+    }
+
+    /// The combined results of all benchmarks.
+    pub fn results(&self) -> PathBuf {
+        self.run_dir.join(COMBINED_FILE)
+    }
+
+    pub fn results_csv(&self) -> PathBuf {
+        self.run_dir.join(COMBINED_CSV_FILE)
+    }
+
+    pub fn markdown_report(&self) -> PathBuf {
+        self.run_dir.join(MARKDOWN_REPORT_FILE)
+    }
+
+    pub fn html_report(&self) -> PathBuf {
+        self.run_dir.join(HTML_REPORT_FILE)
+    }
+
+    pub fn badge(&self) -> PathBuf {
+        self.run_dir.join(BADGE_FILE)
+    }
+
+    pub fn progress(&self) -> PathBuf {
+        self.run_dir.join(PROGRESS_FILE)
+    }
+
+    pub fn upload_record(&self) -> PathBuf {
+        self.run_dir.join(UPLOAD_FILE)
+    }
+
+    /// The chart of the benchmark whose files are named `file_stem`.
+    pub fn chart(&self, file_stem: &str) -> PathBuf {
+        self.run_dir.join(file_stem).join(CHART_FILE)
+    }
+
+    /// The recordings of `--profile`.
+    pub fn profiles(&self) -> PathBuf {
+        self.run_dir.join(PROFILE_DIR)
+    }
+
+    /// The output of the run while `--tui` shows only its end, including the output of docker.
+    pub fn log(&self) -> PathBuf {
+        self.run_dir.join(LOG_FILE)
+    }
+
+    /// The output of docker while `--jobs` builds the benchmark in `dir` (e.g. `benchmark/web/rust/actix-4.4`) before the run.
+    pub fn build_log(&self, dir: &str) -> PathBuf {
+        self.run_dir.join(BUILD_DIR).join(format!("{}.log", dir.trim_start_matches("benchmark/")))
+    }
+
+    /// The run directory of a host of `--hosts` for its workers.
+    pub fn host(&self, host_dir_name: &str) -> OutputPaths {
+        OutputPaths::at(self.run_dir.join(HOSTS_DIR).join(host_dir_name))
+    }
+
+    /// Points `latest` next to the run directory to this run: a relative symlink on Unix,
+    /// elsewhere a file containing the name of the run directory.
+    pub fn mark_latest(&self) -> io::Result<()> {
+        let (Some(output_dir), Some(name)) = (self.run_dir.parent(), self.run_dir.file_name()) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} is not inside an output directory", self.run_dir.display())));
+        };
+        // Replaced in one step, so readers never see a missing `latest`
+        let temp = output_dir.join(format!("{}.tmp", LATEST));
+        let _ = fs::remove_file(&temp);
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(name, &temp)?;
+        #[cfg(not(unix))]
+        fs::write(&temp, name.to_string_lossy().as_bytes())?;
+        fs::rename(&temp, output_dir.join(LATEST))
+    }
+
+    /// The last successful run in `output_dir` (see `mark_latest`), `None` if there is none.
+    pub fn latest(output_dir: &Path) -> Option<OutputPaths> {
+        let latest = output_dir.join(LATEST);
+        let name = match fs::read_link(&latest) {
+            Ok(target) => target,
+            Err(_) => PathBuf::from(fs::read_to_string(&latest).ok()?.trim()),
+        };
+        let run_dir = output_dir.join(name);
+        run_dir.is_dir().then(|| OutputPaths::at(run_dir))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    #[test]
+    fn should_create_run_dirs_and_mark_latest() {
+        let output_dir = TestDir::new("paths");
+        assert_eq!(OutputPaths::latest(&output_dir), None);
+
+        let first = OutputPaths::new_run(&output_dir, "2024-01-31T12:34:56Z").unwrap();
+        let second = OutputPaths::new_run(&output_dir, "2024-01-31T12:34:56Z").unwrap();
+        assert_eq!(first.run_dir(), output_dir.join("2024-01-31T12-34-56Z"));
+        assert_eq!(second.run_dir(), output_dir.join("2024-01-31T12-34-56Z-2"));
+        assert!(second.run_dir().is_dir());
+        assert_eq!(second.results(), output_dir.join("2024-01-31T12-34-56Z-2/results.json"));
+        assert_eq!(second.host("ssh___ci_bench-1").results(), output_dir.join("2024-01-31T12-34-56Z-2/hosts/ssh___ci_bench-1/results.json"));
+
+        first.mark_latest().unwrap();
+        assert_eq!(OutputPaths::latest(&output_dir), Some(OutputPaths::at(output_dir.join("2024-01-31T12-34-56Z"))));
+        second.mark_latest().unwrap();
+        assert_eq!(OutputPaths::latest(&output_dir), Some(OutputPaths::at(output_dir.join("2024-01-31T12-34-56Z-2"))));
+    }
+}
@@ -0,0 +1,69 @@
+use std::fs;
+use std::io;
+use serde::{Deserialize, Serialize};
+use crate::output::paths::OutputPaths;
+use crate::utils::run_metadata::RunMetadata;
+
+/// Written next to the results whenever a benchmark starts or ends, so an interrupted run can be continued with `--resume`.
+pub const PROGRESS_FILE: &str = "progress.json";
+
+/// The state of a run, see `PROGRESS_FILE`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Progress {
+    pub run: Option<RunMetadata>,
+
+    /// `<category>/<benchmark>` of the benchmarks that finished without failure, in the order they finished.
+    pub completed: Vec<String>,
+
+    /// The benchmark that was running when the file was written, it is run again when the run is resumed.
+    pub in_progress: Option<String>,
+}
+
+impl Progress {
+    pub fn is_completed(&self, category: &str, benchmark: &str) -> bool {
+        self.completed.contains(&key(category, benchmark))
+    }
+
+    pub fn start(&mut self, category: &str, benchmark: &str) {
+        self.in_progress = Some(key(category, benchmark));
+    }
+
+    /// Ends the benchmark in progress, it is only skipped on resume if it `succeeded`.
+    pub fn end(&mut self, succeeded: bool) {
+        if let (Some(benchmark), true) = (self.in_progress.take(), succeeded) {
+            if !self.completed.contains(&benchmark) {
+                self.completed.push(benchmark);
+            }
+        }
+    }
+}
+
+fn key(category: &str, benchmark: &str) -> String {
+    format!("{}/{}", category, benchmark)
+}
+
+/// Reads the progress of the run in the run directory of `paths`.
+pub fn load(paths: &OutputPaths) -> io::Result<Progress> {
+    let content = fs::read_to_string(paths.progress())?;
+    serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_only_complete_succeeded_benchmarks() {
+        let mut progress = Progress::default();
+        progress.start("computation", "rust/rust-1.40");
+        progress.end(true);
+        progress.start("web", "go/gin-1.9-go-1.21");
+        progress.end(false);
+        progress.start("web", "java/spring-3.2-java-21");
+
+        assert_eq!(progress.completed, vec!["computation/rust/rust-1.40".to_string()]);
+        assert_eq!(progress.in_progress.as_deref(), Some("web/java/spring-3.2-java-21"));
+        assert!(progress.is_completed("computation", "rust/rust-1.40"));
+        assert!(!progress.is_completed("web", "go/gin-1.9-go-1.21"));
+    }
+}
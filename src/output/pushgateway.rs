@@ -0,0 +1,161 @@
+use std::error::Error;
+use std::time::Duration;
+use crate::output::json::ResultEntry;
+use crate::output::metric::{self, Metric};
+
+const JOB: &str = "sharkbench";
+const METRIC_PREFIX: &str = "sharkbench_";
+const PUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pushes the metrics of every benchmark as gauges to a Prometheus Pushgateway.
+///
+/// The grouping key consists of benchmark, language, version and host,
+/// so a later run of the same benchmark replaces the metrics of the previous one.
+pub struct PushGateway {
+    url: String,
+    host: String,
+}
+
+impl PushGateway {
+    pub fn new(url: &str, host: &str) -> PushGateway {
+        PushGateway {
+            url: url.trim_end_matches('/').to_string(),
+            host: host.to_string(),
+        }
+    }
+
+    /// Pushes the metrics of the entry.
+    /// Failures are only logged, they never fail the benchmark run.
+    pub fn push(&self, entry: &ResultEntry) {
+        match self.try_push(entry) {
+            Ok(()) => logln!(" -> Pushed metrics to {}", self.url),
+            Err(e) => warnln!(" -> Failed to push metrics to {}: {}", self.url, e),
+        }
+    }
+
+    fn try_push(&self, entry: &ResultEntry) -> Result<(), Box<dyn Error>> {
+        let response = reqwest::blocking::Client::new()
+            .put(self.group_url(entry))
+            .header("Content-Type", "text/plain; version=0.0.4")
+            .body(to_exposition_format(entry))
+            .timeout(PUSH_TIMEOUT)
+            .send()?;
+        if !response.status().is_success() {
+            return Err(Box::from(format!("Unexpected status {}", response.status())));
+        }
+        Ok(())
+    }
+
+    /// Example: `<url>/metrics/job/sharkbench/benchmark@base64/cnVzdC9heHVt/language/Rust/version/1.74/host/ci`
+    fn group_url(&self, entry: &ResultEntry) -> String {
+        let mut url = format!("{}/metrics/job/{}", self.url, JOB);
+        for (label, value) in [
+            ("benchmark", entry.benchmark.as_str()),
+            ("language", entry.language.as_str()),
+            ("version", entry.version.as_str()),
+            ("host", self.host.as_str()),
+        ] {
+            url.push_str(&grouping_label(label, value));
+        }
+        url
+    }
+}
+
+/// Renders the metrics of the entry in the Prometheus text exposition format.
+/// The time is left out if the entry is not ranked by it (see `metric::ranked`), e.g. the load duration of web benchmarks,
+/// which are measured by their `rps_median` and `latency_median`.
+fn to_exposition_format(entry: &ResultEntry) -> String {
+    let mut metrics: Vec<(String, i64)> = Vec::new();
+    if metric::ranked(&entry.category).contains(&Metric::Time) {
+        metrics.push(("time_median_ms".to_string(), entry.result.time_median));
+        if let Some(time_p1) = entry.result.time_p1 {
+            metrics.push(("time_p1_ms".to_string(), time_p1));
+        }
+    }
+    if let Some(memory_median) = entry.result.memory_median {
+        metrics.push(("memory_median_bytes".to_string(), memory_median));
+    }
+    if let Some(memory_p99) = entry.result.memory_p99 {
+        metrics.push(("memory_p99_bytes".to_string(), memory_p99));
+    }
+    for (key, value) in &entry.result.additional_data {
+        metrics.push((metric_name(key), value.as_i64()));
+    }
+
+    let mut buffer = String::new();
+    for (name, value) in metrics {
+        buffer.push_str(&format!("# TYPE {}{} gauge\n", METRIC_PREFIX, name));
+        buffer.push_str(&format!("{}{} {}\n", METRIC_PREFIX, name, value));
+    }
+    buffer
+}
+
+/// Replaces all characters that are not allowed in metric names.
+fn metric_name(key: &str) -> String {
+    key.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect()
+}
+
+/// Label values with a slash (or empty ones) must be base64 encoded within the URL path.
+fn grouping_label(label: &str, value: &str) -> String {
+    if value.is_empty() || value.contains('/') {
+        format!("/{}@base64/{}", label, base64_url(value.as_bytes()))
+    } else {
+        format!("/{}/{}", label, value)
+    }
+}
+
+/// Encodes the bytes as URL-safe base64 (RFC 4648) without padding.
+fn base64_url(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    if encoded.is_empty() {
+        // The Pushgateway expects "=" for an empty value
+        encoded.push('=');
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_entry::TestEntry;
+
+    #[test]
+    fn should_encode_base64_url() {
+        assert_eq!(base64_url(b""), "=");
+        assert_eq!(base64_url(b"f"), "Zg");
+        assert_eq!(base64_url(b"fo"), "Zm8");
+        assert_eq!(base64_url(b"foo"), "Zm9v");
+        assert_eq!(base64_url(b"rust/a?"), "cnVzdC9hPw");
+    }
+
+    #[test]
+    fn should_group_by_benchmark_language_version_and_host() {
+        let push_gateway = PushGateway::new("http://localhost:9091/", "ci");
+        assert_eq!(
+            push_gateway.group_url(&TestEntry::new("rust/axum-0.7-rust-1.74").build()),
+            "http://localhost:9091/metrics/job/sharkbench/benchmark@base64/cnVzdC9heHVtLTAuNy1ydXN0LTEuNzQ/language/Rust/version/1.74/host/ci",
+        );
+    }
+
+    #[test]
+    fn should_render_gauges() {
+        let computation = TestEntry::new("rust/a").time(100).memory(2048).build();
+        assert!(to_exposition_format(&computation).starts_with("# TYPE sharkbench_time_median_ms gauge\nsharkbench_time_median_ms 100\n"));
+
+        // The load duration does not tell web benchmarks apart
+        let web = TestEntry::new("rust/axum").category("web").time(15000).memory(2048).rps(1234).build();
+        assert_eq!(to_exposition_format(&web), "\
+# TYPE sharkbench_memory_median_bytes gauge
+sharkbench_memory_median_bytes 2048
+# TYPE sharkbench_rps_median gauge
+sharkbench_rps_median 1234
+");
+    }
+}
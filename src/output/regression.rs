@@ -0,0 +1,357 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::Path;
+use crate::output::json::{self, ResultEntry};
+use crate::output::metric;
+use crate::utils::format::SizeFormat;
+use crate::utils::machine::MachineInfo;
+use crate::utils::style;
+
+pub const DEFAULT_REGRESSION_THRESHOLD: f64 = 10.0;
+
+/// A metric of a benchmark that changed by more than the threshold.
+#[derive(Debug, PartialEq)]
+pub struct MetricDelta {
+    /// `<benchmark> (<language> <version>)`
+    pub benchmark: String,
+
+    /// See `Metric::name`, e.g. `time` or `rps`.
+    pub metric: &'static str,
+    pub baseline: i64,
+    pub current: i64,
+
+    /// Relative change in percent, positive means the value grew (e.g. slower, more memory or more requests per second).
+    pub change_percent: f64,
+
+    /// Whether the base images changed between the runs, which may explain the change.
+    pub images: ImageChange,
+}
+
+/// How the base images of a benchmark changed between the baseline and the current run.
+#[derive(Debug, Default, PartialEq)]
+pub enum ImageChange {
+    /// The digests are missing in one of the runs (e.g. an external service or an older result).
+    #[default]
+    Unknown,
+    Same,
+
+    /// References of the base images whose digest changed, e.g. `rust:1.74`.
+    Changed(Vec<String>),
+}
+
+/// Differences between a baseline and the current run.
+#[derive(Debug, Default, PartialEq)]
+pub struct RegressionReport {
+    pub regressions: Vec<MetricDelta>,
+    pub improvements: Vec<MetricDelta>,
+
+    /// Benchmarks only present in the current run.
+    pub added: Vec<String>,
+
+    /// Benchmarks only present in the baseline.
+    pub removed: Vec<String>,
+
+    /// Hardware of the machine that differs from the baseline, e.g. `cpu_model: Ryzen 7 -> Xeon`.
+    /// Results of different machines are not comparable.
+    pub machine_changes: Vec<String>,
+}
+
+impl RegressionReport {
+    pub fn has_regressions(&self) -> bool {
+        !self.regressions.is_empty()
+    }
+}
+
+impl Display for MetricDelta {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}: {} -> {} ({:+.1}%)", self.benchmark, self.metric, self.baseline, self.current, self.change_percent)?;
+        match &self.images {
+            ImageChange::Unknown => Ok(()),
+            ImageChange::Same => write!(f, " [same base images]"),
+            ImageChange::Changed(references) => write!(f, " [base image changed: {}]", references.join(", ")),
+        }
+    }
+}
+
+impl Display for RegressionReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if !self.machine_changes.is_empty() {
+            writeln!(f, "{}", style::warning("Warning: The baseline ran on a different machine, the results are not comparable:"))?;
+            for change in &self.machine_changes {
+                writeln!(f, " - {}", style::warning(change))?;
+            }
+        }
+
+        let sections: [(&str, Vec<String>); 4] = [
+            ("Regressions", self.regressions.iter().map(style::failure).collect()),
+            ("Improvements", self.improvements.iter().map(style::success).collect()),
+            ("Added", self.added.clone()),
+            ("Removed", self.removed.clone()),
+        ];
+
+        for (title, lines) in sections {
+            writeln!(f, "{} ({}):", title, lines.len())?;
+            for line in lines {
+                writeln!(f, " - {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the results of a combined results file written by a previous run (see `json::load_results`).
+pub fn load_baseline(path: &Path) -> io::Result<Vec<ResultEntry>> {
+    let baseline = json::load_results(path)?;
+    if baseline.schema_version < json::SCHEMA_VERSION {
+        logln!(" -> Baseline has schema version {}, fields added since then are treated as not measured", baseline.schema_version);
+    }
+    if !baseline.failures.is_empty() {
+        logln!(" -> {} benchmark(s) failed in the baseline and are reported as added", baseline.failures.len());
+    }
+    Ok(baseline.results)
+}
+
+/// Compares the current results against the baseline by the metrics of their category (see `metric::compared`).
+/// Benchmarks are matched by benchmark, language, version and sweep level.
+/// Only changes of more than `threshold_percent` are reported, a regression is a change for the worse (e.g. fewer requests per second).
+pub fn compare(baseline: &[ResultEntry], current: &[ResultEntry], threshold_percent: f64) -> RegressionReport {
+    let mut report = RegressionReport::default();
+
+    for entry in current {
+        let previous = match baseline.iter().find(|b| same_benchmark(b, entry)) {
+            Some(previous) => previous,
+            None => {
+                report.added.push(display_name(entry));
+                continue;
+            }
+        };
+
+        for &metric in metric::compared(&entry.category) {
+            let (baseline, current) = match (metric.value(previous), metric.value(entry)) {
+                (Some(baseline), Some(current)) => (baseline, current),
+                // Not measured in one of the runs
+                _ => continue,
+            };
+            if baseline <= 0 {
+                // No meaningful relative change
+                continue;
+            }
+
+            let change_percent = (current - baseline) as f64 / baseline as f64 * 100.0;
+            if change_percent.abs() <= threshold_percent {
+                continue;
+            }
+
+            let delta = MetricDelta {
+                benchmark: display_name(entry),
+                metric: metric.name(),
+                baseline,
+                current,
+                change_percent,
+                images: compare_images(previous, entry),
+            };
+            if (change_percent > 0.0) != metric.higher_is_better() {
+                report.regressions.push(delta);
+            } else {
+                report.improvements.push(delta);
+            }
+        }
+    }
+
+    for entry in baseline {
+        if !current.iter().any(|c| same_benchmark(c, entry)) {
+            report.removed.push(display_name(entry));
+        }
+    }
+
+    let machine = |entries: &[ResultEntry]| entries.iter().find_map(|entry| entry.machine.clone());
+    if let (Some(baseline), Some(current)) = (machine(baseline), machine(current)) {
+        report.machine_changes = machine_changes(&baseline, &current);
+    }
+
+    report
+}
+
+/// Compares the hardware of the machines. Fields unknown on either machine are skipped.
+fn machine_changes(baseline: &MachineInfo, current: &MachineInfo) -> Vec<String> {
+    let fields = [
+        ("cpu_model", baseline.cpu_model.clone(), current.cpu_model.clone()),
+        ("logical_cores", baseline.logical_cores.map(|cores| cores.to_string()), current.logical_cores.map(|cores| cores.to_string())),
+        ("total_memory", baseline.total_memory.map(|memory| memory.bytes_to_string()), current.total_memory.map(|memory| memory.bytes_to_string())),
+        ("virtualization", baseline.virtualization.clone(), current.virtualization.clone()),
+    ];
+    fields.into_iter()
+        .filter_map(|(field, baseline, current)| match (baseline, current) {
+            (Some(baseline), Some(current)) if baseline != current => Some(format!("{}: {} -> {}", field, baseline, current)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Compares the digests of the base images, as the image ID changes with every rebuild.
+fn compare_images(baseline: &ResultEntry, current: &ResultEntry) -> ImageChange {
+    let (baseline, current) = match (&baseline.result.image, &current.result.image) {
+        (Some(baseline), Some(current)) if !baseline.base_digests.is_empty() && !current.base_digests.is_empty() => (baseline, current),
+        _ => return ImageChange::Unknown,
+    };
+
+    let mut changed: Vec<String> = Vec::new();
+    for reference in baseline.base_digests.keys().chain(current.base_digests.keys()) {
+        if baseline.base_digests.get(reference) != current.base_digests.get(reference) && !changed.contains(reference) {
+            changed.push(reference.clone());
+        }
+    }
+    match changed.is_empty() {
+        true => ImageChange::Same,
+        false => ImageChange::Changed(changed),
+    }
+}
+
+/// Whether both entries are the same benchmark of the same category, language, version, framework version and sweep level.
+pub fn same_benchmark(a: &ResultEntry, b: &ResultEntry) -> bool {
+    a.category == b.category
+        && a.benchmark == b.benchmark
+        && a.language == b.language
+        && a.version == b.version
+        && a.metadata.get("framework_version") == b.metadata.get("framework_version")
+        && a.sweep_level() == b.sweep_level()
+}
+
+/// `<benchmark> (<language> <version>)`, with the framework version and the sweep level if it has them.
+pub fn display_name(entry: &ResultEntry) -> String {
+    let mut versions = format!("{} {}", entry.language, entry.version);
+    if let Some(framework_version) = entry.metadata.get("framework_version") {
+        versions.push_str(&format!(", framework {}", framework_version));
+    }
+    if let Some(level) = entry.sweep_level() {
+        versions.push_str(&format!(", {}", level));
+    }
+    format!("{} ({})", entry.benchmark, versions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::utils::image::ImageInfo;
+    use crate::utils::test_dir::TestDir;
+    use crate::utils::test_entry::TestEntry;
+
+    #[test]
+    fn should_report_regressions_and_improvements_beyond_threshold() {
+        let baseline = vec![TestEntry::new("rust/a").time(100).memory(1000).build(), TestEntry::new("rust/b").time(100).memory(1000).build()];
+        let current = vec![TestEntry::new("rust/a").time(120).memory(1050).build(), TestEntry::new("rust/b").time(80).memory(1000).build()];
+
+        let report = compare(&baseline, &current, DEFAULT_REGRESSION_THRESHOLD);
+        assert_eq!(report.regressions, vec![MetricDelta {
+            benchmark: "rust/a (Rust 1.74)".to_string(),
+            metric: "time",
+            baseline: 100,
+            current: 120,
+            change_percent: 20.0,
+            images: ImageChange::Unknown,
+        }]);
+        assert_eq!(report.improvements.len(), 1);
+        assert_eq!(report.improvements[0].metric, "time");
+        assert!(report.has_regressions());
+    }
+
+    #[test]
+    fn should_compare_throughput_and_latency_of_web_benchmarks() {
+        let web = |rps: i32, latency: i32| TestEntry::new("rust/axum").category("web").time(15000).memory(1000).rps(rps).latency(latency).build();
+
+        // The time is the fixed duration of the load
+        let report = compare(&[web(2000, 500)], &[web(1000, 800)], DEFAULT_REGRESSION_THRESHOLD);
+        let regressions: Vec<(&str, f64)> = report.regressions.iter().map(|delta| (delta.metric, delta.change_percent)).collect();
+        assert_eq!(regressions, vec![("rps", -50.0), ("latency", 60.0)]);
+
+        let report = compare(&[web(1000, 500)], &[web(2000, 500)], DEFAULT_REGRESSION_THRESHOLD);
+        assert!(!report.has_regressions());
+        assert_eq!(report.improvements[0].metric, "rps");
+    }
+
+    #[test]
+    fn should_flag_changed_base_images() {
+        let with_image = |time_median: i64, digests: &[(&str, &str)]| {
+            let mut entry = TestEntry::new("rust/a").time(time_median).memory(1000).build();
+            entry.result.image = Some(ImageInfo {
+                id: Some(format!("sha256:{}", time_median)),
+                base_digests: digests.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            });
+            entry
+        };
+        let baseline = vec![with_image(100, &[("rust:1.74", "rust@sha256:a"), ("debian:bookworm", "debian@sha256:b")])];
+
+        let same = compare(&baseline, &[with_image(120, &[("rust:1.74", "rust@sha256:a"), ("debian:bookworm", "debian@sha256:b")])], DEFAULT_REGRESSION_THRESHOLD);
+        assert_eq!(same.regressions[0].images, ImageChange::Same);
+        assert!(same.regressions[0].to_string().ends_with("(+20.0%) [same base images]"));
+
+        let changed = compare(&baseline, &[with_image(120, &[("rust:1.74", "rust@sha256:c"), ("debian:bookworm", "debian@sha256:b")])], DEFAULT_REGRESSION_THRESHOLD);
+        assert_eq!(changed.regressions[0].images, ImageChange::Changed(vec!["rust:1.74".to_string()]));
+        assert!(changed.regressions[0].to_string().ends_with("[base image changed: rust:1.74]"));
+
+        let unknown = compare(&baseline, &[TestEntry::new("rust/a").time(120).memory(1000).build()], DEFAULT_REGRESSION_THRESHOLD);
+        assert_eq!(unknown.regressions[0].images, ImageChange::Unknown);
+    }
+
+    #[test]
+    fn should_warn_about_different_machines() {
+        let on_machine = |cpu_model: &str, logical_cores: Option<usize>| {
+            let mut entry = TestEntry::new("rust/a").time(100).memory(1000).build();
+            entry.machine = Some(MachineInfo {
+                cpu_model: Some(cpu_model.to_string()),
+                logical_cores,
+                total_memory: Some(16 * 1024 * 1024 * 1024),
+                ..Default::default()
+            });
+            entry
+        };
+
+        let report = compare(&[on_machine("Ryzen 7", Some(16))], &[on_machine("Xeon", None)], DEFAULT_REGRESSION_THRESHOLD);
+        assert_eq!(report.machine_changes, vec!["cpu_model: Ryzen 7 -> Xeon"]);
+        assert!(report.to_string().starts_with("Warning: The baseline ran on a different machine, the results are not comparable:\n - cpu_model: Ryzen 7 -> Xeon\n"));
+
+        let same = compare(&[on_machine("Ryzen 7", Some(16))], &[on_machine("Ryzen 7", Some(16))], DEFAULT_REGRESSION_THRESHOLD);
+        assert!(same.machine_changes.is_empty());
+        assert!(same.to_string().starts_with("Regressions (0):"));
+    }
+
+    #[test]
+    fn should_list_added_and_removed_benchmarks() {
+        let baseline = vec![TestEntry::new("rust/a").time(100).memory(1000).build(), TestEntry::new("rust/old").time(100).memory(1000).build()];
+        let current = vec![TestEntry::new("rust/a").time(100).memory(1000).build(), TestEntry::new("rust/new").time(100).memory(1000).build()];
+
+        let report = compare(&baseline, &current, DEFAULT_REGRESSION_THRESHOLD);
+        assert_eq!(report.added, vec!["rust/new (Rust 1.74)"]);
+        assert_eq!(report.removed, vec!["rust/old (Rust 1.74)"]);
+        assert!(!report.has_regressions());
+    }
+
+    #[test]
+    fn should_tell_benchmarks_of_other_categories_and_framework_versions_apart() {
+        let web = |framework_version: &str| TestEntry::new("rust/a").category("web").metadata("framework_version", framework_version).build();
+        assert!(same_benchmark(&web("0.7"), &web("0.7")));
+        assert!(!same_benchmark(&web("0.7"), &web("0.6")));
+        assert!(!same_benchmark(&web("0.7"), &TestEntry::new("rust/a").build()));
+        assert_eq!(display_name(&web("0.7")), "rust/a (Rust 1.74, framework 0.7)");
+
+        let report = compare(&[web("0.6")], &[web("0.7")], DEFAULT_REGRESSION_THRESHOLD);
+        assert_eq!(report.added, vec!["rust/a (Rust 1.74, framework 0.7)"]);
+        assert_eq!(report.removed, vec!["rust/a (Rust 1.74, framework 0.6)"]);
+    }
+
+    #[test]
+    fn should_load_combined_results_file() {
+        let dir = TestDir::new("baseline");
+        let path = dir.join("results.json");
+        let json = serde_json::json!({
+            "results": [serde_json::to_value(TestEntry::new("rust/a").time(100).memory(1000).build()).unwrap()],
+            "failures": [],
+        });
+        fs::write(&path, json.to_string()).unwrap();
+
+        let baseline = load_baseline(&path).unwrap();
+        assert_eq!(baseline.len(), 1);
+        assert_eq!(baseline[0].result.time_median, 100);
+    }
+}
@@ -0,0 +1,179 @@
+use std::cmp::Ordering;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use crate::output::json::ResultEntry;
+use crate::output::metric::Metric;
+use crate::utils::serialization;
+
+/// Time and memory of an entry relative to the baseline of its category (and sweep level),
+/// e.g. a `time_factor` of `2.3` means 2.3 times slower than the baseline.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RelativeScore {
+    /// `<benchmark> (<language> <version>)` of the baseline.
+    /// `None` if the configured baseline language has no result (e.g. it failed).
+    pub baseline: Option<String>,
+
+    /// Median time divided by the median time of the baseline, for web benchmarks the time per request (the inverse of `rps_median`).
+    #[serde(serialize_with = "serialization::significant_digits")]
+    pub time_factor: Option<f64>,
+
+    /// Median memory divided by the median memory of the baseline, `None` if either was not measured.
+    #[serde(serialize_with = "serialization::significant_digits")]
+    pub memory_factor: Option<f64>,
+}
+
+/// Assigns the relative scores to all entries.
+///
+/// Entries are compared within their category and sweep level, see `time` for what fastest means per category.
+/// The baseline is the fastest entry of `baseline_language`
+/// (case-insensitive) or the fastest entry overall if no language is given. Ties are broken by benchmark and version,
+/// so tied entries all get a factor of `1.00`.
+/// If `baseline_language` has no entry, all factors are `None` instead of being relative to another entry.
+pub fn assign(entries: &mut [ResultEntry], baseline_language: Option<&str>) {
+    let mut groups: IndexMap<(String, Option<String>), Vec<usize>> = IndexMap::new();
+    for (i, entry) in entries.iter().enumerate() {
+        groups.entry((entry.category.clone(), entry.sweep_level())).or_default().push(i);
+    }
+
+    for indices in groups.values() {
+        let baseline = indices.iter()
+            .map(|&i| &entries[i])
+            .filter(|entry| baseline_language.is_none_or(|language| entry.language.eq_ignore_ascii_case(language)))
+            .min_by(|a, b| {
+                compare_times(time(a), time(b))
+                    .then_with(|| a.benchmark.cmp(&b.benchmark))
+                    .then_with(|| a.version.cmp(&b.version))
+            })
+            .map(|entry| (display_name(entry), time(entry), entry.result.memory_median));
+
+        for &i in indices {
+            let entry = &entries[i];
+            let score = match &baseline {
+                Some((name, baseline_time, memory)) => RelativeScore {
+                    baseline: Some(name.clone()),
+                    time_factor: factor(time(entry), *baseline_time),
+                    memory_factor: factor(entry.result.memory_median.map(|memory| memory as f64), memory.map(|memory| memory as f64)),
+                },
+                None => RelativeScore { baseline: None, time_factor: None, memory_factor: None },
+            };
+            entries[i].relative = Some(score);
+        }
+    }
+}
+
+/// The time the factors compare: the median time of a round, for web benchmarks the time per request in seconds.
+/// The time of a web round is the fixed duration of the load, so its throughput (`rps_median`) is what differs.
+/// `None` for a web benchmark without throughput.
+fn time(entry: &ResultEntry) -> Option<f64> {
+    match entry.category.as_str() {
+        "web" => Metric::Rps.value(entry).filter(|rps| *rps > 0).map(|rps| 1.0 / rps as f64),
+        _ => Some(entry.result.time_median as f64),
+    }
+}
+
+/// Shorter times first, entries without time last.
+fn compare_times(a: Option<f64>, b: Option<f64>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// `None` if the baseline value is missing or not positive.
+/// Serialized with `serialization::SIGNIFICANT_DIGITS` to keep the output stable.
+fn factor(value: Option<f64>, baseline: Option<f64>) -> Option<f64> {
+    let (value, baseline) = (value?, baseline?);
+    if baseline <= 0.0 {
+        return None;
+    }
+    Some(value / baseline)
+}
+
+/// Formats the factor, e.g. `2.30x`, or `-` if unavailable.
+pub fn format_factor(factor: Option<f64>) -> String {
+    match factor {
+        Some(factor) => format!("{:.2}x", factor),
+        None => "-".to_string(),
+    }
+}
+
+fn display_name(entry: &ResultEntry) -> String {
+    format!("{} ({} {})", entry.benchmark, entry.language, entry.version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_entry::TestEntry;
+
+    fn scores(entries: &[ResultEntry]) -> Vec<(Option<f64>, Option<f64>)> {
+        entries.iter().map(|entry| {
+            let score = entry.relative.as_ref().unwrap();
+            (score.time_factor, score.memory_factor)
+        }).collect()
+    }
+
+    #[test]
+    fn should_compare_against_fastest_per_category() {
+        let mut entries = vec![
+            TestEntry::new("rust/a").version("1.0").time(1000).memory(1000).build(),
+            TestEntry::new("go/a").language("Go").version("1.0").time(2300).memory(500).build(),
+            TestEntry::new("go/b").category("web").language("Go").version("1.0").time(10000).memory(1000).build(),
+        ];
+        assign(&mut entries, None);
+
+        // Without throughput, the time of a web benchmark says nothing
+        assert_eq!(scores(&entries), vec![(Some(1.0), Some(1.0)), (Some(2.3), Some(0.5)), (None, Some(1.0))]);
+        assert_eq!(entries[1].relative.as_ref().unwrap().baseline, Some("rust/a (Rust 1.0)".to_string()));
+    }
+
+    #[test]
+    fn should_compare_web_benchmarks_by_throughput() {
+        let web = |benchmark: &str| TestEntry::new(benchmark).category("web").language("Go").version("1.0").time(10000);
+        let mut entries = vec![web("go/a").rps(2000).build(), web("go/b").rps(8000).build(), web("go/c").build()];
+        assign(&mut entries, None);
+
+        assert_eq!(entries[0].relative.as_ref().unwrap().baseline, Some("go/b (Go 1.0)".to_string()));
+        assert_eq!(scores(&entries), vec![(Some(4.0), None), (Some(1.0), None), (None, None)]);
+    }
+
+    #[test]
+    fn should_compare_against_configured_language() {
+        let mut entries = vec![
+            TestEntry::new("rust/a").version("1.0").time(1000).memory(1000).build(),
+            TestEntry::new("go/a").language("Go").version("1.0").time(2000).memory(500).build(),
+        ];
+        assign(&mut entries, Some("go"));
+
+        assert_eq!(scores(&entries), vec![(Some(0.5), Some(2.0)), (Some(1.0), Some(1.0))]);
+    }
+
+    #[test]
+    fn should_break_ties_deterministically() {
+        let mut entries = vec![
+            TestEntry::new("rust/b").version("1.0").time(1000).memory(2000).build(),
+            TestEntry::new("rust/a").version("1.0").time(1000).memory(1000).build(),
+        ];
+        assign(&mut entries, None);
+
+        assert_eq!(entries[0].relative.as_ref().unwrap().baseline, Some("rust/a (Rust 1.0)".to_string()));
+        assert_eq!(scores(&entries), vec![(Some(1.0), Some(2.0)), (Some(1.0), Some(1.0))]);
+    }
+
+    #[test]
+    fn should_leave_factors_empty_without_baseline() {
+        let mut entries = vec![TestEntry::new("rust/a").version("1.0").time(1000).memory(1000).build()];
+        assign(&mut entries, Some("Go"));
+
+        assert_eq!(entries[0].relative, Some(RelativeScore { baseline: None, time_factor: None, memory_factor: None }));
+    }
+
+    #[test]
+    fn should_not_divide_by_zero() {
+        assert_eq!(factor(Some(10.0), Some(0.0)), None);
+        assert_eq!(format_factor(None), "-");
+        assert_eq!(format_factor(Some(2.346)), "2.35x");
+    }
+}
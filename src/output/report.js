@@ -0,0 +1,182 @@
+(function () {
+    var SVG_NS = 'http://www.w3.org/2000/svg';
+    var rows = JSON.parse(document.getElementById('sharkbench-data').textContent);
+
+    function formatTime(ms) {
+        return ms < 1000 ? ms + ' ms' : (ms / 1000).toFixed(2) + ' s';
+    }
+
+    function formatBytes(bytes) {
+        var kb = bytes / 1024;
+        if (kb < 1024) return kb.toFixed(2) + ' KB';
+        var mb = kb / 1024;
+        if (mb < 1024) return mb.toFixed(2) + ' MB';
+        return (mb / 1024).toFixed(2) + ' GB';
+    }
+
+    function formatRps(rps) {
+        return rps.toLocaleString('en-US') + ' rps';
+    }
+
+    function formatLatency(us) {
+        return (us / 1000).toFixed(2) + ' ms';
+    }
+
+    function formatFactor(factor) {
+        return factor.toFixed(2) + 'x';
+    }
+
+    var columns = [
+        { key: 'category', label: 'Category' },
+        { key: 'benchmark', label: 'Benchmark' },
+        { key: 'language', label: 'Language' },
+        { key: 'version', label: 'Version' },
+        { key: 'time_median', label: 'Median time', format: formatTime },
+        { key: 'rps_median', label: 'Median rps', format: formatRps },
+        { key: 'latency_median', label: 'Median latency', format: formatLatency },
+        { key: 'memory_median', label: 'Median memory', format: formatBytes },
+        { key: 'memory_p99', label: 'P99 memory', format: formatBytes },
+        { key: 'self_reported_memory_median', label: 'Self-reported memory', format: formatBytes },
+        { key: 'relative_time', label: 'Relative time', format: formatFactor },
+        { key: 'relative_memory', label: 'Relative memory', format: formatFactor },
+        { key: 'error', label: 'Status' }
+    ];
+
+    function svg(name, attributes) {
+        var element = document.createElementNS(SVG_NS, name);
+        for (var key in attributes) element.setAttribute(key, attributes[key]);
+        return element;
+    }
+
+    function text(x, y, content, anchor) {
+        var element = svg('text', { x: x, y: y, 'text-anchor': anchor || 'start' });
+        element.textContent = content;
+        return element;
+    }
+
+    function renderTable(container) {
+        var table = document.createElement('table');
+        var head = table.createTHead().insertRow();
+        var body = table.createTBody();
+        // Initially by category and the metrics the entries are ranked by, see `metric::ranked`
+        var sortKey = 'rank';
+        var ascending = true;
+
+        function compare(a, b) {
+            var x = a[sortKey], y = b[sortKey];
+            // failed entries always go last
+            if (x === null || x === undefined) return 1;
+            if (y === null || y === undefined) return -1;
+            var result = typeof x === 'number' ? x - y : String(x).localeCompare(String(y));
+            return ascending ? result : -result;
+        }
+
+        function renderBody() {
+            body.innerHTML = '';
+            rows.slice().sort(compare).forEach(function (row) {
+                var tr = body.insertRow();
+                if (row.error) tr.className = 'failed';
+                columns.forEach(function (column) {
+                    var td = tr.insertCell();
+                    var value = row[column.key];
+                    if (column.key === 'error') {
+                        td.className = 'status';
+                        td.textContent = row.error ? 'failed: ' + row.error : 'ok';
+                    } else if (column.format) {
+                        td.className = 'numeric';
+                        td.textContent = value === null || value === undefined ? '-' : column.format(value);
+                    } else {
+                        td.textContent = value;
+                    }
+                });
+            });
+            Array.prototype.forEach.call(head.cells, function (th, i) {
+                th.className = columns[i].key === sortKey ? (ascending ? 'asc' : 'desc') : '';
+            });
+        }
+
+        columns.forEach(function (column) {
+            var th = document.createElement('th');
+            th.textContent = column.label;
+            th.addEventListener('click', function () {
+                ascending = sortKey === column.key ? !ascending : true;
+                sortKey = column.key;
+                renderBody();
+            });
+            head.appendChild(th);
+        });
+
+        renderBody();
+        container.appendChild(table);
+    }
+
+    function renderBarChart(container, title, entries, key, format) {
+        var barHeight = 18, labelWidth = 260, chartWidth = 320, valueWidth = 90;
+        var max = Math.max.apply(null, entries.map(function (row) { return row[key] || 0; }).concat([1]));
+        var height = entries.length * (barHeight + 4) + 24;
+        var chart = svg('svg', { 'class': 'chart', width: labelWidth + chartWidth + valueWidth, height: height });
+        chart.appendChild(text(0, 12, title));
+        entries.forEach(function (row, i) {
+            var y = 20 + i * (barHeight + 4);
+            var measured = row[key] !== null && row[key] !== undefined;
+            var width = row.error ? chartWidth : Math.max(1, ((row[key] || 0) / max) * chartWidth);
+            chart.appendChild(text(labelWidth - 6, y + 13, row.benchmark + ' ' + row.version, 'end'));
+            chart.appendChild(svg('rect', { 'class': row.error ? 'bar failed' : 'bar', x: labelWidth, y: y, width: width, height: barHeight }));
+            chart.appendChild(text(labelWidth + width + 4, y + 13, row.error ? 'failed' : (measured ? format(row[key]) : '-')));
+        });
+        container.appendChild(chart);
+    }
+
+    function renderScatter(container, row) {
+        var width = 320, height = 140, padding = 36;
+        var times = row.samples.map(function (sample) { return sample.time; });
+        var max = Math.max.apply(null, times.concat([1]));
+        var chart = svg('svg', { 'class': 'chart', width: width, height: height });
+        chart.appendChild(text(0, 12, row.benchmark + ' ' + row.version + ' (grey = warmup)'));
+        chart.appendChild(svg('line', { 'class': 'axis', x1: padding, y1: height - padding, x2: width, y2: height - padding }));
+        chart.appendChild(svg('line', { 'class': 'axis', x1: padding, y1: 20, x2: padding, y2: height - padding }));
+        chart.appendChild(text(padding - 4, 28, formatTime(max), 'end'));
+        chart.appendChild(text(padding - 4, height - padding, '0', 'end'));
+        row.samples.forEach(function (sample, i) {
+            var x = padding + (i + 0.5) * (width - padding) / row.samples.length;
+            var y = height - padding - (sample.time / max) * (height - padding - 20);
+            chart.appendChild(svg('circle', { 'class': sample.warmup ? 'warmup' : 'measured', cx: x, cy: y, r: 4 }));
+        });
+        chart.appendChild(text(padding + (width - padding) / 2, height - padding + 16, 'round', 'middle'));
+        container.appendChild(chart);
+    }
+
+    var root = document.getElementById('sharkbench-report');
+    renderTable(root);
+
+    var categories = [];
+    rows.forEach(function (row) {
+        if (categories.indexOf(row.category) < 0) categories.push(row.category);
+    });
+
+    categories.forEach(function (category) {
+        var entries = rows.filter(function (row) { return row.category === category; });
+        var heading = document.createElement('h2');
+        heading.textContent = category;
+        root.appendChild(heading);
+
+        var bars = document.createElement('div');
+        bars.className = 'charts';
+        columns.filter(function (column) { return ['time_median', 'rps_median', 'latency_median'].indexOf(column.key) >= 0; })
+            .filter(function (column) { return entries.some(function (row) { return row[column.key] !== null && row[column.key] !== undefined; }); })
+            .forEach(function (column) { renderBarChart(bars, column.label, entries, column.key, column.format); });
+        renderBarChart(bars, 'P99 memory', entries, 'memory_p99', formatBytes);
+        root.appendChild(bars);
+
+        var sampled = entries.filter(function (row) { return row.samples && row.samples.length > 0; });
+        if (sampled.length > 0) {
+            var scatterHeading = document.createElement('h3');
+            scatterHeading.textContent = 'Rounds';
+            root.appendChild(scatterHeading);
+            var scatters = document.createElement('div');
+            scatters.className = 'charts';
+            sampled.forEach(function (row) { renderScatter(scatters, row); });
+            root.appendChild(scatters);
+        }
+    });
+})();
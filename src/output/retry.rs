@@ -0,0 +1,75 @@
+use std::path::Path;
+use crate::output::json::LoadedResults;
+
+/// The benchmarks of a previous run to run again with `--retry-failed`.
+#[derive(Debug, Default, PartialEq)]
+pub struct RetrySelection {
+    /// `benchmark/<category>/<language>/<benchmark>` of the failed and skipped benchmarks, sorted.
+    pub retried: Vec<String>,
+
+    /// `<category>/<benchmark>` of the failed and skipped benchmarks whose directory was removed since.
+    /// They are reported and stay recorded as they were.
+    pub removed: Vec<String>,
+}
+
+/// Selects the failed benchmarks of `previous` and the skipped ones without a result in it (e.g. skipped by `--missing`)
+/// that still exist in `benchmark_dir`.
+pub fn select(previous: &LoadedResults, benchmark_dir: &Path) -> RetrySelection {
+    let failed = previous.failures.iter().map(|failure| (&failure.category, &failure.benchmark));
+    let skipped = previous.skipped.iter()
+        .filter(|skipped| !previous.results.iter().any(|entry| entry.category == skipped.category && entry.benchmark == skipped.benchmark))
+        .map(|skipped| (&skipped.category, &skipped.benchmark));
+
+    let mut selection = RetrySelection::default();
+    for (category, benchmark) in failed.chain(skipped) {
+        let dir = benchmark_dir.join(category).join(benchmark);
+        match dir.is_dir() {
+            true => selection.retried.push(dir.display().to_string()),
+            false => selection.removed.push(format!("{}/{}", category, benchmark)),
+        }
+    }
+    selection.retried.sort();
+    selection.retried.dedup();
+    selection.removed.sort();
+    selection.removed.dedup();
+    selection
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use super::*;
+    use crate::output::json::{FailedBenchmark, SkippedBenchmark};
+    use crate::utils::test_dir::TestDir;
+    use crate::utils::test_entry::TestEntry;
+
+    #[test]
+    fn should_select_failed_and_skipped_benchmarks() {
+        let benchmark_dir = TestDir::new("retry");
+        for dir in ["web/go/gin-1.9-go-1.21", "web/rust/axum-0.7-rust-1.74", "web/java/spring-3.2-java-21"] {
+            fs::create_dir_all(benchmark_dir.join(dir)).unwrap();
+        }
+        let failed = |benchmark: &str, version: Option<&str>| FailedBenchmark {
+            category: "web".to_string(),
+            benchmark: benchmark.to_string(),
+            version: version.map(str::to_string),
+            error: "Build failed".to_string(),
+            kind: None,
+        };
+        let skipped = |benchmark: &str| SkippedBenchmark { category: "web".to_string(), benchmark: benchmark.to_string() };
+        let previous = LoadedResults {
+            schema_version: 2,
+            results: vec![TestEntry::new("java/spring-3.2-java-21").category("web").language("Java").version("21").build()],
+            failures: vec![failed("rust/axum-0.7-rust-1.74", None), failed("rust/axum-0.7-rust-1.74", Some("1.75")), failed("zig/zap-0.1", None)],
+            // Spring has a result, so it was skipped because it already ran
+            skipped: vec![skipped("go/gin-1.9-go-1.21"), skipped("java/spring-3.2-java-21")],
+            run: None,
+        };
+
+        let dir = |benchmark: &str| benchmark_dir.join("web").join(benchmark).display().to_string();
+        assert_eq!(select(&previous, &benchmark_dir), RetrySelection {
+            retried: vec![dir("go/gin-1.9-go-1.21"), dir("rust/axum-0.7-rust-1.74")],
+            removed: vec!["web/zig/zap-0.1".to_string()],
+        });
+    }
+}
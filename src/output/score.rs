@@ -0,0 +1,398 @@
+use std::fs;
+use std::path::Path;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use crate::benchmark::benchmark::AdditionalData;
+use crate::output::json::ResultEntry;
+use crate::output::escape;
+use crate::utils::{glob, serialization, toml};
+
+/// Maps metrics to weights and benchmarks to groups to score every language per group, see `compute`.
+///
+/// Example:
+/// ```toml
+/// missing = "penalty"
+/// penalty = 10.0
+/// higher_is_better = ["rps_median"]
+///
+/// [weights]
+/// time_median = 1.0
+/// memory_median = 2.0
+///
+/// [groups]
+/// web = ["web/*"]
+/// rust = ["computation/rust/*", "web/rust/*"]
+/// ```
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ScoringConfig {
+    /// Weight of every metric: `time_median`, `time_p1`, `memory_median`, `memory_p99`, `self_reported_memory_median`,
+    /// `cold_start_ms`, `shutdown_ms` or a key of the additional data (e.g. `latency_p99`).
+    pub weights: IndexMap<String, f64>,
+
+    /// Metrics where a higher value is better (e.g. `rps_median`), all others are better when lower.
+    #[serde(default)]
+    pub higher_is_better: Vec<String>,
+
+    /// Patterns of `<category>/<benchmark>` (see `glob::matches`) by group.
+    /// A benchmark can be part of several groups. Without groups, every category is a group.
+    #[serde(default)]
+    pub groups: IndexMap<String, Vec<String>>,
+
+    #[serde(default)]
+    pub missing: Missing,
+
+    /// The factor assumed for missing metrics with `missing = "penalty"`, at least 1 (the best value).
+    pub penalty: Option<f64>,
+}
+
+/// What happens if an entry of a language does not have a metric (e.g. memory of an external service).
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Missing {
+    /// The language gets no score in the group.
+    #[default]
+    Exclude,
+
+    /// The metric counts with the factor `ScoringConfig::penalty`.
+    Penalty,
+}
+
+/// The composite scores of a run together with the formula they were calculated with.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Scores {
+    pub formula: String,
+    pub scores: Vec<CompositeScore>,
+}
+
+/// The score of a language in a group. `1.0` is the best value in every metric, higher is worse.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct CompositeScore {
+    pub group: String,
+    pub language: String,
+
+    /// `None` if the language was excluded.
+    #[serde(serialize_with = "serialization::significant_digits")]
+    pub score: Option<f64>,
+
+    /// Factor of every metric (geometric mean over the entries), empty if the language was excluded.
+    #[serde(serialize_with = "serialization::significant_digits_map")]
+    pub factors: IndexMap<String, f64>,
+
+    /// Number of entries of the language in the group.
+    pub entries: usize,
+
+    /// `<benchmark> (<version>): <metric>` of the metrics that counted with the penalty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub penalized: Vec<String>,
+
+    /// Why the language got no score, e.g. `rust/axum (1.74) has no memory_median`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excluded: Option<String>,
+}
+
+impl ScoringConfig {
+    /// Reads and validates the TOML file.
+    pub fn read(path: &Path) -> Result<ScoringConfig, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        ScoringConfig::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<ScoringConfig, String> {
+        let config: ScoringConfig = serde_json::from_value(toml::parse(content)?).map_err(|e| e.to_string())?;
+        if config.weights.is_empty() {
+            return Err("`weights` must contain at least one metric".to_string());
+        }
+        if let Some((metric, weight)) = config.weights.iter().find(|(_, weight)| !weight.is_finite() || **weight <= 0.0) {
+            return Err(format!("weight of `{}` must be positive, got {}", metric, weight));
+        }
+        match (config.missing, config.penalty) {
+            (Missing::Penalty, None) => return Err("`missing = \"penalty\"` requires `penalty`".to_string()),
+            (Missing::Penalty, Some(penalty)) if !penalty.is_finite() || penalty < 1.0 => {
+                return Err(format!("`penalty` must be at least 1, got {}", penalty));
+            }
+            (Missing::Exclude, Some(_)) => return Err("`penalty` requires `missing = \"penalty\"`".to_string()),
+            _ => {}
+        }
+        Ok(config)
+    }
+
+    /// The formula of the scores with the weights and the handling of missing metrics, recorded next to the scores.
+    pub fn formula(&self) -> String {
+        let weights: Vec<String> = self.weights.iter().map(|(metric, weight)| format!("{} = {}", metric, weight)).collect();
+        let mut formula = format!(
+            "score = exp(sum(w * ln(f)) / sum(w)) with w: {}; f: geometric mean over the entries of the language in the group \
+            of value / best value of the group in the same category and sweep level",
+            weights.join(", "),
+        );
+        if !self.higher_is_better.is_empty() {
+            formula.push_str(&format!(" (best / value for {})", self.higher_is_better.join(", ")));
+        }
+        formula.push_str(", values below 1 count as 1; missing metrics: ");
+        match (self.missing, self.penalty) {
+            (Missing::Penalty, Some(penalty)) => formula.push_str(&format!("f = {}", penalty)),
+            _ => formula.push_str("the language is excluded from the group"),
+        }
+        formula
+    }
+
+    /// The groups by name, every category if none are configured.
+    fn groups(&self, entries: &[ResultEntry]) -> IndexMap<String, Vec<String>> {
+        if !self.groups.is_empty() {
+            return self.groups.clone();
+        }
+        let mut groups: IndexMap<String, Vec<String>> = IndexMap::new();
+        for entry in entries {
+            groups.entry(entry.category.clone()).or_insert(vec![format!("{}/*", entry.category)]);
+        }
+        groups.sort_keys();
+        groups
+    }
+}
+
+/// Scores every language per group.
+///
+/// Every metric of an entry is divided by the best value of that metric among the entries of the group in the same category
+/// and sweep level, so `1.0` is the best. The factors of a language are the geometric means over its entries in the group,
+/// and the score is their weighted geometric mean. Missing metrics are handled as configured (see `Missing`), never as zero.
+///
+/// Scores are sorted by group and score (best first), excluded languages last.
+pub fn compute(config: &ScoringConfig, entries: &[ResultEntry]) -> Scores {
+    let mut scores: Vec<CompositeScore> = Vec::new();
+    for (group, patterns) in config.groups(entries) {
+        let members: Vec<&ResultEntry> = entries.iter()
+            .filter(|entry| {
+                let id = format!("{}/{}", entry.category, entry.benchmark);
+                patterns.iter().any(|pattern| glob::matches(pattern, &id))
+            })
+            .collect();
+
+        let mut languages: IndexMap<&str, Vec<&ResultEntry>> = IndexMap::new();
+        for entry in &members {
+            languages.entry(entry.language.as_str()).or_default().push(entry);
+        }
+
+        let mut group_scores: Vec<CompositeScore> = languages.into_iter()
+            .map(|(language, language_entries)| score_language(config, &group, language, &language_entries, &members))
+            .collect();
+        group_scores.sort_by(|a, b| match (a.score, b.score) {
+            (Some(a_score), Some(b_score)) => a_score.total_cmp(&b_score).then_with(|| a.language.cmp(&b.language)),
+            (a_score, b_score) => b_score.is_some().cmp(&a_score.is_some()).then_with(|| a.language.cmp(&b.language)),
+        });
+        scores.extend(group_scores);
+    }
+
+    Scores { formula: config.formula(), scores }
+}
+
+fn score_language(
+    config: &ScoringConfig,
+    group: &str,
+    language: &str,
+    entries: &[&ResultEntry],
+    members: &[&ResultEntry],
+) -> CompositeScore {
+    let mut score = CompositeScore {
+        group: group.to_string(),
+        language: language.to_string(),
+        score: None,
+        factors: IndexMap::new(),
+        entries: entries.len(),
+        penalized: Vec::new(),
+        excluded: None,
+    };
+
+    let mut log_factors: IndexMap<&str, f64> = IndexMap::new();
+    for entry in entries {
+        for metric in config.weights.keys() {
+            let higher_is_better = config.higher_is_better.contains(metric);
+            let factor = match (metric_value(entry, metric), best_value(members, entry, metric, higher_is_better)) {
+                (Some(value), Some(best)) if higher_is_better => best / value,
+                (Some(value), Some(best)) => value / best,
+                _ => match (config.missing, config.penalty) {
+                    (Missing::Penalty, Some(penalty)) => {
+                        score.penalized.push(format!("{} ({}): {}", entry.benchmark, entry.version, metric));
+                        penalty
+                    }
+                    _ => {
+                        score.excluded = Some(format!("{} ({}) has no {}", entry.benchmark, entry.version, metric));
+                        score.penalized.clear();
+                        return score;
+                    }
+                },
+            };
+            *log_factors.entry(metric).or_default() += factor.ln();
+        }
+    }
+
+    let count = entries.len() as f64;
+    score.factors = log_factors.iter().map(|(metric, sum)| (metric.to_string(), (sum / count).exp())).collect();
+    let total_weight: f64 = config.weights.values().sum();
+    let weighted: f64 = config.weights.iter().map(|(metric, weight)| weight * log_factors[metric.as_str()] / count).sum();
+    score.score = Some((weighted / total_weight).exp());
+    score
+}
+
+/// The best value of the metric among the entries of the group in the same category and sweep level as `entry`.
+fn best_value(members: &[&ResultEntry], entry: &ResultEntry, metric: &str, higher_is_better: bool) -> Option<f64> {
+    let sweep_level = entry.sweep_level();
+    let values = members.iter()
+        .filter(|member| member.category == entry.category && member.sweep_level() == sweep_level)
+        .filter_map(|member| metric_value(member, metric));
+    match higher_is_better {
+        true => values.reduce(f64::max),
+        false => values.reduce(f64::min),
+    }
+}
+
+/// The value of the metric, at least 1 so factors are always defined.
+fn metric_value(entry: &ResultEntry, metric: &str) -> Option<f64> {
+    let result = &entry.result;
+    let value = match metric {
+        "time_median" => Some(result.time_median),
+        "time_p1" => result.time_p1,
+        "memory_median" => result.memory_median,
+        "memory_p99" => result.memory_p99,
+        "self_reported_memory_median" => result.self_reported_memory_median,
+        "cold_start_ms" => result.cold_start_ms,
+        "shutdown_ms" => result.shutdown_ms,
+        _ => result.additional_data.get(metric).map(AdditionalData::as_i64),
+    };
+    value.map(|value| value.max(1) as f64)
+}
+
+/// Renders the scores as Markdown table per group, followed by the formula.
+pub fn to_markdown(scores: &Scores) -> String {
+    let metrics: Vec<&str> = scores.scores.iter()
+        .flat_map(|score| score.factors.keys().map(|metric| metric.as_str()))
+        .fold(Vec::new(), |mut metrics, metric| {
+            if !metrics.contains(&metric) {
+                metrics.push(metric);
+            }
+            metrics
+        });
+
+    let mut buffer = String::from("\n## Scores\n");
+    let mut groups: IndexMap<&str, Vec<&CompositeScore>> = IndexMap::new();
+    for score in &scores.scores {
+        groups.entry(score.group.as_str()).or_default().push(score);
+    }
+    for (group, group_scores) in groups {
+        buffer.push_str(&format!("\n### {}\n\n| Language | Score |", escape::markdown(group)));
+        for metric in &metrics {
+            buffer.push_str(&format!(" {} |", escape::markdown(metric)));
+        }
+        buffer.push_str(" Entries | Notes |\n|---|---:|");
+        buffer.push_str(&"---:|".repeat(metrics.len()));
+        buffer.push_str("---:|---|\n");
+
+        for score in group_scores {
+            buffer.push_str(&format!("| {} | {} |", escape::markdown(&score.language), format_score(score.score)));
+            for metric in &metrics {
+                buffer.push_str(&format!(" {} |", format_score(score.factors.get(*metric).copied())));
+            }
+            let notes = match (&score.excluded, score.penalized.is_empty()) {
+                (Some(reason), _) => format!("excluded: {}", reason),
+                (None, false) => format!("penalized: {}", score.penalized.join(", ")),
+                (None, true) => String::new(),
+            };
+            buffer.push_str(&format!(" {} | {} |\n", score.entries, escape::markdown(&notes)));
+        }
+    }
+    buffer.push_str(&format!("\n{}\n", scores.formula));
+    buffer
+}
+
+fn format_score(score: Option<f64>) -> String {
+    match score {
+        Some(score) => format!("{:.2}", score),
+        None => "-".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_entry::TestEntry;
+
+    fn config(missing: &str) -> ScoringConfig {
+        ScoringConfig::parse(&format!("{}\n[weights]\ntime_median = 1.0\nmemory_median = 3.0\n", missing)).unwrap()
+    }
+
+    fn entries() -> Vec<ResultEntry> {
+        vec![
+            TestEntry::new("rust/a").version("1.0").time(1000).memory(2000).build(),
+            TestEntry::new("go/a").language("Go").version("1.0").time(2000).memory(1000).build(),
+            TestEntry::new("java/a").language("Java").version("1.0").time(1000).build(),
+        ]
+    }
+
+    #[test]
+    fn should_weight_factors_geometrically() {
+        let scores = compute(&config(""), &entries());
+
+        let rust = &scores.scores[1];
+        assert_eq!((rust.group.as_str(), rust.language.as_str()), ("computation", "Rust"));
+        assert_eq!(rust.factors, IndexMap::from([("time_median".to_string(), 1.0), ("memory_median".to_string(), 2.0)]));
+        // exp((1 * ln(1) + 3 * ln(2)) / 4) = 2^(3/4)
+        assert!((rust.score.unwrap() - 2f64.powf(0.75)).abs() < 1e-9);
+
+        let go = &scores.scores[0];
+        assert!((go.score.unwrap() - 2f64.powf(0.25)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn should_exclude_languages_with_missing_metrics() {
+        let scores = compute(&config(""), &entries());
+
+        let java = scores.scores.last().unwrap();
+        assert_eq!(java.language, "Java");
+        assert_eq!(java.score, None);
+        assert_eq!(java.excluded, Some("java/a (1.0) has no memory_median".to_string()));
+        assert!(scores.formula.ends_with("missing metrics: the language is excluded from the group"));
+    }
+
+    #[test]
+    fn should_penalize_missing_metrics() {
+        let scores = compute(&config("missing = \"penalty\"\npenalty = 16.0"), &entries());
+
+        let java = scores.scores.iter().find(|score| score.language == "Java").unwrap();
+        assert_eq!(java.penalized, vec!["java/a (1.0): memory_median"]);
+        // exp((1 * ln(1) + 3 * ln(16)) / 4) = 16^(3/4)
+        assert!((java.score.unwrap() - 8.0).abs() < 1e-9);
+        assert!(scores.formula.ends_with("missing metrics: f = 16"));
+    }
+
+    #[test]
+    fn should_group_benchmarks_by_pattern() {
+        let config = ScoringConfig::parse("higher_is_better = [\"rps_median\"]\n[weights]\ntime_median = 1\n[groups]\nrust = [\"*/rust/*\"]\n").unwrap();
+        let mut entries = entries();
+        entries.push(TestEntry::new("rust/axum").category("web").version("1.0").time(100).build());
+
+        let scores = compute(&config, &entries);
+        assert_eq!(scores.scores.len(), 1);
+        assert_eq!(scores.scores[0].entries, 2);
+        assert_eq!(scores.scores[0].score, Some(1.0));
+        assert!(scores.formula.contains("(best / value for rps_median)"));
+    }
+
+    #[test]
+    fn should_reject_invalid_configs() {
+        assert_eq!(ScoringConfig::parse("[weights]").unwrap_err(), "`weights` must contain at least one metric");
+        assert_eq!(ScoringConfig::parse("[weights]\ntime_median = 0").unwrap_err(), "weight of `time_median` must be positive, got 0");
+        assert_eq!(
+            ScoringConfig::parse("missing = \"penalty\"\n[weights]\ntime_median = 1").unwrap_err(),
+            "`missing = \"penalty\"` requires `penalty`",
+        );
+        assert!(ScoringConfig::parse("unknown = 1\n[weights]\ntime_median = 1").is_err());
+    }
+
+    #[test]
+    fn should_render_scores_as_markdown() {
+        let markdown = to_markdown(&compute(&config(""), &entries()));
+
+        assert!(markdown.contains("\n### computation\n\n| Language | Score | memory_median | time_median | Entries | Notes |\n"));
+        assert!(markdown.contains("| Go | 1.19 | 1.00 | 2.00 | 1 |  |\n"));
+        assert!(markdown.contains("| Java | - | - | - | 1 | excluded: java/a (1.0) has no memory_median |\n"));
+    }
+}
@@ -0,0 +1,313 @@
+use std::io;
+use clap::ValueEnum;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use crate::output::json::ResultEntry;
+use crate::utils::table;
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS runs (
+    id TEXT PRIMARY KEY,
+    started_at TEXT NOT NULL,
+    git_commit TEXT NOT NULL,
+    machine TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS benchmarks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    run_id TEXT NOT NULL REFERENCES runs(id),
+    category TEXT NOT NULL,
+    benchmark TEXT NOT NULL,
+    language TEXT NOT NULL,
+    version TEXT NOT NULL,
+    host TEXT,
+    time_median INTEGER NOT NULL,
+    memory_median INTEGER,
+    memory_p99 INTEGER,
+    metadata TEXT NOT NULL,
+    additional_data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS samples (
+    benchmark_id INTEGER NOT NULL REFERENCES benchmarks(id),
+    round INTEGER NOT NULL,
+    warmup INTEGER NOT NULL,
+    time INTEGER NOT NULL,
+    memory_median INTEGER,
+    memory_p99 INTEGER
+);
+CREATE INDEX IF NOT EXISTS benchmarks_by_name ON benchmarks (benchmark, language);
+"#;
+
+/// Adds the columns that were introduced after the first version of `SCHEMA` to existing databases.
+const MIGRATIONS: &[(&str, &str)] = &[
+    ("host", "ALTER TABLE benchmarks ADD COLUMN host TEXT;"),
+];
+
+/// Columns of `query_last_results`.
+const LAST_RESULTS_HEADER: [&str; 7] = ["started_at", "git_commit", "language", "version", "time_median", "memory_median", "memory_p99"];
+
+/// Identifies a run of the suite within the history.
+pub struct RunInfo {
+    /// Start of the run in UTC, e.g. `2024-01-31T12:34:56Z`.
+    pub started_at: String,
+
+    /// Commit of the benchmark sources, e.g. `RunMetadata::benchmarks_commit`.
+    pub git_commit: String,
+
+    pub machine: String,
+}
+
+/// Results history of all runs in a SQLite database.
+///
+/// Every benchmark is written in its own transaction as soon as it completes,
+/// so an aborted run keeps the benchmarks finished so far.
+pub struct HistoryDatabase {
+    connection: Connection,
+    run_id: String,
+}
+
+impl HistoryDatabase {
+    /// Opens (or creates) the database at `path` and registers the run.
+    pub fn open(path: &str, run: &RunInfo) -> io::Result<HistoryDatabase> {
+        let connection = Connection::open(path).map_err(io::Error::other)?;
+        let run_id = format!("{}@{}", run.started_at, run.machine);
+        connection.execute_batch(SCHEMA).map_err(io::Error::other)?;
+        migrate(&connection).map_err(io::Error::other)?;
+        connection.execute(
+            "INSERT OR IGNORE INTO runs (id, started_at, git_commit, machine) VALUES (?1, ?2, ?3, ?4)",
+            params![run_id, run.started_at, run.git_commit, run.machine],
+        ).map_err(io::Error::other)?;
+        Ok(HistoryDatabase { connection, run_id })
+    }
+
+    /// Writes the entry together with all of its samples.
+    /// `host` is the host of `--hosts` that ran the benchmark, `None` for the machine of the run.
+    pub fn write(&self, entry: &ResultEntry, host: Option<&str>) -> io::Result<()> {
+        let metadata = serde_json::to_string(&entry.metadata)?;
+        let additional_data = serde_json::to_string(&entry.result.additional_data)?;
+        insert(&self.connection, &self.run_id, entry, host, &metadata, &additional_data).map_err(io::Error::other)
+    }
+}
+
+/// Adds the columns of `MIGRATIONS` that the `benchmarks` table does not have yet.
+fn migrate(connection: &Connection) -> rusqlite::Result<()> {
+    let columns: Vec<String> = connection.prepare("SELECT name FROM pragma_table_info('benchmarks')")?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    for (column, statement) in MIGRATIONS {
+        if !columns.iter().any(|name| name == column) {
+            connection.execute_batch(statement)?;
+        }
+    }
+    Ok(())
+}
+
+/// Inserts the entry and its samples in one transaction, which is rolled back if any statement fails.
+fn insert(connection: &Connection, run_id: &str, entry: &ResultEntry, host: Option<&str>, metadata: &str, additional_data: &str) -> rusqlite::Result<()> {
+    let transaction = connection.unchecked_transaction()?;
+    transaction.execute(
+        "INSERT INTO benchmarks (run_id, category, benchmark, language, version, host, time_median, memory_median, memory_p99, metadata, additional_data) \
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            run_id,
+            entry.category,
+            entry.benchmark,
+            entry.language,
+            entry.version,
+            host,
+            entry.result.time_median,
+            entry.result.memory_median,
+            entry.result.memory_p99,
+            metadata,
+            additional_data,
+        ],
+    )?;
+    let benchmark_id = transaction.last_insert_rowid();
+    {
+        let mut statement = transaction.prepare(
+            "INSERT INTO samples (benchmark_id, round, warmup, time, memory_median, memory_p99) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for (round, sample) in entry.result.samples.iter().enumerate() {
+            statement.execute(params![benchmark_id, round, sample.warmup, sample.time, sample.memory_median, sample.memory_p99])?;
+        }
+    }
+    transaction.commit()
+}
+
+/// A metric of the results history, see `query_history`.
+#[derive(ValueEnum, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HistoryMetric {
+    /// Median time in milliseconds.
+    Time,
+    /// Median memory in bytes.
+    Memory,
+    /// 99th percentile of the memory in bytes.
+    MemoryP99,
+    /// Median requests per second of web benchmarks.
+    Rps,
+    /// Median latency of web benchmarks in microseconds.
+    Latency,
+    /// 99th percentile of the latency of web benchmarks in microseconds.
+    LatencyP99,
+}
+
+impl HistoryMetric {
+    /// The value of the metric in a row of `benchmarks`, the web metrics are additional data.
+    fn expression(&self) -> &'static str {
+        match self {
+            HistoryMetric::Time => "b.time_median",
+            HistoryMetric::Memory => "b.memory_median",
+            HistoryMetric::MemoryP99 => "b.memory_p99",
+            HistoryMetric::Rps => "json_extract(b.additional_data, '$.rps_median.value')",
+            HistoryMetric::Latency => "json_extract(b.additional_data, '$.latency_median.value')",
+            HistoryMetric::LatencyP99 => "json_extract(b.additional_data, '$.latency_p99.value')",
+        }
+    }
+}
+
+/// A result of a benchmark in the history, with the run that measured it.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct HistoryPoint {
+    pub started_at: String,
+    pub git_commit: String,
+    pub machine: String,
+
+    /// The host of `--hosts` that ran the benchmark, `None` for the machine of the run.
+    pub host: Option<String>,
+    pub language: String,
+    pub version: String,
+
+    /// `None` if the metric was not measured (e.g. memory of an external service).
+    pub value: Option<i64>,
+}
+
+/// Returns the `metric` of the last `limit` results of the benchmark (optionally only of `language`), oldest first.
+pub fn query_history(path: &str, benchmark: &str, language: Option<&str>, metric: HistoryMetric, limit: usize) -> io::Result<Vec<HistoryPoint>> {
+    let connection = Connection::open(path).map_err(io::Error::other)?;
+    let query = format!(
+        "SELECT * FROM (\
+        SELECT r.started_at, r.git_commit, r.machine, b.host, b.language, b.version, {} AS value, b.id \
+        FROM benchmarks b JOIN runs r ON r.id = b.run_id \
+        WHERE b.benchmark = ?1 AND (?2 IS NULL OR b.language = ?2) \
+        ORDER BY r.started_at DESC, b.id DESC LIMIT ?3\
+        ) ORDER BY started_at, id",
+        metric.expression(),
+    );
+    let mut statement = connection.prepare(&query).map_err(io::Error::other)?;
+    let points = statement.query_map(params![benchmark, language, limit], |row| Ok(HistoryPoint {
+        started_at: row.get("started_at")?,
+        git_commit: row.get("git_commit")?,
+        machine: row.get("machine")?,
+        host: row.get("host")?,
+        language: row.get("language")?,
+        version: row.get("version")?,
+        value: row.get("value")?,
+    })).map_err(io::Error::other)?;
+    points.collect::<rusqlite::Result<_>>().map_err(io::Error::other)
+}
+
+/// Returns the last `limit` results of the benchmark (optionally only of `language`) as a table, `-` for values that were not measured.
+pub fn query_last_results(path: &str, benchmark: &str, language: Option<&str>, limit: usize) -> io::Result<String> {
+    let connection = Connection::open(path).map_err(io::Error::other)?;
+    let mut statement = connection.prepare(
+        "SELECT r.started_at, r.git_commit, b.language, b.version, b.time_median, b.memory_median, b.memory_p99 \
+        FROM benchmarks b JOIN runs r ON r.id = b.run_id \
+        WHERE b.benchmark = ?1 AND (?2 IS NULL OR b.language = ?2) \
+        ORDER BY r.started_at DESC, b.id DESC LIMIT ?3",
+    ).map_err(io::Error::other)?;
+    let rows = statement.query_map(params![benchmark, language, limit], |row| {
+        let mut cells: Vec<String> = (0..4).map(|i| row.get(i)).collect::<rusqlite::Result<_>>()?;
+        for i in 4..LAST_RESULTS_HEADER.len() {
+            let value: Option<i64> = row.get(i)?;
+            cells.push(value.map(|value| value.to_string()).unwrap_or("-".to_string()));
+        }
+        Ok(cells)
+    }).map_err(io::Error::other)?;
+    let rows: Vec<Vec<String>> = rows.collect::<rusqlite::Result<_>>().map_err(io::Error::other)?;
+    Ok(table::render(&LAST_RESULTS_HEADER.map(str::to_string), &rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmark::benchmark::RoundSample;
+    use crate::utils::test_dir::TestDir;
+    use crate::utils::test_entry::TestEntry;
+
+    fn run(started_at: &str) -> RunInfo {
+        RunInfo { started_at: started_at.to_string(), git_commit: "abc".to_string(), machine: "m1".to_string() }
+    }
+
+    #[test]
+    fn should_insert_entry_and_samples_in_one_transaction() {
+        let dir = TestDir::new("sqlite-insert");
+        let path = dir.join("history.db").to_str().unwrap().to_string();
+        let database = HistoryDatabase::open(&path, &run("2024-01-31T12:34:56Z")).unwrap();
+        let mut entry = TestEntry::new("rust/a").time(100).memory(2048).samples(vec![
+            RoundSample { warmup: true, time: 120, memory_median: Some(2000), memory_p99: Some(2100), ..Default::default() },
+            RoundSample { warmup: false, time: 100, ..Default::default() },
+        ]).build();
+        database.write(&entry, None).unwrap();
+        entry.result.time_median = 90;
+        database.write(&entry, Some("ssh://ci@bench-1")).unwrap();
+
+        let connection = Connection::open(&path).unwrap();
+        let samples: Vec<(i64, i64, bool, i64, Option<i64>)> = connection
+            .prepare("SELECT benchmark_id, round, warmup, time, memory_p99 FROM samples ORDER BY benchmark_id, round").unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(samples, vec![(1, 0, true, 120, Some(2100)), (1, 1, false, 100, None), (2, 0, true, 120, Some(2100)), (2, 1, false, 100, None)]);
+
+        // Opening again registers the run once and keeps the results
+        HistoryDatabase::open(&path, &run("2024-01-31T12:34:56Z")).unwrap();
+        let counts: (i64, i64) = connection.query_row("SELECT (SELECT count(*) FROM runs), (SELECT count(*) FROM benchmarks)", [], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        assert_eq!(counts, (1, 2));
+        let hosts: Vec<Option<String>> = query_history(&path, "rust/a", None, HistoryMetric::Time, 10).unwrap().into_iter().map(|point| point.host).collect();
+        assert_eq!(hosts, vec![None, Some("ssh://ci@bench-1".to_string())]);
+    }
+
+    #[test]
+    fn should_migrate_old_databases() {
+        let dir = TestDir::new("sqlite-migrate");
+        let path = dir.join("history.db").to_str().unwrap().to_string();
+        Connection::open(&path).unwrap().execute_batch(&SCHEMA.replace("    host TEXT,\n", "")).unwrap();
+
+        let database = HistoryDatabase::open(&path, &run("2024-01-31T12:34:56Z")).unwrap();
+        database.write(&TestEntry::new("rust/a").time(100).build(), None).unwrap();
+        assert_eq!(query_history(&path, "rust/a", None, HistoryMetric::Time, 10).unwrap()[0].host, None);
+    }
+
+    #[test]
+    fn should_query_history_of_metric_oldest_first() {
+        let dir = TestDir::new("sqlite-query");
+        let path = dir.join("history.db").to_str().unwrap().to_string();
+        for (started_at, memory_p99) in [("2024-01-01T00:00:00Z", Some(1024)), ("2024-01-02T00:00:00Z", None), ("2024-01-03T00:00:00Z", Some(4096))] {
+            let mut entry = TestEntry::new("rust/a").time(100).memory(2048).build();
+            entry.result.memory_p99 = memory_p99;
+            HistoryDatabase::open(&path, &run(started_at)).unwrap().write(&entry, None).unwrap();
+        }
+
+        let points = query_history(&path, "rust/a", Some("Rust"), HistoryMetric::MemoryP99, 2).unwrap();
+        assert_eq!(points, vec![
+            HistoryPoint {
+                started_at: "2024-01-02T00:00:00Z".to_string(),
+                git_commit: "abc".to_string(),
+                machine: "m1".to_string(),
+                host: None,
+                language: "Rust".to_string(),
+                version: "1.74".to_string(),
+                value: None,
+            },
+            HistoryPoint { started_at: "2024-01-03T00:00:00Z".to_string(), value: Some(4096), ..points[0].clone() },
+        ]);
+        assert_eq!(query_history(&path, "rust/a", Some("Go"), HistoryMetric::Time, 2).unwrap(), vec![]);
+
+        let web = TestEntry::new("rust/axum").time(100).rps(12345).data("latency_p99", 800).build();
+        HistoryDatabase::open(&path, &run("2024-01-04T00:00:00Z")).unwrap().write(&web, None).unwrap();
+        let value = |metric| query_history(&path, "rust/axum", None, metric, 1).unwrap()[0].value;
+        assert_eq!((value(HistoryMetric::Rps), value(HistoryMetric::LatencyP99), value(HistoryMetric::Latency)), (Some(12345), Some(800), None));
+
+        let table = query_last_results(&path, "rust/a", None, 1).unwrap();
+        assert_eq!(table.lines().nth(2).unwrap(), "2024-01-03T00:00:00Z  abc         Rust      1.74     100          2048           4096");
+    }
+}
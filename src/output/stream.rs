@@ -0,0 +1,174 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use indexmap::IndexMap;
+use serde::Serialize;
+use crate::benchmark::benchmark::{BenchmarkResult, RoundSample};
+use crate::utils::log;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Receives every event as JSON, returns `false` once it is not interested anymore (e.g. its channel closed).
+pub type Subscriber = Box<dyn Fn(&serde_json::Value) -> bool + Send>;
+
+/// Frontends of the run besides `--stream-json`, see `subscribe`.
+static SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+
+/// An event of the run, written as one JSON object per line with `--stream-json`.
+/// The `type` and the field names are stable, new fields may be added.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event<'a> {
+    SuiteStarted {
+        /// UTC, e.g. `2024-01-31T12:34:56Z`
+        started_at: String,
+    },
+    /// The benchmarks the run selected, in the order they run.
+    BenchmarksPlanned {
+        /// `<category>/<benchmark>`, e.g. `web/rust/axum-0.7-rust-1.74`.
+        benchmarks: Vec<String>,
+    },
+    BenchmarkStarted {
+        category: &'a str,
+        benchmark: &'a str,
+    },
+    IterationCompleted {
+        category: &'a str,
+        benchmark: &'a str,
+
+        /// 1-based position of the round, warmup rounds included.
+        round: usize,
+        sample: &'a RoundSample,
+    },
+    BenchmarkCompleted {
+        category: &'a str,
+        benchmark: &'a str,
+        language: &'a str,
+        version: &'a str,
+        metadata: &'a IndexMap<String, String>,
+        result: &'a BenchmarkResult,
+    },
+    BenchmarkFailed {
+        category: &'a str,
+        benchmark: &'a str,
+        version: Option<&'a str>,
+        error: &'a str,
+    },
+    BenchmarkSkipped {
+        category: &'a str,
+        benchmark: &'a str,
+    },
+    SuiteCompleted {
+        results: usize,
+        failures: usize,
+        skipped: usize,
+
+        /// Whether the run was aborted by an error outside a benchmark.
+        aborted: bool,
+    },
+}
+
+/// Writes all further events to stdout and moves the human-readable output to stderr.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+    log::to_stderr();
+}
+
+/// Passes all further events to `subscriber`, e.g. of a frontend like `--tui`.
+pub fn subscribe(subscriber: Subscriber) {
+    SUBSCRIBERS.lock().unwrap().push(subscriber);
+}
+
+/// Writes the event if `--stream-json` is enabled and passes it to the subscribers.
+pub fn emit(event: &Event) {
+    {
+        let mut subscribers = SUBSCRIBERS.lock().unwrap();
+        if !subscribers.is_empty() {
+            let value = serde_json::to_value(event).expect("Failed to serialize event");
+            subscribers.retain(|subscriber| subscriber(&value));
+        }
+    }
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let line = serde_json::to_string(event).expect("Failed to serialize event");
+    let mut stdout = std::io::stdout().lock();
+    // Flushed per event, as consumers read them live through a pipe
+    if let Err(e) = writeln!(stdout, "{}", line).and_then(|_| stdout.flush()) {
+        warnln!(" -> Failed to write event: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::benchmark::benchmark::AdditionalData;
+
+    #[test]
+    fn should_tag_events_with_type() {
+        assert_eq!(serde_json::to_value(Event::SuiteStarted { started_at: "2024-01-31T12:34:56Z".to_string() }).unwrap(), json!({
+            "type": "suite_started",
+            "started_at": "2024-01-31T12:34:56Z",
+        }));
+        assert_eq!(serde_json::to_value(Event::BenchmarkStarted { category: "web", benchmark: "rust/axum" }).unwrap(), json!({
+            "type": "benchmark_started",
+            "category": "web",
+            "benchmark": "rust/axum",
+        }));
+        assert_eq!(serde_json::to_value(Event::BenchmarkFailed { category: "web", benchmark: "rust/axum", version: None, error: "Too many errors" }).unwrap(), json!({
+            "type": "benchmark_failed",
+            "category": "web",
+            "benchmark": "rust/axum",
+            "version": null,
+            "error": "Too many errors",
+        }));
+        assert_eq!(serde_json::to_value(Event::BenchmarksPlanned { benchmarks: vec!["web/rust/axum".to_string()] }).unwrap(), json!({
+            "type": "benchmarks_planned",
+            "benchmarks": ["web/rust/axum"],
+        }));
+        assert_eq!(serde_json::to_value(Event::BenchmarkSkipped { category: "web", benchmark: "rust/axum" }).unwrap(), json!({
+            "type": "benchmark_skipped",
+            "category": "web",
+            "benchmark": "rust/axum",
+        }));
+        assert_eq!(serde_json::to_value(Event::SuiteCompleted { results: 2, failures: 1, skipped: 0, aborted: false }).unwrap(), json!({
+            "type": "suite_completed",
+            "results": 2,
+            "failures": 1,
+            "skipped": 0,
+            "aborted": false,
+        }));
+    }
+
+    #[test]
+    fn should_include_sample_and_result() {
+        let sample = RoundSample { time: 1500, memory_median: Some(1024), ..Default::default() };
+        let iteration = serde_json::to_value(Event::IterationCompleted { category: "computation", benchmark: "rust/rust-1.74", round: 3, sample: &sample }).unwrap();
+        assert_eq!(iteration["type"], "iteration_completed");
+        assert_eq!(iteration["round"], 3);
+        assert_eq!(iteration["sample"]["time"], 1500);
+        assert_eq!(iteration["sample"]["memory_median"], 1024);
+        assert_eq!(iteration["sample"]["warmup"], false);
+
+        let mut additional_data = IndexMap::new();
+        additional_data.insert("rps_median".to_string(), AdditionalData::Int(1234));
+        let result = BenchmarkResult { time_median: 1500, additional_data, ..Default::default() };
+        let metadata = IndexMap::from([("mode".to_string(), "Default".to_string())]);
+        let completed = serde_json::to_value(Event::BenchmarkCompleted {
+            category: "computation",
+            benchmark: "rust/rust-1.74",
+            language: "Rust",
+            version: "1.74",
+            metadata: &metadata,
+            result: &result,
+        }).unwrap();
+        assert_eq!(completed["type"], "benchmark_completed");
+        assert_eq!(completed["language"], "Rust");
+        assert_eq!(completed["metadata"]["mode"], "Default");
+        assert_eq!(completed["result"]["time_median"], 1500);
+        assert_eq!(completed["result"]["memory_median"], serde_json::Value::Null);
+        assert_eq!(completed["result"]["additional_data"]["rps_median"]["value"], 1234);
+    }
+}
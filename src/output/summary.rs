@@ -0,0 +1,162 @@
+use crate::output::json::{FailedBenchmark, ResultEntry, SkippedBenchmark};
+use crate::output::metric::{self, Metric};
+use crate::utils::format::SizeFormat;
+use crate::utils::style;
+
+/// Colors a line of the table, see `style`.
+type Paint = fn(&str) -> String;
+
+/// Errors are cut off after this many characters to keep the table readable.
+const MAX_ERROR_LENGTH: usize = 60;
+
+const HEADER: [&str; 8] = ["Benchmark", "Language", "Version", "Median", "Latency", "P99 memory", "Rounds", "Status"];
+
+/// Renders an aligned table of every benchmark of the run, sorted by benchmark and then by `metric::ranked`.
+/// The median is the time, for web benchmarks the requests per second (their rounds load the server for a fixed duration).
+pub fn to_summary_table(entries: &[ResultEntry], failures: &[FailedBenchmark], skipped: &[SkippedBenchmark]) -> String {
+    // (benchmark, entry for sorting, cells, color of the line)
+    let mut rows: Vec<(String, Option<&ResultEntry>, [String; 8], Paint)> = Vec::new();
+    for entry in entries {
+        let benchmark = format!("{}/{}", entry.category, entry.benchmark);
+        let median = metric::ranked(&entry.category)[0];
+        rows.push((benchmark.clone(), Some(entry), [
+            benchmark,
+            entry.language.clone(),
+            entry.version.clone(),
+            median.format(median.value(entry)),
+            Metric::Latency.format(Metric::Latency.value(entry)),
+            entry.result.memory_p99.bytes_to_string(),
+            entry.result.samples.iter().filter(|sample| !sample.warmup).count().to_string(),
+            match &entry.reused_from {
+                Some(reused_from) => format!("reused from {}", reused_from),
+                None => "ok".to_string(),
+            },
+        ], |line| style::success(line)));
+    }
+    for failure in failures {
+        let benchmark = format!("{}/{}", failure.category, failure.benchmark);
+        rows.push((benchmark.clone(), None, [
+            benchmark,
+            "-".to_string(),
+            failure.version.clone().unwrap_or("-".to_string()),
+            "-".to_string(),
+            "-".to_string(),
+            "-".to_string(),
+            "-".to_string(),
+            format!("failed: {}", truncate(&failure.error)),
+        ], |line| style::failure(line)));
+    }
+    for skipped in skipped {
+        let benchmark = format!("{}/{}", skipped.category, skipped.benchmark);
+        rows.push((benchmark.clone(), None, [
+            benchmark,
+            "-".to_string(),
+            "-".to_string(),
+            "-".to_string(),
+            "-".to_string(),
+            "-".to_string(),
+            "-".to_string(),
+            "skipped".to_string(),
+        ], |line| style::warning(line)));
+    }
+    rows.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| match (a.1, b.1) {
+        (Some(a), Some(b)) => metric::compare_ranked(a, b),
+        (a, b) => a.is_none().cmp(&b.is_none()),
+    }));
+
+    let mut widths: Vec<usize> = HEADER.iter().map(|h| h.chars().count()).collect();
+    for (_, _, cells, _) in &rows {
+        for (i, cell) in cells.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+
+    let mut buffer = String::new();
+    push_line(&mut buffer, &HEADER.map(|h| h.to_string()), &widths, str::to_string);
+    push_line(&mut buffer, &widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<String>>(), &widths, str::to_string);
+    for (_, _, cells, paint) in &rows {
+        push_line(&mut buffer, cells, &widths, *paint);
+    }
+    buffer
+}
+
+/// Example: `12 passed, 1 failed, 3 skipped`
+pub fn to_outcome_line(entries: &[ResultEntry], failures: &[FailedBenchmark], skipped: &[SkippedBenchmark]) -> String {
+    format!("{} passed, {} failed, {} skipped", entries.len(), failures.len(), skipped.len())
+}
+
+/// Numbers (median, latency, memory, rounds) are right-aligned, everything else left-aligned.
+/// The line is colored after it was aligned, the escape codes have no width.
+fn push_line(buffer: &mut String, cells: &[String], widths: &[usize], paint: Paint) {
+    let line: Vec<String> = cells.iter().enumerate().map(|(i, cell)| match i {
+        3..=6 => format!("{:>width$}", cell, width = widths[i]),
+        _ => format!("{:<width$}", cell, width = widths[i]),
+    }).collect();
+    buffer.push_str(&paint(line.join("  ").trim_end()));
+    buffer.push('\n');
+}
+
+/// Returns the first line of the error, cut off after `MAX_ERROR_LENGTH` characters.
+fn truncate(error: &str) -> String {
+    let first_line = error.lines().next().unwrap_or_default();
+    if first_line.chars().count() <= MAX_ERROR_LENGTH && !error.contains('\n') {
+        return first_line.to_string();
+    }
+    let mut truncated: String = first_line.chars().take(MAX_ERROR_LENGTH).collect();
+    truncated.push_str("...");
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmark::benchmark::RoundSample;
+    use crate::utils::test_entry::TestEntry;
+
+    #[test]
+    fn should_align_and_sort_by_benchmark_then_rank() {
+        // A warmup round and two measured rounds
+        let measured = |benchmark: &str, version: &str, time_median: i64| {
+            let sample = RoundSample { warmup: false, time: time_median, ..Default::default() };
+            TestEntry::new(benchmark).version(version).time(time_median).memory_p99(2 * 1024 * 1024)
+                .samples(vec![RoundSample { warmup: true, ..sample.clone() }, sample.clone(), sample])
+        };
+        let web = |version: &str, rps_median: i32| measured("rust/actix", version, 15000).category("web").rps(rps_median).latency(1500).build();
+        let table = to_summary_table(
+            &[
+                measured("rust/b", "1.74", 1500).build(),
+                measured("rust/a", "1.74", 999).build(),
+                web("1.74", 10000),
+                measured("rust/b", "1.40", 1200).build(),
+                web("1.40", 12000),
+            ],
+            &[FailedBenchmark {
+                category: "computation".to_string(),
+                benchmark: "rust/c".to_string(),
+                version: None,
+                error: "Too many errors".to_string(),
+                kind: None,
+            }],
+            &[SkippedBenchmark { category: "web".to_string(), benchmark: "rust/axum".to_string() }],
+        );
+
+        assert_eq!(table, "\
+Benchmark           Language  Version      Median  Latency  P99 memory  Rounds  Status
+------------------  --------  -------  ----------  -------  ----------  ------  -----------------------
+computation/rust/a  Rust      1.74         999 ms        -     2.00 MB       2  ok
+computation/rust/b  Rust      1.40         1.20 s        -     2.00 MB       2  ok
+computation/rust/b  Rust      1.74         1.50 s        -     2.00 MB       2  ok
+computation/rust/c  -         -                 -        -           -       -  failed: Too many errors
+web/rust/actix      Rust      1.40     12,000 rps  1.50 ms     2.00 MB       2  ok
+web/rust/actix      Rust      1.74     10,000 rps  1.50 ms     2.00 MB       2  ok
+web/rust/axum       -         -                 -        -           -       -  skipped
+");
+    }
+
+    #[test]
+    fn should_truncate_errors() {
+        assert_eq!(truncate("short"), "short");
+        assert_eq!(truncate("first\nsecond"), "first...");
+        assert_eq!(truncate(&"x".repeat(70)), format!("{}...", "x".repeat(60)));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::benchmark::benchmark::RoundSample;
+use crate::output::escape;
+use crate::output::json::ResultEntry;
+use crate::utils::format::{SizeFormat, TimeFormat};
+
+const WIDTH: f64 = 640.0;
+const PANEL_HEIGHT: f64 = 220.0;
+const TITLE_HEIGHT: f64 = 30.0;
+
+// Space for the tick labels around the plot area of a panel
+const MARGIN_LEFT: f64 = 80.0;
+const MARGIN_RIGHT: f64 = 20.0;
+const MARGIN_TOP: f64 = 30.0;
+const MARGIN_BOTTOM: f64 = 40.0;
+
+const TICK_COUNT: usize = 5;
+
+const STYLE: &str = "text{font:12px sans-serif;fill:#333}.title{font-weight:bold}.axis{stroke:#999}.grid{stroke:#eee}\
+.measured{fill:#1f77b4;stroke:#1f77b4}.warmup{fill:none;stroke:#999}.p99{fill:none;stroke:#ff7f0e;stroke-dasharray:4}\
+.line{fill:none;stroke:#1f77b4}";
+
+/// A point of a series: round (1-based, warmup rounds included), value and whether it is a warmup round.
+type Point = (usize, i64, bool);
+
+/// Renders the time of every round and (if measured) the memory of every round as a standalone SVG.
+/// Warmup rounds are drawn as hollow grey circles, measured rounds as filled circles connected by a line.
+/// Returns `None` if the entry has no rounds.
+pub fn to_svg_chart(entry: &ResultEntry) -> Option<String> {
+    let samples = &entry.result.samples;
+    if samples.is_empty() {
+        return None;
+    }
+
+    let times: Vec<Point> = points(samples, |sample| Some(sample.time));
+    let memory_median: Vec<Point> = points(samples, |sample| sample.memory_median);
+    let memory_p99: Vec<Point> = points(samples, |sample| sample.memory_p99);
+
+    let panel_count = if memory_median.is_empty() && memory_p99.is_empty() { 1 } else { 2 };
+    let height = TITLE_HEIGHT + PANEL_HEIGHT * panel_count as f64;
+    let mut buffer = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n<style>{}</style>\n",
+        WIDTH, height, WIDTH, height, STYLE,
+    );
+    buffer.push_str(&format!(
+        "<text class=\"title\" x=\"{}\" y=\"20\">{}</text>\n",
+        MARGIN_LEFT,
+        escape::xml(&format!("{} ({} {})", entry.benchmark, entry.language, entry.version)),
+    ));
+
+    let rounds = samples.len();
+    push_panel(&mut buffer, TITLE_HEIGHT, "Time per round", rounds, &[("measured", &times)], &|value| value.millis_to_string());
+    if panel_count > 1 {
+        push_panel(
+            &mut buffer,
+            TITLE_HEIGHT + PANEL_HEIGHT,
+            "Memory per round (median, p99 dashed)",
+            rounds,
+            &[("measured", &memory_median), ("p99", &memory_p99)],
+            &|value| value.bytes_to_string(),
+        );
+    }
+
+    buffer.push_str("</svg>\n");
+    Some(buffer)
+}
+
+fn points<F>(samples: &[RoundSample], value: F) -> Vec<Point>
+    where F: Fn(&RoundSample) -> Option<i64> {
+    samples.iter().enumerate()
+        .filter_map(|(i, sample)| value(sample).map(|value| (i + 1, value, sample.warmup)))
+        .collect()
+}
+
+/// Draws the axes with ticks and the series (by CSS class) into the panel at `top`.
+/// The y-axis starts at 0, so differences are not exaggerated.
+fn push_panel(
+    buffer: &mut String,
+    top: f64,
+    title: &str,
+    rounds: usize,
+    series: &[(&str, &Vec<Point>)],
+    format: &dyn Fn(i64) -> String,
+) {
+    let left = MARGIN_LEFT;
+    let right = WIDTH - MARGIN_RIGHT;
+    let plot_top = top + MARGIN_TOP;
+    let bottom = top + PANEL_HEIGHT - MARGIN_BOTTOM;
+
+    let max = series.iter().flat_map(|(_, points)| points.iter().map(|(_, value, _)| *value)).max().unwrap_or(0);
+    let y_ticks = nice_ticks(max as f64, TICK_COUNT);
+    let y_max = *y_ticks.last().unwrap();
+    let x = |round: usize| match rounds {
+        1 => (left + right) / 2.0,
+        _ => left + (round - 1) as f64 / (rounds - 1) as f64 * (right - left),
+    };
+    let y = |value: f64| bottom - value / y_max * (bottom - plot_top);
+
+    buffer.push_str(&format!("<g>\n<text class=\"title\" x=\"{}\" y=\"{:.1}\">{}</text>\n", left, plot_top - 10.0, escape::xml(title)));
+
+    for tick in &y_ticks {
+        buffer.push_str(&format!(
+            "<line class=\"grid\" x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"/>\n<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>\n",
+            left, y(*tick), right, y(*tick), left - 6.0, y(*tick) + 4.0, escape::xml(&format(*tick as i64)),
+        ));
+    }
+    for tick in nice_ticks(rounds as f64, TICK_COUNT).iter().map(|tick| *tick as usize).filter(|tick| (1..=rounds).contains(tick)) {
+        buffer.push_str(&format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>\n",
+            x(tick), bottom + 16.0, tick,
+        ));
+    }
+    buffer.push_str(&format!("<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">Round</text>\n", (left + right) / 2.0, bottom + 32.0));
+    buffer.push_str(&format!(
+        "<line class=\"axis\" x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"/>\n<line class=\"axis\" x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"/>\n",
+        left, plot_top, left, bottom, left, bottom, right, bottom,
+    ));
+
+    for (class, points) in series {
+        let measured: Vec<String> = points.iter()
+            .filter(|(_, _, warmup)| !warmup)
+            .map(|(round, value, _)| format!("{:.1},{:.1}", x(*round), y(*value as f64)))
+            .collect();
+        if measured.len() > 1 {
+            let line_class = if *class == "p99" { "p99" } else { "line" };
+            buffer.push_str(&format!("<polyline class=\"{}\" points=\"{}\"/>\n", line_class, measured.join(" ")));
+        }
+        for (round, value, warmup) in points.iter() {
+            let class = if *warmup { "warmup" } else { class };
+            buffer.push_str(&format!(
+                "<circle class=\"{}\" cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\"><title>#{}: {}</title></circle>\n",
+                class, x(*round), y(*value as f64), round, escape::xml(&format(*value)),
+            ));
+        }
+    }
+    buffer.push_str("</g>\n");
+}
+
+/// Returns about `count` evenly spaced ticks from 0 to at least `max` with a step of 1, 2 or 5 times a power of ten.
+fn nice_ticks(max: f64, count: usize) -> Vec<f64> {
+    if max <= 0.0 {
+        return vec![0.0, 1.0];
+    }
+
+    let raw_step = max / (count - 1).max(1) as f64;
+    let magnitude = 10f64.powf(raw_step.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0].iter()
+        .map(|factor| factor * magnitude)
+        .find(|step| *step >= raw_step)
+        .unwrap();
+
+    let mut ticks = vec![0.0];
+    while *ticks.last().unwrap() < max {
+        ticks.push(ticks.len() as f64 * step);
+    }
+    ticks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_entry::TestEntry;
+
+    #[test]
+    fn should_calculate_nice_ticks() {
+        assert_eq!(nice_ticks(1530.0, 5), vec![0.0, 500.0, 1000.0, 1500.0, 2000.0]);
+        assert_eq!(nice_ticks(10.0, 5), vec![0.0, 5.0, 10.0]);
+        assert_eq!(nice_ticks(3.0, 5), vec![0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(nice_ticks(0.0, 5), vec![0.0, 1.0]);
+    }
+
+    #[test]
+    fn should_distinguish_warmup_rounds() {
+        let svg = to_svg_chart(&TestEntry::new("rust/rust-1.74").time(1500).samples(vec![
+            RoundSample { warmup: true, time: 1800, ..Default::default() },
+            RoundSample { time: 1500, ..Default::default() },
+            RoundSample { time: 1530, ..Default::default() },
+        ]).build()).unwrap();
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"640\" height=\"250\""));
+        assert_eq!(svg.matches("<circle class=\"warmup\"").count(), 1);
+        assert_eq!(svg.matches("<circle class=\"measured\"").count(), 2);
+        assert!(svg.contains("<title>#1: 1.80 s</title>"));
+        assert!(svg.contains(">2.00 s</text>"));
+        assert!(!svg.contains("Memory per round"));
+    }
+
+    #[test]
+    fn should_add_memory_panel_if_measured() {
+        let svg = to_svg_chart(&TestEntry::new("rust/rust-1.74").time(1500).samples(vec![
+            RoundSample { time: 1500, memory_median: Some(1024 * 1024), memory_p99: Some(2 * 1024 * 1024), ..Default::default() },
+        ]).build()).unwrap();
+
+        assert!(svg.contains("height=\"470\""));
+        assert!(svg.contains("Memory per round"));
+        assert!(svg.contains("<circle class=\"p99\""));
+        assert!(svg.contains("<title>#1: 1.00 MB</title>"));
+    }
+
+    #[test]
+    fn should_skip_entries_without_rounds() {
+        assert_eq!(to_svg_chart(&TestEntry::new("rust/rust-1.74").time(1500).build()), None);
+    }
+}
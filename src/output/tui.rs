@@ -0,0 +1,465 @@
+use std::fs::File;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::{cursor, execute, terminal};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::symbols::bar;
+use ratatui::text::Line;
+use ratatui::widgets::{Paragraph, Sparkline};
+use ratatui::{Frame, Terminal};
+use serde_json::Value;
+use crate::output::stream;
+use crate::utils::format::{SizeFormat, TimeFormat};
+use crate::utils::log;
+
+/// How long the key reader waits for a key before it checks whether the TUI stopped.
+const KEY_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The log lines kept for the log pane, the log file (see `start`) has all of them.
+const MAX_LOG_LINES: usize = 1000;
+
+/// The bars of the sparkline, the fastest round gets the lowest bar instead of none.
+const SPARKS: bar::Set = bar::Set { empty: bar::ONE_EIGHTH, ..bar::NINE_LEVELS };
+
+/// Set by the keys, checked by the benchmark before every round (see `is_requested`).
+static SKIP: AtomicBool = AtomicBool::new(false);
+static QUIT: AtomicBool = AtomicBool::new(false);
+
+static TUI: Mutex<Option<Tui>> = Mutex::new(None);
+
+/// Whether the TUI is running, the key reader stops once it is not.
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// What the user asked for with the keys of the TUI.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Request {
+    /// Skip the current benchmark, it is recorded as skipped.
+    Skip,
+
+    /// Quit the run, the remaining benchmarks are not run.
+    Quit,
+}
+
+/// What the renderer receives from the run.
+enum Message {
+    Event(Value),
+    Log(String),
+    Memory(i64),
+
+    /// The terminal was resized.
+    Redraw,
+    Stop,
+}
+
+struct Tui {
+    sender: Sender<Message>,
+    renderer: JoinHandle<()>,
+    keys: JoinHandle<()>,
+}
+
+/// Takes over the terminal for `--tui`: the queue of the run, the rounds and memory of the current benchmark and the log.
+/// The run is followed through its events (see `stream::subscribe`) and its output (see `log::capture`),
+/// which is also written to `log_file` including the output of docker.
+/// Returns `false` if stdout is not an interactive terminal, the plain output is used then.
+pub fn start(log_file: &Path) -> bool {
+    if !io::stdout().is_terminal() {
+        return false;
+    }
+    let file = match File::create(log_file) {
+        Ok(file) => Some(file),
+        Err(e) => {
+            warnln!(" -> Warning: Could not create {}, the output is only shown: {}", log_file.display(), e);
+            None
+        }
+    };
+    // Keys without Enter, Ctrl+C is read as a key so the run can still end gracefully
+    if terminal::enable_raw_mode().is_err() {
+        return false;
+    }
+    // The alternate screen keeps the output before the run
+    let terminal = execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)
+        .and_then(|()| Terminal::new(CrosstermBackend::new(io::stdout())));
+    let Ok(terminal) = terminal else {
+        restore_terminal();
+        return false;
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let events = sender.clone();
+    stream::subscribe(Box::new(move |event| events.send(Message::Event(event.clone())).is_ok()));
+    let output = sender.clone();
+    log::capture(Box::new(move |text| {
+        if let Some(mut file) = file.as_ref() {
+            let _ = file.write_all(text.as_bytes());
+        }
+        let _ = output.send(Message::Log(text.to_string()));
+    }));
+    RUNNING.store(true, Ordering::Relaxed);
+    let resized = sender.clone();
+    let keys = thread::spawn(move || read_keys(resized));
+    let renderer = thread::spawn(move || render(receiver, terminal));
+    *TUI.lock().unwrap() = Some(Tui { sender, renderer, keys });
+    true
+}
+
+/// Gives the terminal back, does nothing if the TUI is not running.
+pub fn stop() {
+    let Some(tui) = TUI.lock().unwrap().take() else {
+        return;
+    };
+    RUNNING.store(false, Ordering::Relaxed);
+    let _ = tui.keys.join();
+    let _ = tui.sender.send(Message::Stop);
+    let _ = tui.renderer.join();
+    log::release();
+}
+
+/// Passes a memory sample of the container (see `DockerStatsReader`) to the TUI, if it is running.
+pub fn memory_sampled(bytes: i64) {
+    if let Some(tui) = TUI.lock().unwrap().as_ref() {
+        let _ = tui.sender.send(Message::Memory(bytes));
+    }
+}
+
+/// Whether the user asked to skip the current benchmark or to quit since the last `take_request`.
+/// Checked before every round, the benchmark stops measuring then.
+pub fn is_requested() -> bool {
+    SKIP.load(Ordering::Relaxed) || quit_requested()
+}
+
+/// What the user asked for, a skip only applies to the current benchmark, a quit to the rest of the run.
+pub fn take_request() -> Option<Request> {
+    match (quit_requested(), SKIP.swap(false, Ordering::Relaxed)) {
+        (true, _) => Some(Request::Quit),
+        (false, true) => Some(Request::Skip),
+        (false, false) => None,
+    }
+}
+
+/// Quits the run before the next round, when `q` is pressed.
+pub fn request_quit() {
+    QUIT.store(true, Ordering::Relaxed);
+}
+
+pub fn quit_requested() -> bool {
+    QUIT.load(Ordering::Relaxed)
+}
+
+/// Forgets a skip that arrived after the last round of the previous benchmark.
+pub fn reset_skip() {
+    SKIP.store(false, Ordering::Relaxed);
+}
+
+fn restore_terminal() {
+    let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+}
+
+/// Reads the keys until the TUI stops, a resize of the terminal redraws the screen.
+fn read_keys(resized: Sender<Message>) {
+    while RUNNING.load(Ordering::Relaxed) {
+        if !event::poll(KEY_POLL_INTERVAL).unwrap_or(false) {
+            continue;
+        }
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Char('s' | 'S') => {
+                    logln!(" -> Skipping the current benchmark after the current round");
+                    SKIP.store(true, Ordering::Relaxed);
+                }
+                KeyCode::Char('q' | 'Q') => {
+                    logln!(" -> Quitting after the current round");
+                    request_quit();
+                }
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    logln!(" -> Quitting after the current round");
+                    request_quit();
+                }
+                _ => {}
+            },
+            Ok(Event::Resize(_, _)) => {
+                let _ = resized.send(Message::Redraw);
+            }
+            Ok(_) => {}
+            Err(_) => return,
+        }
+    }
+}
+
+fn render<B: Backend>(receiver: Receiver<Message>, mut terminal: Terminal<B>) {
+    let mut state = State::default();
+    while let Ok(mut message) = receiver.recv() {
+        loop {
+            match message {
+                Message::Stop => {
+                    restore_terminal();
+                    // The log stays on the screen, like the plain output
+                    let mut stdout = io::stdout();
+                    for line in state.log.iter().chain(Some(&state.partial).filter(|partial| !partial.is_empty())) {
+                        let _ = writeln!(stdout, "{}", line);
+                    }
+                    let _ = stdout.flush();
+                    return;
+                }
+                Message::Event(event) => state.apply(&event),
+                Message::Log(text) => state.log(&text),
+                Message::Memory(bytes) => state.memory = Some(bytes),
+                Message::Redraw => {}
+            }
+            match receiver.try_recv() {
+                Ok(next) => message = next,
+                Err(_) => break,
+            }
+        }
+        // The size of the terminal is taken on every draw
+        let _ = terminal.draw(|frame| state.draw(frame));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Status {
+    Pending,
+    Running,
+    Ok,
+    Failed,
+    Skipped,
+}
+
+impl Status {
+    fn marker(&self) -> char {
+        match self {
+            Status::Pending => ' ',
+            Status::Running => '>',
+            Status::Ok => '✓',
+            Status::Failed => '✗',
+            Status::Skipped => '-',
+        }
+    }
+}
+
+/// What the TUI shows, built from the events and the output of the run.
+#[derive(Debug, Default)]
+struct State {
+    /// `<category>/<benchmark>` of the planned benchmarks, benchmarks that were not planned are added when they start.
+    queue: Vec<(String, Status)>,
+    current: Option<usize>,
+
+    /// Rounds of the current benchmark so far, warmup included.
+    rounds: usize,
+    warmup_rounds: usize,
+
+    /// Times of the measured rounds of the current benchmark.
+    times: Vec<i64>,
+
+    /// The last memory sample of the container.
+    memory: Option<i64>,
+
+    log: Vec<String>,
+
+    /// The output after the last line break.
+    partial: String,
+}
+
+impl State {
+    fn apply(&mut self, event: &Value) {
+        let key = format!("{}/{}", event["category"].as_str().unwrap_or_default(), event["benchmark"].as_str().unwrap_or_default());
+        match event["type"].as_str().unwrap_or_default() {
+            "benchmarks_planned" => {
+                self.queue = event["benchmarks"].as_array().into_iter().flatten()
+                    .filter_map(|benchmark| benchmark.as_str())
+                    .map(|benchmark| (benchmark.to_string(), Status::Pending))
+                    .collect();
+                self.current = None;
+            }
+            "benchmark_started" => {
+                self.finish_current();
+                let index = self.index(&key);
+                self.queue[index].1 = Status::Running;
+                self.current = Some(index);
+                self.rounds = 0;
+                self.warmup_rounds = 0;
+                self.times.clear();
+                self.memory = None;
+            }
+            "iteration_completed" => {
+                let sample = &event["sample"];
+                self.rounds = event["round"].as_u64().unwrap_or_default() as usize;
+                match sample["warmup"].as_bool().unwrap_or_default() {
+                    true => self.warmup_rounds += 1,
+                    false => self.times.extend(sample["time"].as_i64()),
+                }
+            }
+            "benchmark_failed" => {
+                let index = self.index(&key);
+                self.queue[index].1 = Status::Failed;
+            }
+            "benchmark_skipped" => {
+                let index = self.index(&key);
+                self.queue[index].1 = Status::Skipped;
+            }
+            "suite_completed" => self.finish_current(),
+            _ => {}
+        }
+    }
+
+    /// The current benchmark ended without failing or being skipped.
+    fn finish_current(&mut self) {
+        if let Some((_, status @ Status::Running)) = self.current.and_then(|index| self.queue.get_mut(index)) {
+            *status = Status::Ok;
+        }
+    }
+
+    fn index(&mut self, key: &str) -> usize {
+        match self.queue.iter().position(|(benchmark, _)| benchmark == key) {
+            Some(index) => index,
+            None => {
+                self.queue.push((key.to_string(), Status::Pending));
+                self.queue.len() - 1
+            }
+        }
+    }
+
+    fn log(&mut self, text: &str) {
+        for c in text.chars() {
+            match c {
+                '\n' => self.log.push(std::mem::take(&mut self.partial)),
+                '\r' => {}
+                c => self.partial.push(c),
+            }
+        }
+        let excess = self.log.len().saturating_sub(MAX_LOG_LINES);
+        self.log.drain(..excess);
+    }
+
+    fn count(&self, status: Status) -> usize {
+        self.queue.iter().filter(|(_, current)| *current == status).count()
+    }
+
+    /// The screen: the header, the queue around the current benchmark, the current benchmark and the end of the log.
+    fn draw(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let queue_lines = (area.height as usize / 3).max(1).min(self.queue.len());
+        let current = self.current.and_then(|index| self.queue.get(index));
+        let [header, queue, current_area, log] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Length(queue_lines as u16 + 1),
+            Constraint::Length(if current.is_some() { 4 } else { 0 }),
+            Constraint::Min(0),
+        ]).areas(area);
+
+        frame.render_widget(Paragraph::new(format!(
+            "sharkbench  {}/{} done  {} failed  {} skipped    [s] skip  [q] quit",
+            self.count(Status::Ok) + self.count(Status::Failed) + self.count(Status::Skipped),
+            self.queue.len(),
+            self.count(Status::Failed),
+            self.count(Status::Skipped),
+        )), header);
+
+        let first = self.current.unwrap_or_default().saturating_sub(queue_lines / 2).min(self.queue.len() - queue_lines);
+        let queue_text: Vec<Line> = self.queue[first..first + queue_lines].iter()
+            .map(|(benchmark, status)| Line::from(format!(" {} {}", status.marker(), benchmark)))
+            .collect();
+        frame.render_widget(Paragraph::new(queue_text), queue);
+
+        if let Some((benchmark, _)) = current {
+            frame.render_widget(Paragraph::new(vec![
+                Line::from(format!("Current: {}", benchmark)),
+                Line::from(format!(
+                    "  Rounds: {} ({} warmup)  Last: {}  Memory: {}",
+                    self.rounds,
+                    self.warmup_rounds,
+                    self.times.last().map(|time| time.millis_to_string()).unwrap_or("-".to_string()),
+                    self.memory.bytes_to_string(),
+                )),
+            ]), current_area);
+            let sparkline_area = Rect { x: current_area.x + 2, y: current_area.y + 2, width: current_area.width.saturating_sub(2), height: 1 };
+            frame.render_widget(sparkline(&self.times, sparkline_area.width as usize), sparkline_area.intersection(area));
+        }
+
+        let log_lines: Vec<&String> = self.log.iter().chain(Some(&self.partial).filter(|partial| !partial.is_empty())).collect();
+        let shown = &log_lines[log_lines.len().saturating_sub(log.height as usize)..];
+        frame.render_widget(Paragraph::new(shown.iter().map(|line| Line::from(line.as_str())).collect::<Vec<Line>>()), log);
+    }
+}
+
+/// One bar per value of the last `width` values, scaled between the smallest and the largest value.
+fn sparkline(values: &[i64], width: usize) -> Sparkline<'static> {
+    let values = &values[values.len().saturating_sub(width)..];
+    let min = values.iter().min().copied().unwrap_or_default();
+    let max = values.iter().max().copied().unwrap_or_default();
+    Sparkline::default()
+        .data(values.iter().map(|value| (value - min) as u64).collect::<Vec<u64>>())
+        .max((max - min).max(1) as u64)
+        .bar_set(SPARKS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use serde_json::json;
+
+    /// The rows of the screen without trailing spaces, the empty rows at the end are left out.
+    fn draw(state: &State, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| state.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let mut rows: Vec<String> = (0..height)
+            .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect::<String>().trim_end().to_string())
+            .collect();
+        while rows.last().is_some_and(|row| row.is_empty()) {
+            rows.pop();
+        }
+        rows
+    }
+
+    #[test]
+    fn should_scale_sparkline() {
+        let state = |times: Vec<i64>| State { queue: vec![("web/go/gin".to_string(), Status::Running)], current: Some(0), times, ..Default::default() };
+        assert_eq!(draw(&state(vec![5, 5]), 20, 8)[6], "  ▁▁");
+        assert_eq!(draw(&state(vec![100, 450, 100, 800]), 20, 8)[6], "  ▁▄▁█");
+        assert_eq!(draw(&state(vec![100, 200, 150, 800]), 5, 8)[6], "  ▁▁█");
+    }
+
+    #[test]
+    fn should_follow_events_of_run() {
+        let mut state = State::default();
+        state.apply(&json!({"type": "benchmarks_planned", "benchmarks": ["computation/rust/rust-1.74", "web/go/gin", "web/rust/axum"]}));
+        state.apply(&json!({"type": "benchmark_started", "category": "computation", "benchmark": "rust/rust-1.74"}));
+        state.apply(&json!({"type": "benchmark_started", "category": "web", "benchmark": "go/gin"}));
+        state.apply(&json!({"type": "iteration_completed", "category": "web", "benchmark": "go/gin", "round": 1, "sample": {"warmup": true, "time": 900}}));
+        state.apply(&json!({"type": "iteration_completed", "category": "web", "benchmark": "go/gin", "round": 2, "sample": {"warmup": false, "time": 1200}}));
+        state.memory = Some(2 * 1024 * 1024);
+        state.log(" -> Running round 3");
+        state.log("\n -> Waiting");
+
+        assert_eq!(draw(&state, 70, 20), vec![
+            "sharkbench  1/3 done  0 failed  0 skipped    [s] skip  [q] quit",
+            "",
+            " ✓ computation/rust/rust-1.74",
+            " > web/go/gin",
+            "   web/rust/axum",
+            "",
+            "Current: web/go/gin",
+            "  Rounds: 2 (1 warmup)  Last: 1.20 s  Memory: 2.00 MB",
+            "  ▁",
+            "",
+            " -> Running round 3",
+            " -> Waiting",
+        ]);
+
+        state.apply(&json!({"type": "benchmark_skipped", "category": "web", "benchmark": "go/gin"}));
+        state.apply(&json!({"type": "benchmark_started", "category": "web", "benchmark": "rust/axum"}));
+        state.apply(&json!({"type": "benchmark_failed", "category": "web", "benchmark": "rust/axum", "error": "Too many errors"}));
+        state.apply(&json!({"type": "suite_completed"}));
+        let statuses: Vec<Status> = state.queue.iter().map(|(_, status)| *status).collect();
+        assert_eq!(statuses, vec![Status::Ok, Status::Skipped, Status::Failed]);
+    }
+}
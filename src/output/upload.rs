@@ -0,0 +1,170 @@
+use std::thread;
+use std::time::Duration;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+/// Environment variable with the bearer token of the collection API.
+pub const TOKEN_ENV: &str = "SHARKBENCH_UPLOAD_TOKEN";
+
+/// Written into the output directory after a successful upload.
+pub const UPLOAD_FILE: &str = "upload.json";
+
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Attempts per upload, only server errors (5xx) are retried.
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Uploads the combined results of a run to a collection API.
+///
+/// The payload is POSTed as JSON, the server answers with the id it assigned to the run (`{"run_id": "..."}`).
+pub struct Uploader {
+    url: String,
+    token: Option<String>,
+}
+
+/// The server-assigned id of an uploaded run, recorded in `UPLOAD_FILE`.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UploadRecord {
+    pub run_id: String,
+    pub url: String,
+}
+
+#[derive(Deserialize)]
+struct UploadResponse {
+    run_id: serde_json::Value,
+}
+
+/// Prepares the combined results (`results.json`) for the upload.
+/// The hostnames of the machine blocks are removed unless `allow_hostname` is set.
+pub fn to_payload(combined_json: &str, allow_hostname: bool) -> serde_json::Result<String> {
+    let mut payload: serde_json::Value = serde_json::from_str(combined_json)?;
+    if !allow_hostname {
+        remove_hostname(&mut payload);
+        if let Some(results) = payload.get_mut("results").and_then(|results| results.as_array_mut()) {
+            results.iter_mut().for_each(remove_hostname);
+        }
+    }
+    serde_json::to_string(&payload)
+}
+
+fn remove_hostname(value: &mut serde_json::Value) {
+    if let Some(machine) = value.get_mut("machine").and_then(|machine| machine.as_object_mut()) {
+        machine.remove("hostname");
+    }
+}
+
+impl Uploader {
+    /// Reads the token from `TOKEN_ENV`. Without a token, the request is sent unauthenticated.
+    pub fn from_env(url: &str) -> Uploader {
+        Uploader {
+            url: url.to_string(),
+            token: std::env::var(TOKEN_ENV).ok().filter(|token| !token.is_empty()),
+        }
+    }
+
+    /// Prints what `upload` would send.
+    pub fn dry_run(&self, payload: &str) {
+        logln!(
+            " -> Would upload {} bytes to {} ({})",
+            payload.len(),
+            self.url,
+            match self.token {
+                Some(_) => format!("with the token of ${}", TOKEN_ENV),
+                None => format!("unauthenticated, ${} is not set", TOKEN_ENV),
+            },
+        );
+        logln!("{}", payload);
+    }
+
+    /// Uploads the payload and returns the run id assigned by the server.
+    /// Server errors are retried with an exponential backoff, all other errors fail immediately.
+    pub fn upload(&self, payload: &str) -> Result<UploadRecord, String> {
+        let mut attempt = 1;
+        loop {
+            match self.try_upload(payload) {
+                Ok(run_id) => return Ok(UploadRecord { run_id, url: self.url.clone() }),
+                Err((e, transient)) if transient && attempt < MAX_ATTEMPTS => {
+                    let backoff = Duration::from_secs(1 << (attempt - 1));
+                    warnln!(" -> Failed to upload (attempt {}/{}): {}, retrying in {}s", attempt, MAX_ATTEMPTS, e, backoff.as_secs());
+                    thread::sleep(backoff);
+                    attempt += 1;
+                }
+                Err((e, _)) => return Err(e),
+            }
+        }
+    }
+
+    /// Returns the run id, or the error and whether it is worth retrying.
+    fn try_upload(&self, payload: &str) -> Result<String, (String, bool)> {
+        let mut request = reqwest::blocking::Client::new()
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .body(payload.to_string())
+            .timeout(UPLOAD_TIMEOUT);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().map_err(|e| (e.to_string(), false))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(status_error(status, payload.len()));
+        }
+        let body = response.text().map_err(|e| (e.to_string(), false))?;
+        parse_run_id(&body).map_err(|e| (e, false))
+    }
+}
+
+/// Returns the error of an unsuccessful status and whether it is worth retrying.
+fn status_error(status: StatusCode, payload_length: usize) -> (String, bool) {
+    match status {
+        StatusCode::UNAUTHORIZED => (format!("Unauthorized, check the token in ${}", TOKEN_ENV), false),
+        StatusCode::PAYLOAD_TOO_LARGE => (format!("The results ({} bytes) exceed the size limit of the server", payload_length), false),
+        status if status.is_server_error() => (format!("Server error {}", status), true),
+        status => (format!("Unexpected status {}", status), false),
+    }
+}
+
+fn parse_run_id(body: &str) -> Result<String, String> {
+    let response: UploadResponse = serde_json::from_str(body).map_err(|e| format!("Invalid response {:?}: {}", body, e))?;
+    match response.run_id {
+        serde_json::Value::String(run_id) if !run_id.is_empty() => Ok(run_id),
+        serde_json::Value::Number(run_id) => Ok(run_id.to_string()),
+        run_id => Err(format!("Invalid run id {}", run_id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_remove_hostnames_unless_allowed() {
+        let combined = r#"{"machine":{"hostname":"ci-1","os":"Linux"},"results":[{"machine":{"hostname":"ci-1"}},{"benchmark":"rust/a"}]}"#;
+
+        let payload: serde_json::Value = serde_json::from_str(&to_payload(combined, false).unwrap()).unwrap();
+        assert_eq!(payload["machine"], serde_json::json!({"os": "Linux"}));
+        assert_eq!(payload["results"][0]["machine"], serde_json::json!({}));
+        assert_eq!(payload["results"][1]["benchmark"], "rust/a");
+
+        let payload: serde_json::Value = serde_json::from_str(&to_payload(combined, true).unwrap()).unwrap();
+        assert_eq!(payload["machine"]["hostname"], "ci-1");
+    }
+
+    #[test]
+    fn should_only_retry_server_errors() {
+        assert!(status_error(StatusCode::UNAUTHORIZED, 10).0.contains(TOKEN_ENV));
+        assert!(!status_error(StatusCode::UNAUTHORIZED, 10).1);
+        assert_eq!(status_error(StatusCode::PAYLOAD_TOO_LARGE, 10), ("The results (10 bytes) exceed the size limit of the server".to_string(), false));
+        assert!(status_error(StatusCode::BAD_GATEWAY, 10).1);
+        assert!(!status_error(StatusCode::BAD_REQUEST, 10).1);
+    }
+
+    #[test]
+    fn should_parse_run_id() {
+        assert_eq!(parse_run_id(r#"{"run_id":"abc"}"#), Ok("abc".to_string()));
+        assert_eq!(parse_run_id(r#"{"run_id":42}"#), Ok("42".to_string()));
+        assert!(parse_run_id(r#"{"run_id":""}"#).is_err());
+        assert!(parse_run_id("<html>").is_err());
+    }
+}
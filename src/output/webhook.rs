@@ -0,0 +1,174 @@
+use std::error::Error;
+use std::thread;
+use std::time::Duration;
+use serde::Serialize;
+use crate::output::regression::RegressionReport;
+use crate::utils::format::format_duration;
+
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Attempts per notification, transient failures (connection errors, 429 and 5xx) are retried.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Only the largest regressions are part of the notification to keep the message short.
+const MAX_REGRESSIONS: usize = 5;
+
+/// Notifies a webhook (e.g. of Slack or Mattermost) when the suite has finished.
+pub struct Webhook {
+    url: String,
+}
+
+/// The payload posted to the webhook.
+/// `text` is shown by Slack-compatible webhooks, the remaining fields are meant for other consumers.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Notification {
+    pub text: String,
+
+    /// Whether the run was cut short by an unexpected error.
+    pub aborted: bool,
+    pub duration_seconds: u64,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub skipped: usize,
+
+    /// The largest regressions against the baseline, `None` without `--baseline`.
+    pub regressions: Option<Vec<String>>,
+
+    /// Path to the HTML report.
+    pub report: String,
+}
+
+impl Notification {
+    pub fn new(
+        aborted: bool,
+        duration: Duration,
+        (succeeded, failed, skipped): (usize, usize, usize),
+        regressions: Option<&RegressionReport>,
+        report: &str,
+    ) -> Notification {
+        let regressions = regressions.map(|report| {
+            let mut deltas: Vec<_> = report.regressions.iter().collect();
+            deltas.sort_by(|a, b| b.change_percent.total_cmp(&a.change_percent));
+            deltas.iter().take(MAX_REGRESSIONS).map(|delta| delta.to_string()).collect::<Vec<String>>()
+        });
+
+        let mut text = format!(
+            "Sharkbench {} after {}: {} succeeded, {} failed, {} skipped.",
+            if aborted { "aborted" } else { "finished" },
+            format_duration(duration),
+            succeeded,
+            failed,
+            skipped,
+        );
+        if let Some(regressions) = &regressions {
+            match regressions.is_empty() {
+                true => text.push_str("\nNo regressions."),
+                false => {
+                    text.push_str("\nRegressions:");
+                    for regression in regressions {
+                        text.push_str(&format!("\n- {}", regression));
+                    }
+                }
+            }
+        }
+        text.push_str(&format!("\nReport: {}", report));
+
+        Notification {
+            text,
+            aborted,
+            duration_seconds: duration.as_secs(),
+            succeeded,
+            failed,
+            skipped,
+            regressions,
+            report: report.to_string(),
+        }
+    }
+}
+
+impl Webhook {
+    pub fn new(url: &str) -> Webhook {
+        Webhook { url: url.to_string() }
+    }
+
+    /// Posts the notification.
+    /// Failures are only logged, they never change the outcome of the run.
+    pub fn notify(&self, notification: &Notification) {
+        for attempt in 1..=MAX_ATTEMPTS {
+            match self.try_notify(notification) {
+                Ok(()) => {
+                    logln!(" -> Notified {}", self.url);
+                    return;
+                }
+                Err((e, transient)) => {
+                    warnln!(" -> Failed to notify {} (attempt {}/{}): {}", self.url, attempt, MAX_ATTEMPTS, e);
+                    if !transient {
+                        return;
+                    }
+                    if attempt < MAX_ATTEMPTS {
+                        thread::sleep(Duration::from_secs(attempt as u64));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the error and whether it is worth retrying.
+    fn try_notify(&self, notification: &Notification) -> Result<(), (Box<dyn Error>, bool)> {
+        let response = reqwest::blocking::Client::new()
+            .post(&self.url)
+            .json(notification)
+            .timeout(NOTIFY_TIMEOUT)
+            .send()
+            .map_err(|e| (Box::from(e), true))?;
+        let status = response.status();
+        if !status.is_success() {
+            let transient = status.is_server_error() || status.as_u16() == 429;
+            return Err((Box::from(format!("Unexpected status {}", status)), transient));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::regression::{ImageChange, MetricDelta};
+
+    fn delta(benchmark: &str, change_percent: f64) -> MetricDelta {
+        MetricDelta {
+            benchmark: benchmark.to_string(),
+            metric: "time",
+            baseline: 100,
+            current: (100.0 + change_percent) as i64,
+            change_percent,
+            images: ImageChange::Unknown,
+        }
+    }
+
+    #[test]
+    fn should_list_largest_regressions_first() {
+        let report = RegressionReport {
+            regressions: (1..=7).map(|i| delta(&format!("rust/{}", i), i as f64 * 10.0)).collect(),
+            ..Default::default()
+        };
+        let notification = Notification::new(false, Duration::from_secs(7500), (10, 1, 2), Some(&report), "result/report.html");
+
+        let regressions = notification.regressions.as_ref().unwrap();
+        assert_eq!(regressions.len(), 5);
+        assert_eq!(regressions[0], "rust/7 time: 100 -> 170 (+70.0%)");
+        assert!(notification.text.starts_with("Sharkbench finished after 2h 05m: 10 succeeded, 1 failed, 2 skipped.\nRegressions:\n- rust/7 time"));
+        assert!(notification.text.ends_with("\nReport: result/report.html"));
+    }
+
+    #[test]
+    fn should_serialize_generic_and_slack_fields() {
+        let notification = Notification::new(true, Duration::from_secs(200), (0, 0, 0), None, "result/report.html");
+        let json = serde_json::to_value(&notification).unwrap();
+
+        assert_eq!(json["text"], "Sharkbench aborted after 3m 20s: 0 succeeded, 0 failed, 0 skipped.\nReport: result/report.html");
+        assert_eq!(json["aborted"], true);
+        assert_eq!(json["duration_seconds"], 200);
+        assert_eq!(json["regressions"], serde_json::Value::Null);
+    }
+}
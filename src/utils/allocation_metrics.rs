@@ -0,0 +1,81 @@
+use std::time::Duration;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use crate::benchmark::benchmark::AdditionalData;
+use crate::benchmark::error::BenchmarkError;
+
+/// Path of the endpoint reporting the allocations of the process since its start as JSON, e.g. `{"bytes": 4096, "count": 12}`,
+/// e.g. counted by the global allocator of `benchmark/computation/rust/rust-1.40/src/counting_allocator.rs`.
+pub const ALLOCATIONS_PATH: &str = "/_sharkbench/allocations";
+
+/// Additional data key of the bytes allocated per request of a round.
+pub const BYTES_KEY: &str = "allocated_bytes";
+
+/// Additional data key of the number of allocations per request of a round.
+pub const COUNT_KEY: &str = "allocation_count";
+
+/// Cumulative allocation counters of the process.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct AllocationCounters {
+    pub bytes: i64,
+    pub count: i64,
+}
+
+impl AllocationCounters {
+    /// The allocations between `before` and these counters.
+    /// Counters that went backwards (e.g. the process restarted) count from zero.
+    pub fn since(&self, before: &AllocationCounters) -> AllocationCounters {
+        let delta = |after: i64, before: i64| if after >= before { after - before } else { after };
+        AllocationCounters {
+            bytes: delta(self.bytes, before.bytes),
+            count: delta(self.count, before.count),
+        }
+    }
+
+    /// Adds the allocations of a round with this many requests to its additional data, per request,
+    /// so web rounds with a different number of requests stay comparable.
+    pub fn insert_into(&self, requests: i64, additional_data: &mut IndexMap<String, AdditionalData>) {
+        let per_request = |value: i64| AdditionalData::Int((value / requests.max(1)).min(i32::MAX as i64) as i32);
+        additional_data.insert(BYTES_KEY.to_string(), per_request(self.bytes));
+        additional_data.insert(COUNT_KEY.to_string(), per_request(self.count));
+    }
+}
+
+/// Queries the allocation counters of the service at `base_url` with the `client` shared by the rounds.
+pub fn query(client: &reqwest::blocking::Client, base_url: &str) -> Result<AllocationCounters, BenchmarkError> {
+    let url = format!("{}{}", base_url.trim_end_matches('/'), ALLOCATIONS_PATH);
+    let response = client
+        .get(&url)
+        .timeout(Duration::from_secs(5))
+        .send()?;
+    if !response.status().is_success() {
+        return Err(BenchmarkError::HttpStatus { status: response.status().as_u16(), url });
+    }
+    parse(&response.text()?)
+}
+
+fn parse(body: &str) -> Result<AllocationCounters, BenchmarkError> {
+    serde_json::from_str(body).map_err(|e| BenchmarkError::ParseError(format!("Invalid allocation counters {:?}: {}", body, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_calculate_allocations_per_request() {
+        let before = parse(r#"{"bytes": 1000, "count": 10}"#).unwrap();
+        let round = parse(r#"{"bytes": 41000, "count": 410}"#).unwrap().since(&before);
+        assert_eq!(round, AllocationCounters { bytes: 40000, count: 400 });
+        assert!(parse("1000").is_err());
+
+        let mut additional_data = IndexMap::new();
+        round.insert_into(100, &mut additional_data);
+        assert_eq!(additional_data, IndexMap::from([
+            (BYTES_KEY.to_string(), AdditionalData::Int(400)),
+            (COUNT_KEY.to_string(), AdditionalData::Int(4)),
+        ]));
+        // Restarted in between
+        assert_eq!(AllocationCounters { bytes: 50, count: 1 }.since(&before), AllocationCounters { bytes: 50, count: 1 });
+    }
+}
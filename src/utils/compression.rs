@@ -0,0 +1,374 @@
+/// Sent as `Accept-Encoding` by the clients of the rounds, the bodies are decoded with `decode`.
+pub const ACCEPT_ENCODING: &str = "gzip, deflate";
+
+/// Limit of a decoded body, so a small response that inflates to gigabytes fails instead of exhausting the memory.
+pub const MAX_DECODED_SIZE: usize = 64 * 1024 * 1024;
+
+/// Decodes a response body by its `Content-Encoding` (e.g. `gzip`, `deflate` or `gzip, deflate` for several codings).
+/// `None` and `identity` leave the body unchanged. Fails if a coding decodes to more than `MAX_DECODED_SIZE` bytes.
+pub fn decode(content_encoding: Option<&str>, body: &[u8]) -> Result<Vec<u8>, String> {
+    let codings: Vec<String> = content_encoding.unwrap_or_default()
+        .split(',')
+        .map(|coding| coding.trim().to_ascii_lowercase())
+        .filter(|coding| !coding.is_empty() && coding != "identity")
+        .collect();
+
+    // The codings are listed in the order they were applied
+    let mut body = body.to_vec();
+    for coding in codings.iter().rev() {
+        body = match coding.as_str() {
+            "gzip" | "x-gzip" => decode_gzip(&body)?,
+            "deflate" => decode_deflate(&body)?,
+            _ => return Err(format!("unsupported Content-Encoding {:?}", coding)),
+        };
+    }
+    Ok(body)
+}
+
+/// RFC 1952: a header, the deflated data, the CRC-32 and the size of the uncompressed data.
+fn decode_gzip(data: &[u8]) -> Result<Vec<u8>, String> {
+    const FHCRC: u8 = 0x02;
+    const FEXTRA: u8 = 0x04;
+    const FNAME: u8 = 0x08;
+    const FCOMMENT: u8 = 0x10;
+
+    if data.len() < 18 || data[0] != 0x1f || data[1] != 0x8b || data[2] != 8 {
+        return Err("invalid gzip header".to_string());
+    }
+    let flags = data[3];
+    let mut position = 10;
+    if flags & FEXTRA != 0 {
+        let length = *data.get(position).ok_or("truncated gzip header")? as usize
+            | (*data.get(position + 1).ok_or("truncated gzip header")? as usize) << 8;
+        position += 2 + length;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let end = data.get(position..).and_then(|rest| rest.iter().position(|byte| *byte == 0)).ok_or("truncated gzip header")?;
+            position += end + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        position += 2;
+    }
+
+    let (decoded, used) = inflate(data.get(position..).ok_or("truncated gzip header")?)?;
+    let trailer = data.get(position + used..position + used + 8).ok_or("truncated gzip trailer")?;
+    let expected_crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    let expected_size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+    if crc32(&decoded) != expected_crc || decoded.len() as u32 != expected_size {
+        return Err("gzip checksum mismatch".to_string());
+    }
+    Ok(decoded)
+}
+
+/// RFC 1950 (zlib) as the standard requires, or raw deflate data as some servers send instead.
+fn decode_deflate(data: &[u8]) -> Result<Vec<u8>, String> {
+    let is_zlib = data.len() >= 6 && data[0] & 0x0f == 8 && (data[0] as u16 * 256 + data[1] as u16).is_multiple_of(31);
+    if !is_zlib {
+        return inflate(data).map(|(decoded, _)| decoded);
+    }
+    if data[1] & 0x20 != 0 {
+        return Err("zlib preset dictionaries are not supported".to_string());
+    }
+
+    let (decoded, used) = inflate(&data[2..])?;
+    let trailer = data.get(2 + used..6 + used).ok_or("truncated zlib trailer")?;
+    if adler32(&decoded) != u32::from_be_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]) {
+        return Err("zlib checksum mismatch".to_string());
+    }
+    Ok(decoded)
+}
+
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+/// Order in which the code lengths of the code length alphabet are stored in dynamic blocks.
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// Decodes raw deflate data (RFC 1951), returns the data and the number of bytes consumed.
+fn inflate(data: &[u8]) -> Result<(Vec<u8>, usize), String> {
+    inflate_limited(data, MAX_DECODED_SIZE)
+}
+
+fn inflate_limited(data: &[u8], max_size: usize) -> Result<(Vec<u8>, usize), String> {
+    let mut reader = BitReader { data, position: 0, bit: 0 };
+    let mut output: Vec<u8> = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let length = reader.bits(16)? as usize;
+                let complement = reader.bits(16)? as usize;
+                if length != !complement & 0xffff {
+                    return Err("invalid stored block length".to_string());
+                }
+                let bytes = data.get(reader.position..reader.position + length).ok_or("truncated stored block")?;
+                output.extend_from_slice(bytes);
+                reader.position += length;
+                check_size(&output, max_size)?;
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                let literals = Huffman::new(&lengths);
+                let distances = Huffman::new(&[5u8; 30]);
+                inflate_block(&mut reader, &mut output, &literals, &distances, max_size)?;
+            }
+            2 => {
+                let (literals, distances) = read_dynamic_codes(&mut reader)?;
+                inflate_block(&mut reader, &mut output, &literals, &distances, max_size)?;
+            }
+            _ => return Err("invalid block type".to_string()),
+        }
+        if last {
+            reader.align();
+            return Ok((output, reader.position));
+        }
+    }
+}
+
+fn read_dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_length_count = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &index in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[index] = reader.bits(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&code_lengths);
+
+    let mut lengths: Vec<u8> = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (value, repeat) = match code_length_code.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => (*lengths.last().ok_or("repeated code length without previous length")?, 3 + reader.bits(2)?),
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() > literal_count + distance_count || lengths[256] == 0 {
+        return Err("invalid code lengths".to_string());
+    }
+    Ok((Huffman::new(&lengths[..literal_count]), Huffman::new(&lengths[literal_count..])))
+}
+
+fn check_size(output: &[u8], max_size: usize) -> Result<(), String> {
+    match output.len() > max_size {
+        true => Err(format!("decoded body exceeds {} bytes", max_size)),
+        false => Ok(()),
+    }
+}
+
+fn inflate_block(reader: &mut BitReader, output: &mut Vec<u8>, literals: &Huffman, distances: &Huffman, max_size: usize) -> Result<(), String> {
+    loop {
+        let symbol = literals.decode(reader)?;
+        match symbol {
+            0..=255 => output.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let index = symbol as usize - 257;
+                if index >= LENGTH_BASE.len() {
+                    return Err("invalid length symbol".to_string());
+                }
+                let length = LENGTH_BASE[index] as usize + reader.bits(LENGTH_EXTRA[index])? as usize;
+
+                let index = distances.decode(reader)? as usize;
+                if index >= DISTANCE_BASE.len() {
+                    return Err("invalid distance symbol".to_string());
+                }
+                let distance = DISTANCE_BASE[index] as usize + reader.bits(DISTANCE_EXTRA[index])? as usize;
+                if distance > output.len() {
+                    return Err("distance too far back".to_string());
+                }
+
+                // Byte by byte, as the copy may overlap with its own output
+                let start = output.len() - distance;
+                for i in 0..length {
+                    output.push(output[start + i]);
+                }
+                check_size(output, max_size)?;
+            }
+        }
+    }
+}
+
+/// Reads the bits of the data starting with the least significant bit of every byte.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bit: u8,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, count: u8) -> Result<u32, String> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = *self.data.get(self.position).ok_or("unexpected end of deflate data")?;
+            value |= ((byte >> self.bit) as u32 & 1) << i;
+            self.bit += 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.position += 1;
+            }
+        }
+        Ok(value)
+    }
+
+    /// Skips the remaining bits of the current byte.
+    fn align(&mut self) {
+        if self.bit != 0 {
+            self.bit = 0;
+            self.position += 1;
+        }
+    }
+}
+
+/// A canonical Huffman code by the number of codes per length and the symbols ordered by code.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; 16];
+        for length in 1..16 {
+            offsets[length] = offsets[length - 1] + counts[length - 1];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        // The first code and its index among the symbols of the current length
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..16 {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code".to_string())
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `{"id":1,"name":"sharkbench","values":[1,2,3,4,5]}` 20 times, deflated with fixed Huffman codes.
+    const DEFLATED: [u8; 61] = [
+        0xab, 0x56, 0xca, 0x4c, 0x51, 0xb2, 0x32, 0xd4, 0x51, 0xca, 0x4b, 0xcc, 0x4d, 0x55, 0xb2, 0x52, 0x2a, 0xce, 0x48, 0x2c,
+        0xca, 0x4e, 0x4a, 0xcd, 0x4b, 0xce, 0x50, 0xd2, 0x51, 0x2a, 0x4b, 0xcc, 0x29, 0x4d, 0x2d, 0x56, 0xb2, 0x8a, 0x36, 0xd4,
+        0x31, 0xd2, 0x31, 0xd6, 0x31, 0xd1, 0x31, 0x8d, 0xad, 0xad, 0x1e, 0xd5, 0x30, 0xaa, 0x61, 0x54, 0xc3, 0x60, 0xd6, 0x00,
+        0x00,
+    ];
+
+    fn json() -> Vec<u8> {
+        br#"{"id":1,"name":"sharkbench","values":[1,2,3,4,5]}"#.repeat(20)
+    }
+
+    fn gzipped() -> Vec<u8> {
+        let mut data = vec![0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03];
+        data.extend_from_slice(&DEFLATED);
+        data.extend_from_slice(&[0xeb, 0x37, 0x1f, 0x44, 0xd4, 0x03, 0x00, 0x00]);
+        data
+    }
+
+    #[test]
+    fn should_decode_gzip() {
+        assert_eq!(decode(Some("gzip"), &gzipped()).unwrap(), json());
+        assert_eq!(decode(Some("X-GZIP"), &gzipped()).unwrap(), json());
+
+        let mut corrupted = gzipped();
+        let last = corrupted.len() - 8;
+        corrupted[last] ^= 1;
+        assert_eq!(decode(Some("gzip"), &corrupted).unwrap_err(), "gzip checksum mismatch");
+    }
+
+    #[test]
+    fn should_decode_zlib_and_raw_deflate() {
+        let zlib = [
+            0x78, 0xda, 0x33, 0xd6, 0x33, 0x34, 0xb1, 0x36, 0xb7, 0x30, 0xd5, 0x33, 0xb6, 0xb4, 0x36, 0xd0, 0x33, 0xb7, 0x00, 0x00,
+            0x1b, 0xb4, 0x03, 0x48,
+        ];
+        assert_eq!(decode(Some("deflate"), &zlib).unwrap(), b"3.14;785.39;0.78");
+        assert_eq!(decode(Some("deflate"), &DEFLATED).unwrap(), json());
+    }
+
+    #[test]
+    fn should_decode_dynamic_huffman_codes() {
+        let zlib = [
+            0x78, 0xda, 0x6d, 0x8e, 0x4b, 0x12, 0x80, 0x20, 0x0c, 0x43, 0xaf, 0xd2, 0xab, 0xf1, 0x53, 0x14, 0x11, 0x2d, 0x54, 0xd1,
+            0xd3, 0x6b, 0xa9, 0x0b, 0xc7, 0x71, 0x93, 0x45, 0xd2, 0x97, 0xc6, 0xa6, 0x1e, 0x46, 0x8a, 0x4b, 0x86, 0xe2, 0x1d, 0x4c,
+            0xea, 0x3c, 0x20, 0x7b, 0x85, 0x41, 0xbb, 0xd9, 0x78, 0x50, 0x95, 0x22, 0xac, 0x34, 0x98, 0x00, 0x1a, 0xd3, 0x3e, 0x8b,
+            0x21, 0xf7, 0x62, 0xa7, 0xcd, 0xe1, 0x9b, 0xf8, 0x6d, 0xe8, 0x52, 0x7d, 0x20, 0x51, 0xa4, 0x5c, 0xc0, 0xde, 0x9f, 0xbf,
+            0x60, 0x0b, 0x78, 0x08, 0x87, 0x4c, 0x35, 0xb7, 0x09, 0x17, 0x5d, 0x1c, 0x96, 0x40, 0xf1,
+        ];
+        let expected = "dog jumps the lazy sharkbench axum quick brown axum jumps quick over sharkbench lazy sharkbench axum fox jumps \
+            jumps rust dog sharkbench lazy rust the dog fox lazy lazy axum";
+        assert_eq!(String::from_utf8(decode(Some("deflate"), &zlib).unwrap()).unwrap(), expected);
+    }
+
+    #[test]
+    fn should_limit_decoded_size() {
+        assert_eq!(inflate_limited(&DEFLATED, 980).unwrap().0, json());
+        assert_eq!(inflate_limited(&DEFLATED, 979).unwrap_err(), "decoded body exceeds 979 bytes");
+        assert_eq!(inflate_limited(&[0x01, 0x02, 0x00, 0xfd, 0xff, b'o', b'k'], 1).unwrap_err(), "decoded body exceeds 1 bytes");
+    }
+
+    #[test]
+    fn should_decode_stored_blocks_and_identity() {
+        assert_eq!(decode(Some("deflate"), &[0x01, 0x02, 0x00, 0xfd, 0xff, b'o', b'k']).unwrap(), b"ok");
+        assert_eq!(decode(None, b"plain").unwrap(), b"plain");
+        assert_eq!(decode(Some("identity"), b"plain").unwrap(), b"plain");
+        assert_eq!(decode(Some("br"), b"plain").unwrap_err(), "unsupported Content-Encoding \"br\"");
+    }
+}
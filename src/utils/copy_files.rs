@@ -4,8 +4,8 @@ use crate::utils::meta_data_parser::CopyValue;
 
 pub(crate) const COMMON_DIR: &str = "_common";
 
-/// Copy files from the source to the destination.
-pub(crate) fn copy_files(work_dir: &str, files: &Vec<CopyValue>) {
+/// Copy files from the source to the destination, fails if a source file is missing.
+pub(crate) fn copy_files(work_dir: &str, files: &Vec<CopyValue>) -> Result<(), String> {
     for file in files {
         let (src, dst) = match file {
             CopyValue::Primitive(src) => (src, src),
@@ -16,9 +16,10 @@ pub(crate) fn copy_files(work_dir: &str, files: &Vec<CopyValue>) {
         let final_dst = format!("{work_dir}/{dst}");
         let parent_dir = Path::new(&final_dst).parent().unwrap();
         fs::create_dir_all(parent_dir).expect("Failed to create directory");
-        fs::copy(final_src, final_dst).expect("Failed to copy file");
-        println!(" -> Copied {COMMON_DIR}/{src} to {dst}");
+        fs::copy(final_src, final_dst).map_err(|e| format!("Failed to copy {COMMON_DIR}/{src} to {dst}: {e}"))?;
+        logln!(" -> Copied {COMMON_DIR}/{src} to {dst}");
     }
+    Ok(())
 }
 
 /// Reverts the copied files by deleting the destination files.
@@ -31,7 +32,7 @@ pub(crate) fn delete_copied_files(work_dir: &str, files: &Vec<CopyValue>) {
 
         let final_dst = format!("{work_dir}/{dst}");
         fs::remove_file(&final_dst).expect("Failed to remove file");
-        println!(" -> Removed {dst}");
+        logln!(" -> Removed {dst}");
 
         let parent_path = Path::new(&final_dst).parent().unwrap();
         delete_empty_folder(parent_path, work_dir);
@@ -44,13 +45,12 @@ fn delete_empty_folder(folder_path: &Path, work_dir: &str) {
         Ok(mut entries) => {
             if entries.next().is_none() {
                 fs::remove_dir(folder_path).expect("Failed to remove directory");
-                println!(" -> Removed {}", folder_path.display().to_string().replace(work_dir, "")[1..].to_string());
+                logln!(" -> Removed {}", &folder_path.display().to_string().replace(work_dir, "")[1..]);
 
                 let parent_path = Path::new(folder_path).parent().unwrap();
                 delete_empty_folder(parent_path, work_dir);
-                return;
             }
         }
-        Err(_) => println!(" -> Error while reading directory {folder_path:?}"),
+        Err(_) => warnln!(" -> Error while reading directory {folder_path:?}"),
     }
 }
@@ -0,0 +1,104 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+/// The context of the docker CLI without configuration, it is not stored in the contexts directory.
+pub const DEFAULT_CONTEXT: &str = "default";
+
+/// The endpoint of the `default` context without `DOCKER_HOST`.
+const DEFAULT_ENDPOINT: &str = "unix:///var/run/docker.sock";
+
+/// A Docker context selected with `--docker-context`, recorded in the run metadata.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DockerContext {
+    pub name: String,
+
+    /// The endpoint of the Docker daemon, e.g. `unix:///Users/me/.colima/default/docker.sock` or `ssh://ci@bench-1`.
+    pub endpoint: String,
+}
+
+impl DockerContext {
+    /// Resolves the context like the docker CLI: `default` is the local daemon (or `DOCKER_HOST`),
+    /// every other context is read from the contexts directory of `config_dir` (see `config_dir`).
+    /// Fails with the names of the available contexts if there is none of this name.
+    pub fn resolve(name: &str, config_dir: &Path) -> Result<DockerContext, String> {
+        if name == DEFAULT_CONTEXT {
+            let endpoint = env::var("DOCKER_HOST").unwrap_or(DEFAULT_ENDPOINT.to_string());
+            return Ok(DockerContext { name: name.to_string(), endpoint });
+        }
+
+        let contexts = stored_contexts(config_dir);
+        match contexts.iter().find(|context| context.name == name) {
+            Some(context) => Ok(context.clone()),
+            None => {
+                let available: Vec<&str> = [DEFAULT_CONTEXT].into_iter().chain(contexts.iter().map(|context| context.name.as_str())).collect();
+                Err(format!("unknown Docker context {:?}, available: {}", name, available.join(", ")))
+            }
+        }
+    }
+
+    /// Makes every docker command of this process and its children (builds, compose, stats and exec) use the context.
+    /// Must be called before any other thread starts.
+    pub fn select(&self) {
+        // `DOCKER_HOST` wins over `DOCKER_CONTEXT`, so it is only kept for the `default` context it configures
+        match self.name == DEFAULT_CONTEXT {
+            true => env::remove_var("DOCKER_CONTEXT"),
+            false => {
+                env::set_var("DOCKER_CONTEXT", &self.name);
+                env::remove_var("DOCKER_HOST");
+            }
+        }
+    }
+}
+
+/// The configuration directory of the docker CLI: `DOCKER_CONFIG` or `~/.docker`.
+pub fn config_dir() -> PathBuf {
+    match env::var_os("DOCKER_CONFIG") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME").unwrap_or_default()).join(".docker"),
+    }
+}
+
+/// The contexts in `contexts/meta/<id>/meta.json` of `config_dir`, sorted by name.
+/// The docker CLI names the directories after the SHA-256 of the name, the name is also part of the file.
+fn stored_contexts(config_dir: &Path) -> Vec<DockerContext> {
+    let Ok(entries) = fs::read_dir(config_dir.join("contexts").join("meta")) else {
+        return Vec::new();
+    };
+    let mut contexts: Vec<DockerContext> = entries
+        .filter_map(|entry| fs::read_to_string(entry.ok()?.path().join("meta.json")).ok())
+        .filter_map(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .filter_map(|meta| Some(DockerContext {
+            name: meta["Name"].as_str()?.to_string(),
+            endpoint: meta["Endpoints"]["docker"]["Host"].as_str()?.to_string(),
+        }))
+        .collect();
+    contexts.sort_by(|a, b| a.name.cmp(&b.name));
+    contexts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    #[test]
+    fn should_resolve_stored_contexts() {
+        let config_dir = TestDir::new("docker-context");
+        for (id, meta) in [
+            ("0a1b", r#"{"Name":"remote","Metadata":{},"Endpoints":{"docker":{"Host":"ssh://ci@bench-1","SkipTLSVerify":false}}}"#),
+            ("2c3d", r#"{"Name":"colima","Metadata":{"Description":"colima"},"Endpoints":{"docker":{"Host":"unix:///home/me/.colima/default/docker.sock"}}}"#),
+            ("4e5f", "not json"),
+        ] {
+            let dir = config_dir.join("contexts/meta").join(id);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("meta.json"), meta).unwrap();
+        }
+
+        assert_eq!(DockerContext::resolve("remote", &config_dir), Ok(DockerContext { name: "remote".to_string(), endpoint: "ssh://ci@bench-1".to_string() }));
+        assert_eq!(DockerContext::resolve("colima", &config_dir).unwrap().endpoint, "unix:///home/me/.colima/default/docker.sock");
+        assert_eq!(DockerContext::resolve("default", &config_dir).unwrap().name, "default");
+        assert_eq!(DockerContext::resolve("other", &config_dir), Err("unknown Docker context \"other\", available: default, colima, remote".to_string()));
+    }
+}
@@ -1,6 +1,13 @@
-use std::{fs, thread, time::Duration};
-use std::process::Command;
+use std::{fs, thread};
+use std::fs::File;
+use std::time::{Duration, Instant};
+use std::panic::{self, AssertUnwindSafe};
+use std::process::{Command, ExitStatus};
 use std::path::Path;
+use crate::benchmark::benchmark::BENCHMARK_SERVICE;
+use crate::benchmark::deadline::{self, Target};
+use crate::benchmark::error::BenchmarkError;
+use crate::utils::log;
 
 const IGNORE_FILE: &str = r#"
 .dart_tool
@@ -10,42 +17,225 @@ node_modules
 target
 "#;
 
+/// How the images of a benchmark are built, by default from the cache and the local base images.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BuildOptions {
+    /// Pull newer versions of the base images.
+    pub pull: bool,
+
+    /// Build without the cache.
+    pub no_cache: bool,
+
+    /// Further flags of `docker compose build`, e.g. `--progress=plain`.
+    pub extra_args: Vec<String>,
+}
+
+impl BuildOptions {
+    fn build_command(&self) -> Vec<&str> {
+        let mut command = vec!["docker", "compose", "build"];
+        command.extend(self.pull.then_some("--pull"));
+        command.extend(self.no_cache.then_some("--no-cache"));
+        command.extend(self.extra_args.iter().map(String::as_str));
+        command
+    }
+}
+
 /// Starts a docker container with the given `compose_file`.
-/// The container is stopped after the function `on_container_started` has finished.
+/// The container is stopped after the function `on_container_started` has finished, also if it failed or panicked,
+/// so the next benchmark can start its container.
 /// If `compose_file` is `None`, the directory is expected to contain a docker-compose.yml file.
-pub fn run_docker_compose<F>(dir: &str, delay: Duration, compose_file: Option<&str>, on_container_started: F)
+/// The images are built with `build` and removed with the containers if `remove_images` is set.
+/// Returns the result of `on_container_started` and how long the graceful shutdown of the containers (`docker compose stop`) took,
+/// `None` if stopping failed.
+pub fn run_docker_compose<T, F>(
+    dir: &str,
+    delay: Duration,
+    compose_file: Option<&str>,
+    build: &BuildOptions,
+    remove_images: bool,
+    on_container_started: F,
+) -> Result<(T, Option<Duration>), BenchmarkError>
     where
-        F: FnOnce(),
+        F: FnOnce() -> Result<T, BenchmarkError>,
 {
     if let Some(compose_file_content) = compose_file {
-        fs::write(format!("{}/docker-compose.yml", dir), compose_file_content).unwrap();
-        fs::write(format!("{}/.dockerignore", dir), IGNORE_FILE).unwrap();
+        fs::write(format!("{}/docker-compose.yml", dir), compose_file_content)?;
+        fs::write(format!("{}/.dockerignore", dir), IGNORE_FILE)?;
+    }
+
+    let outcome = start_containers(dir, build).map(|()| {
+        // A heuristic to wait for the container to be ready
+        logln!(" -> Waiting for container to be ready");
+        thread::sleep(delay);
+        panic::catch_unwind(AssertUnwindSafe(on_container_started))
+    });
+
+    // The teardown is not guarded, so the next benchmark starts without the containers
+    deadline::guard(None);
+    logln!(" -> Stopping container");
+    // Stopped separately, so removing the containers and images is not part of the shutdown time
+    let start = Instant::now();
+    let shutdown = match run_shell(&["docker", "compose", "stop"], dir) {
+        Ok(()) => Some(start.elapsed()),
+        Err(e) => {
+            warnln!(" -> Warning: Could not stop the container gracefully: {}", e);
+            None
+        }
+    };
+    let down: &[&str] = match remove_images {
+        true => &["docker", "compose", "down", "--rmi", "all"],
+        false => &["docker", "compose", "down"],
+    };
+    let teardown = run_shell(down, dir).map_err(BenchmarkError::TeardownFailed);
+
+    if compose_file.is_some() {
+        fs::remove_file(format!("{}/docker-compose.yml", dir))?;
+        fs::remove_file(format!("{}/.dockerignore", dir))?;
     }
 
-    println!(" -> Building image");
-    run_shell(&["docker", "compose", "up", "--build", "-d"], dir);
+    // The error of the benchmark is the cause, a failing teardown only fails an otherwise successful benchmark
+    match outcome {
+        Ok(Ok(Ok(result))) => teardown.map(|()| (result, shutdown)),
+        Ok(Ok(Err(e))) | Err(e) => {
+            if let Err(teardown) = teardown {
+                warnln!(" -> Warning: {}", teardown);
+            }
+            Err(e)
+        }
+        Ok(Err(payload)) => panic::resume_unwind(payload),
+    }
+}
 
-    // A heuristic to wait for the container to be ready
-    println!(" -> Waiting for container to be ready");
-    thread::sleep(delay);
+/// Builds the images with `build` and starts the containers, the default builds them with `docker compose up --build`.
+/// The build and then the container are guarded by the deadline of the benchmark, see `deadline::guard`.
+fn start_containers(dir: &str, build: &BuildOptions) -> Result<(), BenchmarkError> {
+    logln!(" -> Building image");
+    let up: &[&str] = match *build == BuildOptions::default() {
+        true => &["docker", "compose", "up", "--build", "-d"],
+        false => {
+            run_guarded(&build.build_command(), dir).map_err(BenchmarkError::BuildFailed)?;
+            &["docker", "compose", "up", "-d"]
+        }
+    };
+    run_guarded(up, dir).map_err(BenchmarkError::BuildFailed)?;
+    match container_id(BENCHMARK_SERVICE) {
+        Ok(id) => deadline::guard(Some(Target::Container(id))),
+        Err(e) => warnln!(" -> Warning: The container cannot be stopped at the benchmark timeout: {}", e),
+    }
+    Ok(())
+}
 
-    on_container_started();
+/// Builds the images of the compose project in `dir` without starting it, the output of docker is written to `log`.
+/// The `compose_file` is written like by `run_docker_compose` and removed again.
+pub fn build_images(dir: &str, compose_file: Option<&str>, build: &BuildOptions, log: &File) -> Result<(), String> {
+    let command = build.build_command();
+    let run = || Command::new(command[0]).args(&command[1..]).current_dir(dir).stdout(log.try_clone()?).stderr(log.try_clone()?).status();
+    match with_compose_file(dir, compose_file, run)? {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("build failed ({})", status)),
+        Err(e) => Err(format!("failed to execute command {:?}: {}", command, e)),
+    }
+}
 
-    println!(" -> Stopping container");
-    run_shell(&["docker", "compose", "down", "--rmi", "all"], dir);
+/// Removes the images of the compose project in `dir` built by `build_images`, with the `compose_file` like there.
+pub fn remove_images(dir: &str, compose_file: Option<&str>) -> Result<(), String> {
+    with_compose_file(dir, compose_file, || run_shell(&["docker", "compose", "down", "--rmi", "all"], dir))?
+}
 
+/// Runs `f` while the `compose_file` (if any) is written to `dir`.
+fn with_compose_file<T>(dir: &str, compose_file: Option<&str>, f: impl FnOnce() -> T) -> Result<T, String> {
+    if let Some(compose_file_content) = compose_file {
+        fs::write(format!("{}/docker-compose.yml", dir), compose_file_content).map_err(|e| e.to_string())?;
+        fs::write(format!("{}/.dockerignore", dir), IGNORE_FILE).map_err(|e| e.to_string())?;
+    }
+    let outcome = f();
     if compose_file.is_some() {
-        fs::remove_file(format!("{}/docker-compose.yml", dir)).unwrap();
-        fs::remove_file(format!("{}/.dockerignore", dir)).unwrap();
+        let _ = fs::remove_file(format!("{}/docker-compose.yml", dir));
+        let _ = fs::remove_file(format!("{}/.dockerignore", dir));
+    }
+    Ok(outcome)
+}
+
+/// Executes the shell command in the running `benchmark` service of the compose project in `dir`
+/// and returns its output (stdout followed by stderr, as some runtimes print their version to stderr).
+pub fn exec_in_container(dir: &str, command: &str) -> Result<String, String> {
+    let output = Command::new("docker")
+        .args(["compose", "exec", "-T", "benchmark", "sh", "-c", command])
+        .current_dir(Path::new(dir))
+        .output()
+        .map_err(|e| format!("failed to execute `{}`: {}", command, e))?;
+
+    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    if !output.status.success() {
+        return Err(format!("`{}` failed ({}): {}", command, output.status, text.trim()));
+    }
+    Ok(text)
+}
+
+/// Returns the host PID of the main process of the running container.
+pub fn container_pid(container_name: &str) -> Result<u32, String> {
+    let pid = inspect_container(container_name, "{{.State.Pid}}")?;
+    match pid.parse::<u32>() {
+        Ok(pid) if pid > 0 => Ok(pid),
+        _ => Err(format!("container {} is not running (pid {:?})", container_name, pid)),
+    }
+}
+
+/// The ID of the container, unlike its name unique to this start of the benchmark.
+fn container_id(container_name: &str) -> Result<String, String> {
+    inspect_container(container_name, "{{.Id}}")
+}
+
+fn inspect_container(container_name: &str, format: &str) -> Result<String, String> {
+    let output = Command::new("docker")
+        .args(["inspect", "--format", format, container_name])
+        .output()
+        .map_err(|e| format!("failed to execute docker inspect: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("docker inspect failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
     }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
-fn run_shell(cmd: &[&str], working_dir: &str) {
+fn run_shell(cmd: &[&str], working_dir: &str) -> Result<(), String> {
+    let status = shell_command(cmd, working_dir).status().map_err(|e| format!("failed to execute command {:?}: {}", cmd, e))?;
+    check_status(cmd, status)
+}
+
+/// Like `run_shell`, the process is killed once the benchmark runs out of time (see `deadline::guard`).
+fn run_guarded(cmd: &[&str], working_dir: &str) -> Result<(), String> {
+    let mut child = shell_command(cmd, working_dir).spawn().map_err(|e| format!("failed to execute command {:?}: {}", cmd, e))?;
+    deadline::guard(Some(Target::Process(child.id())));
+    let status = child.wait();
+    deadline::guard(None);
+    check_status(cmd, status.map_err(|e| format!("failed to wait for command {:?}: {}", cmd, e))?)
+}
+
+fn shell_command(cmd: &[&str], working_dir: &str) -> Command {
     let mut command = Command::new(cmd[0]);
     command.args(&cmd[1..]);
     command.current_dir(Path::new(working_dir));
-    let status = command.status().expect(&format!("failed to execute command: {:?}", cmd));
+    command.stdout(log::child_stdout()).stderr(log::child_stderr());
+    command
+}
+
+fn check_status(cmd: &[&str], status: ExitStatus) -> Result<(), String> {
     if !status.success() {
-        panic!("Command failed: {:?}", cmd);
+        return Err(format!("Command failed ({}): {:?}", status, cmd));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_build_with_options() {
+        assert_eq!(BuildOptions::default().build_command(), vec!["docker", "compose", "build"]);
+
+        let build = BuildOptions { pull: true, no_cache: true, extra_args: vec!["--progress=plain".to_string()] };
+        assert_eq!(build.build_command(), vec!["docker", "compose", "build", "--pull", "--no-cache", "--progress=plain"]);
     }
 }
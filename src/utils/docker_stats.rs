@@ -1,8 +1,12 @@
+use std::fmt;
 use std::process::{Command, Child};
 use std::io::{BufReader, BufRead};
 use std::sync::{Arc, Mutex};
 use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crate::output::tui;
 use crate::utils::percentile;
 
 // The start of a stats line is marked by the following bytes.
@@ -13,7 +17,75 @@ pub struct DockerStatsReader {
     container_name: &'static str,
     is_tracking: Arc<Mutex<bool>>,
     process: Option<Child>,
-    ram_usage: Arc<Mutex<Vec<i64>>>,
+
+    /// Memory samples of the current window with the time they were received.
+    ram_usage: Arc<Mutex<Vec<(Instant, i64)>>>,
+
+    /// Start of the current window.
+    window_start: Instant,
+
+    stabilization_prefix: Option<StabilizationPrefix>,
+
+    /// Whether the warning about missing memory samples was already printed.
+    warned_unavailable: bool,
+}
+
+/// The first part of each window that is discarded before computing the memory usage,
+/// e.g. to exclude the memory spike of a runtime warming up.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StabilizationPrefix {
+    /// Discard the first n samples.
+    pub samples: Option<usize>,
+
+    /// Discard all samples received within the first n milliseconds.
+    pub ms: Option<u64>,
+}
+
+/// A line of `docker stats --format json`, parsed by `parse_stats_line`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsSample {
+    /// The name of the container.
+    pub name: String,
+
+    /// The memory usage in bytes.
+    pub memory: i64,
+
+    /// The memory limit of the container in bytes, `None` if docker reports none.
+    pub memory_limit: Option<i64>,
+
+    /// The memory usage in percent of the limit, `None` if docker reports none.
+    pub memory_percent: Option<f64>,
+}
+
+/// Why a line of `docker stats` has no sample.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// The line is empty, e.g. only the prefix `docker stats` prints before every refresh.
+    Empty,
+
+    /// The line is not JSON.
+    InvalidJson(String),
+
+    /// A field is missing or not a string.
+    MissingField(&'static str),
+
+    /// Docker has no numbers (yet) and prints `--`, e.g. while the container starts.
+    Unavailable,
+
+    /// A size with an unknown unit or without a number, e.g. `1.5PiB`.
+    InvalidSize(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "empty line"),
+            ParseError::InvalidJson(e) => write!(f, "invalid JSON: {}", e),
+            ParseError::MissingField(field) => write!(f, "missing field {}", field),
+            ParseError::Unavailable => write!(f, "no numbers available"),
+            ParseError::InvalidSize(size) => write!(f, "invalid size {:?}", size),
+        }
+    }
 }
 
 pub struct MemoryUsage {
@@ -28,9 +100,16 @@ impl DockerStatsReader {
             is_tracking: Arc::new(Mutex::new(false)),
             process: None,
             ram_usage: Arc::new(Mutex::new(Vec::new())),
+            window_start: Instant::now(),
+            stabilization_prefix: None,
+            warned_unavailable: false,
         }
     }
 
+    pub fn set_stabilization_prefix(&mut self, prefix: Option<StabilizationPrefix>) {
+        self.stabilization_prefix = prefix;
+    }
+
     pub fn run(&mut self) -> Receiver<()> {
         let (tx, rx) = mpsc::channel();
         let container_name = self.container_name;
@@ -38,48 +117,27 @@ impl DockerStatsReader {
         let ram_usage = Arc::clone(&self.ram_usage);
 
         let child = Command::new("docker")
-            .args(&["stats", "--format", "json"])
+            .args(["stats", "--format", "json"])
             .stdout(std::process::Stdio::piped())
-            .spawn()
-            .expect("failed to execute process");
+            .spawn();
 
-        self.process = Some(child);
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                // Timings still work without docker stats, memory is reported as unavailable.
+                warnln!(" -> Warning: Could not start docker stats: {}", e);
+                return rx;
+            }
+        };
 
-        let stdout = self.process.as_mut().unwrap().stdout.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        self.process = Some(child);
         let reader = BufReader::new(stdout);
 
         std::thread::spawn(move || {
-            for line in reader.lines() {
-                if *is_tracking.lock().unwrap() {
-                    let line = line.unwrap();
-                    let trimmed = if line.as_bytes().starts_with(STATS_PREFIX) {
-                        // Trim off the prefix
-                        &line[STATS_PREFIX.len()..]
-                    } else {
-                        &line[..]
-                    };
-                    if trimmed.is_empty() {
-                        continue;
-                    }
-
-                    let json: serde_json::Value = match serde_json::from_str(trimmed) {
-                        Ok(json) => json,
-                        Err(e) => {
-                            println!("Failed to parse JSON: {} \n {}", trimmed, e);
-                            continue;
-                        }
-                    };
-                    let name = json["Name"].as_str().unwrap();
-                    if name != container_name {
-                        continue;
-                    }
-                    let mem_usage = get_bytes_of_ram(json["MemUsage"].as_str().unwrap());
-                    ram_usage.lock().unwrap().push(mem_usage);
-                }
-            }
-
-            println!("Docker stats reader thread finished");
-            tx.send(()).unwrap();
+            read_samples(reader, container_name, &is_tracking, &ram_usage);
+            logln!("Docker stats reader thread finished");
+            let _ = tx.send(());
         });
 
         rx
@@ -92,44 +150,273 @@ impl DockerStatsReader {
 
     pub fn start(&mut self) {
         self.ram_usage.lock().unwrap().clear();
+        self.window_start = Instant::now();
         let mut is_tracking = self.is_tracking.lock().unwrap();
         *is_tracking = true;
     }
 
     pub fn dispose(&mut self) {
         if let Some(child) = &mut self.process {
-            child.kill().expect("failed to kill process");
+            if let Err(e) = child.kill() {
+                warnln!(" -> Failed to stop docker stats: {}", e);
+            }
         }
     }
 
-    pub fn get_memory_usage(&self) -> MemoryUsage {
-        let mut ram_usage = self.ram_usage.lock().unwrap();
-        ram_usage.sort();
-        if ram_usage.len() == 0 {
-            return MemoryUsage {
-                median: 0,
-                p99: 0,
-            };
+    /// Returns the memory usage of the current window,
+    /// or `None` if no samples were received (e.g. docker stats is not available).
+    pub fn get_memory_usage(&mut self) -> Option<MemoryUsage> {
+        let samples: Vec<(Duration, i64)> = self.ram_usage.lock().unwrap().iter()
+            .map(|(time, bytes)| (time.saturating_duration_since(self.window_start), *bytes))
+            .collect();
+        let mut ram_usage = stable_samples(&samples, self.stabilization_prefix);
+        if ram_usage.is_empty() && !samples.is_empty() {
+            logln!(" -> Stabilization prefix {:?} discarded all {} memory samples. Using all samples instead.", self.stabilization_prefix, samples.len());
+            ram_usage = samples.iter().map(|(_, bytes)| *bytes).collect();
         }
-        MemoryUsage {
+        if ram_usage.is_empty() {
+            if !self.warned_unavailable {
+                self.warned_unavailable = true;
+                warnln!(" -> Warning: No memory samples received from docker stats. Memory is not reported.");
+            }
+            return None;
+        }
+        Some(MemoryUsage {
             median: percentile::p50(&ram_usage),
             p99: percentile::p99(&ram_usage),
+        })
+    }
+}
+
+/// Reads the output of `docker stats` until it ends, the memory of `container_name` is sampled while `is_tracking` is set.
+fn read_samples(output: impl BufRead, container_name: &str, is_tracking: &Mutex<bool>, ram_usage: &Mutex<Vec<(Instant, i64)>>) {
+    for line in output.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                warnln!(" -> Warning: Could not read docker stats: {}", e);
+                break;
+            }
+        };
+
+        if *is_tracking.lock().unwrap() {
+            match parse_stats_line(&line) {
+                Ok(sample) if sample.name == container_name => {
+                    ram_usage.lock().unwrap().push((Instant::now(), sample.memory));
+                    tui::memory_sampled(sample.memory);
+                }
+                Ok(_) | Err(ParseError::Empty) | Err(ParseError::Unavailable) => {}
+                Err(e) => warnln!("Failed to parse docker stats ({}): {:?}", e, line),
+            }
         }
     }
 }
 
+/// Returns the memory samples after the stabilization prefix.
+/// `samples` contains the offset of each sample since the start of the window.
+fn stable_samples(samples: &[(Duration, i64)], prefix: Option<StabilizationPrefix>) -> Vec<i64> {
+    let prefix = prefix.unwrap_or_default();
+    samples.iter()
+        .skip(prefix.samples.unwrap_or(0))
+        .filter(|(offset, _)| *offset >= Duration::from_millis(prefix.ms.unwrap_or(0)))
+        .map(|(_, bytes)| *bytes)
+        .collect()
+}
+
+/// Parses a line of `docker stats --format json`, e.g.
+/// `{"Name":"benchmark","MemUsage":"1.5GiB / 7.6GiB","MemPerc":"19.74%",...}`.
+/// The sample belongs to any container, see `StatsSample::name`.
+pub fn parse_stats_line(line: &str) -> Result<StatsSample, ParseError> {
+    let trimmed = if line.as_bytes().starts_with(STATS_PREFIX) {
+        // Trim off the prefix
+        &line[STATS_PREFIX.len()..]
+    } else {
+        line
+    };
+    if trimmed.is_empty() {
+        return Err(ParseError::Empty);
+    }
+
+    let json: serde_json::Value = serde_json::from_str(trimmed).map_err(|e| ParseError::InvalidJson(e.to_string()))?;
+    let name = json["Name"].as_str().ok_or(ParseError::MissingField("Name"))?;
+    let mem_usage = json["MemUsage"].as_str().ok_or(ParseError::MissingField("MemUsage"))?;
+
+    // `<usage> / <limit>`
+    let (usage, limit) = match mem_usage.split_once('/') {
+        Some((usage, limit)) => (usage.trim(), Some(limit.trim())),
+        None => (mem_usage.trim(), None),
+    };
+    let memory = parse_size(usage)?.ok_or(ParseError::Unavailable)?;
+    let memory_limit = match limit {
+        Some(limit) => parse_size(limit)?,
+        None => None,
+    };
+    let memory_percent = match json["MemPerc"].as_str().map(str::trim) {
+        None | Some(PLACEHOLDER) => None,
+        Some(percent) => Some(percent.trim_end_matches('%').parse::<f64>().map_err(|_| ParseError::InvalidSize(percent.to_string()))?),
+    };
+
+    Ok(StatsSample { name: name.to_string(), memory, memory_limit, memory_percent })
+}
+
+/// What `docker stats` prints instead of a number it does not have.
+const PLACEHOLDER: &str = "--";
+
+/// The bytes of a size of `docker stats`, `None` for `PLACEHOLDER`.
+fn parse_size(size: &str) -> Result<Option<i64>, ParseError> {
+    if size == PLACEHOLDER {
+        return Ok(None);
+    }
+    get_bytes_of_ram(size).map(Some).ok_or_else(|| ParseError::InvalidSize(size.to_string()))
+}
+
 /// Parses the given memory usage string and returns the number of bytes.
+/// Docker prints the memory in binary units, so every unit is a power of 1024.
 /// Example: "1.5GiB" -> 1610612736
-fn get_bytes_of_ram(mem_usage: &str) -> i64 {
-    let mem_usage_regex = Regex::new(r"(\d*\.?\d+)(\w+)").unwrap();
-    let mem_usage_match = mem_usage_regex.captures(mem_usage).unwrap();
-    let mem_usage_value = mem_usage_match.get(1).unwrap().as_str().parse::<f64>().unwrap();
-    let mem_usage_unit = mem_usage_match.get(2).unwrap().as_str();
-
-    match mem_usage_unit {
-        "KiB" => (mem_usage_value * 1024.0) as i64,
-        "MiB" => (mem_usage_value * 1024.0 * 1024.0) as i64,
-        "GiB" => (mem_usage_value * 1024.0 * 1024.0 * 1024.0) as i64,
-        _ => panic!("Unknown unit: {}", mem_usage_unit),
+pub(crate) fn get_bytes_of_ram(mem_usage: &str) -> Option<i64> {
+    let mem_usage_regex = Regex::new(r"(\d*\.?\d+)\s*(\w+)").unwrap();
+    let mem_usage_match = mem_usage_regex.captures(mem_usage)?;
+    let mem_usage_value = mem_usage_match.get(1)?.as_str().parse::<f64>().ok()?;
+    let mem_usage_unit = mem_usage_match.get(2)?.as_str();
+
+    let exponent = match mem_usage_unit {
+        "B" => 0,
+        "KiB" => 1,
+        "MiB" => 2,
+        "GiB" => 3,
+        "TiB" => 4,
+        _ => return None,
+    };
+    Some((mem_usage_value * 1024f64.powi(exponent)).round() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples() -> Vec<(Duration, i64)> {
+        vec![
+            (Duration::from_millis(100), 900),
+            (Duration::from_millis(600), 300),
+            (Duration::from_millis(1100), 200),
+            (Duration::from_millis(1600), 210),
+        ]
+    }
+
+    #[test]
+    fn should_keep_all_samples_without_prefix() {
+        assert_eq!(stable_samples(&samples(), None), vec![900, 300, 200, 210]);
+    }
+
+    #[test]
+    fn should_discard_first_samples() {
+        assert_eq!(stable_samples(&samples(), Some(StabilizationPrefix { samples: Some(2), ms: None })), vec![200, 210]);
+        assert_eq!(stable_samples(&samples(), Some(StabilizationPrefix { samples: Some(10), ms: None })), Vec::<i64>::new());
+    }
+
+    #[test]
+    fn should_discard_samples_within_first_milliseconds() {
+        assert_eq!(stable_samples(&samples(), Some(StabilizationPrefix { samples: None, ms: Some(500) })), vec![300, 200, 210]);
+    }
+
+    #[test]
+    fn should_apply_both_limits() {
+        assert_eq!(stable_samples(&samples(), Some(StabilizationPrefix { samples: Some(1), ms: Some(1000) })), vec![200, 210]);
+    }
+
+    #[test]
+    fn should_parse_stats_line() {
+        let line = "\u{1b}[2J\u{1b}[H{\"Name\":\"benchmark\",\"MemUsage\":\"1.5GiB / 7.6GiB\",\"MemPerc\":\"19.74%\"}";
+        assert_eq!(parse_stats_line(line), Ok(StatsSample {
+            name: "benchmark".to_string(),
+            memory: 1610612736,
+            memory_limit: Some(8160437862),
+            memory_percent: Some(19.74),
+        }));
+    }
+
+    #[test]
+    fn should_parse_unit_suffixes() {
+        let memory = |mem_usage: &str| parse_stats_line(&format!(r#"{{"Name":"benchmark","MemUsage":"{}"}}"#, mem_usage)).map(|sample| sample.memory);
+        assert_eq!(memory("512B / 1GiB"), Ok(512));
+        assert_eq!(memory("512KiB / 1GiB"), Ok(524288));
+        assert_eq!(memory("12.5MiB / 1GiB"), Ok(13107200));
+        assert_eq!(memory("2GiB / 4GiB"), Ok(2147483648));
+        assert_eq!(memory("12.5MiB"), Ok(13107200));
+        assert_eq!(memory("1.5PiB / 2PiB"), Err(ParseError::InvalidSize("1.5PiB".to_string())));
+        assert_eq!(memory("MiB / 1GiB"), Err(ParseError::InvalidSize("MiB".to_string())));
+    }
+
+    #[test]
+    fn should_convert_binary_units() {
+        assert_eq!(get_bytes_of_ram("0B"), Some(0));
+        assert_eq!(get_bytes_of_ram("512B"), Some(512));
+        assert_eq!(get_bytes_of_ram("1KiB"), Some(1024));
+        assert_eq!(get_bytes_of_ram("1.5KiB"), Some(1536));
+        assert_eq!(get_bytes_of_ram("1MiB"), Some(1048576));
+        assert_eq!(get_bytes_of_ram("123.4MiB"), Some(129394278));
+        assert_eq!(get_bytes_of_ram("1GiB"), Some(1073741824));
+        assert_eq!(get_bytes_of_ram("7.6GiB"), Some(8160437862));
+        assert_eq!(get_bytes_of_ram("1TiB"), Some(1099511627776));
+        assert_eq!(get_bytes_of_ram("512 MiB"), Some(536870912));
+
+        // Decimal units are not printed for the memory, they would be off by 2.4% per power
+        assert_eq!(get_bytes_of_ram("1MB"), None);
+        assert_eq!(get_bytes_of_ram("1kB"), None);
+    }
+
+    #[test]
+    fn should_parse_placeholders() {
+        assert_eq!(parse_stats_line(r#"{"Name":"benchmark","MemUsage":"-- / --","MemPerc":"--"}"#), Err(ParseError::Unavailable));
+        let sample = parse_stats_line(r#"{"Name":"benchmark","MemUsage":"12MiB / --","MemPerc":"--"}"#).unwrap();
+        assert_eq!((sample.memory, sample.memory_limit, sample.memory_percent), (12582912, None, None));
+    }
+
+    #[test]
+    fn should_reject_malformed_stats_lines() {
+        assert_eq!(parse_stats_line(""), Err(ParseError::Empty));
+        assert_eq!(parse_stats_line("\u{1b}[2J\u{1b}[H"), Err(ParseError::Empty));
+        assert!(matches!(parse_stats_line("not json"), Err(ParseError::InvalidJson(_))));
+        assert_eq!(parse_stats_line(r#"{"Name":"benchmark"}"#), Err(ParseError::MissingField("MemUsage")));
+        assert_eq!(parse_stats_line(r#"{"MemUsage":"1MiB / 2MiB"}"#), Err(ParseError::MissingField("Name")));
+        assert_eq!(parse_stats_line(r#"{"Name":"benchmark","MemUsage":"1MiB / 2MiB","MemPerc":"a lot"}"#), Err(ParseError::InvalidSize("a lot".to_string())));
+    }
+
+    #[test]
+    fn should_sample_memory_of_container_from_output() {
+        let output = concat!(
+            "\u{1b}[2J\u{1b}[H{\"Name\":\"benchmark\",\"MemUsage\":\"-- / --\",\"MemPerc\":\"--\"}\n",
+            "{\"Name\":\"web-data-source\",\"MemUsage\":\"1GiB / 2GiB\",\"MemPerc\":\"50.00%\"}\n",
+            "\u{1b}[2J\u{1b}[H{\"Name\":\"benchmark\",\"MemUsage\":\"10MiB / 2GiB\",\"MemPerc\":\"0.49%\"}\n",
+            "\u{1b}[2J\u{1b}[H\n",
+            "not json\n",
+            "{\"Name\":\"benchmark\",\"MemUsage\":\"30MiB / 2GiB\",\"MemPerc\":\"1.46%\"}\n",
+        );
+        let mut reader = DockerStatsReader::new("benchmark");
+        reader.start();
+        read_samples(output.as_bytes(), "benchmark", &reader.is_tracking, &reader.ram_usage);
+        reader.stop();
+
+        let bytes = || reader.ram_usage.lock().unwrap().iter().map(|(_, bytes)| *bytes).collect::<Vec<i64>>();
+        assert_eq!(bytes(), vec![10485760, 31457280]);
+
+        // Not sampled while not tracking
+        read_samples(output.as_bytes(), "benchmark", &reader.is_tracking, &reader.ram_usage);
+        assert_eq!(bytes(), vec![10485760, 31457280]);
+        assert_eq!(reader.get_memory_usage().map(|memory| memory.median), Some(10485760));
+    }
+
+    #[test]
+    fn should_report_no_memory_without_samples() {
+        let mut reader = DockerStatsReader::new("benchmark");
+        reader.start();
+        reader.stop();
+        assert!(reader.get_memory_usage().is_none());
+    }
+
+    #[test]
+    fn should_deserialize_prefix() {
+        assert_eq!(serde_yaml::from_str::<StabilizationPrefix>("samples: 3").unwrap(), StabilizationPrefix { samples: Some(3), ms: None });
+        assert_eq!(serde_yaml::from_str::<StabilizationPrefix>("ms: 500").unwrap(), StabilizationPrefix { samples: None, ms: Some(500) });
     }
 }
@@ -0,0 +1,126 @@
+use std::fs;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::utils::input_hash;
+
+/// Returns the commit of the git repository containing `dir`, or `None` if it is not part of a git checkout.
+pub fn git_commit_of(dir: &str) -> Option<String> {
+    Command::new("git")
+        .args(["-C", dir, "rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|commit| !commit.is_empty())
+}
+
+/// Returns an identifier of this machine (`/etc/machine-id`, falling back to the host name), or `unknown`.
+pub fn machine_id() -> String {
+    ["/etc/machine-id", "/var/lib/dbus/machine-id", "/etc/hostname"].iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .map(|content| content.trim().to_string())
+        .find(|id| !id.is_empty())
+        .unwrap_or("unknown".to_string())
+}
+
+/// Returns the host name of this machine, or `unknown`.
+pub fn host_name() -> String {
+    fs::read_to_string("/etc/hostname").ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("unknown".to_string())
+}
+
+/// Replaces an identifier of this machine (e.g. `host_name`) for `--anonymize`, with its hash,
+/// so the results of different machines stay apart without naming them.
+/// Example: `anonymous-5f0c8e1d2a3b4c6d`
+pub fn anonymize(id: &str) -> String {
+    format!("anonymous-{}", input_hash::hash_text(id))
+}
+
+/// Returns the current time in UTC.
+/// Example: `2024-01-31T12:34:56Z`
+pub fn now_utc() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    format_utc(seconds)
+}
+
+/// Returns the milliseconds since the unix epoch.
+pub fn now_unix_ms() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0)
+}
+
+/// Formats seconds since the unix epoch as ISO 8601 in UTC.
+pub fn format_utc(seconds: u64) -> String {
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+
+    // Civil date from days since epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+    )
+}
+
+/// Parses ISO 8601 in UTC as written by `format_utc` into seconds since the unix epoch.
+pub fn parse_utc(value: &str) -> Option<u64> {
+    let (date, time) = value.strip_suffix('Z')?.split_once('T')?;
+    let date: Vec<i64> = date.split('-').map(|part| part.parse().ok()).collect::<Option<_>>()?;
+    let time: Vec<u64> = time.split(':').map(|part| part.parse().ok()).collect::<Option<_>>()?;
+    let (&[year, month, day], &[hours, minutes, seconds]) = (date.as_slice(), time.as_slice()) else {
+        return None;
+    };
+
+    // Days since epoch from the civil date, see `format_utc`
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = u64::try_from(era * 146097 + day_of_era - 719468).ok()?;
+    Some(days * 86400 + hours * 3600 + minutes * 60 + seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_anonymize_ids() {
+        assert_eq!(anonymize("ci-runner-1"), anonymize("ci-runner-1"));
+        assert_ne!(anonymize("ci-runner-1"), anonymize("ci-runner-2"));
+        assert!(!anonymize("ci-runner-1").contains("ci-runner"));
+    }
+
+    #[test]
+    fn should_format_utc() {
+        assert_eq!(format_utc(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_utc(951782400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_utc(1706704496), "2024-01-31T12:34:56Z");
+    }
+
+    #[test]
+    fn should_parse_formatted_utc() {
+        for seconds in [0, 951782400, 1706704496, 4102444799] {
+            assert_eq!(parse_utc(&format_utc(seconds)), Some(seconds));
+        }
+        assert_eq!(parse_utc("2024-01-31"), None);
+        assert_eq!(parse_utc("2024-01-31T12:34Z"), None);
+    }
+}
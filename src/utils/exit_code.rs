@@ -0,0 +1,45 @@
+/// The exit codes of sharkbench, documented in the README.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitCode {
+    Ok = 0,
+
+    /// At least one benchmark failed (or the run was aborted at the first failure).
+    BenchmarkFailed = 1,
+
+    /// Every benchmark passed, but results regressed against `--baseline` or do not match `--golden`.
+    Regressed = 2,
+
+    /// The run could not start or was aborted outside of a benchmark, e.g. an invalid command line, config or Docker setup.
+    Environment = 3,
+
+    /// Stopped by a second Ctrl+C of `watch` (128 + SIGINT), without cleaning up.
+    Interrupted = 130,
+}
+
+impl ExitCode {
+    /// The code of a run that finished all of its benchmarks.
+    pub fn of_run(failed: bool, regressed: bool) -> ExitCode {
+        match (failed, regressed) {
+            (true, _) => ExitCode::BenchmarkFailed,
+            (false, true) => ExitCode::Regressed,
+            (false, false) => ExitCode::Ok,
+        }
+    }
+
+    pub fn exit(self) -> ! {
+        std::process::exit(self as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_prefer_failures_over_regressions() {
+        assert_eq!(ExitCode::of_run(false, false), ExitCode::Ok);
+        assert_eq!(ExitCode::of_run(true, true), ExitCode::BenchmarkFailed);
+        assert_eq!(ExitCode::of_run(false, true), ExitCode::Regressed);
+        assert_eq!(ExitCode::Environment as i32, 3);
+    }
+}
@@ -0,0 +1,187 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// How numbers are printed in the logs and reports, see `--number-format`. The results keep plain numbers.
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberFormat {
+    /// `12,345.67`
+    #[default]
+    Comma,
+
+    /// `12.345,67`
+    Period,
+
+    /// `12 345.67`
+    Space,
+
+    /// `12345.67`
+    Plain,
+}
+
+impl NumberFormat {
+    /// The thousands and the decimal separator.
+    fn separators(self) -> (Option<char>, char) {
+        match self {
+            NumberFormat::Comma => (Some(','), '.'),
+            NumberFormat::Period => (Some('.'), ','),
+            NumberFormat::Space => (Some(' '), '.'),
+            NumberFormat::Plain => (None, '.'),
+        }
+    }
+
+    /// Example: `12,345` with `Comma`
+    pub fn integer(self, value: i64) -> String {
+        let digits = value.unsigned_abs().to_string();
+        let mut grouped = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if let (Some(separator), true) = (self.separators().0, i > 0 && (digits.len() - i).is_multiple_of(3)) {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        match value < 0 {
+            true => format!("-{}", grouped),
+            false => grouped,
+        }
+    }
+
+    /// Example: `12,345.68` with `Comma` and 2 decimals
+    pub fn decimal(self, value: f64, decimals: usize) -> String {
+        let formatted = format!("{:.*}", decimals, value.abs());
+        let (integer, fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+        let integer = self.integer(integer.parse().unwrap_or(i64::MAX));
+        let sign = if value < 0.0 && formatted.chars().any(|c| c.is_ascii_digit() && c != '0') { "-" } else { "" };
+        match fraction.is_empty() {
+            true => format!("{}{}", sign, integer),
+            false => format!("{}{}{}{}", sign, integer, self.separators().1, fraction),
+        }
+    }
+}
+
+/// The `NumberFormat` of the process, see `set_number_format`.
+static NUMBER_FORMAT: AtomicU8 = AtomicU8::new(NumberFormat::Comma as u8);
+
+pub fn set_number_format(format: NumberFormat) {
+    NUMBER_FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+pub fn number_format() -> NumberFormat {
+    match NUMBER_FORMAT.load(Ordering::Relaxed) {
+        1 => NumberFormat::Period,
+        2 => NumberFormat::Space,
+        3 => NumberFormat::Plain,
+        _ => NumberFormat::Comma,
+    }
+}
+
+/// Formats an integer with the `NumberFormat` of the process, e.g. `12,345`.
+pub fn format_number(value: i64) -> String {
+    number_format().integer(value)
+}
+
+pub trait SizeFormat {
+    fn bytes_to_string(&self) -> String;
+}
+
+impl SizeFormat for i64 {
+    fn bytes_to_string(&self) -> String {
+        let format = number_format();
+        let kb = *self as f64 / 1024.0;
+        if kb < 1024.0 {
+            return format!("{} KB", format.decimal(kb, 2));
+        }
+        let mb = kb / 1024.0;
+        if mb < 1024.0 {
+            return format!("{} MB", format.decimal(mb, 2));
+        }
+        let gb = mb / 1024.0;
+        format!("{} GB", format.decimal(gb, 2))
+    }
+}
+
+impl SizeFormat for Option<i64> {
+    /// Formats the bytes or "-" if not measured.
+    fn bytes_to_string(&self) -> String {
+        match self {
+            Some(bytes) => bytes.bytes_to_string(),
+            None => "-".to_string(),
+        }
+    }
+}
+
+pub trait TimeFormat {
+    fn millis_to_string(&self) -> String;
+}
+
+impl TimeFormat for i64 {
+    /// Formats milliseconds, switching to seconds from 1000 ms on.
+    /// Example: 999 -> "999 ms", 12345 -> "12.35 s"
+    fn millis_to_string(&self) -> String {
+        if *self < 1000 {
+            return format!("{} ms", self);
+        }
+        format!("{} s", number_format().decimal(*self as f64 / 1000.0, 2))
+    }
+}
+
+/// Example: `2h 05m`, `3m 20s`
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0..=3599 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+/// Parses a duration like `90s`, `30m`, `24h` or `7d`, e.g. of `--max-age`.
+pub fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let unit_seconds = match duration.chars().last() {
+        Some('s') => 1,
+        Some('m') => 60,
+        Some('h') => 60 * 60,
+        Some('d') => 24 * 60 * 60,
+        _ => return Err(format!("expected a number with the unit s, m, h or d, got {:?}", duration)),
+    };
+    let value: u64 = duration[..duration.len() - 1].parse().map_err(|_| format!("expected a number with the unit s, m, h or d, got {:?}", duration))?;
+    Ok(Duration::from_secs(value * unit_seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_format_bytes() {
+        assert_eq!(512.bytes_to_string(), "0.50 KB");
+        assert_eq!((3 * 1024 * 1024).bytes_to_string(), "3.00 MB");
+        assert_eq!((1536 * 1024 * 1024).bytes_to_string(), "1.50 GB");
+        assert_eq!(Some(512).bytes_to_string(), "0.50 KB");
+        assert_eq!(None.bytes_to_string(), "-");
+    }
+
+    #[test]
+    fn should_group_digits() {
+        assert_eq!(NumberFormat::Comma.integer(1234567), "1,234,567");
+        assert_eq!(NumberFormat::Comma.integer(-12345), "-12,345");
+        assert_eq!(NumberFormat::Comma.integer(999), "999");
+        assert_eq!(NumberFormat::Period.integer(12345), "12.345");
+        assert_eq!(NumberFormat::Space.integer(12345), "12 345");
+        assert_eq!(NumberFormat::Plain.integer(12345), "12345");
+
+        assert_eq!(NumberFormat::Comma.decimal(12345.678, 2), "12,345.68");
+        assert_eq!(NumberFormat::Period.decimal(12345.678, 2), "12.345,68");
+        assert_eq!(NumberFormat::Comma.decimal(-0.001, 2), "0.00");
+        assert_eq!(NumberFormat::Comma.decimal(-1.5, 0), "-2");
+    }
+
+    #[test]
+    fn should_format_millis() {
+        assert_eq!(0.millis_to_string(), "0 ms");
+        assert_eq!(999.millis_to_string(), "999 ms");
+        assert_eq!(1000.millis_to_string(), "1.00 s");
+        assert_eq!(12345.millis_to_string(), "12.35 s");
+    }
+}
@@ -0,0 +1,76 @@
+use std::time::Duration;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use crate::benchmark::benchmark::AdditionalData;
+use crate::benchmark::error::BenchmarkError;
+
+/// Path of the endpoint reporting the garbage collection counters of the runtime since the start of the process,
+/// as JSON, e.g. `{"pause_count": 12, "pause_time_ms": 34}`.
+pub const GC_PATH: &str = "/_sharkbench/gc";
+
+/// Additional data key of the number of GC pauses during a round.
+pub const PAUSE_COUNT_KEY: &str = "gc_pause_count";
+
+/// Additional data key of the total GC pause time (in ms) during a round.
+pub const PAUSE_TIME_KEY: &str = "gc_pause_time_ms";
+
+/// Cumulative GC counters of the runtime.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct GcCounters {
+    pub pause_count: i64,
+    pub pause_time_ms: i64,
+}
+
+impl GcCounters {
+    /// The pauses between `before` and these counters.
+    /// Counters that went backwards (e.g. the process restarted) count from zero.
+    pub fn since(&self, before: &GcCounters) -> GcCounters {
+        let delta = |after: i64, before: i64| if after >= before { after - before } else { after };
+        GcCounters {
+            pause_count: delta(self.pause_count, before.pause_count),
+            pause_time_ms: delta(self.pause_time_ms, before.pause_time_ms),
+        }
+    }
+
+    /// Adds the counters of a round to its additional data.
+    pub fn insert_into(&self, additional_data: &mut IndexMap<String, AdditionalData>) {
+        additional_data.insert(PAUSE_COUNT_KEY.to_string(), AdditionalData::Int(self.pause_count.min(i32::MAX as i64) as i32));
+        additional_data.insert(PAUSE_TIME_KEY.to_string(), AdditionalData::Int(self.pause_time_ms.min(i32::MAX as i64) as i32));
+    }
+}
+
+/// Queries the GC counters of the service at `base_url` with the `client` shared by the rounds.
+pub fn query(client: &reqwest::blocking::Client, base_url: &str) -> Result<GcCounters, BenchmarkError> {
+    let url = format!("{}{}", base_url.trim_end_matches('/'), GC_PATH);
+    let response = client
+        .get(&url)
+        .timeout(Duration::from_secs(5))
+        .send()?;
+    if !response.status().is_success() {
+        return Err(BenchmarkError::HttpStatus { status: response.status().as_u16(), url });
+    }
+    parse(&response.text()?)
+}
+
+fn parse(body: &str) -> Result<GcCounters, BenchmarkError> {
+    serde_json::from_str(body).map_err(|e| BenchmarkError::ParseError(format!("Invalid GC counters {:?}: {}", body, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_counters() {
+        assert_eq!(parse(r#"{"pause_count": 12, "pause_time_ms": 34}"#).unwrap(), GcCounters { pause_count: 12, pause_time_ms: 34 });
+        assert!(parse("12").is_err());
+    }
+
+    #[test]
+    fn should_calculate_pauses_of_round() {
+        let before = GcCounters { pause_count: 10, pause_time_ms: 100 };
+        assert_eq!(GcCounters { pause_count: 13, pause_time_ms: 130 }.since(&before), GcCounters { pause_count: 3, pause_time_ms: 30 });
+        // Restarted in between
+        assert_eq!(GcCounters { pause_count: 2, pause_time_ms: 5 }.since(&before), GcCounters { pause_count: 2, pause_time_ms: 5 });
+    }
+}
@@ -0,0 +1,42 @@
+/// Whether `value` matches `pattern`, where `*` matches any (possibly empty) sequence of characters
+/// and `?` matches a single character.
+pub fn matches(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+    let (mut p, mut v) = (0, 0);
+    // Position of the last `*` and the value position it was tried at, to backtrack to
+    let mut star: Option<(usize, usize)> = None;
+    while v < value.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, v));
+            p += 1;
+        } else if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if let Some((star_p, star_v)) = star {
+            p = star_p + 1;
+            v = star_v + 1;
+            star = Some((star_p, star_v + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_match_wildcards() {
+        assert!(matches("web/*", "web/rust/axum"));
+        assert!(matches("*/rust/*", "computation/rust/rust-1.74"));
+        assert!(matches("axum-*-rust-1.74", "axum-0.7-rust-1.74"));
+        assert!(matches("rust-1.?4", "rust-1.74"));
+        assert!(matches("*", ""));
+        assert!(!matches("web/*", "computation/rust"));
+        assert!(!matches("web/rust", "web/rust/axum"));
+        assert!(!matches("rust-1.?", "rust-1.74"));
+    }
+}
@@ -1,12 +1,18 @@
 use std::collections::HashMap;
+use indexmap::IndexMap;
 use reqwest;
 use tokio;
 use std::time::Duration;
 use tokio::{task, time};
 use rand::seq::SliceRandom;
 use reqwest::StatusCode;
+use reqwest::header;
 use tokio::task::JoinHandle;
+use crate::benchmark::benchmark::Response;
+use crate::benchmark::error::BenchmarkError;
+use crate::utils::compression;
 use crate::utils::percentile;
+use crate::utils::transport::content_encoding;
 use crate::utils::serialization::SerializedValue;
 
 pub struct HttpLoadResult {
@@ -17,6 +23,9 @@ pub struct HttpLoadResult {
     pub rps_p99: i32,
     pub latency_median: Duration,
     pub latency_p99: Duration,
+
+    /// The first response of the first connection, e.g. to check its shape.
+    pub first_response: Option<Response>,
 }
 
 type RequestValidatorFn = fn(&str, &HashMap<String, SerializedValue>) -> bool;
@@ -24,42 +33,40 @@ type RequestValidatorFn = fn(&str, &HashMap<String, SerializedValue>) -> bool;
 pub fn run_http_load_test(
     concurrency: usize,
     duration: Duration,
-    requests: &Vec<(String, HashMap<String, SerializedValue>)>,
+    requests: &[(String, HashMap<String, SerializedValue>)],
     request_validator: RequestValidatorFn,
     verbose: bool,
-) -> HttpLoadResult {
-    let rt = tokio::runtime::Runtime::new().unwrap();
-    let result = rt.block_on(run_load_test(
+) -> Result<HttpLoadResult, BenchmarkError> {
+    let rt = tokio::runtime::Runtime::new()?;
+    rt.block_on(run_load_test(
         concurrency,
         duration,
         requests,
         request_validator,
         verbose,
-    ));
-    result
+    ))
 }
 
 async fn run_load_test(
     concurrency: usize,
     duration: Duration,
-    requests: &Vec<(String, HashMap<String, SerializedValue>)>,
+    requests: &[(String, HashMap<String, SerializedValue>)],
     request_validator: RequestValidatorFn,
     verbose: bool,
-) -> HttpLoadResult {
+) -> Result<HttpLoadResult, BenchmarkError> {
     let mut handles: Vec<JoinHandle<ThreadResult>> = Vec::new();
 
     for _ in 0..concurrency {
-        let mut requests_clone = requests.clone();
+        let mut requests_clone = requests.to_vec();
         requests_clone.shuffle(&mut rand::thread_rng());
 
         let handle = task::spawn(async move {
             let mut local_success_count = 0;
             let mut success_count_temp = 0;
             let mut local_fail_count = 0;
-            let mut local_latency_us: Vec<u64> = Vec::new();
-            local_latency_us.reserve(100000);
-            let mut rps_per_second: Vec<i32> = Vec::new();
-            rps_per_second.reserve(100000);
+            let mut local_latency_us: Vec<u64> = Vec::with_capacity(100000);
+            let mut rps_per_second: Vec<i32> = Vec::with_capacity(100000);
+            let mut first_response: Option<Response> = None;
 
             let client = reqwest::Client::builder()
                 .timeout(Duration::from_secs(5))
@@ -69,26 +76,34 @@ async fn run_load_test(
             'outer: loop {
                 for (uri, expected_response) in &requests_clone {
                     let request_start = std::time::Instant::now();
-                    match client.get(uri).send().await {
+                    match client.get(uri).header(header::ACCEPT_ENCODING, compression::ACCEPT_ENCODING).send().await {
                         Ok(response) => {
                             let status = &response.status();
-                            let body = response.text().await.unwrap();
+                            let content_encoding = content_encoding(response.headers());
+                            let bytes = response.bytes().await.unwrap();
+                            // Taken before the body is decoded, so it does not include the decompression
                             let latency_us = request_start.elapsed().as_micros() as u64;
-                            if *status == StatusCode::OK && request_validator(&body, &expected_response) {
+                            let body = compression::decode(content_encoding.as_deref(), &bytes)
+                                .map(|body| String::from_utf8_lossy(&body).to_string())
+                                .unwrap_or_else(|e| format!("<undecodable body: {}>", e));
+                            if first_response.is_none() {
+                                first_response = Some(Response { status: Some(status.as_u16()), body: body.clone(), size: bytes.len(), phases: IndexMap::new() });
+                            }
+                            if *status == StatusCode::OK && request_validator(&body, expected_response) {
                                 local_success_count += 1;
                                 local_latency_us.push(latency_us);
                             } else {
                                 local_fail_count += 1;
                                 if verbose {
-                                    println!("Unexpected response {} for {}: {}, expected: {:?}", *status, uri, body, expected_response);
-                                    println!("Success: {}, Fail: {}", local_success_count, local_fail_count);
+                                    logln!("Unexpected response {} for {}: {}, expected: {:?}", *status, uri, body, expected_response);
+                                    logln!("Success: {}, Fail: {}", local_success_count, local_fail_count);
                                 }
                             }
                         }
                         Err(e) => {
                             if verbose {
-                                println!("Request to {} failed: {}", uri, e);
-                                println!("Success: {}, Fail: {}", local_success_count, local_fail_count);
+                                logln!("Request to {} failed: {}", uri, e);
+                                logln!("Success: {}, Fail: {}", local_success_count, local_fail_count);
                             }
                             local_fail_count += 1;
                         },
@@ -112,6 +127,7 @@ async fn run_load_test(
                 latency_us: local_latency_us,
                 rps_per_second,
                 total_time: Duration::from_millis(start.elapsed().as_millis() as u64),
+                first_response,
             }
         });
 
@@ -122,7 +138,8 @@ async fn run_load_test(
 
     let mut handle_results: Vec<ThreadResult> = Vec::new();
     for handle in handles.into_iter() {
-        handle_results.push(handle.await.unwrap());
+        // A panicking validator (e.g. a response without an expected key) fails the round
+        handle_results.push(handle.await.map_err(|e| BenchmarkError::InvalidResponse(e.to_string()))?);
     }
 
     // max time of all threads
@@ -138,12 +155,12 @@ async fn run_load_test(
     let fail_count = handle_results.iter().fold(0, |acc, x| acc + x.fail_count);
 
     if success_count == 0 {
-        panic!("No successful requests. Something is wrong.")
+        return Err(BenchmarkError::InvalidResponse(format!("no successful requests ({} failed)", fail_count)));
     }
 
     let rps_per_second: Vec<i32> = {
         let all_vectors: Vec<Vec<i32>> = handle_results.iter().map(|x| x.rps_per_second.clone()).collect();
-        let mut rps_per_second: Vec<i32> = Vec::new();
+        let mut rps_per_second: Vec<i32> = Vec::with_capacity(100000);
         for i in 0..all_vectors[0].len() {
             let mut sum = 0;
             for vector in &all_vectors {
@@ -151,19 +168,14 @@ async fn run_load_test(
             }
             rps_per_second.push(sum);
         }
-        rps_per_second.sort();
         rps_per_second
     };
-    let latency_us: Vec<u64> = {
-        let mut latency_us: Vec<u64> = handle_results.iter().fold(Vec::new(), |mut acc, x| {
-            acc.extend(x.latency_us.clone());
-            acc
-        });
-        latency_us.sort();
-        latency_us
-    };
+    let latency_us: Vec<u64> = handle_results.iter().fold(Vec::new(), |mut acc, x| {
+        acc.extend(x.latency_us.clone());
+        acc
+    });
 
-    HttpLoadResult {
+    Ok(HttpLoadResult {
         success_count,
         fail_count,
         total_time,
@@ -171,7 +183,8 @@ async fn run_load_test(
         rps_p99: percentile::p1(&rps_per_second), // inverse because we want the worst case
         latency_median: Duration::from_micros(percentile::p50(&latency_us)),
         latency_p99: Duration::from_micros(percentile::p99(&latency_us)),
-    }
+        first_response: handle_results.into_iter().find_map(|x| x.first_response),
+    })
 }
 
 struct ThreadResult {
@@ -184,4 +197,5 @@ struct ThreadResult {
 
     latency_us: Vec<u64>,
     total_time: Duration,
+    first_response: Option<Response>,
 }
@@ -0,0 +1,114 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// The image a benchmark ran in.
+/// Tags like `rust:1.74` move, so two runs of the same benchmark may use different base image contents.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ImageInfo {
+    /// ID of the built image, e.g. `sha256:3f5a...`. It changes with every rebuild.
+    pub id: Option<String>,
+
+    /// Digest of every base image of the Dockerfile by its reference, e.g. `rust:1.74` -> `rust@sha256:9b2c...`.
+    /// Base images without a digest (e.g. only built locally) are missing.
+    pub base_digests: IndexMap<String, String>,
+}
+
+/// Inspects the image of the running `container_name` and the base images of the Dockerfile in `dir`.
+/// Failures are logged and leave the respective fields empty.
+pub fn inspect(dir: &str, container_name: &str) -> ImageInfo {
+    let id = match docker(&["inspect", "--format", "{{.Image}}", container_name]) {
+        Ok(id) => Some(id),
+        Err(e) => {
+            warnln!(" -> Warning: Could not determine the image: {}", e);
+            None
+        }
+    };
+
+    let mut base_digests = IndexMap::new();
+    let dockerfile = fs::read_to_string(Path::new(dir).join("Dockerfile")).unwrap_or_default();
+    for reference in parse_base_images(&dockerfile) {
+        match docker(&["image", "inspect", "--format", "{{join .RepoDigests \"\\n\"}}", &reference]) {
+            Ok(digests) => match digests.lines().next() {
+                Some(digest) => {
+                    base_digests.insert(reference, digest.to_string());
+                }
+                None => warnln!(" -> Warning: Base image {} has no digest", reference),
+            },
+            Err(e) => warnln!(" -> Warning: Could not determine the digest of {}: {}", reference, e),
+        }
+    }
+
+    let image = ImageInfo { id, base_digests };
+    logln!(" -> Image: {}", image.id.as_deref().unwrap_or("-"));
+    for (reference, digest) in &image.base_digests {
+        logln!("    {} = {}", reference, digest);
+    }
+    image
+}
+
+/// Returns the external images of the `FROM` instructions, in order and without duplicates.
+/// References to earlier build stages, `scratch` and references with build arguments are skipped.
+fn parse_base_images(dockerfile: &str) -> Vec<String> {
+    let mut stages: Vec<String> = Vec::new();
+    let mut images: Vec<String> = Vec::new();
+    for line in dockerfile.lines() {
+        let mut words = line.split_whitespace();
+        if !words.next().is_some_and(|instruction| instruction.eq_ignore_ascii_case("FROM")) {
+            continue;
+        }
+        let mut words = words.filter(|word| !word.starts_with("--"));
+        let Some(reference) = words.next() else {
+            continue;
+        };
+        if let (Some(keyword), Some(stage)) = (words.next(), words.next()) {
+            if keyword.eq_ignore_ascii_case("AS") {
+                stages.push(stage.to_lowercase());
+            }
+        }
+
+        let is_stage = stages.iter().any(|stage| stage.eq_ignore_ascii_case(reference));
+        if is_stage || reference == "scratch" || reference.contains('$') || images.iter().any(|image| image == reference) {
+            continue;
+        }
+        images.push(reference.to_string());
+    }
+    images
+}
+
+fn docker(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("docker")
+        .args(args)
+        .output()
+        .map_err(|e| format!("failed to execute docker: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_external_base_images() {
+        let dockerfile = "\
+FROM rust:1.74-slim as builder
+RUN cargo build --release
+FROM --platform=linux/amd64 debian:bookworm-slim AS runner
+FROM builder AS test
+FROM scratch
+FROM ${BASE_IMAGE}
+from rust:1.74-slim
+";
+        assert_eq!(parse_base_images(dockerfile), vec!["rust:1.74-slim", "debian:bookworm-slim"]);
+    }
+
+    #[test]
+    fn should_ignore_missing_dockerfile() {
+        assert!(parse_base_images("").is_empty());
+    }
+}
@@ -0,0 +1,132 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Generated directories (see the `.dockerignore` of the runner) and files of the OS are not inputs.
+const EXCLUDED: [&str; 8] = [".dart_tool", ".gradle", "build", "node_modules", "target", ".git", ".DS_Store", "Thumbs.db"];
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Hashes the inputs of a benchmark: every file of `dir`, the `category.yaml` of `category_dir` and the requested `version`.
+///
+/// Files are hashed by their path relative to `dir` (with `/` separators) in sorted order,
+/// and line endings are normalized to `\n`, so checkouts on different platforms have the same hash.
+pub fn hash_inputs(dir: &str, category_dir: &str, version: &str) -> io::Result<String> {
+    let mut files: Vec<String> = Vec::new();
+    collect_files(Path::new(dir), "", &mut files)?;
+    files.sort();
+
+    let mut hash = Fnv::new();
+    hash.write(b"version\0");
+    hash.write(version.as_bytes());
+    for file in &files {
+        hash.write_file(file, &fs::read(Path::new(dir).join(file))?);
+    }
+    if let Ok(category) = fs::read(Path::new(category_dir).join("category.yaml")) {
+        hash.write_file("../category.yaml", &category);
+    }
+    Ok(format!("{:016x}", hash.0))
+}
+
+/// Hashes a text with the hash of `hash_inputs`, e.g. to replace an identifier that should not be published.
+pub fn hash_text(text: &str) -> String {
+    let mut hash = Fnv::new();
+    hash.write(text.as_bytes());
+    format!("{:016x}", hash.0)
+}
+
+/// Collects the relative paths of the files below `dir`.
+fn collect_files(dir: &Path, prefix: &str, files: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if EXCLUDED.contains(&name.as_str()) {
+            continue;
+        }
+
+        let path = format!("{}{}", prefix, name);
+        if entry.file_type()?.is_dir() {
+            collect_files(&entry.path(), &format!("{}/", path), files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// 64-bit FNV-1a, which is stable across platforms and Rust versions (unlike `DefaultHasher`).
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Fnv {
+        Fnv(FNV_OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    /// The path and length separate the files, so moving content from one file to another changes the hash.
+    fn write_file(&mut self, path: &str, content: &[u8]) {
+        let content = normalize_line_endings(content);
+        self.write(format!("\0{}\0{}\0", path, content.len()).as_bytes());
+        self.write(&content);
+    }
+}
+
+fn normalize_line_endings(content: &[u8]) -> Vec<u8> {
+    let mut normalized = Vec::with_capacity(content.len());
+    for (i, byte) in content.iter().enumerate() {
+        if *byte == b'\r' && content.get(i + 1) == Some(&b'\n') {
+            continue;
+        }
+        normalized.push(*byte);
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    fn benchmark_dir(name: &str, files: &[(&str, &str)]) -> TestDir {
+        let dir = TestDir::new(&format!("hash-{}", name));
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn should_hash_sources_and_version() {
+        let root = benchmark_dir("sources", &[("Dockerfile", "FROM rust:1.74\n"), ("src/main.rs", "fn main() {}\n")]);
+        let dir = root.to_str().unwrap().to_string();
+        let hash = hash_inputs(&dir, &dir, "1.74").unwrap();
+
+        assert_eq!(hash.len(), 16);
+        assert_eq!(hash_inputs(&dir, &dir, "1.74").unwrap(), hash);
+        assert_ne!(hash_inputs(&dir, &dir, "1.75").unwrap(), hash);
+
+        fs::write(Path::new(&dir).join("src/main.rs"), "fn main() { println!(); }\n").unwrap();
+        assert_ne!(hash_inputs(&dir, &dir, "1.74").unwrap(), hash);
+    }
+
+    #[test]
+    fn should_ignore_generated_files_and_line_endings() {
+        let root = benchmark_dir("generated", &[("Dockerfile", "FROM rust:1.74\n"), ("src/main.rs", "fn main() {}\n")]);
+        let dir = root.to_str().unwrap().to_string();
+        let hash = hash_inputs(&dir, &dir, "1.74").unwrap();
+
+        fs::create_dir_all(Path::new(&dir).join("target/release")).unwrap();
+        fs::write(Path::new(&dir).join("target/release/benchmark"), "binary").unwrap();
+        fs::write(Path::new(&dir).join("Dockerfile"), "FROM rust:1.74\r\n").unwrap();
+        assert_eq!(hash_inputs(&dir, &dir, "1.74").unwrap(), hash);
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt::Arguments;
+use std::io::{BufRead, BufReader, IsTerminal, PipeReader, Write};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Whether the human-readable output goes to stderr, keeping stdout free for machine-readable output (see `--stream-json`).
+static TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Receives the human-readable output instead of stdout / stderr while the terminal is taken over (see `--tui`).
+pub type Capture = Box<dyn Fn(&str) + Send>;
+
+static CAPTURE: Mutex<Option<Capture>> = Mutex::new(None);
+
+/// Like `println!`, but writes to stderr after `log::to_stderr()`.
+macro_rules! logln {
+    () => {
+        $crate::utils::log::write(format_args!("\n"))
+    };
+    ($($arg:tt)*) => {
+        $crate::utils::log::write(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+/// Like `print!`, but writes to stderr after `log::to_stderr()`.
+macro_rules! log {
+    ($($arg:tt)*) => {
+        $crate::utils::log::write(format_args!($($arg)*))
+    };
+}
+
+/// Like `logln!`, in the color of warnings (see `style::warning`).
+macro_rules! warnln {
+    ($($arg:tt)*) => {
+        $crate::utils::log::write(format_args!("{}\n", $crate::utils::style::warning(format_args!($($arg)*))))
+    };
+}
+
+/// Like `logln!`, in the color of failures (see `style::failure`).
+macro_rules! errorln {
+    ($($arg:tt)*) => {
+        $crate::utils::log::write(format_args!("{}\n", $crate::utils::style::failure(format_args!($($arg)*))))
+    };
+}
+
+/// Moves all further human-readable output to stderr.
+pub fn to_stderr() {
+    TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+/// Passes all further output to `capture` until `release` is called, also the output of child processes (see `child_stdout`).
+pub fn capture(capture: Capture) {
+    *CAPTURE.lock().unwrap() = Some(capture);
+}
+
+pub fn release() {
+    *CAPTURE.lock().unwrap() = None;
+}
+
+pub fn is_captured() -> bool {
+    CAPTURE.lock().unwrap().is_some()
+}
+
+pub fn write(args: Arguments) {
+    if let Some(capture) = CAPTURE.lock().unwrap().as_ref() {
+        capture(&args.to_string());
+        return;
+    }
+    // Unlike `print!`, errors (e.g. a closed pipe) are ignored: the benchmark should not fail because of logging
+    let _ = match TO_STDERR.load(Ordering::Relaxed) {
+        true => std::io::stderr().write_fmt(args),
+        false => std::io::stdout().write_fmt(args),
+    };
+}
+
+/// Whether the human-readable output goes to an interactive terminal (e.g. not to a CI log or a file).
+pub fn is_terminal() -> bool {
+    match TO_STDERR.load(Ordering::Relaxed) {
+        true => std::io::stderr().is_terminal(),
+        false => std::io::stdout().is_terminal(),
+    }
+}
+
+/// Where child processes (e.g. `docker compose up`) should write their output.
+pub fn child_stdout() -> Stdio {
+    match (is_captured(), TO_STDERR.load(Ordering::Relaxed)) {
+        (true, _) => captured_pipe(),
+        (false, true) => Stdio::from(std::io::stderr()),
+        (false, false) => Stdio::inherit(),
+    }
+}
+
+/// Where child processes should write their errors.
+pub fn child_stderr() -> Stdio {
+    match is_captured() {
+        true => captured_pipe(),
+        false => Stdio::inherit(),
+    }
+}
+
+/// A pipe whose lines are passed to the capture, until the child process (and every other end) closed it.
+fn captured_pipe() -> Stdio {
+    match std::io::pipe() {
+        Ok((reader, writer)) => {
+            thread::spawn(move || forward_lines(reader));
+            Stdio::from(writer)
+        }
+        Err(_) => Stdio::null(),
+    }
+}
+
+fn forward_lines(reader: PipeReader) {
+    for line in BufReader::new(reader).lines().map_while(Result::ok) {
+        write(format_args!("{}\n", line));
+    }
+}
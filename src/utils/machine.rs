@@ -0,0 +1,241 @@
+use std::collections::HashSet;
+use std::fs;
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use crate::utils::environment;
+
+/// Vendors / products of the DMI data of known hypervisors and cloud instances.
+const KNOWN_VIRTUALIZATIONS: [(&str, &str); 12] = [
+    ("amazon ec2", "aws"),
+    ("google", "gcp"),
+    ("microsoft corporation", "hyper-v"),
+    ("virtual machine", "hyper-v"),
+    ("vmware", "vmware"),
+    ("virtualbox", "virtualbox"),
+    ("innotek", "virtualbox"),
+    ("qemu", "qemu"),
+    ("kvm", "kvm"),
+    ("xen", "xen"),
+    ("digitalocean", "digitalocean"),
+    ("hetzner", "hetzner"),
+];
+
+/// The machine that produced the results.
+/// Every field is `None` if it could not be determined.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct MachineInfo {
+    /// Omitted with `--anonymize`.
+    pub hostname: Option<String>,
+    pub cpu_model: Option<String>,
+    pub physical_cores: Option<usize>,
+    pub logical_cores: Option<usize>,
+
+    /// Total RAM in bytes.
+    pub total_memory: Option<i64>,
+    pub os: Option<String>,
+    pub kernel: Option<String>,
+    pub docker_version: Option<String>,
+
+    /// `bare-metal` or the detected hypervisor / cloud (e.g. `kvm`, `aws`).
+    pub virtualization: Option<String>,
+}
+
+impl MachineInfo {
+    /// Collects the information about this machine.
+    pub fn collect(anonymize: bool) -> MachineInfo {
+        let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok();
+        let (cpu_model, physical_cores, logical_cores) = match &cpuinfo {
+            Some(cpuinfo) => parse_cpuinfo(cpuinfo),
+            None => (None, None, None),
+        };
+
+        MachineInfo {
+            hostname: match anonymize {
+                true => None,
+                false => Some(environment::host_name()).filter(|name| name != "unknown"),
+            },
+            cpu_model,
+            physical_cores,
+            logical_cores: logical_cores.or(std::thread::available_parallelism().ok().map(|n| n.get())),
+            total_memory: fs::read_to_string("/proc/meminfo").ok().and_then(|meminfo| parse_meminfo(&meminfo)),
+            os: fs::read_to_string("/etc/os-release").ok().and_then(|os_release| parse_os_release(&os_release)),
+            kernel: read_trimmed("/proc/sys/kernel/osrelease"),
+            docker_version: command_output("docker", &["version", "--format", "{{.Server.Version}}"]),
+            virtualization: detect_virtualization(
+                &[read_trimmed("/sys/class/dmi/id/sys_vendor"), read_trimmed("/sys/class/dmi/id/product_name")],
+                cpuinfo.as_deref(),
+            ),
+        }
+    }
+
+    /// The information `docker info --format '{{json .}}'` reports about a (remote) Docker host, `None` if it is not valid JSON.
+    /// Docker does not report the CPU model, the physical cores and the virtualization.
+    pub fn from_docker_info(json: &str, anonymize: bool) -> Option<MachineInfo> {
+        let info: serde_json::Value = serde_json::from_str(json.trim()).ok()?;
+        let text = |key: &str| info[key].as_str().map(str::to_string).filter(|value| !value.is_empty());
+        Some(MachineInfo {
+            hostname: text("Name").filter(|_| !anonymize),
+            cpu_model: None,
+            physical_cores: None,
+            logical_cores: info["NCPU"].as_u64().map(|cores| cores as usize),
+            total_memory: info["MemTotal"].as_i64(),
+            os: text("OperatingSystem"),
+            kernel: text("KernelVersion"),
+            docker_version: text("ServerVersion"),
+            virtualization: None,
+        })
+    }
+
+    pub fn print_info(&self) {
+        logln!(" - Host: {}", self.hostname.as_deref().unwrap_or("-"));
+        logln!(" - CPU: {} ({:?} cores, {:?} threads)", self.cpu_model.as_deref().unwrap_or("-"), self.physical_cores, self.logical_cores);
+        logln!(" - RAM: {:?} bytes", self.total_memory);
+        logln!(" - OS: {} (kernel {})", self.os.as_deref().unwrap_or("-"), self.kernel.as_deref().unwrap_or("-"));
+        logln!(" - Docker: {}", self.docker_version.as_deref().unwrap_or("-"));
+        logln!(" - Virtualization: {}", self.virtualization.as_deref().unwrap_or("-"));
+        logln!();
+    }
+}
+
+/// Returns the CPU model, the number of physical cores and the number of logical cores.
+fn parse_cpuinfo(cpuinfo: &str) -> (Option<String>, Option<usize>, Option<usize>) {
+    let mut model = None;
+    let mut logical = 0;
+    let mut cores: HashSet<(String, String)> = HashSet::new();
+    let mut physical_id = String::new();
+    for line in cpuinfo.lines() {
+        let (key, value) = match line.split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => continue,
+        };
+        match key {
+            "processor" => logical += 1,
+            "model name" | "Model" if model.is_none() => model = Some(value.to_string()),
+            "physical id" => physical_id = value.to_string(),
+            "core id" => {
+                cores.insert((physical_id.clone(), value.to_string()));
+            }
+            _ => {}
+        }
+    }
+
+    let logical = Some(logical).filter(|&n| n > 0);
+    // Without topology information (e.g. some ARM machines), every logical core is a physical one
+    let physical = if cores.is_empty() { logical } else { Some(cores.len()) };
+    (model, physical, logical)
+}
+
+/// Returns `MemTotal` in bytes.
+fn parse_meminfo(meminfo: &str) -> Option<i64> {
+    let line = meminfo.lines().find(|line| line.starts_with("MemTotal:"))?;
+    let kb = line.trim_start_matches("MemTotal:").trim().trim_end_matches("kB").trim().parse::<i64>().ok()?;
+    Some(kb * 1024)
+}
+
+/// Returns `PRETTY_NAME` of `/etc/os-release`, e.g. `Debian GNU/Linux 12 (bookworm)`.
+fn parse_os_release(os_release: &str) -> Option<String> {
+    let line = os_release.lines().find(|line| line.starts_with("PRETTY_NAME="))?;
+    Some(line.trim_start_matches("PRETTY_NAME=").trim_matches('"').to_string()).filter(|name| !name.is_empty())
+}
+
+/// Matches the DMI data against known hypervisors.
+/// Falls back to the `hypervisor` CPU flag for unknown ones.
+fn detect_virtualization(dmi: &[Option<String>], cpuinfo: Option<&str>) -> Option<String> {
+    for value in dmi.iter().flatten() {
+        let value = value.to_lowercase();
+        if let Some((_, name)) = KNOWN_VIRTUALIZATIONS.iter().find(|(pattern, _)| value.contains(pattern)) {
+            return Some(name.to_string());
+        }
+    }
+
+    let cpuinfo = cpuinfo?;
+    let has_hypervisor_flag = cpuinfo.lines()
+        .filter(|line| line.starts_with("flags"))
+        .any(|line| line.split_whitespace().any(|flag| flag == "hypervisor"));
+    match has_hypervisor_flag {
+        true => Some("unknown-vm".to_string()),
+        false if dmi.iter().any(|value| value.is_some()) => Some("bare-metal".to_string()),
+        // Neither DMI data nor flags available
+        false => None,
+    }
+}
+
+fn read_trimmed(path: &str) -> Option<String> {
+    fs::read_to_string(path).ok().map(|content| content.trim().to_string()).filter(|content| !content.is_empty())
+}
+
+fn command_output(command: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(command).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string()).filter(|output| !output.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CPUINFO: &str = "\
+processor\t: 0
+model name\t: AMD Ryzen 7 5800X 8-Core Processor
+physical id\t: 0
+core id\t\t: 0
+flags\t\t: fpu vme sse2
+
+processor\t: 1
+model name\t: AMD Ryzen 7 5800X 8-Core Processor
+physical id\t: 0
+core id\t\t: 0
+flags\t\t: fpu vme sse2
+
+processor\t: 2
+model name\t: AMD Ryzen 7 5800X 8-Core Processor
+physical id\t: 0
+core id\t\t: 1
+flags\t\t: fpu vme sse2
+";
+
+    #[test]
+    fn should_parse_cpuinfo() {
+        assert_eq!(parse_cpuinfo(CPUINFO), (Some("AMD Ryzen 7 5800X 8-Core Processor".to_string()), Some(2), Some(3)));
+        assert_eq!(parse_cpuinfo("processor\t: 0\nprocessor\t: 1\n"), (None, Some(2), Some(2)));
+        assert_eq!(parse_cpuinfo(""), (None, None, None));
+    }
+
+    #[test]
+    fn should_parse_meminfo() {
+        assert_eq!(parse_meminfo("MemTotal:       16318440 kB\nMemFree:         1000 kB\n"), Some(16318440 * 1024));
+        assert_eq!(parse_meminfo("MemFree: 1000 kB\n"), None);
+    }
+
+    #[test]
+    fn should_parse_os_release() {
+        assert_eq!(parse_os_release("NAME=\"Debian GNU/Linux\"\nPRETTY_NAME=\"Debian GNU/Linux 12 (bookworm)\"\n"), Some("Debian GNU/Linux 12 (bookworm)".to_string()));
+        assert_eq!(parse_os_release("NAME=Arch\n"), None);
+    }
+
+    #[test]
+    fn should_detect_virtualization() {
+        assert_eq!(detect_virtualization(&[Some("Amazon EC2".to_string()), Some("m5.large".to_string())], None), Some("aws".to_string()));
+        assert_eq!(detect_virtualization(&[Some("QEMU".to_string()), None], None), Some("qemu".to_string()));
+        assert_eq!(detect_virtualization(&[Some("ASUS".to_string()), None], Some(CPUINFO)), Some("bare-metal".to_string()));
+        assert_eq!(detect_virtualization(&[None, None], Some("flags\t: fpu hypervisor\n")), Some("unknown-vm".to_string()));
+        assert_eq!(detect_virtualization(&[None, None], None), None);
+    }
+
+    #[test]
+    fn should_parse_docker_info() {
+        let info = r#"{"Name":"bench-1","NCPU":8,"MemTotal":16777216000,"OperatingSystem":"Ubuntu 22.04.4 LTS","KernelVersion":"6.5.0","ServerVersion":""}"#;
+        assert_eq!(MachineInfo::from_docker_info(info, false), Some(MachineInfo {
+            hostname: Some("bench-1".to_string()),
+            logical_cores: Some(8),
+            total_memory: Some(16777216000),
+            os: Some("Ubuntu 22.04.4 LTS".to_string()),
+            kernel: Some("6.5.0".to_string()),
+            ..Default::default()
+        }));
+        assert_eq!(MachineInfo::from_docker_info(info, true).unwrap().hostname, None);
+        assert_eq!(MachineInfo::from_docker_info("Cannot connect to the Docker daemon", false), None);
+    }
+}
@@ -1,6 +1,51 @@
 use std::fs;
+use std::path::Path;
+use std::time::Duration;
 use indexmap::IndexMap;
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Deserialize, Deserializer};
+use serde::de::Error;
+use crate::benchmark::benchmark::Aggregation;
+use crate::utils::docker_stats::{get_bytes_of_ram, StabilizationPrefix};
+use crate::utils::format::{format_duration, parse_duration, SizeFormat};
+use crate::utils::transport::Transport;
+
+const DEFAULT_WARMUP: usize = 1;
+const DEFAULT_EXTENDED_WARMUP: usize = 3;
+const DEFAULT_ROUNDS: usize = 5;
+
+/// Defaults shared by all benchmarks of a category (e.g. `benchmark/computation/category.yaml`).
+///
+/// Precedence (highest first):
+/// 1. The value in the `benchmark.yaml` of the benchmark
+/// 2. The value in the `category.yaml` of the category
+/// 3. The built-in default
+#[derive(Serialize, Deserialize, Default)]
+pub struct CategoryMetaData {
+    /// Number of warmup rounds.
+    pub warmup: Option<usize>,
+
+    /// Number of warmup rounds for benchmarks with `extended_warmup: true`.
+    pub extended_warmup: Option<usize>,
+
+    /// Number of measured rounds.
+    pub rounds: Option<usize>,
+
+    /// Number of iterations per round (ONLY for computation benchmarks).
+    pub iterations: Option<u64>,
+
+    /// Part of each round's memory samples to discard.
+    pub memory_stabilization: Option<StabilizationPrefix>,
+
+    /// Concurrency levels to measure one after another (ONLY for web benchmarks).
+    pub concurrency_sweep: Option<Vec<usize>>,
+
+    /// Iteration counts to measure one after another (ONLY for computation benchmarks).
+    pub iterations_sweep: Option<Vec<u64>>,
+
+    /// Pause after every measured round in seconds, `0` disables it.
+    #[serde(default, deserialize_with = "deserialize_cooldown")]
+    pub cooldown_seconds: Option<Duration>,
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct BenchmarkMetaData {
@@ -14,11 +59,73 @@ pub struct BenchmarkMetaData {
     #[serde(rename = "version_regex")]
     pub language_version_regex: Option<IndexMap<String, String>>,
 
+    /// Command printing the runtime version inside the container, e.g. `node --version`.
+    pub version_command: Option<String>,
+
+    /// Command preparing the state of the benchmark inside the container once after boot, e.g. `./seed-db.sh`.
+    pub setup_command: Option<String>,
+
+    /// Labels to select the benchmark with `--tags` and `--exclude-tags`, e.g. `[fast, needs-db]`.
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Memory budget, either bytes or with a unit (e.g. `512MiB`). The benchmark fails if its `memory_p99` exceeds it.
+    #[serde(default, deserialize_with = "deserialize_bytes")]
+    pub max_memory: Option<i64>,
+
+    /// Wall-clock budget of the whole benchmark (e.g. `30m`), like `--benchmark-timeout`. The benchmark fails once it runs longer.
+    #[serde(default, deserialize_with = "deserialize_duration")]
+    pub max_duration: Option<Duration>,
+
+    /// Measured rounds further than this many standard deviations from the mean of the warmup rounds are re-run (e.g. `3`),
+    /// see `benchmark::is_suspect`.
+    #[serde(default, deserialize_with = "deserialize_sigma")]
+    pub outlier_sigma: Option<f64>,
+
+    /// Pause after every measured round in seconds (e.g. `0.5`), `0` disables it for benchmarks that need no cooldown.
+    #[serde(default, deserialize_with = "deserialize_cooldown")]
+    pub cooldown_seconds: Option<Duration>,
+
     #[serde(default = "default_as_false")]
     pub extended_warmup: bool,
 
+    pub warmup: Option<usize>,
+
     pub runs: Option<usize>,
 
+    pub memory_stabilization: Option<StabilizationPrefix>,
+
+    /// Whether the benchmark serves its self-reported memory (see `self_reported_memory::MEMORY_PATH`).
+    #[serde(default = "default_as_false")]
+    pub self_reported_memory: bool,
+
+    /// Path POSTed after every round to reset the state of the benchmark, e.g. `/reset`.
+    pub reset_path: Option<String>,
+
+    /// Whether the benchmark serves its GC counters (see `gc_metrics::GC_PATH`).
+    #[serde(default = "default_as_false")]
+    pub gc_metrics: bool,
+
+    /// Whether the benchmark serves its allocation counters (see `allocation_metrics::ALLOCATIONS_PATH`).
+    #[serde(default = "default_as_false")]
+    pub allocation_metrics: bool,
+
+    /// Aggregation of additional data keys over the rounds, e.g. `gc_pause_count: sum`.
+    #[serde(default)]
+    pub aggregation: IndexMap<String, Aggregation>,
+
+    /// Whether to measure the first round after boot separately as `cold_start_ms`.
+    #[serde(default = "default_as_false")]
+    pub cold_start: bool,
+
+    pub iterations: Option<u64>,
+
+    /// Measures every number of iterations instead of `iterations`, e.g. `[10000, 100000, 1000000]`.
+    pub iterations_sweep: Option<Vec<u64>>,
+
+    #[serde(default)]
+    pub transport: Transport,
+
     pub copy: Option<Vec<CopyValue>>,
 }
 
@@ -34,6 +141,33 @@ pub struct WebBenchmarkMetaData {
     #[serde(rename = "version_regex")]
     pub language_version_regex: Option<IndexMap<String, String>>,
 
+    /// Command printing the runtime version inside the container, e.g. `node --version`.
+    pub version_command: Option<String>,
+
+    /// Command preparing the state of the benchmark inside the container once after boot, e.g. `./seed-db.sh`.
+    pub setup_command: Option<String>,
+
+    /// Labels to select the benchmark with `--tags` and `--exclude-tags`, e.g. `[fast, needs-db]`.
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Memory budget, either bytes or with a unit (e.g. `512MiB`). The benchmark fails if its `memory_p99` exceeds it.
+    #[serde(default, deserialize_with = "deserialize_bytes")]
+    pub max_memory: Option<i64>,
+
+    /// Wall-clock budget of the whole benchmark (e.g. `30m`), like `--benchmark-timeout`. The benchmark fails once it runs longer.
+    #[serde(default, deserialize_with = "deserialize_duration")]
+    pub max_duration: Option<Duration>,
+
+    /// Measured rounds further than this many standard deviations from the mean of the warmup rounds are re-run (e.g. `3`),
+    /// see `benchmark::is_suspect`.
+    #[serde(default, deserialize_with = "deserialize_sigma")]
+    pub outlier_sigma: Option<f64>,
+
+    /// Pause after every measured round in seconds (e.g. `0.5`), `0` disables it for benchmarks that need no cooldown.
+    #[serde(default, deserialize_with = "deserialize_cooldown")]
+    pub cooldown_seconds: Option<Duration>,
+
     pub framework: String,
 
     #[serde(default = "default_as_false")]
@@ -50,11 +184,87 @@ pub struct WebBenchmarkMetaData {
     #[serde(default = "default_as_false")]
     pub extended_warmup: bool,
 
+    pub warmup: Option<usize>,
+
+    pub runs: Option<usize>,
+
+    pub memory_stabilization: Option<StabilizationPrefix>,
+
+    /// Whether the benchmark serves its self-reported memory (see `self_reported_memory::MEMORY_PATH`).
+    #[serde(default = "default_as_false")]
+    pub self_reported_memory: bool,
+
+    /// Path POSTed after every round to reset the state of the benchmark, e.g. `/reset`.
+    pub reset_path: Option<String>,
+
+    /// Whether the benchmark serves its GC counters (see `gc_metrics::GC_PATH`).
+    #[serde(default = "default_as_false")]
+    pub gc_metrics: bool,
+
+    /// Whether the benchmark serves its allocation counters (see `allocation_metrics::ALLOCATIONS_PATH`).
+    #[serde(default = "default_as_false")]
+    pub allocation_metrics: bool,
+
+    /// Aggregation of additional data keys over the rounds, e.g. `gc_pause_count: sum`.
+    #[serde(default)]
+    pub aggregation: IndexMap<String, Aggregation>,
+
     pub concurrency: Option<usize>,
 
+    /// Measures every concurrency level instead of `concurrency`, e.g. `[1, 2, 4, 8]`.
+    pub concurrency_sweep: Option<Vec<usize>>,
+
+    /// Warmup rounds of single workloads (`element` or `shells`), e.g. `shells: 3`. Every workload then warms up on its own
+    /// before the measured rounds instead of the `warmup` rounds of all workloads, the ones without an entry with `warmup` rounds.
+    #[serde(default)]
+    pub workload_warmup: IndexMap<String, usize>,
+
     pub copy: Option<Vec<CopyValue>>,
 }
 
+/// The file of a benchmark directory with the meta data of its language, see `BenchmarkManifest`.
+pub(crate) const MANIFEST_FILE: &str = "_benchmark.yaml";
+
+/// The meta data of the language of a benchmark for the reports and the website, e.g. `benchmark/web/rust/axum-0.7/_benchmark.yaml`.
+/// Optional, the results of a benchmark without it have no `manifest`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct BenchmarkManifest {
+    /// Display name, e.g. `C#`. The `language` of the benchmark has to match it.
+    #[serde(default)]
+    pub language: String,
+
+    /// e.g. `https://www.rust-lang.org`
+    #[serde(default)]
+    pub website: String,
+
+    /// How the benchmark runs its code.
+    #[serde(default)]
+    pub mode: CompilationMode,
+
+    /// The versions the benchmark may use, e.g. `['1.40', '1.79']`, any if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub versions: Vec<String>,
+
+    /// Added to the `tags` of the benchmark, e.g. `[compiled]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// How a benchmark runs its code, the `mode` of its `BenchmarkManifest`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompilationMode {
+    /// The usual way of the language, e.g. compiled for Rust and interpreted for Python.
+    #[default]
+    Default,
+
+    /// Compiled ahead of time, e.g. a native image of Java.
+    Aot,
+
+    /// Compiled just in time, e.g. Dart run from its sources.
+    Jit,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(untagged)]
 pub(crate) enum CopyValue {
@@ -66,36 +276,173 @@ fn default_as_false() -> bool {
     false
 }
 
+/// Bytes as number or with a unit of `docker stats` (`KiB`, `MiB` or `GiB`), e.g. `512MiB`.
+fn deserialize_bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+    match Option::<serde_yaml::Value>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(serde_yaml::Value::Number(bytes)) => bytes.as_i64().map(Some).ok_or_else(|| D::Error::custom(format!("invalid number of bytes {}", bytes))),
+        Some(serde_yaml::Value::String(size)) => get_bytes_of_ram(&size).map(Some).ok_or_else(|| D::Error::custom(format!("invalid size {:?}, expected e.g. 512MiB", size))),
+        Some(other) => Err(D::Error::custom(format!("invalid size {:?}, expected e.g. 512MiB", other))),
+    }
+}
+
+/// A duration with the unit `s`, `m`, `h` or `d`, e.g. `30m`, see `format::parse_duration`.
+fn deserialize_duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    match Option::<String>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(duration) => parse_duration(&duration).map(Some).map_err(|e| D::Error::custom(format!("invalid duration: {}", e))),
+    }
+}
+
+/// A positive number of standard deviations.
+fn deserialize_sigma<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    match Option::<f64>::deserialize(deserializer)? {
+        Some(sigma) if !sigma.is_finite() || sigma <= 0.0 => Err(D::Error::custom(format!("invalid outlier_sigma {}, expected a positive number", sigma))),
+        sigma => Ok(sigma),
+    }
+}
+
+/// A non-negative number of seconds, e.g. `0.5`, that fits into a `Duration`.
+fn deserialize_cooldown<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    match Option::<f64>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(seconds) => Duration::try_from_secs_f64(seconds).map(Some)
+            .map_err(|_| D::Error::custom(format!("invalid cooldown_seconds {}, expected a non-negative number", seconds))),
+    }
+}
+
+impl CategoryMetaData {
+    pub fn print_info(&self) {
+        logln!(" - Category warmup: {:?}", self.warmup);
+        logln!(" - Category extended warmup: {:?}", self.extended_warmup);
+        logln!(" - Category rounds: {:?}", self.rounds);
+        logln!(" - Category iterations: {:?}", self.iterations);
+        logln!(" - Category memory stabilization: {:?}", self.memory_stabilization);
+        logln!(" - Category concurrency sweep: {:?}", self.concurrency_sweep);
+        logln!(" - Category iterations sweep: {:?}", self.iterations_sweep);
+        logln!(" - Category cooldown: {:?}", self.cooldown_seconds);
+        logln!();
+    }
+
+    /// Reads the `category.yaml` of the given category directory.
+    /// Returns the default (empty) config if the file does not exist.
+    pub fn read_from_directory(dir: &str) -> Result<CategoryMetaData, serde_yaml::Error> {
+        let path = format!("{}/category.yaml", dir);
+        if !Path::new(&path).exists() {
+            return Ok(CategoryMetaData::default());
+        }
+        let contents = fs::read_to_string(&path).expect("Failed to read category.yaml");
+        serde_yaml::from_str(&contents)
+    }
+
+    /// Resolves the number of warmup rounds of a benchmark.
+    /// An explicit `warmup` of the benchmark wins over its `extended_warmup` flag.
+    pub fn resolve_warmup(&self, warmup: Option<usize>, extended_warmup: bool) -> usize {
+        match (warmup, extended_warmup) {
+            (Some(warmup), _) => warmup,
+            (None, true) => self.extended_warmup.unwrap_or(DEFAULT_EXTENDED_WARMUP),
+            (None, false) => self.warmup.unwrap_or(DEFAULT_WARMUP),
+        }
+    }
+
+    /// Resolves the number of measured rounds of a benchmark.
+    pub fn resolve_rounds(&self, runs: Option<usize>) -> usize {
+        runs.or(self.rounds).unwrap_or(DEFAULT_ROUNDS)
+    }
+
+    /// Resolves the pause after every measured round of a benchmark.
+    pub fn resolve_cooldown(&self, cooldown: Option<Duration>, default: Duration) -> Duration {
+        cooldown.or(self.cooldown_seconds).unwrap_or(default)
+    }
+
+    /// Resolves the part of each round's memory samples to discard.
+    pub fn resolve_memory_stabilization(&self, memory_stabilization: Option<StabilizationPrefix>) -> Option<StabilizationPrefix> {
+        memory_stabilization.or(self.memory_stabilization)
+    }
+
+    /// Resolves the concurrency levels to sweep, `None` to only measure a single concurrency.
+    pub fn resolve_concurrency_sweep(&self, concurrency_sweep: &Option<Vec<usize>>) -> Option<Vec<usize>> {
+        concurrency_sweep.clone().or(self.concurrency_sweep.clone()).filter(|levels| !levels.is_empty())
+    }
+
+    /// Resolves the iteration counts to sweep, `None` to only measure a single number of iterations.
+    pub fn resolve_iterations_sweep(&self, iterations_sweep: &Option<Vec<u64>>) -> Option<Vec<u64>> {
+        iterations_sweep.clone().or(self.iterations_sweep.clone()).filter(|levels| !levels.is_empty())
+    }
+
+    /// Resolves the number of iterations per round of a benchmark.
+    pub fn resolve_iterations(&self, iterations: Option<u64>, default: u64) -> u64 {
+        iterations.or(self.iterations).unwrap_or(default)
+    }
+}
+
 impl BenchmarkMetaData {
     pub fn print_info(&self) {
-        println!(" - Language: {}", self.language);
-        println!(" - Mode: {}", self.mode);
-        println!(" - Language version: {:?}", self.language_version);
-        println!(" - Language version regex: {}", self.language_version_regex.debug_serialize());
-        println!();
+        logln!(" - Language: {}", self.language);
+        logln!(" - Mode: {}", self.mode);
+        logln!(" - Language version: {:?}", self.language_version);
+        logln!(" - Language version regex: {}", self.language_version_regex.debug_serialize());
+        logln!(" - Version command: {:?}", self.version_command);
+        logln!(" - Setup command: {:?}", self.setup_command);
+        logln!(" - Tags: {:?}", self.tags);
+        logln!(" - Max memory: {}", self.max_memory.bytes_to_string());
+        logln!(" - Max duration: {}", self.max_duration.map(format_duration).unwrap_or("-".to_string()));
+        logln!(" - Outlier sigma: {:?}", self.outlier_sigma);
+        logln!(" - Cooldown: {:?}", self.cooldown_seconds);
+        logln!(" - Transport: {:?}", self.transport);
+        logln!(" - Self-reported memory: {}", self.self_reported_memory);
+        logln!(" - Reset path: {:?}", self.reset_path);
+        logln!(" - GC metrics: {}", self.gc_metrics);
+        logln!(" - Allocation metrics: {}", self.allocation_metrics);
+        logln!(" - Aggregation: {:?}", self.aggregation);
+        logln!(" - Cold start: {}", self.cold_start);
+        logln!();
     }
 
     pub fn read_from_directory(dir: &str) -> Result<BenchmarkMetaData, serde_yaml::Error> {
         let contents = fs::read_to_string(format!("{}/benchmark.yaml", dir)).expect("Failed to read benchmark.yaml");
-        serde_yaml::from_str(&contents)
+        BenchmarkMetaData::parse(&contents)
+    }
+
+    /// Parses a `benchmark.yaml`, a `reset_path` is only allowed with the HTTP transport as it is POSTed.
+    pub fn parse(contents: &str) -> Result<BenchmarkMetaData, serde_yaml::Error> {
+        let meta_data: BenchmarkMetaData = serde_yaml::from_str(contents)?;
+        if meta_data.reset_path.is_some() && meta_data.transport == Transport::Tcp {
+            return Err(serde_yaml::Error::custom("reset_path is not supported with transport: tcp"));
+        }
+        Ok(meta_data)
     }
 }
 
 impl WebBenchmarkMetaData {
     pub fn print_info(&self) {
-        println!(" - Language: {}", self.language);
-        println!(" - Mode: {}", self.mode);
-        println!(" - Language version: {:?}", self.language_version);
-        println!(" - Language version regex: {}", self.language_version_regex.debug_serialize());
-        println!(" - Framework: {}", self.framework);
-        println!(" - Framework stdlib: {}", self.framework_stdlib);
-        println!(" - Framework website: {}", self.framework_website);
-        println!(" - Framework flavor: {}", self.framework_flavor);
-        println!(" - Framework version: {:?}", self.framework_version);
-        println!(" - Framework version regex: {:?}", self.framework_version_regex.debug_serialize());
-        println!(" - Concurrency: {:?}", self.concurrency);
-        println!(" - Copy: {:?}", self.copy);
-        println!();
+        logln!(" - Language: {}", self.language);
+        logln!(" - Mode: {}", self.mode);
+        logln!(" - Language version: {:?}", self.language_version);
+        logln!(" - Language version regex: {}", self.language_version_regex.debug_serialize());
+        logln!(" - Version command: {:?}", self.version_command);
+        logln!(" - Setup command: {:?}", self.setup_command);
+        logln!(" - Tags: {:?}", self.tags);
+        logln!(" - Max memory: {}", self.max_memory.bytes_to_string());
+        logln!(" - Max duration: {}", self.max_duration.map(format_duration).unwrap_or("-".to_string()));
+        logln!(" - Outlier sigma: {:?}", self.outlier_sigma);
+        logln!(" - Cooldown: {:?}", self.cooldown_seconds);
+        logln!(" - Framework: {}", self.framework);
+        logln!(" - Framework stdlib: {}", self.framework_stdlib);
+        logln!(" - Framework website: {}", self.framework_website);
+        logln!(" - Framework flavor: {}", self.framework_flavor);
+        logln!(" - Framework version: {:?}", self.framework_version);
+        logln!(" - Framework version regex: {:?}", self.framework_version_regex.debug_serialize());
+        logln!(" - Concurrency: {:?}", self.concurrency);
+        logln!(" - Concurrency sweep: {:?}", self.concurrency_sweep);
+        logln!(" - Workload warmup: {:?}", self.workload_warmup);
+        logln!(" - Self-reported memory: {}", self.self_reported_memory);
+        logln!(" - Reset path: {:?}", self.reset_path);
+        logln!(" - GC metrics: {}", self.gc_metrics);
+        logln!(" - Allocation metrics: {}", self.allocation_metrics);
+        logln!(" - Aggregation: {:?}", self.aggregation);
+        logln!(" - Copy: {:?}", self.copy);
+        logln!();
     }
 
     pub fn read_from_directory(dir: &str) -> Result<WebBenchmarkMetaData, serde_yaml::Error> {
@@ -116,3 +463,162 @@ impl VersionRegexSerializer for Option<IndexMap<String, String>> {
         }
     }
 }
+
+impl BenchmarkManifest {
+    /// Reads the manifest of the benchmark in `dir`, `None` if it has none.
+    /// Fails if it is invalid or misses its `language` or `website`.
+    pub fn read_for_benchmark(dir: &str) -> Result<Option<BenchmarkManifest>, String> {
+        let path = Path::new(dir).join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&path).map_err(|e| format!("could not read {}: {}", MANIFEST_FILE, e))?;
+        BenchmarkManifest::parse(&contents).map(Some)
+    }
+
+    fn parse(contents: &str) -> Result<BenchmarkManifest, String> {
+        let manifest: BenchmarkManifest = serde_yaml::from_str(contents).map_err(|e| format!("invalid {}: {}", MANIFEST_FILE, e))?;
+        for (field, value) in [("language", &manifest.language), ("website", &manifest.website)] {
+            if value.trim().is_empty() {
+                return Err(format!("missing `{}` in {}", field, MANIFEST_FILE));
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// What of the meta data of the benchmark contradicts the manifest.
+    pub fn check_benchmark(&self, language: &str, versions: &[String]) -> Vec<String> {
+        let mut problems = Vec::new();
+        if language != self.language {
+            problems.push(format!("`language` {:?} differs from {:?} of {}", language, self.language, MANIFEST_FILE));
+        }
+        if !self.versions.is_empty() {
+            for version in versions.iter().filter(|version| !self.versions.contains(version)) {
+                problems.push(format!("version {} is not one of the `versions` of {}", version, MANIFEST_FILE));
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_use_built_in_defaults() {
+        let category = CategoryMetaData::default();
+        assert_eq!(category.resolve_warmup(None, false), DEFAULT_WARMUP);
+        assert_eq!(category.resolve_warmup(None, true), DEFAULT_EXTENDED_WARMUP);
+        assert_eq!(category.resolve_rounds(None), DEFAULT_ROUNDS);
+        assert_eq!(category.resolve_iterations(None, 42), 42);
+        assert_eq!(category.resolve_memory_stabilization(None), None);
+    }
+
+    #[test]
+    fn should_prefer_category_over_defaults() {
+        let category: CategoryMetaData = serde_yaml::from_str("warmup: 2\nextended_warmup: 6\nrounds: 10\niterations: 1000\n").unwrap();
+        assert_eq!(category.resolve_warmup(None, false), 2);
+        assert_eq!(category.resolve_warmup(None, true), 6);
+        assert_eq!(category.resolve_rounds(None), 10);
+        assert_eq!(category.resolve_iterations(None, 42), 1000);
+    }
+
+    #[test]
+    fn should_prefer_benchmark_over_category() {
+        let category: CategoryMetaData = serde_yaml::from_str("warmup: 2\nextended_warmup: 6\nrounds: 10\niterations: 1000\n").unwrap();
+        assert_eq!(category.resolve_warmup(Some(0), false), 0);
+        assert_eq!(category.resolve_warmup(Some(4), true), 4);
+        assert_eq!(category.resolve_rounds(Some(3)), 3);
+        assert_eq!(category.resolve_iterations(Some(7), 42), 7);
+    }
+
+    #[test]
+    fn should_parse_benchmark_manifest() {
+        let manifest = BenchmarkManifest::parse("language: Dart\nwebsite: https://dart.dev\nmode: jit\nversions: ['2.14', '3.2']\ntags: [compiled]\n").unwrap();
+        assert_eq!(manifest.website, "https://dart.dev");
+        assert_eq!(manifest.mode, CompilationMode::Jit);
+        assert_eq!(manifest.tags, vec!["compiled"]);
+        assert_eq!(BenchmarkManifest::parse("language: Dart\nwebsite: https://dart.dev\n").unwrap().mode, CompilationMode::Default);
+        assert_eq!(BenchmarkManifest::parse("language: Dart\n"), Err("missing `website` in _benchmark.yaml".to_string()));
+        assert!(BenchmarkManifest::parse("language: [Dart]\n").unwrap_err().starts_with("invalid _benchmark.yaml: "));
+        assert!(BenchmarkManifest::parse("language: Dart\nwebsite: https://dart.dev\nmode: JIT\n").unwrap_err().contains("unknown variant `JIT`"));
+
+        assert!(manifest.check_benchmark("Dart", &["2.14".to_string()]).is_empty());
+        assert_eq!(manifest.check_benchmark("dart", &["2.14".to_string(), "2.19".to_string()]), vec![
+            "`language` \"dart\" differs from \"Dart\" of _benchmark.yaml",
+            "version 2.19 is not one of the `versions` of _benchmark.yaml",
+        ]);
+    }
+
+    #[test]
+    fn should_resolve_cooldown() {
+        let default = Duration::from_secs(2);
+        assert_eq!(CategoryMetaData::default().resolve_cooldown(None, default), default);
+
+        let category: CategoryMetaData = serde_yaml::from_str("cooldown_seconds: 0.5").unwrap();
+        assert_eq!(category.resolve_cooldown(None, default), Duration::from_millis(500));
+        assert_eq!(category.resolve_cooldown(Some(Duration::ZERO), default), Duration::ZERO);
+
+        let cooldown = |yaml: &str| serde_yaml::from_str::<BenchmarkMetaData>(&format!("language: Rust\nmode: Native\nversion: ['1.74']\n{}", yaml)).map(|meta_data| meta_data.cooldown_seconds);
+        assert_eq!(cooldown("cooldown_seconds: 0\n").unwrap(), Some(Duration::ZERO));
+        assert!(cooldown("cooldown_seconds: -1\n").unwrap_err().to_string().contains("expected a non-negative number"));
+        assert!(cooldown("cooldown_seconds: 1e30\n").is_err());
+    }
+
+    #[test]
+    fn should_resolve_concurrency_sweep() {
+        let category: CategoryMetaData = serde_yaml::from_str("concurrency_sweep: [1, 2, 4]").unwrap();
+        assert_eq!(category.resolve_concurrency_sweep(&None), Some(vec![1, 2, 4]));
+        assert_eq!(category.resolve_concurrency_sweep(&Some(vec![8])), Some(vec![8]));
+        assert_eq!(CategoryMetaData::default().resolve_concurrency_sweep(&Some(vec![])), None);
+    }
+
+    #[test]
+    fn should_resolve_iterations_sweep() {
+        let category: CategoryMetaData = serde_yaml::from_str("iterations_sweep: [10000, 100000]").unwrap();
+        assert_eq!(category.resolve_iterations_sweep(&None), Some(vec![10000, 100000]));
+        assert_eq!(category.resolve_iterations_sweep(&Some(vec![1000])), Some(vec![1000]));
+        assert_eq!(CategoryMetaData::default().resolve_iterations_sweep(&None), None);
+    }
+
+    #[test]
+    fn should_resolve_memory_stabilization() {
+        let category: CategoryMetaData = serde_yaml::from_str("memory_stabilization:\n  samples: 2\n").unwrap();
+        assert_eq!(category.resolve_memory_stabilization(None), Some(StabilizationPrefix { samples: Some(2), ms: None }));
+        assert_eq!(category.resolve_memory_stabilization(Some(StabilizationPrefix { samples: None, ms: Some(500) })), Some(StabilizationPrefix { samples: None, ms: Some(500) }));
+    }
+
+    #[test]
+    fn should_parse_max_memory() {
+        let max_memory = |yaml: &str| serde_yaml::from_str::<BenchmarkMetaData>(&format!("language: Rust\nmode: Native\nversion: ['1.74']\n{}", yaml)).map(|meta_data| meta_data.max_memory);
+        assert_eq!(max_memory("").unwrap(), None);
+        assert_eq!(max_memory("max_memory: 1048576\n").unwrap(), Some(1024 * 1024));
+        assert_eq!(max_memory("max_memory: 512MiB\n").unwrap(), Some(512 * 1024 * 1024));
+        assert!(max_memory("max_memory: 512 apples\n").unwrap_err().to_string().contains("invalid size"));
+    }
+
+    #[test]
+    fn should_parse_max_duration() {
+        let max_duration = |yaml: &str| serde_yaml::from_str::<BenchmarkMetaData>(&format!("language: Rust\nmode: Native\nversion: ['1.74']\n{}", yaml)).map(|meta_data| meta_data.max_duration);
+        assert_eq!(max_duration("").unwrap(), None);
+        assert_eq!(max_duration("max_duration: 30m\n").unwrap(), Some(Duration::from_secs(30 * 60)));
+        assert!(max_duration("max_duration: 30\n").unwrap_err().to_string().contains("invalid duration"));
+    }
+
+    #[test]
+    fn should_parse_outlier_sigma() {
+        let outlier_sigma = |yaml: &str| serde_yaml::from_str::<BenchmarkMetaData>(&format!("language: Rust\nmode: Native\nversion: ['1.74']\n{}", yaml)).map(|meta_data| meta_data.outlier_sigma);
+        assert_eq!(outlier_sigma("").unwrap(), None);
+        assert_eq!(outlier_sigma("outlier_sigma: 3\n").unwrap(), Some(3.0));
+        assert!(outlier_sigma("outlier_sigma: 0\n").unwrap_err().to_string().contains("expected a positive number"));
+    }
+
+    #[test]
+    fn should_reject_reset_path_with_tcp_transport() {
+        let parse = |yaml: &str| BenchmarkMetaData::parse(&format!("language: Rust\nmode: Native\nversion: ['1.74']\n{}", yaml));
+        assert_eq!(parse("reset_path: /reset\n").unwrap().reset_path.as_deref(), Some("/reset"));
+        assert_eq!(parse("transport: tcp\n").unwrap().transport, Transport::Tcp);
+        assert_eq!(parse("reset_path: /reset\ntransport: tcp\n").err().map(|e| e.to_string()), Some("reset_path is not supported with transport: tcp".to_string()));
+    }
+}
@@ -1,12 +1,40 @@
+// Declared first, so its macros are available in all other modules
+#[macro_use]
+pub mod log;
+pub mod allocation_metrics;
+pub mod compression;
 pub mod copy_files;
+pub mod docker_context;
 pub mod docker_runner;
 pub mod docker_stats;
+pub mod environment;
+pub mod exit_code;
+pub mod format;
+pub mod gc_metrics;
+pub mod glob;
 pub mod http_load_tester;
+pub mod image;
+pub mod input_hash;
+pub mod machine;
 pub mod meta_data_parser;
 pub mod panic;
 pub mod percentile;
+pub mod result_diff;
 pub mod result_reader;
+pub mod reset;
 pub mod result_writer;
+pub mod run_metadata;
+pub mod self_reported_memory;
 pub mod serialization;
+pub mod server_timing;
+pub mod style;
+pub mod suite_config;
+pub mod table;
+#[cfg(test)]
+pub mod test_dir;
+#[cfg(test)]
+pub mod test_entry;
+pub mod toml;
+pub mod transport;
 pub mod version;
 pub mod version_migrator;
@@ -1,24 +1,32 @@
 use crate::utils::panic::panic_with_stacktrace;
 
+// The values do not need to be sorted. They are sorted in a copy, the slice of the caller is never modified.
+
 /// Returns the 1st percentile of the given values.
 /// Avoids MIN if possible.
-pub fn p1<T: Copy>(values: &Vec<T>) -> T {
-    p_higher(values, 0.01)
+pub fn p1<T: Copy + Ord>(values: &[T]) -> T {
+    p_higher(&sorted(values), 0.01)
 }
 
 /// Returns the 50th percentile of the given values.
 /// Avoids MAX if possible.
-pub fn p50<T: Copy>(values: &Vec<T>) -> T {
-    p_lower(values, 0.5)
+pub fn p50<T: Copy + Ord>(values: &[T]) -> T {
+    p_lower(&sorted(values), 0.5)
 }
 
 /// Returns the 99th percentile of the given values.
 /// Avoids MAX if possible.
-pub fn p99<T: Copy>(values: &Vec<T>) -> T {
-    p_lower(values, 0.99)
+pub fn p99<T: Copy + Ord>(values: &[T]) -> T {
+    p_lower(&sorted(values), 0.99)
+}
+
+fn sorted<T: Copy + Ord>(values: &[T]) -> Vec<T> {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    sorted
 }
 
-fn p_lower<T: Copy>(values: &Vec<T>, percentile: f64) -> T {
+fn p_lower<T: Copy>(values: &[T], percentile: f64) -> T {
     if values.is_empty() {
         panic_with_stacktrace("Cannot calculate percentile of empty vector");
     }
@@ -30,7 +38,7 @@ fn p_lower<T: Copy>(values: &Vec<T>, percentile: f64) -> T {
     values[index]
 }
 
-fn p_higher<T: Copy>(values: &Vec<T>, percentile: f64) -> T {
+fn p_higher<T: Copy>(values: &[T], percentile: f64) -> T {
     if values.is_empty() {
         panic_with_stacktrace("Cannot calculate percentile of empty vector");
     }
@@ -122,6 +130,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn should_not_require_sorted_values() {
+        let values = vec![7, 3, 10, 1, 5, 2, 9, 4, 8, 6];
+        assert_eq!(p1(&values), 2);
+        assert_eq!(p50(&values), 5);
+        assert_eq!(p99(&values), 9);
+    }
+
+    #[test]
+    fn should_not_modify_values() {
+        let values = vec![7, 3, 10, 1, 5, 2, 9, 4, 8, 6];
+        let copy = values.clone();
+        p1(&values);
+        p50(&values);
+        p99(&values);
+        assert_eq!(values, copy);
+    }
+
     mod test_p99 {
         use super::*;
 
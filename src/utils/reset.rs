@@ -0,0 +1,14 @@
+use std::time::Duration;
+use crate::benchmark::error::BenchmarkError;
+
+/// Sends `POST <url>` to reset the state of the service (e.g. truncate a cache or a database) between rounds.
+pub fn post(url: &str) -> Result<(), BenchmarkError> {
+    let response = reqwest::blocking::Client::new()
+        .post(url)
+        .timeout(Duration::from_secs(30))
+        .send()?;
+    if !response.status().is_success() {
+        return Err(BenchmarkError::HttpStatus { status: response.status().as_u16(), url: url.to_string() });
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use std::fmt::{Display, Formatter};
+use crate::benchmark::benchmark::{AdditionalData, BenchmarkResult};
+
+/// A difference between the `additional_data` of two results.
+#[derive(Debug, PartialEq)]
+pub enum AdditionalDataChange {
+    Added { key: String, value: AdditionalData },
+    Removed { key: String, value: AdditionalData },
+    Changed { key: String, old: AdditionalData, new: AdditionalData },
+}
+
+impl Display for AdditionalDataChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AdditionalDataChange::Added { key, value } => write!(f, "+ {}: {}", key, value),
+            AdditionalDataChange::Removed { key, value } => write!(f, "- {}: {}", key, value),
+            AdditionalDataChange::Changed { key, old, new } => write!(f, "~ {}: {} -> {}", key, old, new),
+        }
+    }
+}
+
+/// Compares the `additional_data` of two results.
+/// Keys are reported in the order of `old`, followed by the keys only present in `new`.
+pub fn diff_additional_data(old: &BenchmarkResult, new: &BenchmarkResult) -> Vec<AdditionalDataChange> {
+    let mut changes: Vec<AdditionalDataChange> = Vec::new();
+
+    for (key, old_value) in &old.additional_data {
+        match new.additional_data.get(key) {
+            Some(new_value) if new_value != old_value => changes.push(AdditionalDataChange::Changed {
+                key: key.clone(),
+                old: old_value.clone(),
+                new: new_value.clone(),
+            }),
+            Some(_) => {}
+            None => changes.push(AdditionalDataChange::Removed {
+                key: key.clone(),
+                value: old_value.clone(),
+            }),
+        }
+    }
+
+    for (key, new_value) in &new.additional_data {
+        if !old.additional_data.contains_key(key) {
+            changes.push(AdditionalDataChange::Added {
+                key: key.clone(),
+                value: new_value.clone(),
+            });
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indexmap::IndexMap;
+
+    fn result(additional_data: &[(&str, i32)]) -> BenchmarkResult {
+        BenchmarkResult {
+            time_median: 0,
+            memory_median: Some(0),
+            memory_p99: Some(0),
+            additional_data: additional_data.iter()
+                .map(|(k, v)| (k.to_string(), AdditionalData::Int(*v)))
+                .collect::<IndexMap<String, AdditionalData>>(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn should_report_nothing_for_equal_data() {
+        assert_eq!(diff_additional_data(&result(&[("a", 1)]), &result(&[("a", 1)])), vec![]);
+    }
+
+    #[test]
+    fn should_report_added_removed_and_changed_keys() {
+        let changes = diff_additional_data(
+            &result(&[("same", 1), ("changed", 2), ("removed", 3)]),
+            &result(&[("same", 1), ("changed", 5), ("added", 4)]),
+        );
+
+        assert_eq!(changes, vec![
+            AdditionalDataChange::Changed { key: "changed".to_string(), old: AdditionalData::Int(2), new: AdditionalData::Int(5) },
+            AdditionalDataChange::Removed { key: "removed".to_string(), value: AdditionalData::Int(3) },
+            AdditionalDataChange::Added { key: "added".to_string(), value: AdditionalData::Int(4) },
+        ]);
+        assert_eq!(changes.iter().map(|c| c.to_string()).collect::<Vec<String>>(), vec![
+            "~ changed: 2 -> 5",
+            "- removed: 3",
+            "+ added: 4",
+        ]);
+    }
+}
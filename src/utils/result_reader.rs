@@ -1,6 +1,7 @@
 use std::collections::{HashMap, HashSet};
 use std::fs;
 
+#[derive(Default)]
 pub struct ResultMap {
     /// Map of language to a set of directories that have been benchmarked
     pub computation: HashMap<String, HashSet<String>>,
@@ -9,15 +10,6 @@ pub struct ResultMap {
     pub web: HashMap<String, HashSet<String>>,
 }
 
-impl Default for ResultMap {
-    fn default() -> Self {
-        ResultMap {
-            computation: HashMap::new(),
-            web: HashMap::new(),
-        }
-    }
-}
-
 pub fn read_existing_result_map() -> ResultMap {
     let mut result_map = ResultMap {
         computation: HashMap::new(),
@@ -31,7 +23,7 @@ pub fn read_existing_result_map() -> ResultMap {
 }
 
 fn read_from_csv(csv_path: &str, dir_column: usize, map: &mut HashMap<String, HashSet<String>>) {
-    let csv_content: String = fs::read_to_string(csv_path).unwrap_or(String::new());
+    let csv_content: String = fs::read_to_string(csv_path).unwrap_or_default();
     read_from_csv_content(csv_content.as_str(), dir_column, map);
 }
 
@@ -50,7 +42,7 @@ fn read_from_csv_content(csv_content: &str, dir_column: usize, map: &mut HashMap
         let language = full_dir[0];
         let dir = full_dir[1];
 
-        let language_map = map.entry(language.to_string()).or_insert(HashSet::new());
+        let language_map = map.entry(language.to_string()).or_default();
         language_map.insert(dir.to_string());
     });
 }
@@ -21,12 +21,12 @@ pub fn write_result_to_file(
     values: &Vec<(&str, &str)>,
     on_conflict: for<'a> fn(&'a [&'a str], &'a [&'a str]) -> &'a [&'a str],
 ) -> io::Result<()> {
-    println!(" -> Writing result:");
+    logln!(" -> Writing result:");
     for (key, value) in descriptors {
-        println!("    - {}: {}", key, value);
+        logln!("    - {}: {}", key, value);
     }
     for (key, value) in values {
-        println!("    - {}: {}", key, value);
+        logln!("    - {}: {}", key, value);
     }
 
     let header: String = {
@@ -35,7 +35,7 @@ pub fn write_result_to_file(
         format!("{},{}", descriptor_keys, values_keys)
     };
 
-    let old_contents = fs::read_to_string(file_path).unwrap_or(String::new());
+    let old_contents = fs::read_to_string(file_path).unwrap_or_default();
 
     let descriptor_values: Vec<&str> = descriptors.iter().map(|(_, v)| *v).collect::<Vec<&str>>();
     let value_values: Vec<&str> = values.iter().map(|(_, v)| *v).collect::<Vec<&str>>();
@@ -73,14 +73,12 @@ fn get_updated_contents(
             if columns.starts_with(descriptor_values) {
                 found = true;
                 let mut new_line: Vec<&str> = Vec::new();
-                for i in 0..descriptor_values.len() {
-                    new_line.push(descriptor_values[i]);
-                }
+                new_line.extend_from_slice(descriptor_values);
 
                 let old_values: &[&str] = &columns[descriptor_values.len()..columns.len()];
                 let result: &[&str] = on_conflict(old_values, value_values);
                 for value in result {
-                    new_line.push(&value);
+                    new_line.push(value);
                 }
                 temp_lines.push(new_line);
             } else {
@@ -105,7 +103,7 @@ fn get_updated_contents(
         .collect()
 }
 
-fn write_lines_to_file(file_path: &str, lines: &Vec<String>, header: &String) -> io::Result<()> {
+fn write_lines_to_file(file_path: &str, lines: &[String], header: &str) -> io::Result<()> {
     let output: String = {
         let mut buffer = String::new();
 
@@ -115,7 +113,7 @@ fn write_lines_to_file(file_path: &str, lines: &Vec<String>, header: &String) ->
 
         // Data
         for line in lines {
-            buffer.push_str(&line);
+            buffer.push_str(line);
             buffer.push('\n');
         }
 
@@ -125,10 +123,10 @@ fn write_lines_to_file(file_path: &str, lines: &Vec<String>, header: &String) ->
     // create directory if it does not exist
     let parent_dir = Path::new(file_path).parent().unwrap();
     if !parent_dir.exists() {
-        fs::create_dir_all(parent_dir).expect(format!("Failed to create directory {}", parent_dir.display()).as_str());
+        fs::create_dir_all(parent_dir).unwrap_or_else(|_| panic!("Failed to create directory {}", parent_dir.display()));
     }
 
-    fs::write(file_path, output.as_bytes()).expect(format!("Failed to write {}", file_path).as_str());
+    fs::write(file_path, output.as_bytes()).unwrap_or_else(|_| panic!("Failed to write {}", file_path));
 
     Ok(())
 }
@@ -136,6 +134,7 @@ fn write_lines_to_file(file_path: &str, lines: &Vec<String>, header: &String) ->
 /// Compares two lines column-wise. Both lines must have the same number of columns.
 /// If a column is detected as a version number, it will be compared as such.
 /// Example: 1.2 < 1.10 (as opposed to 1.2 > 1.10 when comparing as strings)
+#[allow(clippy::ptr_arg)]
 fn compare_lines(a: &Vec<&str>, b: &Vec<&str>) -> Ordering {
     for i in 0..a.len() {
         let a_value = a[i];
@@ -148,7 +147,7 @@ fn compare_lines(a: &Vec<&str>, b: &Vec<&str>) -> Ordering {
                     ordering => return ordering,
                 }
             }
-            _ => match a_value.cmp(&b_value) {
+            _ => match a_value.cmp(b_value) {
                 Ordering::Equal => continue, // check next column
                 ordering => return ordering,
             }
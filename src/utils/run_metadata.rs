@@ -0,0 +1,162 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::utils::docker_context::DockerContext;
+use crate::utils::environment;
+use crate::utils::format::format_duration;
+
+/// Directory of the benchmark definitions, whose commit is recorded.
+const BENCHMARK_DIR: &str = "benchmark";
+
+/// Provenance of a run: when it ran, with which sharkbench and benchmark definitions and with which options.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RunMetadata {
+    /// Example: `2024-01-31T12:34:56Z`
+    pub started_at: String,
+
+    /// `None` while the run is still going (e.g. in the file of an entry).
+    pub finished_at: Option<String>,
+
+    /// Wall time of the whole run, `None` while the run is still going.
+    pub duration_seconds: Option<u64>,
+
+    pub sharkbench_version: String,
+
+    /// Commit of the benchmark definitions, `None` if they are not part of a git checkout.
+    pub benchmarks_commit: Option<String>,
+
+    /// The effective command line options (including defaults).
+    pub options: serde_json::Value,
+
+    /// `<category>/<language>/<benchmark>` of the benchmarks selected by `--language`, `--lang-version` and `--benchmark`,
+    /// empty without filters.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub selected: Vec<String>,
+
+    /// The tags of `--tags` and `--exclude-tags` the benchmarks were selected by.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_tags: Vec<String>,
+
+    /// The patterns of `--skip-language` and `--skip-benchmark`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skip_languages: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skip_benchmarks: Vec<String>,
+
+    /// `<category>/<language>/<benchmark>` of the benchmarks the other filters select but the skip filters
+    /// (`--skip-language`, `--skip-benchmark` and `--exclude-tags`) excluded explicitly. They did not run and did not fail.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded: Vec<String>,
+
+    /// The seed of `--shuffle`, `None` if the benchmarks ran in order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+
+    /// When the run was continued with `--resume`, `started_at` stays the start of the original run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resumed_at: Vec<String>,
+
+    /// The context of `--docker-context` with its endpoint, `None` for the environment of the docker CLI.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docker_context: Option<DockerContext>,
+}
+
+impl RunMetadata {
+    /// Starts the run now.
+    pub fn start(options: serde_json::Value) -> RunMetadata {
+        RunMetadata {
+            started_at: environment::now_utc(),
+            finished_at: None,
+            duration_seconds: None,
+            sharkbench_version: env!("CARGO_PKG_VERSION").to_string(),
+            benchmarks_commit: environment::git_commit_of(BENCHMARK_DIR),
+            options,
+            selected: Vec::new(),
+            tags: Vec::new(),
+            exclude_tags: Vec::new(),
+            skip_languages: Vec::new(),
+            skip_benchmarks: Vec::new(),
+            excluded: Vec::new(),
+            seed: None,
+            resumed_at: Vec::new(),
+            docker_context: None,
+        }
+    }
+
+    /// Continues the `original` run: keeps its start and records that it was resumed now.
+    pub fn resume(&mut self, original: &RunMetadata) {
+        let resumed_at = std::mem::replace(&mut self.started_at, original.started_at.clone());
+        self.resumed_at = original.resumed_at.iter().cloned().chain([resumed_at]).collect();
+    }
+
+    /// `duration` is the wall time of this process, a resumed run lasted since the start of the original run.
+    pub fn finish(&mut self, duration: Duration) {
+        let finished_at = environment::now_utc();
+        self.duration_seconds = match (self.resumed_at.is_empty(), environment::parse_utc(&self.started_at), environment::parse_utc(&finished_at)) {
+            (false, Some(started), Some(finished)) => Some(finished.saturating_sub(started)),
+            _ => Some(duration.as_secs()),
+        };
+        self.finished_at = Some(finished_at);
+    }
+
+    /// A single line for reports.
+    /// Example: `Started 2024-01-31T12:34:56Z, finished 2024-01-31T13:00:00Z after 25m 04s with sharkbench 0.1.0, benchmarks at 1a2b3c4`
+    pub fn summary(&self) -> String {
+        let mut summary = format!("Started {}", self.started_at);
+        if let (Some(finished_at), Some(duration_seconds)) = (&self.finished_at, self.duration_seconds) {
+            summary.push_str(&format!(", finished {} after {}", finished_at, format_duration(Duration::from_secs(duration_seconds))));
+        }
+        summary.push_str(&format!(" with sharkbench {}", self.sharkbench_version));
+        if let Some(commit) = &self.benchmarks_commit {
+            summary.push_str(&format!(", benchmarks at {}", &commit[..commit.len().min(7)]));
+        }
+        if let Some(resumed_at) = self.resumed_at.last() {
+            summary.push_str(&format!(", resumed {}", resumed_at));
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> RunMetadata {
+        RunMetadata {
+            started_at: "2024-01-31T12:34:56Z".to_string(),
+            sharkbench_version: "0.1.0".to_string(),
+            benchmarks_commit: Some("1a2b3c4d5e6f".to_string()),
+            options: serde_json::json!({"web": true}),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn should_summarize_unfinished_run() {
+        assert_eq!(metadata().summary(), "Started 2024-01-31T12:34:56Z with sharkbench 0.1.0, benchmarks at 1a2b3c4");
+    }
+
+    #[test]
+    fn should_summarize_finished_run() {
+        let mut metadata = metadata();
+        metadata.finished_at = Some("2024-01-31T13:00:00Z".to_string());
+        metadata.duration_seconds = Some(1504);
+        metadata.benchmarks_commit = None;
+
+        assert_eq!(metadata.summary(), "Started 2024-01-31T12:34:56Z, finished 2024-01-31T13:00:00Z after 25m 04s with sharkbench 0.1.0");
+    }
+
+    #[test]
+    fn should_keep_start_of_resumed_run() {
+        let mut resumed = RunMetadata { started_at: "2024-01-31T15:00:00Z".to_string(), ..metadata() };
+        resumed.resume(&metadata());
+        assert_eq!(resumed.started_at, "2024-01-31T12:34:56Z");
+        assert_eq!(resumed.resumed_at, vec!["2024-01-31T15:00:00Z".to_string()]);
+        assert!(resumed.summary().ends_with(", benchmarks at 1a2b3c4, resumed 2024-01-31T15:00:00Z"));
+
+        // Lasted since the original start, not only the 5 seconds of the last process
+        resumed.finish(Duration::from_secs(5));
+        assert!(resumed.duration_seconds.unwrap() > 5);
+    }
+}
@@ -0,0 +1,34 @@
+use std::time::Duration;
+use crate::benchmark::error::BenchmarkError;
+
+/// Path of the endpoint reporting the memory the application thinks it's using (e.g. heap used).
+/// The response body is the number of bytes as plain text, e.g. `123456`.
+pub const MEMORY_PATH: &str = "/_sharkbench/memory";
+
+/// Queries the self-reported memory of the service at `base_url` in bytes with the `client` shared by the rounds.
+pub fn query(client: &reqwest::blocking::Client, base_url: &str) -> Result<i64, BenchmarkError> {
+    let url = format!("{}{}", base_url.trim_end_matches('/'), MEMORY_PATH);
+    let response = client
+        .get(&url)
+        .timeout(Duration::from_secs(5))
+        .send()?;
+    if !response.status().is_success() {
+        return Err(BenchmarkError::HttpStatus { status: response.status().as_u16(), url });
+    }
+    parse(&response.text()?)
+}
+
+fn parse(body: &str) -> Result<i64, BenchmarkError> {
+    body.trim().parse::<i64>().map_err(|e| BenchmarkError::ParseError(format!("Invalid self-reported memory {:?}: {}", body, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_bytes() {
+        assert_eq!(parse("123456\n").unwrap(), 123456);
+        assert!(parse("12 MB").is_err());
+    }
+}
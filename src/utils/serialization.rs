@@ -1,8 +1,14 @@
 use std::fmt::{Display, Formatter};
-use serde::{Deserialize, Serialize};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize, Serializer};
+
+/// Floats of the serialized results are rounded to this many significant digits,
+/// so the last digits of a division do not show up in diffs.
+pub const SIGNIFICANT_DIGITS: i32 = 4;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(untagged)]
+#[allow(clippy::enum_variant_names)]
 pub enum SerializedValue {
     StringValue(String),
     IntValue(i32),
@@ -29,3 +35,57 @@ impl Display for SerializedValue {
         }
     }
 }
+
+/// Serializes the map sorted by key, so the output does not depend on the insertion order.
+pub fn sorted_map<K, V, S>(map: &IndexMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where K: Serialize + Ord, V: Serialize, S: Serializer {
+    let mut entries: Vec<(&K, &V)> = map.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    serializer.collect_map(entries)
+}
+
+/// Serializes the float rounded to `SIGNIFICANT_DIGITS`.
+pub fn significant_digits<S: Serializer>(value: &Option<f64>, serializer: S) -> Result<S::Ok, S::Error> {
+    value.map(|value| round_significant(value, SIGNIFICANT_DIGITS)).serialize(serializer)
+}
+
+/// Serializes the map sorted by key with the floats rounded to `SIGNIFICANT_DIGITS`.
+pub fn significant_digits_map<S: Serializer>(map: &IndexMap<String, f64>, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut entries: Vec<(&String, f64)> = map.iter().map(|(key, value)| (key, round_significant(*value, SIGNIFICANT_DIGITS))).collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    serializer.collect_map(entries)
+}
+
+fn round_significant(value: f64, digits: i32) -> f64 {
+    if value == 0.0 || !value.is_finite() {
+        return value;
+    }
+    let magnitude = value.abs().log10().floor() as i32;
+    let factor = 10f64.powi(digits - 1 - magnitude);
+    (value * factor).round() / factor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_round_to_significant_digits() {
+        assert_eq!(round_significant(2.0 / 3.0, 4), 0.6667);
+        assert_eq!(round_significant(1234.5678, 4), 1235.0);
+        assert_eq!(round_significant(0.000123456, 4), 0.0001235);
+        assert_eq!(round_significant(0.0, 4), 0.0);
+    }
+
+    #[derive(Serialize)]
+    struct Sorted {
+        #[serde(serialize_with = "sorted_map")]
+        map: IndexMap<String, i32>,
+    }
+
+    #[test]
+    fn should_serialize_maps_sorted() {
+        let map = IndexMap::from([("b".to_string(), 2), ("a".to_string(), 1)]);
+        assert_eq!(serde_json::to_string(&Sorted { map }).unwrap(), r#"{"map":{"a":1,"b":2}}"#);
+    }
+}
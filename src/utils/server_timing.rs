@@ -0,0 +1,59 @@
+use std::time::Duration;
+use indexmap::IndexMap;
+use crate::benchmark::benchmark::AdditionalData;
+
+/// Header in which a benchmark reports the named phases of a request, as defined by the W3C Server Timing spec,
+/// e.g. `Server-Timing: setup;dur=1.5, compute;dur=42.25` (durations in ms, fractions allowed).
+pub const HEADER: &str = "server-timing";
+
+/// Prefix of the additional data keys of the phases, e.g. `phase_setup_us`.
+pub const PHASE_KEY_PREFIX: &str = "phase_";
+
+/// The phases of the header values in the order they appear.
+/// Metrics without (valid) `dur` are ignored, the last duration of a repeated name is kept.
+pub fn parse(values: &[&str]) -> IndexMap<String, Duration> {
+    let mut phases = IndexMap::new();
+    for metric in values.iter().flat_map(|value| value.split(',')) {
+        let mut parts = metric.split(';').map(str::trim);
+        let name = parts.next().unwrap_or_default();
+        let duration = parts
+            .filter_map(|parameter| parameter.split_once('='))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case("dur"))
+            .and_then(|(_, value)| value.trim().trim_matches('"').parse::<f64>().ok())
+            .and_then(|ms| Duration::try_from_secs_f64(ms / 1000.0).ok());
+        if let (false, Some(duration)) = (name.is_empty(), duration) {
+            phases.insert(name.to_string(), duration);
+        }
+    }
+    phases
+}
+
+/// Adds the phases of a round to its additional data, aggregated per phase like other additional data.
+pub fn insert_into(phases: &IndexMap<String, Duration>, additional_data: &mut IndexMap<String, AdditionalData>) {
+    for (name, duration) in phases {
+        additional_data.insert(
+            format!("{}{}_us", PHASE_KEY_PREFIX, name),
+            AdditionalData::Duration(duration.as_micros().min(i64::MAX as u128) as i64),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_phases() {
+        let phases = parse(&["setup;dur=1.5, compute;desc=\"Compute\";dur=42.25", "cache;desc=hit, db;dur=-1, queue;dur=1e300, total;dur=\"50\""]);
+        assert_eq!(phases, IndexMap::from([
+            ("setup".to_string(), Duration::from_micros(1500)),
+            ("compute".to_string(), Duration::from_micros(42250)),
+            ("total".to_string(), Duration::from_millis(50)),
+        ]));
+
+        let mut additional_data = IndexMap::new();
+        insert_into(&phases, &mut additional_data);
+        assert_eq!(additional_data["phase_compute_us"], AdditionalData::Duration(42250));
+        assert!(parse(&[""]).is_empty());
+    }
+}
@@ -0,0 +1,69 @@
+use std::env;
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::utils::log;
+
+/// Whether the human-readable output is colored, see `init`.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// Colors the further output if it goes to an interactive terminal, unless `--no-color` or `NO_COLOR` (see https://no-color.org) is set.
+/// Must be called after the output was moved to stderr (see `log::to_stderr`).
+pub fn init(no_color: bool) {
+    ENABLED.store(is_enabled(no_color, env::var_os("NO_COLOR"), log::is_terminal()), Ordering::Relaxed);
+}
+
+/// Passed checks and runs, e.g. `12 passed, 0 failed, 0 skipped`.
+pub fn success(text: impl Display) -> String {
+    paint(GREEN, text)
+}
+
+/// Problems the run continues with, e.g. a missing digest or a skipped benchmark.
+pub fn warning(text: impl Display) -> String {
+    paint(YELLOW, text)
+}
+
+/// Failed benchmarks and regressions.
+pub fn failure(text: impl Display) -> String {
+    paint(RED, text)
+}
+
+/// The text is written as it is while the output is captured (see `--tui`), the log pane does not render colors.
+fn paint(color: &str, text: impl Display) -> String {
+    match ENABLED.load(Ordering::Relaxed) && !log::is_captured() {
+        true => colored(color, text),
+        false => text.to_string(),
+    }
+}
+
+fn colored(color: &str, text: impl Display) -> String {
+    format!("{}{}{}", color, text, RESET)
+}
+
+/// `NO_COLOR` disables the colors if it is set to anything but an empty string.
+fn is_enabled(no_color: bool, no_color_env: Option<OsString>, terminal: bool) -> bool {
+    !no_color && no_color_env.is_none_or(|value| value.is_empty()) && terminal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_only_color_terminals() {
+        assert!(is_enabled(false, None, true));
+        assert!(is_enabled(false, Some(OsString::new()), true));
+        assert!(!is_enabled(false, None, false));
+        assert!(!is_enabled(true, None, true));
+        assert!(!is_enabled(false, Some(OsString::from("1")), true));
+
+        assert_eq!(colored(RED, "1 failed"), "\x1b[31m1 failed\x1b[0m");
+        // Not enabled by `init` in tests
+        assert_eq!(failure("1 failed"), "1 failed");
+    }
+}
@@ -0,0 +1,41 @@
+/// Renders an aligned table for the terminal: the header, a line of dashes and one line per row,
+/// with the columns separated by two spaces and trailing whitespace removed.
+pub fn render(header: &[String], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|cell| cell.chars().count()).collect();
+    for cells in rows {
+        for (i, cell) in cells.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+
+    let mut buffer = String::new();
+    push_line(&mut buffer, header, &widths);
+    push_line(&mut buffer, &widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<String>>(), &widths);
+    for cells in rows {
+        push_line(&mut buffer, cells, &widths);
+    }
+    buffer
+}
+
+fn push_line(buffer: &mut String, cells: &[String], widths: &[usize]) {
+    let line: Vec<String> = cells.iter().enumerate().map(|(i, cell)| format!("{:<width$}", cell, width = widths[i])).collect();
+    buffer.push_str(line.join("  ").trim_end());
+    buffer.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_align_columns() {
+        let header = ["Name".to_string(), "Status".to_string()];
+        let rows = vec![vec!["rust/äöü-long".to_string(), "ok".to_string()], vec!["go".to_string(), "".to_string()]];
+        assert_eq!(render(&header, &rows), "\
+Name           Status
+-------------  ------
+rust/äöü-long  ok
+go
+");
+    }
+}
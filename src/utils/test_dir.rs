@@ -0,0 +1,37 @@
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A directory of a test below the temporary directory, e.g. `/tmp/sharkbench-filter-1234`, removed again when it is dropped.
+/// The name and the process ID keep tests that run in parallel apart.
+pub struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    /// An empty directory, the leftovers of an earlier test that did not finish are removed.
+    pub fn new(name: &str) -> TestDir {
+        let path = std::env::temp_dir().join(format!("sharkbench-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap_or_else(|e| panic!("Could not create {}: {}", path.display(), e));
+        TestDir { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
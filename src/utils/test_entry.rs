@@ -0,0 +1,80 @@
+use crate::benchmark::benchmark::{AdditionalData, BenchmarkResult, RoundSample};
+use crate::output::json::ResultEntry;
+
+/// Builds the `ResultEntry` of a test, by default a computation benchmark of Rust 1.74 without measurements.
+/// Example: `TestEntry::new("rust/axum").category("web").rps(1234).build()`
+pub struct TestEntry {
+    entry: ResultEntry,
+}
+
+impl TestEntry {
+    pub fn new(benchmark: &str) -> TestEntry {
+        TestEntry { entry: ResultEntry::new("computation", benchmark, "Rust", "1.74", &[], BenchmarkResult::default()) }
+    }
+
+    pub fn category(mut self, category: &str) -> TestEntry {
+        self.entry.category = category.to_string();
+        self
+    }
+
+    pub fn language(mut self, language: &str) -> TestEntry {
+        self.entry.language = language.to_string();
+        self
+    }
+
+    pub fn version(mut self, version: &str) -> TestEntry {
+        self.entry.version = version.to_string();
+        self
+    }
+
+    pub fn metadata(mut self, key: &str, value: &str) -> TestEntry {
+        self.entry.metadata.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Median time in milliseconds.
+    pub fn time(mut self, time_median: i64) -> TestEntry {
+        self.entry.result.time_median = time_median;
+        self
+    }
+
+    pub fn time_p1(mut self, time_p1: i64) -> TestEntry {
+        self.entry.result.time_p1 = Some(time_p1);
+        self
+    }
+
+    /// Median memory in bytes.
+    pub fn memory(mut self, memory_median: i64) -> TestEntry {
+        self.entry.result.memory_median = Some(memory_median);
+        self
+    }
+
+    pub fn memory_p99(mut self, memory_p99: i64) -> TestEntry {
+        self.entry.result.memory_p99 = Some(memory_p99);
+        self
+    }
+
+    /// Median requests per second of a web benchmark.
+    pub fn rps(self, rps_median: i32) -> TestEntry {
+        self.data("rps_median", rps_median)
+    }
+
+    /// Median latency of a web benchmark in microseconds.
+    pub fn latency(self, latency_median: i32) -> TestEntry {
+        self.data("latency_median", latency_median)
+    }
+
+    pub fn data(mut self, key: &str, value: i32) -> TestEntry {
+        self.entry.result.additional_data.insert(key.to_string(), AdditionalData::Int(value));
+        self
+    }
+
+    pub fn samples(mut self, samples: Vec<RoundSample>) -> TestEntry {
+        self.entry.result.samples = samples;
+        self
+    }
+
+    pub fn build(self) -> ResultEntry {
+        self.entry
+    }
+}
@@ -0,0 +1,443 @@
+use serde_json::{Map, Value};
+
+/// Parses the subset of TOML used by the configuration files into JSON, so it can be deserialized with serde:
+/// tables (`[a.b]`), arrays of tables (`[[a]]`), bare, quoted and dotted keys, basic and literal strings,
+/// integers, floats, booleans, arrays (also spanning lines) and inline tables. Dates and multi-line strings are not supported.
+pub fn parse(content: &str) -> Result<Value, String> {
+    let mut parser = Parser { chars: content.chars().collect(), position: 0, line: 1 };
+    parser.parse_document().map_err(|e| format!("line {}: {}", parser.line, e))
+}
+
+/// Writes the JSON object as TOML: the values of a table before its sub tables (`[a.b]`), `null` values are omitted.
+/// Only produces the subset `parse` reads, so `parse(&to_string(value))` returns the value without its `null`s.
+pub fn to_string(value: &Map<String, Value>) -> String {
+    let mut buffer = String::new();
+    write_table(&mut buffer, &[], value);
+    buffer.trim_start().to_string()
+}
+
+fn write_table(buffer: &mut String, path: &[String], table: &Map<String, Value>) {
+    let (tables, values): (Vec<_>, Vec<_>) = table.iter()
+        .filter(|(_, value)| !value.is_null())
+        .partition(|(_, value)| value.is_object());
+    if !path.is_empty() && (!values.is_empty() || tables.is_empty()) {
+        buffer.push_str(&format!("\n[{}]\n", path.join(".")));
+    }
+    for (key, value) in values {
+        buffer.push_str(&format!("{} = {}\n", format_key(key), format_value(value)));
+    }
+    for (key, value) in tables {
+        let path: Vec<String> = path.iter().cloned().chain([format_key(key)]).collect();
+        write_table(buffer, &path, value.as_object().expect("Partitioned by objects"));
+    }
+}
+
+fn format_key(key: &str) -> String {
+    match !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        true => key.to_string(),
+        // JSON strings are valid basic strings
+        false => Value::String(key.to_string()).to_string(),
+    }
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Array(values) => format!("[{}]", values.iter().map(format_value).collect::<Vec<String>>().join(", ")),
+        Value::Object(table) => format!("{{ {} }}", table.iter()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(key, value)| format!("{} = {}", format_key(key), format_value(value)))
+            .collect::<Vec<String>>()
+            .join(", ")),
+        value => value.to_string(),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn parse_document(&mut self) -> Result<Value, String> {
+        let mut root = Map::new();
+        // The path of the current table, the last element of an array of tables is addressed by its index
+        let mut table: Vec<String> = Vec::new();
+        loop {
+            self.skip_whitespace_and_comments(true);
+            match self.peek() {
+                None => return Ok(Value::Object(root)),
+                Some('[') => {
+                    self.position += 1;
+                    let is_array = self.eat('[');
+                    let path = self.parse_key()?;
+                    self.expect(']')?;
+                    if is_array {
+                        self.expect(']')?;
+                    }
+                    table = open_table(&mut root, &path, is_array)?;
+                }
+                Some(_) => {
+                    let path = self.parse_key()?;
+                    self.expect('=')?;
+                    let value = self.parse_value()?;
+                    let parent = resolve(&mut root, &table)?;
+                    insert(parent, &path, value)?;
+                }
+            }
+            self.skip_whitespace_and_comments(false);
+            match self.peek() {
+                None | Some('\n') => {}
+                Some(c) => return Err(format!("unexpected {:?} after the value", c)),
+            }
+        }
+    }
+
+    /// A bare, quoted or dotted key.
+    fn parse_key(&mut self) -> Result<Vec<String>, String> {
+        let mut path = Vec::new();
+        loop {
+            self.skip_spaces();
+            let part = match self.peek() {
+                Some('"') => self.parse_basic_string()?,
+                Some('\'') => self.parse_literal_string()?,
+                _ => {
+                    let start = self.position;
+                    while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                        self.position += 1;
+                    }
+                    if start == self.position {
+                        return Err("expected a key".to_string());
+                    }
+                    self.chars[start..self.position].iter().collect()
+                }
+            };
+            path.push(part);
+            self.skip_spaces();
+            if !self.eat('.') {
+                return Ok(path);
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value, String> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('"') => Ok(Value::String(self.parse_basic_string()?)),
+            Some('\'') => Ok(Value::String(self.parse_literal_string()?)),
+            Some('[') => self.parse_array(),
+            Some('{') => self.parse_inline_table(),
+            Some(_) => self.parse_scalar(),
+            None => Err("expected a value".to_string()),
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespace_and_comments(true);
+            if self.eat(']') {
+                return Ok(Value::Array(values));
+            }
+            values.push(self.parse_value()?);
+            self.skip_whitespace_and_comments(true);
+            if !self.eat(',') {
+                self.skip_whitespace_and_comments(true);
+                self.expect(']')?;
+                return Ok(Value::Array(values));
+            }
+        }
+    }
+
+    fn parse_inline_table(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut table = Map::new();
+        self.skip_spaces();
+        if self.eat('}') {
+            return Ok(Value::Object(table));
+        }
+        loop {
+            let path = self.parse_key()?;
+            self.expect('=')?;
+            let value = self.parse_value()?;
+            insert(&mut table, &path, value)?;
+            self.skip_spaces();
+            if !self.eat(',') {
+                self.expect('}')?;
+                return Ok(Value::Object(table));
+            }
+        }
+    }
+
+    /// Booleans, integers (with `_` separators) and floats. `inf` and `nan` are rejected, as JSON can not represent them.
+    fn parse_scalar(&mut self) -> Result<Value, String> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || "+-._".contains(c)) {
+            self.position += 1;
+        }
+        let word: String = self.chars[start..self.position].iter().collect();
+        match word.as_str() {
+            "true" => return Ok(Value::Bool(true)),
+            "false" => return Ok(Value::Bool(false)),
+            _ => {}
+        }
+
+        let number = word.replace('_', "");
+        if let Ok(value) = number.parse::<i64>() {
+            return Ok(Value::from(value));
+        }
+        match number.parse::<f64>().ok().and_then(serde_json::Number::from_f64) {
+            Some(value) => Ok(Value::Number(value)),
+            None => Err(format!("invalid value {:?}", word)),
+        }
+    }
+
+    fn parse_basic_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                None | Some('\n') => return Err("unterminated string".to_string()),
+                Some('"') => return Ok(value),
+                Some('\\') => match self.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('r') => value.push('\r'),
+                    Some('"') => value.push('"'),
+                    Some('\\') => value.push('\\'),
+                    Some('u') => {
+                        let code: String = (0..4).filter_map(|_| self.next()).collect();
+                        let c = u32::from_str_radix(&code, 16).ok().and_then(char::from_u32)
+                            .ok_or(format!("invalid escape \\u{}", code))?;
+                        value.push(c);
+                    }
+                    c => return Err(format!("invalid escape {:?}", c)),
+                },
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn parse_literal_string(&mut self) -> Result<String, String> {
+        self.expect('\'')?;
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                None | Some('\n') => return Err("unterminated string".to_string()),
+                Some('\'') => return Ok(value),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(|c| c == ' ' || c == '\t' || c == '\r') {
+            self.position += 1;
+        }
+    }
+
+    /// Skips spaces and comments, and line breaks if `newlines` is set.
+    fn skip_whitespace_and_comments(&mut self, newlines: bool) {
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                Some('#') => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.position += 1;
+                    }
+                }
+                Some('\n') if newlines => {
+                    self.next();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.next();
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_spaces();
+        match self.eat(expected) {
+            true => Ok(()),
+            false => Err(format!("expected {:?}", expected)),
+        }
+    }
+}
+
+/// Creates the table (or appends a table to the array of tables) and returns its path for `resolve`.
+fn open_table(root: &mut Map<String, Value>, path: &[String], is_array: bool) -> Result<Vec<String>, String> {
+    let (last, parents) = path.split_last().expect("Keys have at least one part");
+    let mut resolved = Vec::new();
+    let mut table = root;
+    for key in parents {
+        let value = table.entry(key.clone()).or_insert_with(|| Value::Object(Map::new()));
+        resolved.push(key.clone());
+        table = match value {
+            Value::Object(table) => table,
+            // Tables below an array of tables belong to its last element
+            Value::Array(tables) => {
+                resolved.push(tables.len().saturating_sub(1).to_string());
+                tables.last_mut().and_then(Value::as_object_mut).ok_or(format!("`{}` is not a table", key))?
+            }
+            _ => return Err(format!("`{}` is not a table", key)),
+        };
+    }
+
+    resolved.push(last.clone());
+    match (table.entry(last.clone()).or_insert_with(|| if is_array { Value::Array(Vec::new()) } else { Value::Object(Map::new()) }), is_array) {
+        (Value::Array(tables), true) => {
+            tables.push(Value::Object(Map::new()));
+            resolved.push((tables.len() - 1).to_string());
+        }
+        (Value::Object(_), false) => {}
+        _ => return Err(format!("`{}` is defined twice with different types", path.join("."))),
+    }
+    Ok(resolved)
+}
+
+fn resolve<'a>(root: &'a mut Map<String, Value>, path: &[String]) -> Result<&'a mut Map<String, Value>, String> {
+    let mut table = root;
+    let mut parts = path.iter();
+    while let Some(key) = parts.next() {
+        table = match table.get_mut(key) {
+            Some(Value::Object(table)) => table,
+            Some(Value::Array(tables)) => {
+                let index: usize = parts.next().and_then(|index| index.parse().ok()).ok_or(format!("`{}` is not a table", key))?;
+                tables.get_mut(index).and_then(Value::as_object_mut).ok_or(format!("`{}` is not a table", key))?
+            }
+            _ => return Err(format!("`{}` is not a table", key)),
+        };
+    }
+    Ok(table)
+}
+
+/// Inserts the value at the dotted key, creating the intermediate tables.
+fn insert(table: &mut Map<String, Value>, path: &[String], value: Value) -> Result<(), String> {
+    let (last, parents) = path.split_last().expect("Keys have at least one part");
+    let mut table = table;
+    for key in parents {
+        table = table.entry(key.clone()).or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or(format!("`{}` is not a table", key))?;
+    }
+    if table.contains_key(last) {
+        return Err(format!("`{}` is defined twice", path.join(".")));
+    }
+    table.insert(last.clone(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn should_parse_tables_and_values() {
+        let content = r#"
+# Comment
+title = "Sharkbench \"results\"" # Trailing comment
+path = 'C:\benchmarks'
+rounds = 1_000
+weight = 0.5
+exponent = 1e3
+enabled = true
+site.name = "web"
+
+[weights]
+time_median = 1
+"memory p99" = -2.5
+
+[groups.web]
+patterns = [
+    "web/*", # Multi-line
+    "rust/axum-*",
+]
+options = { warmup = 2, cold = false }
+
+[[runs]]
+name = "a"
+
+[[runs]]
+name = "b"
+
+[runs.env]
+key = "value"
+"#;
+        assert_eq!(parse(content).unwrap(), json!({
+            "title": "Sharkbench \"results\"",
+            "path": "C:\\benchmarks",
+            "rounds": 1000,
+            "weight": 0.5,
+            "exponent": 1000.0,
+            "enabled": true,
+            "site": {"name": "web"},
+            "weights": {"time_median": 1, "memory p99": -2.5},
+            "groups": {"web": {"patterns": ["web/*", "rust/axum-*"], "options": {"warmup": 2, "cold": false}}},
+            "runs": [{"name": "a"}, {"name": "b", "env": {"key": "value"}}],
+        }));
+    }
+
+    #[test]
+    fn should_write_parsable_toml() {
+        let value = json!({
+            "name": "suite \"a\"",
+            "empty": {},
+            "output": {"formats": ["json", "csv"], "dir": null, "nested": {"charts": true}},
+            "rounds": 5,
+            "tags": {"kernel version": "6.1", "list": [{"a": 1, "b": null}]},
+        });
+        let content = to_string(value.as_object().unwrap());
+        assert_eq!(content, r#"name = "suite \"a\""
+rounds = 5
+
+[empty]
+
+[output]
+formats = ["json", "csv"]
+
+[output.nested]
+charts = true
+
+[tags]
+"kernel version" = "6.1"
+list = [{ a = 1 }]
+"#);
+        assert_eq!(parse(&content).unwrap(), json!({
+            "name": "suite \"a\"",
+            "empty": {},
+            "output": {"formats": ["json", "csv"], "nested": {"charts": true}},
+            "rounds": 5,
+            "tags": {"kernel version": "6.1", "list": [{"a": 1}]},
+        }));
+    }
+
+    #[test]
+    fn should_report_line_of_errors() {
+        assert_eq!(parse("a = 1\nb = \n").unwrap_err(), "line 2: invalid value \"\"");
+        assert_eq!(parse("a = 1\na = 2").unwrap_err(), "line 2: `a` is defined twice");
+        assert_eq!(parse("a = \"open").unwrap_err(), "line 1: unterminated string");
+        assert_eq!(parse("a = 1 b = 2").unwrap_err(), "line 1: unexpected 'b' after the value");
+    }
+}
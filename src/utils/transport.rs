@@ -0,0 +1,213 @@
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::time::{Duration, Instant};
+use indexmap::IndexMap;
+use reqwest::header;
+use serde::{Deserialize, Serialize};
+use crate::benchmark::benchmark::Response;
+use crate::benchmark::error::BenchmarkError;
+use crate::utils::{compression, server_timing};
+
+/// How the runner talks to a computation benchmark.
+///
+/// - `http`: `GET /?iterations=<n>`, the response body contains the result. The connection is kept alive across rounds.
+///   gzip and deflate are accepted and decoded after the response was received, so the measured time does not include the decompression.
+/// - `tcp`: The runner sends `<n>\n`, closes its write half and reads the result until the server closes the connection.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    #[default]
+    Http,
+    Tcp,
+}
+
+/// Sends the computation requests of a benchmark over its transport.
+///
+/// The HTTP client is built once and keeps the connection alive across rounds,
+/// so the measured time is not dominated by the TCP and HTTP setup for fast workloads.
+pub struct Client {
+    transport: Transport,
+    http: reqwest::blocking::Client,
+}
+
+impl Client {
+    pub fn new(transport: Transport) -> Self {
+        Client { transport, http: reqwest::blocking::Client::new() }
+    }
+
+    /// Sends a single computation request with the given `iterations` to `base_url` and returns the response.
+    /// The TCP transport only uses the host and port of `base_url` and opens a connection per request.
+    pub fn send(&self, base_url: &str, iterations: u64, timeout: Duration) -> Result<Response, BenchmarkError> {
+        self.send_timed(base_url, iterations, timeout).map(|(response, _)| response)
+    }
+
+    /// Like `send`, also returns the time until the whole body was received. Its decoding is not part of it.
+    pub fn send_timed(&self, base_url: &str, iterations: u64, timeout: Duration) -> Result<(Response, Duration), BenchmarkError> {
+        match self.transport {
+            Transport::Http => {
+                let start = Instant::now();
+                let response = match self.send_http(base_url, iterations, timeout) {
+                    // The kept-alive connection is closed if the container restarted, retry once on a new one
+                    Err(e) if is_stale_connection(&e) => self.send_http(base_url, iterations, timeout),
+                    response => response,
+                }?;
+                let status = response.status().as_u16();
+                let content_encoding = content_encoding(response.headers());
+                let server_timing: Vec<&str> = response.headers().get_all(server_timing::HEADER).iter().filter_map(|value| value.to_str().ok()).collect();
+                let phases = server_timing::parse(&server_timing);
+                let bytes = response.bytes()?;
+                let time = start.elapsed();
+                let body = compression::decode(content_encoding.as_deref(), &bytes).map_err(BenchmarkError::ParseError)?;
+                Ok((Response { status: Some(status), body: String::from_utf8_lossy(&body).to_string(), size: bytes.len(), phases }, time))
+            }
+            Transport::Tcp => {
+                let url = reqwest::Url::parse(base_url).map_err(|e| BenchmarkError::Connection(format!("invalid URL {}: {}", base_url, e)))?;
+                let host = url.host_str().ok_or_else(|| BenchmarkError::Connection(format!("URL without host: {}", base_url)))?;
+                let port = url.port_or_known_default().ok_or_else(|| BenchmarkError::Connection(format!("URL without port: {}", base_url)))?;
+                let start = Instant::now();
+                let mut stream = TcpStream::connect((host, port))?;
+                stream.set_read_timeout(Some(timeout))?;
+                stream.write_all(format!("{}\n", iterations).as_bytes())?;
+                stream.shutdown(Shutdown::Write)?;
+                let mut bytes = Vec::new();
+                stream.read_to_end(&mut bytes)?;
+                let time = start.elapsed();
+                let body = String::from_utf8_lossy(&bytes).to_string();
+                Ok((Response { status: None, body, size: bytes.len(), phases: IndexMap::new() }, time))
+            }
+        }
+    }
+
+    fn send_http(&self, base_url: &str, iterations: u64, timeout: Duration) -> reqwest::Result<reqwest::blocking::Response> {
+        self.http.get(base_url)
+            .query(&[("iterations", iterations.to_string())])
+            .header(header::ACCEPT_ENCODING, compression::ACCEPT_ENCODING)
+            .timeout(timeout)
+            .send()
+    }
+}
+
+/// A response of the HTTP transport with an unsuccessful status as `BenchmarkError::HttpStatus`,
+/// so the retry policy tells a bad request from an overloaded service. Responses of the TCP transport have no status.
+pub fn check_status(response: &Response, url: &str) -> Result<(), BenchmarkError> {
+    match response.status {
+        Some(status) if !(200..300).contains(&status) => Err(BenchmarkError::HttpStatus { status, url: url.to_string() }),
+        _ => Ok(()),
+    }
+}
+
+/// The `Content-Encoding` of a response, `None` if it is missing or not ASCII.
+pub fn content_encoding(headers: &header::HeaderMap) -> Option<String> {
+    headers.get(header::CONTENT_ENCODING).and_then(|value| value.to_str().ok()).map(str::to_string)
+}
+
+/// The request failed before a response was received, but not because the server is slow.
+fn is_stale_connection(error: &reqwest::Error) -> bool {
+    (error.is_connect() || error.is_request()) && !error.is_timeout()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn should_deserialize_lowercase() {
+        assert_eq!(serde_yaml::from_str::<Transport>("tcp").unwrap(), Transport::Tcp);
+        assert_eq!(serde_yaml::from_str::<Transport>("http").unwrap(), Transport::Http);
+    }
+
+    #[test]
+    fn should_send_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut line = String::new();
+            BufReader::new(&mut stream).read_line(&mut line).unwrap();
+            stream.write_all(format!("got {}", line.trim()).as_bytes()).unwrap();
+        });
+
+        let response = Client::new(Transport::Tcp).send(&format!("http://{}", address), 42, Duration::from_secs(5)).unwrap();
+        server.join().unwrap();
+        assert_eq!(response.status, None);
+        assert_eq!(response.body, "got 42");
+    }
+
+    /// Answers the requests of a connection until the client closes it or `requests_per_connection` were answered.
+    /// Returns the number of accepted connections and answered requests.
+    fn serve_http(listener: TcpListener, connections: usize, requests_per_connection: usize) -> (usize, usize) {
+        let mut requests = 0;
+        for _ in 0..connections {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            for _ in 0..requests_per_connection {
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                    line.clear();
+                }
+                if line.is_empty() {
+                    break;
+                }
+                requests += 1;
+                writer.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: keep-alive\r\n\r\nok").unwrap();
+            }
+        }
+        (connections, requests)
+    }
+
+    #[test]
+    fn should_reuse_connection_across_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || serve_http(listener, 1, 3));
+
+        let client = Client::new(Transport::Http);
+        for _ in 0..3 {
+            assert_eq!(client.send(&url, 1, Duration::from_secs(5)).unwrap().body, "ok");
+        }
+        // A second connection would never be accepted and the requests would time out
+        assert_eq!(server.join().unwrap(), (1, 3));
+    }
+
+    #[test]
+    fn should_decode_compressed_responses() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut headers = String::new();
+            while reader.read_line(&mut headers).unwrap() > 0 && !headers.ends_with("\r\n\r\n") {}
+
+            let gzipped = [
+                0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0x33, 0xd6, 0x33, 0x34, 0xb1, 0x36, 0xb7, 0x30, 0xd5, 0x33,
+                0xb6, 0xb4, 0x36, 0xd0, 0x33, 0xb7, 0x00, 0x00, 0xa8, 0xae, 0xa2, 0x21, 0x10, 0x00, 0x00, 0x00,
+            ];
+            let mut writer = stream;
+            writer.write_all(format!("HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n", gzipped.len()).as_bytes()).unwrap();
+            writer.write_all(&gzipped).unwrap();
+            headers
+        });
+
+        let response = Client::new(Transport::Http).send(&url, 1, Duration::from_secs(5)).unwrap();
+        assert_eq!(response.body, "3.14;785.39;0.78");
+        assert!(server.join().unwrap().to_lowercase().contains("accept-encoding: gzip, deflate\r\n"));
+    }
+
+    #[test]
+    fn should_reconnect_after_server_closed_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        // Closes the connection after every request, like a restarted container
+        let server = thread::spawn(move || serve_http(listener, 2, 1));
+
+        let client = Client::new(Transport::Http);
+        assert_eq!(client.send(&url, 1, Duration::from_secs(5)).unwrap().body, "ok");
+        assert_eq!(client.send(&url, 1, Duration::from_secs(5)).unwrap().body, "ok");
+        assert_eq!(server.join().unwrap(), (2, 2));
+    }
+}
@@ -39,36 +39,37 @@ impl VersionMigrator {
 
             match migrate_contents(contents, &t.regex, &self.initial_version, &self.target_version) {
                 Ok(new_contents) => {
-                    std::fs::write(&t.path, new_contents).expect(format!("Could not write {}", t.path).as_str());
-                }
-                Err(e) => {
-                    match e {
-                        MigrationError::VersionNotFound => {
-                            panic!("Expected {} in {} but found none.", self.initial_version, t.path);
-                        }
-                        MigrationError::InvalidVersion(version) => {
-                            panic!("Expected {} in {} but found {}.", self.initial_version, t.path, version);
-                        }
-                        MigrationError::InvalidRegex(regex) => {
-                            panic!("Regex in file {} is invalid: {}", t.path, regex);
-                        }
-                    }
+                    std::fs::write(&t.path, new_contents).unwrap_or_else(|_| panic!("Could not write {}", t.path));
                 }
+                Err(e) => panic!("{}", describe(e, &t.path, &self.initial_version)),
             }
         }
     }
 
+    /// Checks that every file contains the initial version, without writing anything.
+    /// `read` returns the contents of a path, e.g. of the `_common` file it is copied from before the run.
+    pub fn check<R>(&self, read: R) -> Result<(), String>
+        where R: Fn(&str) -> std::io::Result<String>
+    {
+        for t in &self.transformations {
+            let contents = read(&t.path).map_err(|e| format!("Could not read {}: {}", t.path, e))?;
+            migrate_contents(&contents, &t.regex, &self.initial_version, &self.target_version)
+                .map_err(|e| describe(e, &t.path, &self.initial_version))?;
+        }
+        Ok(())
+    }
+
     pub fn restore(&self) {
         for t in &self.transformations {
-            let contents = t.original.as_ref().expect(format!("Could not restore {} (original not found). This should not happen.", t.path).as_str());
-            std::fs::write(&t.path, contents).expect(format!("Could not write {}", t.path).as_str());
+            let contents = t.original.as_ref().unwrap_or_else(|| panic!("Could not restore {} (original not found). This should not happen.", t.path));
+            std::fs::write(&t.path, contents).unwrap_or_else(|_| panic!("Could not write {}", t.path));
         }
     }
 
     /// Store the contents in self.transformations.original
     fn load_original_contents(&mut self) {
         for t in &mut self.transformations {
-            let contents = std::fs::read_to_string(&t.path).expect(format!("Could not read {}", t.path).as_str());
+            let contents = std::fs::read_to_string(&t.path).unwrap_or_else(|_| panic!("Could not read {}", t.path));
             t.original = Some(contents);
         }
     }
@@ -81,6 +82,14 @@ enum MigrationError {
     InvalidVersion(String),
 }
 
+fn describe(error: MigrationError, path: &str, initial_version: &str) -> String {
+    match error {
+        MigrationError::VersionNotFound => format!("Expected {} in {} but found none.", initial_version, path),
+        MigrationError::InvalidVersion(version) => format!("Expected {} in {} but found {}.", initial_version, path, version),
+        MigrationError::InvalidRegex(regex) => format!("Regex in file {} is invalid: {}", path, regex),
+    }
+}
+
 fn build_initial_transformation(dir: &str, regex: Option<IndexMap<String, String>>) -> Vec<Transformation> {
     regex
         .unwrap_or_else(|| {